@@ -104,6 +104,7 @@ pub async fn export_task_data_handler(
             
             if task_dir.exists() {
                 let audio_dir = "audio";
+                let image_dir = "images";
                 
                 for entry in std::fs::read_dir(&task_dir)? {
                     let entry = entry?;
@@ -111,12 +112,19 @@ pub async fn export_task_data_handler(
                     
                     if path.is_file() {
                         if let Some(ext) = path.extension() {
-                            if ext == "wav" {
+                            let folder = if ext == "wav" {
+                                Some(audio_dir)
+                            } else if ext == "png" {
+                                Some(image_dir) // Received SSTV pictures
+                            } else {
+                                None
+                            };
+                            if let Some(folder) = folder {
                                 let file_name = path.file_name()
                                     .unwrap_or_default()
                                     .to_string_lossy();
                                 
-                                let zip_path = format!("{}/{}", audio_dir, file_name);
+                                let zip_path = format!("{}/{}", folder, file_name);
                                 zip.start_file(zip_path, options)?;
                                 
                                 let file_content = std::fs::read(&path)?;
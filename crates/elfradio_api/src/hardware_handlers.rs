@@ -0,0 +1,31 @@
+// Device listing endpoints for the settings page.
+
+use axum::extract::Json;
+use elfradio_hardware::{list_audio_devices, list_serial_ports};
+use elfradio_types::{AudioDeviceInfo, SerialPortInfo};
+use tracing::{debug, info};
+
+use crate::error::ApiError; // Local ApiError
+
+/// Handles GET /api/hardware/audio_devices: every input and output device with its capabilities.
+pub async fn list_audio_devices_handler() -> Result<Json<Vec<AudioDeviceInfo>>, ApiError> {
+    info!("Received request to list audio devices.");
+    // Device enumeration talks to the sound system and can block.
+    let devices = tokio::task::spawn_blocking(list_audio_devices)
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("Audio device listing task failed: {}", e)))?
+        .map_err(|e| ApiError::InternalServerError(format!("Failed to list audio devices: {}", e)))?;
+    debug!("Returning {} audio device entries.", devices.len());
+    Ok(Json(devices))
+}
+
+/// Handles GET /api/hardware/serial_ports: serial ports with USB VID/PID/serial number where known.
+pub async fn list_serial_ports_handler() -> Result<Json<Vec<SerialPortInfo>>, ApiError> {
+    info!("Received request to list serial ports.");
+    let ports = tokio::task::spawn_blocking(list_serial_ports)
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("Serial port listing task failed: {}", e)))?
+        .map_err(|e| ApiError::InternalServerError(format!("Failed to list serial ports: {}", e)))?;
+    debug!("Returning {} serial ports.", ports.len());
+    Ok(Json(ports))
+}
@@ -1,5 +1,6 @@
 mod error; // Add this line to declare the error module
 mod test_handlers; // ADD THIS LINE to declare the new handlers module
+mod hardware_handlers;
 
 /// Request body for the temporary translation test endpoint.
 #[derive(Deserialize, Debug)]
@@ -182,6 +183,8 @@ pub async fn run_server(
         .route("/api/test/llm", post(test_llm_handler)) // Add the new LLM test route
         .route("/api/test/tts", post(test_handlers::test_tts_handler)) // ADD THESE TWO NEW ROUTES for TTS and STT testing
         .route("/api/test/stt", post(test_handlers::test_stt_handler))
+        .route("/api/hardware/audio_devices", get(hardware_handlers::list_audio_devices_handler))
+        .route("/api/hardware/serial_ports", get(hardware_handlers::list_serial_ports_handler))
         .with_state(app_state) // Pass only AppState
         .layer(cors); // 应用 CORS 中间件
 
@@ -324,6 +327,9 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     
     // Radio Status
     send_ws_msg(&client_tx, WebSocketMessage::RadioStatusUpdate(ConnectionStatus::Unknown), "Radio");
+
+    // Audio Status
+    send_ws_msg(&client_tx, WebSocketMessage::AudioStatusUpdate(ConnectionStatus::Unknown), "Audio");
     // --- END NEW LOGIC ---
 
 
@@ -718,6 +724,11 @@ pub async fn update_config_handler(
                            )))
                      }
                 },
+                // Not produced when saving; listed to keep the match exhaustive.
+                ElfConfigError::SstvTemplateNotFound(name) => Err(ApiError::InternalServerError(format!(
+                    "SSTV template '{}' not found",
+                    name
+                ))),
             }
         }
     }
@@ -945,6 +956,7 @@ fn message_type_for_debug(msg: &WebSocketMessage) -> String {
     match msg {
         WebSocketMessage::Log(_) => "日志".to_string(),
         WebSocketMessage::RadioStatusUpdate(_) => "无线电状态更新".to_string(),
+        WebSocketMessage::AudioStatusUpdate(_) => "音频状态更新".to_string(),
         WebSocketMessage::SdrStatusUpdate(_) => "SDR状态更新".to_string(),
         WebSocketMessage::LlmStatusUpdate(_) => "LLM状态更新".to_string(),
         WebSocketMessage::SttStatusUpdate(_) => "STT状态更新".to_string(),
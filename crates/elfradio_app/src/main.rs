@@ -21,7 +21,7 @@ use std::io::{self, Write};
 use elfradio_core::audio_processor::audio_input_processor;
 // 添加API服务器运行函数导入
 use elfradio_api::run_server;
-use elfradio_types::{TxItem, AudioMessage, AudioOutputSender, LogEntry, LogDirection, LogContentType, SystemServiceStatus, WebSocketMessage};
+use elfradio_types::{TxItem, AudioMessage, LogEntry, LogDirection, LogContentType, SystemServiceStatus, WebSocketMessage};
 use anyhow::anyhow; // Assuming Result is not used elsewhere
 // 添加数据库初始化函数导入
 use elfradio_db::init_db;
@@ -44,6 +44,10 @@ use elfradio_aux_client::create_aux_client; // Import from elfradio_aux_client c
 use elfradio_types::ConnectionStatus;
 // 增加导入 periodic_network_connectivity_monitor 函数
 use elfradio_core::network_monitor::periodic_network_connectivity_monitor;
+// --- CAT rig control ---
+use elfradio_hardware::open_rig_control;
+// --- Audio backends ---
+use elfradio_hardware::{open_audio_backend, AudioOutputSender, AudioSupervisor, SupervisorTiming};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -80,7 +84,7 @@ async fn main() -> anyhow::Result<()> {
     // --- Create channels ---
     let (tx_sender, tx_receiver) = mpsc::unbounded_channel::<TxItem>();
     let (log_entry_tx, log_entry_rx) = mpsc::unbounded_channel::<LogEntry>();
-    let (_audio_input_sender, audio_input_receiver) = mpsc::unbounded_channel::<AudioMessage>();
+    let (audio_input_sender, audio_input_receiver) = mpsc::unbounded_channel::<AudioMessage>();
     let (shutdown_tx, _shutdown_rx_main) = watch::channel(false);
 
     // 创建用于WebSocketMessage状态更新的通道
@@ -278,29 +282,93 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // --- Send Placeholder SDR Status Update (Step 5.7.7) ---
-    info!("SDR: Sending placeholder status (Disconnected) on startup.");
-
-    // Send Log Entry for SDR placeholder status
-    let sdr_log_content = "SDR: Status check (placeholder) - Disconnected.";
-    let sdr_log_entry = LogEntry {
-        timestamp: Utc::now(),
-        direction: LogDirection::Internal,
-        content_type: LogContentType::Status,
-        content: sdr_log_content.to_string(),
-    };
-    if log_entry_tx.send(sdr_log_entry).is_err() {
-        error!("Failed to send SDR placeholder log entry to MPSC channel.");
-    } else {
-        debug!("SDR placeholder log entry sent to MPSC channel.");
+    // --- Connect CAT Rig Control (rigctld or native serial driver) ---
+    let cat_configured = [&config.hardware.cat_protocol, &config.hardware.rigctld_address]
+        .iter()
+        .any(|v| v.as_deref().is_some_and(|s| !s.trim().is_empty()));
+    if cat_configured {
+        info!("CAT: Connecting to rig...");
+        let hardware_config = config.hardware.clone();
+        let connect_result = tokio::task::spawn_blocking(move || open_rig_control(&hardware_config)).await;
+
+        let radio_status = match connect_result {
+            Ok(Ok(Some(rig))) => {
+                *app_state.rig_control.write().await = Some(rig);
+                info!("CAT: Rig control connected.");
+                ConnectionStatus::Connected
+            }
+            Ok(Ok(None)) => ConnectionStatus::Disconnected,
+            Ok(Err(e)) => {
+                error!("CAT: Failed to connect to rig: {}", e);
+                ConnectionStatus::Error
+            }
+            Err(e) => {
+                error!("CAT: Rig connection task failed: {:?}", e);
+                ConnectionStatus::Error
+            }
+        };
+
+        if status_update_tx.send(WebSocketMessage::RadioStatusUpdate(radio_status.clone())).is_err() {
+            error!("Failed to send RadioStatusUpdate({:?}) to MPSC channel.", radio_status);
+        }
     }
 
-    // Send WebSocketMessage for SDR placeholder status
-    let sdr_status_update_msg = WebSocketMessage::SdrStatusUpdate(ConnectionStatus::Disconnected);
-    if status_update_tx.send(sdr_status_update_msg.clone()).is_err() { // Clone if status_update_tx is used again soon
-        error!("Failed to send SdrStatusUpdate(Disconnected) to MPSC channel.");
-    } else {
-        debug!("SdrStatusUpdate(Disconnected) successfully sent to MPSC channel.");
+    // --- Start Audio Backend (cpal, WAV file, null or rtl_tcp SDR) ---
+    // The supervisor owns the streams and reopens them if the device drops out;
+    // it must stay alive for as long as audio should flow.
+    let mut _audio_supervisor = None;
+    // With the SDR as the audio source, the supervisor reports SDR status itself.
+    let mut sdr_active = false;
+    match open_audio_backend(&config.hardware) {
+        Ok(backend) => {
+            sdr_active = backend.name() == "rtl_tcp";
+            let output_slot = app_state.audio_output_sender.clone();
+            let on_output = move |sender: Option<AudioOutputSender>| {
+                if let Some(sender) = &sender {
+                    info!("Audio: Output running at {} Hz, {} channel(s)", sender.sample_rate(), sender.channels());
+                }
+                *output_slot.blocking_lock() = sender;
+            };
+            // Hand the supervisor a clone so a finished replay doesn't close the RX channel.
+            match AudioSupervisor::spawn(
+                backend,
+                audio_input_sender.clone(),
+                on_output,
+                status_update_tx.clone(),
+                SupervisorTiming::default(),
+            ) {
+                Ok(supervisor) => _audio_supervisor = Some(supervisor),
+                Err(e) => error!("Audio: Failed to start audio supervisor: {}", e),
+            }
+        }
+        Err(e) => error!("Audio: Failed to open audio backend: {}", e),
+    }
+
+    // --- Send Placeholder SDR Status Update (Step 5.7.7) ---
+    if !sdr_active {
+        info!("SDR: Sending placeholder status (Disconnected) on startup.");
+
+        // Send Log Entry for SDR placeholder status
+        let sdr_log_content = "SDR: Status check (placeholder) - Disconnected.";
+        let sdr_log_entry = LogEntry {
+            timestamp: Utc::now(),
+            direction: LogDirection::Internal,
+            content_type: LogContentType::Status,
+            content: sdr_log_content.to_string(),
+        };
+        if log_entry_tx.send(sdr_log_entry).is_err() {
+            error!("Failed to send SDR placeholder log entry to MPSC channel.");
+        } else {
+            debug!("SDR placeholder log entry sent to MPSC channel.");
+        }
+
+        // Send WebSocketMessage for SDR placeholder status
+        let sdr_status_update_msg = WebSocketMessage::SdrStatusUpdate(ConnectionStatus::Disconnected);
+        if status_update_tx.send(sdr_status_update_msg.clone()).is_err() { // Clone if status_update_tx is used again soon
+            error!("Failed to send SdrStatusUpdate(Disconnected) to MPSC channel.");
+        } else {
+            debug!("SdrStatusUpdate(Disconnected) successfully sent to MPSC channel.");
+        }
     }
     // --- End Placeholder SDR Status Update ---
 
@@ -1,4 +1,5 @@
 use elfradio_types::Config as AppConfig; // Alias to avoid naming collision
+use elfradio_types::SstvTemplate;
 use config::{Config as ConfigRs, Environment, File, ConfigError as RsConfigError};
 // use directories::ProjectDirs; // REMOVED
 use std::path::PathBuf;
@@ -19,6 +20,9 @@ pub enum ConfigError {
     #[error("Configuration parsing or validation error: {0}")]
     Config(#[from] RsConfigError),
 
+    #[error("SSTV template '{0}' not found")]
+    SstvTemplateNotFound(String),
+
     // #[error("Could not determine a valid configuration directory")] // REMOVED as DirectoryError is no longer distinct from IoError for CWD
     // DirectoryError, // REMOVED
 }
@@ -183,6 +187,34 @@ pub fn get_user_config_value<T: serde::de::DeserializeOwned>(key: &str) -> Resul
     }
 }
 
+/// Loads the SSTV overlay template `name`.
+///
+/// Looks for `sstv_templates/<name>.toml` next to `elfradio_config.toml` (the CWD) first,
+/// so users can override or add templates, then in the shipped `config/sstv_templates/`.
+pub fn load_sstv_template(name: &str) -> Result<SstvTemplate, ConfigError> {
+    let app_dir = env::current_dir()?;
+    let search_dirs = [app_dir.join("sstv_templates"), app_dir.join("config").join("sstv_templates")];
+    load_sstv_template_from(&search_dirs, name)
+}
+
+fn load_sstv_template_from(search_dirs: &[PathBuf], name: &str) -> Result<SstvTemplate, ConfigError> {
+    // Template names are bare file stems; anything path-like is rejected.
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(ConfigError::SstvTemplateNotFound(name.to_string()));
+    }
+    let Some(path) = search_dirs.iter().map(|dir| dir.join(format!("{}.toml", name))).find(|path| path.exists()) else {
+        warn!("SSTV template '{}' not found in {:?}", name, search_dirs);
+        return Err(ConfigError::SstvTemplateNotFound(name.to_string()));
+    };
+    debug!("Loading SSTV template from {:?}", path);
+    let template = ConfigRs::builder()
+        .add_source(File::from(path.clone()).required(true))
+        .build()?
+        .try_deserialize::<SstvTemplate>()?;
+    info!("Loaded SSTV template '{}' with {} text overlay(s).", name, template.text.len());
+    Ok(template)
+}
+
 /// Converts a serde_json::Value to a toml_edit::Item.
 /// Handles basic types (null, bool, number, string). Arrays/Objects need careful mapping.
 fn json_to_toml_value(json_val: &JsonValue) -> Result<Item, ConfigError> {
@@ -393,6 +425,49 @@ mod tests {
         assert_eq!(config.timing.ptt_post_delay_ms, 100);
     }
 
+    #[test]
+    fn test_load_sstv_template_prefers_user_dir() {
+        use elfradio_types::{SstvImageFit, SstvTextAnchor};
+
+        let temp_dir = Builder::new().prefix("elfradio_sstv_tpl").tempdir().expect("Failed to create temp dir");
+        let user_dir = temp_dir.path().join("sstv_templates");
+        let shipped_dir = temp_dir.path().join("config").join("sstv_templates");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::create_dir_all(&shipped_dir).unwrap();
+        fs::write(shipped_dir.join("default.toml"), "fit = \"crop\"\n").unwrap();
+        fs::write(
+            user_dir.join("default.toml"),
+            r#"
+            fit = "letterbox"
+            background = [0, 0, 64]
+
+            [[text]]
+            text = "{theircall} de {mycall}"
+            anchor = "bottom_right"
+            x = 4
+            y = 2
+            scale = 2
+            outline = [0, 0, 0]
+            "#,
+        )
+        .unwrap();
+        let dirs = [user_dir, shipped_dir.clone()];
+
+        let template = load_sstv_template_from(&dirs, "default").expect("Template should load");
+        assert_eq!(template.fit, SstvImageFit::Letterbox);
+        assert_eq!(template.background, [0, 0, 64]);
+        assert_eq!(template.text.len(), 1);
+        let overlay = &template.text[0];
+        assert_eq!(overlay.anchor, SstvTextAnchor::BottomRight);
+        assert_eq!((overlay.x, overlay.y, overlay.scale), (4, 2, 2));
+        assert_eq!(overlay.color, [255, 255, 255]);
+        assert_eq!(overlay.outline, Some([0, 0, 0]));
+
+        assert_eq!(load_sstv_template_from(&dirs[1..], "default").unwrap().fit, SstvImageFit::Crop);
+        assert_matches!(load_sstv_template_from(&dirs, "missing"), Err(ConfigError::SstvTemplateNotFound(_)));
+        assert_matches!(load_sstv_template_from(&dirs, "../default"), Err(ConfigError::SstvTemplateNotFound(_)));
+    }
+
     // Add tests for get_user_config_value
     // - Test case where user file exists and key exists
     // - Test case where user file exists but key does NOT exist (expect Ok(None))
@@ -3,11 +3,16 @@
 use super::error::CoreError; // Use the new error module from the parent
 use super::state::AppState; // Use the state module from the parent
 use elfradio_types::{
-    AudioMessage, LogContentType, LogDirection, LogEntry, AiConfig, // Added AiConfig
+    AudioMessage, LogContentType, LogDirection, LogEntry, AiConfig, TaskInfo, DtmfAction, SubtoneConfig, RxAudioConfig, // Added AiConfig
     WebSocketMessage, SystemServiceStatus, AiError, // Added for 5.7.6.2
 };
 // use elfradio_ai::{AiClient, SttParams}; // Add if STT logic is included later
 use elfradio_dsp::vad::VadProcessor;
+use elfradio_dsp::VadError;
+use elfradio_dsp::{
+    parse_aprs, AfskDecoder, AfskParams, AprsPacket, Ax25Frame, DecodedSstvImage, DtmfDetector, LinearResampler, RxChainParams,
+    RxConditioner, SstvReceiver, Subtone, SubtoneDetector,
+};
 use webrtc_vad::VadMode;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -21,6 +26,7 @@ use tracing::{debug, error, info, warn, trace, instrument};
 use hound; // Import the hound crate directly
 use elfradio_ai::SttParams; // Added import for STT logic
 use elfradio_db::insert_log_entry; // 导入数据库插入函数
+use crate::logging;
 use uuid::Uuid; // 用于 task_id
 // use elfradio_ai::AiError; // 删除或注释掉此行
 
@@ -159,7 +165,7 @@ fn construct_stt_params(ai_config: &AiConfig) -> Result<SttParams, CoreError> {
         })
         .unwrap();
 
-    let sample_rate_value = 16000;
+    let sample_rate_value = STT_SAMPLE_RATE;
     debug!("Using language '{}' and sample rate {} for STT", language_code, sample_rate_value);
 
     Ok(SttParams {
@@ -279,6 +285,478 @@ pub async fn process_stt_request(
     }
 }
 
+// ----------------------------------------------------------------------------
+// SSTV Reception
+// ----------------------------------------------------------------------------
+
+/// Runs SSTV detection on its own thread, since decoding a finished picture
+/// takes a while. Audio goes in through the returned sender; pictures come
+/// back on `image_tx`.
+fn spawn_sstv_receiver(
+    sample_rate: u32,
+    image_tx: mpsc::UnboundedSender<DecodedSstvImage>,
+) -> Option<std::sync::mpsc::Sender<Vec<f32>>> {
+    let mut receiver = match SstvReceiver::new(sample_rate) {
+        Ok(receiver) => receiver,
+        Err(e) => {
+            error!("Failed to initialize SSTV receiver: {}", e);
+            return None;
+        }
+    };
+    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<Vec<f32>>();
+    let spawned = std::thread::Builder::new().name("sstv-rx".to_string()).spawn(move || {
+        while let Ok(chunk) = audio_rx.recv() {
+            if let Some(picture) = receiver.push(&chunk) {
+                if image_tx.send(picture).is_err() {
+                    break;
+                }
+            }
+        }
+        debug!("SSTV receiver thread finished.");
+    });
+    match spawned {
+        Ok(_) => Some(audio_tx),
+        Err(e) => {
+            error!("Failed to spawn SSTV receiver thread: {}", e);
+            None
+        }
+    }
+}
+
+/// Saves a received SSTV picture into the active task's directory and logs it.
+#[instrument(skip(app_state, picture, log_entry_tx), fields(mode = %picture.mode))]
+async fn save_sstv_image(
+    app_state: &AppState,
+    picture: DecodedSstvImage,
+    log_entry_tx: &mpsc::UnboundedSender<LogEntry>,
+) -> AudioProcessingOutcome<()> {
+    let Some(task_info) = app_state.get_active_task_info().await else {
+        warn!("Received an SSTV picture but no task is active, discarding it.");
+        return Ok(());
+    };
+    let mode_slug = picture.mode.name().replace(' ', "_").to_lowercase();
+    let filename = format!("sstv_rx_{}_{}.png", mode_slug, Uuid::new_v4());
+    let image_path = task_info.task_dir.join(&filename);
+    info!(
+        task_id = %task_info.id,
+        lines = picture.lines_received,
+        clock_ratio = picture.clock_ratio,
+        "Saving received SSTV picture to {:?}", image_path
+    );
+
+    let image = picture.image;
+    tokio::task::spawn_blocking(move || image.save(&image_path))
+        .await
+        .map_err(|e| CoreError::AudioError(format!("SSTV save task failed: {}", e)))?
+        .map_err(|e| CoreError::AudioError(format!("Failed to save SSTV picture: {}", e)))?;
+
+    let entry = LogEntry {
+        timestamp: Utc::now(),
+        direction: LogDirection::Incoming,
+        content_type: LogContentType::Image,
+        content: filename,
+    };
+    record_incoming_entry(app_state, &task_info, entry, log_entry_tx, "SSTV").await;
+    Ok(())
+}
+
+/// Writes a decoded reception to the task log file and database, and forwards it to
+/// the log channel. `kind` names the decoder in error messages.
+async fn record_incoming_entry(
+    app_state: &AppState,
+    task_info: &TaskInfo,
+    entry: LogEntry,
+    log_entry_tx: &mpsc::UnboundedSender<LogEntry>,
+    kind: &str,
+) {
+    if let Err(e) = logging::write_log_entry(&task_info.task_dir, &entry) {
+        error!(task_id = %task_info.id, "Failed to write {} log entry: {}", kind, e);
+    }
+    if let Err(e) = insert_log_entry(&app_state.db_pool, task_info.id, &entry).await {
+        error!(task_id = %task_info.id, "Failed to insert {} log entry into database: {:?}", kind, e);
+    }
+    if log_entry_tx.send(entry).is_err() {
+        error!(task_id = %task_info.id, "Failed to send {} log entry via MPSC channel.", kind);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// AX.25 / APRS Packet Reception
+// ----------------------------------------------------------------------------
+
+/// Runs the AFSK1200 packet decoder on its own thread. Audio goes in through the
+/// returned sender; frames that pass the FCS check come back on `frame_tx`.
+fn spawn_packet_receiver(
+    sample_rate: u32,
+    frame_tx: mpsc::UnboundedSender<Ax25Frame>,
+) -> Option<std::sync::mpsc::Sender<Vec<f32>>> {
+    let mut decoder = match AfskDecoder::new(&AfskParams::new(sample_rate)) {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            error!("Failed to initialize AFSK packet decoder: {}", e);
+            return None;
+        }
+    };
+    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<Vec<f32>>();
+    let spawned = std::thread::Builder::new().name("packet-rx".to_string()).spawn(move || {
+        while let Ok(chunk) = audio_rx.recv() {
+            for frame in decoder.push(&chunk) {
+                if frame_tx.send(frame).is_err() {
+                    return;
+                }
+            }
+        }
+        debug!("Packet receiver thread finished.");
+    });
+    match spawned {
+        Ok(_) => Some(audio_tx),
+        Err(e) => {
+            error!("Failed to spawn packet receiver thread: {}", e);
+            None
+        }
+    }
+}
+
+/// Logs a received AX.25 frame in monitor format, followed by a summary line when the
+/// information field is an APRS report this build understands.
+#[instrument(skip(app_state, frame, log_entry_tx), fields(source = %frame.source))]
+async fn log_received_packet(
+    app_state: &AppState,
+    frame: Ax25Frame,
+    log_entry_tx: &mpsc::UnboundedSender<LogEntry>,
+) {
+    let Some(task_info) = app_state.get_active_task_info().await else {
+        debug!("Received an AX.25 frame but no task is active, discarding it.");
+        return;
+    };
+    let mut content = frame.to_string();
+    match parse_aprs(&frame.info) {
+        Ok(AprsPacket::Other { .. }) => {}
+        Ok(packet) => content = format!("{}\n{}", content, packet),
+        Err(e) => debug!("AX.25 frame is not a valid APRS report: {}", e),
+    }
+    info!(task_id = %task_info.id, "Received packet: {}", frame);
+
+    let entry = LogEntry {
+        timestamp: Utc::now(),
+        direction: LogDirection::Incoming,
+        content_type: LogContentType::Text,
+        content,
+    };
+    record_incoming_entry(app_state, &task_info, entry, log_entry_tx, "packet").await;
+}
+
+// ----------------------------------------------------------------------------
+// DTMF Detection & Remote Control
+// ----------------------------------------------------------------------------
+
+/// Runs the DTMF detector on its own thread and groups keys into sequences, which end
+/// with '#' or after `timeout_ms` without a key. Sequences come back on `sequence_tx`
+/// without the '#'.
+fn spawn_dtmf_receiver(
+    sample_rate: u32,
+    timeout_ms: u64,
+    sequence_tx: mpsc::UnboundedSender<String>,
+) -> Option<std::sync::mpsc::Sender<Vec<f32>>> {
+    let mut detector = match DtmfDetector::new(sample_rate) {
+        Ok(detector) => detector,
+        Err(e) => {
+            error!("Failed to initialize DTMF detector: {}", e);
+            return None;
+        }
+    };
+    let timeout_samples = timeout_ms * sample_rate as u64 / 1000;
+    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<Vec<f32>>();
+    let spawned = std::thread::Builder::new().name("dtmf-rx".to_string()).spawn(move || {
+        let mut sequence = String::new();
+        let mut quiet_samples = 0u64;
+        while let Ok(chunk) = audio_rx.recv() {
+            let keys = detector.push(&chunk);
+            if keys.is_empty() {
+                quiet_samples += chunk.len() as u64;
+            } else {
+                quiet_samples = 0;
+            }
+            let mut completed = Vec::new();
+            for key in keys.chars() {
+                if key == '#' {
+                    completed.push(std::mem::take(&mut sequence));
+                } else {
+                    sequence.push(key);
+                }
+            }
+            if quiet_samples >= timeout_samples {
+                completed.push(std::mem::take(&mut sequence));
+            }
+            for sequence in completed.into_iter().filter(|sequence| !sequence.is_empty()) {
+                if sequence_tx.send(sequence).is_err() {
+                    return;
+                }
+            }
+        }
+        debug!("DTMF receiver thread finished.");
+    });
+    match spawned {
+        Ok(_) => Some(audio_tx),
+        Err(e) => {
+            error!("Failed to spawn DTMF receiver thread: {}", e);
+            None
+        }
+    }
+}
+
+/// Logs a received DTMF sequence, with the PIN masked, and runs the remote command it
+/// selects, if any.
+#[instrument(skip_all)]
+async fn handle_dtmf_sequence(
+    app_state: &Arc<AppState>,
+    sequence: String,
+    log_entry_tx: &mpsc::UnboundedSender<LogEntry>,
+    status_update_tx: &mpsc::UnboundedSender<WebSocketMessage>,
+) {
+    let pin = app_state.config.security.dtmf_pin.as_deref().filter(|pin| !pin.is_empty());
+    let command = app_state.config.dtmf.command_for(&sequence, pin);
+    let mut content = match pin.and_then(|pin| sequence.strip_prefix(pin)) {
+        Some(code) => format!("DTMF: {}{}", "*".repeat(pin.map_or(0, str::len)), code),
+        None => format!("DTMF: {}", sequence),
+    };
+    match command.map(|command| &command.action) {
+        Some(DtmfAction::StopTask) => content.push_str(" (remote command: stop task)"),
+        Some(DtmfAction::SendText { text }) => content.push_str(&format!(" (remote command: send \"{}\")", text)),
+        None => {}
+    }
+    info!("Received {}", content);
+
+    if let Some(task_info) = app_state.get_active_task_info().await {
+        let entry = LogEntry {
+            timestamp: Utc::now(),
+            direction: LogDirection::Incoming,
+            content_type: LogContentType::Text,
+            content,
+        };
+        record_incoming_entry(app_state, &task_info, entry, log_entry_tx, "DTMF").await;
+    }
+
+    match command.map(|command| command.action.clone()) {
+        Some(DtmfAction::StopTask) => {
+            if let Err(e) = crate::task_manager::stop_task(app_state.clone()).await {
+                error!("DTMF remote command failed to stop the task: {}", e);
+            }
+        }
+        Some(DtmfAction::SendText { text }) => {
+            if let Err(e) = crate::tx_processor::queue_text_for_transmission(
+                app_state.clone(),
+                text,
+                log_entry_tx,
+                status_update_tx,
+            )
+            .await
+            {
+                error!("DTMF remote command failed to queue text: {}", e);
+            }
+        }
+        None => {}
+    }
+}
+
+// ----------------------------------------------------------------------------
+// CTCSS / DCS Squelch & Scan
+// ----------------------------------------------------------------------------
+
+/// Builds the subtone detector when the squelch needs a tone or scan mode is on, and
+/// returns it with the tone the squelch needs. A bad `rx` setting leaves the squelch open.
+fn subtone_squelch(config: &SubtoneConfig, sample_rate: u32) -> (Option<SubtoneDetector>, Option<Subtone>) {
+    let required = match config.rx.as_deref().filter(|rx| !rx.trim().is_empty()).map(str::parse::<Subtone>) {
+        Some(Ok(subtone)) => Some(subtone),
+        Some(Err(e)) => {
+            error!("Ignoring subtone.rx setting: {}", e);
+            None
+        }
+        None => None,
+    };
+    if required.is_none() && !config.scan {
+        return (None, None);
+    }
+    match SubtoneDetector::new(sample_rate) {
+        Ok(detector) => {
+            info!("Subtone detector running (squelch tone: {:?}, scan: {}).", required, config.scan);
+            (Some(detector), required)
+        }
+        Err(e) => {
+            error!("Failed to initialize subtone detector: {}", e);
+            (None, None)
+        }
+    }
+}
+
+/// Logs the tone or code a received station is using (scan mode).
+async fn log_subtone(
+    app_state: &AppState,
+    task_info: &TaskInfo,
+    subtone: Subtone,
+    log_entry_tx: &mpsc::UnboundedSender<LogEntry>,
+) {
+    let content = match subtone {
+        // The receiver's discriminator may invert DCS, which turns a code into the
+        // other polarity of itself.
+        Subtone::Dcs { .. } => {
+            format!("Subtone: {} (or {} if the receiver inverts)", subtone, subtone.inverted().canonical())
+        }
+        Subtone::Ctcss(_) => format!("Subtone: {}", subtone),
+    };
+    info!(task_id = %task_info.id, "{}", content);
+    let entry = LogEntry {
+        timestamp: Utc::now(),
+        direction: LogDirection::Incoming,
+        content_type: LogContentType::Text,
+        content,
+    };
+    record_incoming_entry(app_state, task_info, entry, log_entry_tx, "subtone").await;
+}
+
+// ----------------------------------------------------------------------------
+// RX Audio Conditioning
+// ----------------------------------------------------------------------------
+
+/// Builds the receive chain that cleans up audio ahead of VAD and STT. Returns None when
+/// it is disabled or misconfigured, which leaves received audio as it is.
+fn rx_conditioner(config: &RxAudioConfig, sample_rate: u32) -> Option<RxConditioner> {
+    if !config.enabled {
+        return None;
+    }
+    let params = RxChainParams::new(sample_rate)
+        .with_noise_reduction(config.noise_reduction)
+        .with_notch(config.notch)
+        .with_agc(config.agc.then_some(config.agc_target_dbfs));
+    match RxConditioner::new(&params) {
+        Ok(conditioner) => {
+            info!("RX audio conditioning running: {:?}", params);
+            Some(conditioner)
+        }
+        Err(e) => {
+            error!("Failed to initialize RX audio conditioning, audio will be unprocessed: {}", e);
+            None
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// VAD Segmentation
+// ----------------------------------------------------------------------------
+
+/// Rate received speech is segmented and transcribed at. WebRTC VAD only takes 8, 16,
+/// 32 or 48 kHz, so other input rates (44.1 kHz) are resampled to it first.
+const STT_SAMPLE_RATE: u32 = 16000;
+/// VAD frame length; WebRTC VAD takes 10, 20 or 30 ms.
+const VAD_FRAME_MS: usize = 30;
+/// Silence that ends a speech segment.
+const SEGMENT_HANGOVER_MS: usize = 600;
+/// Less speech than this is a click or a noise burst, not worth transcribing.
+const SEGMENT_MIN_MS: usize = 300;
+/// Long overs are cut here, so STT gets them in pieces.
+const SEGMENT_MAX_MS: usize = 30_000;
+
+/// Cuts received audio into speech segments with the VAD, so STT gets one request per
+/// utterance rather than one per capture chunk.
+struct SpeechSegmenter {
+    resampler: LinearResampler,
+    vad: VadProcessor,
+    /// Samples waiting for a full VAD frame.
+    pending: Vec<i16>,
+    segment: Vec<i16>,
+    /// VAD frames of silence at the end of `segment`.
+    silent_frames: usize,
+    speaking: bool,
+}
+
+impl SpeechSegmenter {
+    /// Creates a segmenter for audio at `sample_rate`; segments come out at `STT_SAMPLE_RATE`.
+    fn new(sample_rate: u32) -> Result<Self, VadError> {
+        Ok(Self {
+            resampler: LinearResampler::new(sample_rate, STT_SAMPLE_RATE),
+            vad: VadProcessor::new(STT_SAMPLE_RATE, VAD_FRAME_MS, VadMode::Aggressive)?,
+            pending: Vec::new(),
+            segment: Vec::new(),
+            silent_frames: 0,
+            speaking: false,
+        })
+    }
+
+    /// Feeds received audio and returns the segments it completes, as PCM L16 bytes.
+    fn push(&mut self, samples: &[f32]) -> Vec<Vec<u8>> {
+        let frame_len = self.vad.frame_size_samples();
+        let samples_per_ms = self.vad.sample_rate() as usize / 1000;
+        let samples = self.resampler.process(samples);
+        self.pending.extend(samples.iter().map(|&sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16));
+
+        let mut segments = Vec::new();
+        let mut start = 0;
+        while self.pending.len() - start >= frame_len {
+            let frame = &self.pending[start..start + frame_len];
+            start += frame_len;
+            match self.vad.process_chunk(frame) {
+                Ok(Some(speaking)) => self.speaking = speaking,
+                Ok(None) => {}
+                Err(e) => warn!("VAD failed on a frame: {}", e),
+            }
+            if self.speaking {
+                self.segment.extend_from_slice(frame);
+                self.silent_frames = 0;
+            } else if !self.segment.is_empty() {
+                self.segment.extend_from_slice(frame);
+                self.silent_frames += 1;
+            }
+
+            let ended = self.silent_frames * VAD_FRAME_MS >= SEGMENT_HANGOVER_MS;
+            if ended || self.segment.len() >= SEGMENT_MAX_MS * samples_per_ms {
+                let speech = self.segment.len() - self.silent_frames * frame_len;
+                if speech >= SEGMENT_MIN_MS * samples_per_ms {
+                    segments.push(self.segment.iter().flat_map(|sample| sample.to_le_bytes()).collect());
+                }
+                self.segment.clear();
+                self.silent_frames = 0;
+            }
+        }
+        self.pending.drain(..start);
+        segments
+    }
+}
+
+/// Runs the speech segmenter on its own thread. Audio goes in through the returned
+/// sender; finished segments come back on `segment_tx`.
+fn spawn_speech_segmenter(sample_rate: u32, segment_tx: mpsc::UnboundedSender<Vec<u8>>) -> Option<std::sync::mpsc::Sender<Vec<f32>>> {
+    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<Vec<f32>>();
+    // The VAD can't move between threads, so it is built on the segmenter thread,
+    // which reports back whether that worked.
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel::<bool>();
+    let spawned = std::thread::Builder::new().name("speech-segmenter".to_string()).spawn(move || {
+        let mut segmenter = match SpeechSegmenter::new(sample_rate) {
+            Ok(segmenter) => segmenter,
+            Err(e) => {
+                error!("Failed to initialize VAD, received speech won't be transcribed: {}", e);
+                let _ = ready_tx.send(false);
+                return;
+            }
+        };
+        let _ = ready_tx.send(true);
+        while let Ok(chunk) = audio_rx.recv() {
+            for segment in segmenter.push(&chunk) {
+                if segment_tx.send(segment).is_err() {
+                    return;
+                }
+            }
+        }
+        debug!("Speech segmenter thread finished.");
+    });
+    match spawned {
+        Ok(_) => ready_rx.recv().unwrap_or(false).then_some(audio_tx),
+        Err(e) => {
+            error!("Failed to spawn speech segmenter thread: {}", e);
+            None
+        }
+    }
+}
+
 // TODO: Implement process_stt_request function if needed
 // pub async fn process_stt_request(...) -> Result<String, CoreError> { ... } 
 
@@ -293,8 +771,85 @@ pub async fn audio_input_processor(
 ) {
     info!("Starting audio input processor task.");
 
+    // Backends deliver RX audio at the configured input rate.
+    let (sstv_image_tx, mut sstv_image_rx) = mpsc::unbounded_channel::<DecodedSstvImage>();
+    let sstv_audio_tx = spawn_sstv_receiver(app_state.config.hardware.input_sample_rate, sstv_image_tx);
+    let (packet_frame_tx, mut packet_frame_rx) = mpsc::unbounded_channel::<Ax25Frame>();
+    let packet_audio_tx = spawn_packet_receiver(app_state.config.hardware.input_sample_rate, packet_frame_tx);
+    let (dtmf_sequence_tx, mut dtmf_sequence_rx) = mpsc::unbounded_channel::<String>();
+    let dtmf_audio_tx = if app_state.config.dtmf.detect_enabled {
+        spawn_dtmf_receiver(
+            app_state.config.hardware.input_sample_rate,
+            app_state.config.dtmf.sequence_timeout_ms,
+            dtmf_sequence_tx,
+        )
+    } else {
+        None
+    };
+    // Decoders that listen to every chunk of RX audio while a task is active.
+    let decoder_feeds: Vec<(&str, std::sync::mpsc::Sender<Vec<f32>>)> =
+        [("SSTV", sstv_audio_tx), ("Packet", packet_audio_tx), ("DTMF", dtmf_audio_tx)]
+            .into_iter()
+            .filter_map(|(name, feed)| feed.map(|feed| (name, feed)))
+            .collect();
+    let (mut subtone_detector, required_subtone) =
+        subtone_squelch(&app_state.config.subtone, app_state.config.hardware.input_sample_rate);
+    let mut reported_subtone: Option<Subtone> = None;
+    let mut rx_conditioner = rx_conditioner(&app_state.config.rx_audio, app_state.config.hardware.input_sample_rate);
+    let (speech_segment_tx, mut speech_segment_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    let speech_audio_tx = spawn_speech_segmenter(app_state.config.hardware.input_sample_rate, speech_segment_tx);
+
     loop {
         tokio::select! {
+            Some(picture) = sstv_image_rx.recv() => {
+                if let Err(e) = save_sstv_image(&app_state, picture, &log_entry_tx).await {
+                    error!("Failed to store received SSTV picture: {}", e);
+                }
+            }
+
+            Some(frame) = packet_frame_rx.recv() => {
+                log_received_packet(&app_state, frame, &log_entry_tx).await;
+            }
+
+            Some(sequence) = dtmf_sequence_rx.recv() => {
+                handle_dtmf_sequence(&app_state, sequence, &log_entry_tx, &status_update_tx).await;
+            }
+
+            Some(audio_data_bytes) = speech_segment_rx.recv() => {
+                let Some(task_info) = app_state.get_active_task_info().await else {
+                    debug!("Speech segment completed after the task ended, not transcribing it.");
+                    continue;
+                };
+                debug!(task_id=%task_info.id, "Speech segment complete, triggering STT ({} bytes).", audio_data_bytes.len());
+                let stt_app_state_clone = app_state.clone();
+                let stt_log_tx_clone = log_entry_tx.clone();
+                let stt_status_tx_clone = status_update_tx.clone();
+                let task_id_for_stt_log = task_info.id; // 克隆 task_id 以传递给 spawned 任务
+
+                // Spawn a new task for the STT request to avoid blocking the audio processor loop.
+                tokio::spawn(async move {
+                    match process_stt_request(
+                        stt_app_state_clone,
+                        audio_data_bytes, // Pass Vec<u8>
+                        &stt_log_tx_clone,    // Pass reference to cloned sender
+                        &stt_status_tx_clone  // Pass reference to cloned sender
+                    ).await {
+                        Ok(transcript) => {
+                            if !transcript.is_empty() {
+                                info!(task_id=%task_id_for_stt_log, "STT successful (called from audio_input_processor). Transcript length: {}", transcript.len());
+                                // Further processing of transcript (e.g., sending to LLM for auto-reply) would happen here or be queued.
+                            } else {
+                                info!(task_id=%task_id_for_stt_log, "STT successful (called from audio_input_processor) but returned empty transcript.");
+                            }
+                        }
+                        Err(e) => {
+                            error!(task_id=%task_id_for_stt_log, "Error calling process_stt_request from audio_input_processor: {:?}", e);
+                            // Error is already logged and status pushed by process_stt_request itself.
+                        }
+                    }
+                });
+            }
+
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
                     info!("Shutdown signal received in audio processor. Exiting.");
@@ -312,50 +867,36 @@ pub async fn audio_input_processor(
                             if let Some(task_info) = active_task_info_option { // 从 Option 获取 task_info
                                 // --- Task is active: Process audio data ---\
                                 trace!(task_id=%task_info.id, "Processing audio data chunk (size: {}) for active task.", f32_data.len());
-                                // TODO: Implement VAD processing using f32_data
-                                // TODO: If speech detected, save segment using task_info.task_dir
-
-                                // --- Placeholder: Convert f32 to Vec<u8> (PCM L16) for STT ---
-                                // This is a simplified conversion. Real VAD would provide segments.
-                                // For now, let's assume f32_data is a segment ready for STT.
-                                let mut audio_data_bytes = Vec::with_capacity(f32_data.len() * 2);
-                                for &sample_f32 in &f32_data {
-                                    let sample_i16 = (sample_f32 * i16::MAX as f32) as i16;
-                                    audio_data_bytes.extend_from_slice(&sample_i16.to_le_bytes());
+                                let heard = subtone_detector.as_mut().and_then(|detector| detector.push(&f32_data));
+                                if app_state.config.subtone.scan && heard != reported_subtone {
+                                    reported_subtone = heard;
+                                    if let Some(subtone) = heard {
+                                        log_subtone(&app_state, &task_info, subtone, &log_entry_tx).await;
+                                    }
+                                }
+                                // Tone squelch: without the required tone, nothing is decoded or transcribed.
+                                if let Some(required) = &required_subtone {
+                                    if !heard.is_some_and(|heard| heard.is_equivalent(required)) {
+                                        trace!(task_id=%task_info.id, "Subtone squelch closed (heard {:?}).", heard);
+                                        continue;
+                                    }
                                 }
-                                // --- End Placeholder Conversion ---
-
-                                if !audio_data_bytes.is_empty() {
-                                    debug!(task_id=%task_info.id, "Placeholder: Triggering STT for processed audio chunk ({} bytes).", audio_data_bytes.len());
-                                    let stt_app_state_clone = app_state.clone();
-                                    let stt_log_tx_clone = log_entry_tx.clone();
-                                    let stt_status_tx_clone = status_update_tx.clone();
-                                    let task_id_for_stt_log = task_info.id; // 克隆 task_id 以传递给 spawned 任务
-
-                                    // Spawn a new task for the STT request to avoid blocking the audio processor loop.
-                                    tokio::spawn(async move {
-                                        match process_stt_request(
-                                            stt_app_state_clone,
-                                            audio_data_bytes, // Pass Vec<u8>
-                                            &stt_log_tx_clone,    // Pass reference to cloned sender
-                                            &stt_status_tx_clone  // Pass reference to cloned sender
-                                        ).await {
-                                            Ok(transcript) => {
-                                                if !transcript.is_empty() {
-                                                    info!(task_id=%task_id_for_stt_log, "STT successful (called from audio_input_processor). Transcript length: {}", transcript.len());
-                                                    // Further processing of transcript (e.g., sending to LLM for auto-reply) would happen here or be queued.
-                                                } else {
-                                                    info!(task_id=%task_id_for_stt_log, "STT successful (called from audio_input_processor) but returned empty transcript.");
-                                                }
-                                            }
-                                            Err(e) => {
-                                                error!(task_id=%task_id_for_stt_log, "Error calling process_stt_request from audio_input_processor: {:?}", e);
-                                                // Error is already logged and status pushed by process_stt_request itself.
-                                            }
-                                        }
-                                    });
+                                for (name, feed) in &decoder_feeds {
+                                    if feed.send(f32_data.clone()).is_err() {
+                                        warn!("{} receiver thread has stopped.", name);
+                                    }
+                                }
+                                // Decoders get the raw signal; noise reduction and AGC are for VAD and STT.
+                                let f32_data = match rx_conditioner.as_mut() {
+                                    Some(conditioner) => conditioner.process(&f32_data),
+                                    None => f32_data,
+                                };
+                                // TODO: If speech detected, save segment using task_info.task_dir
+                                if let Some(feed) = &speech_audio_tx {
+                                    if feed.send(f32_data).is_err() {
+                                        warn!("Speech segmenter thread has stopped.");
+                                    }
                                 }
-                                // TODO: If segment complete, potentially trigger STT (consider task_info.is_simulation?)
                             } else {
                                 // --- No active task: Skip processing ---\
                                 trace!("No active task, skipping audio data processing (size: {}).", f32_data.len());
@@ -382,4 +923,78 @@ pub async fn audio_input_processor(
     debug!("Audio input processor task finished.");
 }
 
-// 或者，如果您已经有类似功能的函数，只需确保它是公开的并重命名为 audio_input_processor 
\ No newline at end of file
+// 或者，如果您已经有类似功能的函数，只需确保它是公开的并重命名为 audio_input_processor 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// Vowel-like sound: glottal pitch partials shaped by two formants.
+    fn vowel(seconds: f32, sample_rate: u32) -> Vec<f32> {
+        let rate = sample_rate as f32;
+        (0..(seconds * rate) as usize)
+            .map(|n| {
+                let t = n as f32 / rate;
+                let pitch = 120.0 + 20.0 * (2.0 * PI * 2.0 * t).sin();
+                (1..30)
+                    .map(|k| {
+                        let hz = k as f32 * pitch;
+                        let formants = 1.0 / (1.0 + ((hz - 700.0) / 150.0).powi(2)) + 0.5 / (1.0 + ((hz - 1200.0) / 200.0).powi(2));
+                        formants * (2.0 * PI * k as f32 * pitch * t).sin() / k as f32
+                    })
+                    .sum::<f32>()
+                    * 0.4
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_one_stt_segment_per_utterance() {
+        let mut segmenter = SpeechSegmenter::new(16000).unwrap();
+        let mut audio = vec![0.0; 16000];
+        audio.extend(vowel(1.5, 16000));
+        audio.extend(vec![0.0; 16000]);
+        // Capture delivers 20 ms chunks, which don't line up with 30 ms VAD frames.
+        let segments: Vec<Vec<u8>> = audio.chunks(320).flat_map(|chunk| segmenter.push(chunk)).collect();
+        assert_eq!(segments.len(), 1);
+        let seconds = segments[0].len() as f32 / 2.0 / 16000.0;
+        assert!((1.4..2.3).contains(&seconds), "{} s", seconds);
+    }
+
+    #[test]
+    fn test_segments_at_44100() {
+        // WebRTC VAD can't run at 44.1 kHz; the segmenter resamples to STT_SAMPLE_RATE.
+        let mut segmenter = SpeechSegmenter::new(44100).unwrap();
+        let mut audio = vec![0.0; 44100];
+        audio.extend(vowel(1.5, 44100));
+        audio.extend(vec![0.0; 44100]);
+        let segments: Vec<Vec<u8>> = audio.chunks(882).flat_map(|chunk| segmenter.push(chunk)).collect();
+        assert_eq!(segments.len(), 1);
+        let seconds = segments[0].len() as f32 / 2.0 / STT_SAMPLE_RATE as f32;
+        assert!((1.4..2.3).contains(&seconds), "{} s", seconds);
+    }
+
+    #[test]
+    fn test_segmenter_thread() {
+        let (segment_tx, mut segment_rx) = mpsc::unbounded_channel();
+        let audio_tx = spawn_speech_segmenter(48000, segment_tx).expect("segmenter thread");
+        let mut audio = vec![0.0; 48000];
+        audio.extend(vowel(1.5, 48000));
+        audio.extend(vec![0.0; 48000]);
+        for chunk in audio.chunks(960) {
+            audio_tx.send(chunk.to_vec()).unwrap();
+        }
+        // Closing the feed ends the thread once it has worked through the audio.
+        drop(audio_tx);
+        assert!(segment_rx.blocking_recv().is_some());
+        assert!(segment_rx.blocking_recv().is_none());
+    }
+
+    #[test]
+    fn test_silence_and_clicks_not_sent_to_stt() {
+        let mut segmenter = SpeechSegmenter::new(16000).unwrap();
+        let mut audio = vec![0.0; 32000];
+        audio[8000..8080].iter_mut().for_each(|x| *x = 0.9);
+        assert!(audio.chunks(320).all(|chunk| segmenter.push(chunk).is_empty()));
+    }
+}
@@ -1,6 +1,7 @@
 use thiserror::Error;
 use elfradio_types::AiError;
 use elfradio_hardware::HardwareError;
+use elfradio_dsp::DspError;
 use elfradio_types::PttSignalParseError;
 use hound::Error as HoundError;
 use serde_json::Error as SerdeJsonError;
@@ -25,6 +26,8 @@ pub enum CoreError {
     PttSignalParseError(#[from] PttSignalParseError),
     #[error("Serial port not configured for PTT")]
     PttPortNotConfigured,
+    #[error("CAT rig control is not connected")]
+    RigNotConnected,
     #[error("Audio decoding error: {0}")]
     AudioDecodeError(#[from] HoundError),
     #[error("Serialization/deserialization error: {0}")]
@@ -63,4 +66,6 @@ pub enum CoreError {
     AiNotConfigured,
     #[error("Auxiliary service not configured: {0}")]
     AuxServiceNotConfigured(String),
+    #[error("Signal processing error: {0}")]
+    DspError(#[from] DspError),
 } 
\ No newline at end of file
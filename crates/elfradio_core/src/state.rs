@@ -4,11 +4,12 @@ use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use elfradio_types::{
     Config,
-    ClientMap, AudioOutputSender, TxItem, TaskInfo, TaskStatus,
+    ClientMap, TxItem, TaskInfo, TaskStatus,
     AuxServiceClient,
     LogEntry, WebSocketMessage,
 };
 use elfradio_ai::AiClient;
+use elfradio_hardware::{AudioOutputSender, PttController, SharedRigControl};
 use sqlx::SqlitePool;
 
 /// Shared application state accessible across tasks and handlers.
@@ -22,6 +23,9 @@ pub struct AppState {
     pub is_transmitting: Arc<Mutex<bool>>,
     pub ai_client: Arc<RwLock<Option<Arc<dyn AiClient + Send + Sync>>>>,
     pub aux_client: Arc<RwLock<Option<Arc<dyn AuxServiceClient + Send + Sync>>>>,
+    pub rig_control: Arc<RwLock<Option<SharedRigControl>>>,
+    /// Persistent PTT line, opened on first transmission and kept open afterwards.
+    pub ptt_controller: Arc<RwLock<Option<Arc<PttController>>>>,
     pub active_task: Mutex<Option<TaskInfo>>,
     pub task_status: Mutex<TaskStatus>,
     pub shutdown_tx: watch::Sender<bool>,
@@ -54,6 +58,8 @@ impl AppState {
             is_transmitting,
             ai_client: Arc::new(RwLock::new(None)),
             aux_client: Arc::new(RwLock::new(None)),
+            rig_control: Arc::new(RwLock::new(None)),
+            ptt_controller: Arc::new(RwLock::new(None)),
             active_task: Mutex::new(None),
             task_status: Mutex::new(TaskStatus::Idle),
             shutdown_tx,
@@ -166,8 +166,9 @@ mod tests {
     use crate::error::CoreError;
     // Ensure all necessary types from elfradio_types are imported
     use elfradio_types::{
-        TaskMode, TaskStatus, Config, TaskInfo, AudioOutputSender, TxItem, // Add AudioMessage if needed by tests
+        TaskMode, TaskStatus, Config, TaskInfo, TxItem, // Add AudioMessage if needed by tests
     };
+    use elfradio_hardware::AudioOutputSender;
     use std::sync::Arc;
     // Ensure RwLock, OnceCell, broadcast are imported if used, mpsc, watch, Mutex definitely needed
     use tokio::sync::{mpsc, watch, Mutex, RwLock, OnceCell};
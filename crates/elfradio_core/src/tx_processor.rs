@@ -5,12 +5,19 @@ use super::state::AppState; // Use the parent\'s state module
 use elfradio_types::{
     TxItem, PttSignal, AiConfig, AiProvider,
     LogEntry, LogDirection, LogContentType,
-    TaskInfo, // ADDED AiError for mapping
+    TaskInfo, Config, SignalToneConfig, SstvTemplate, SubtoneConfig, TxFraming, // ADDED AiError for mapping
     WebSocketMessage, SystemServiceStatus, AiError, // Added for 5.7.6.1
+    TX_AUDIO_SAMPLE_RATE,
 };
 use elfradio_ai::TtsParams; // Removed AiError
-use elfradio_hardware;
+use elfradio_hardware::PttController;
+use elfradio_dsp::{
+    condition_tx_audio, encode_sstv, generate_dtmf_audio, generate_pocsag_audio, generate_psk_audio, generate_tone_sequence,
+    mix_subtone, prepare_sstv_image, resample, DspError, DtmfParams, PocsagMessage, PocsagParams, PskMode, PskParams,
+    SignalToneParams, SstvMode, Subtone, SubtoneParams, ToneSequence, TxChainParams,
+};
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::watch; // 新增导入
@@ -52,7 +59,6 @@ async fn process_tx_item(
     let db_pool = &app_state.db_pool;
 
     // --- Get Hardware/Timing Config ---
-    let serial_port = app_state.config.hardware.serial_port.as_deref();
     let ptt_signal_str = &app_state.config.hardware.ptt_signal;
     let ptt_pre_delay = app_state.config.timing.ptt_pre_delay_ms;
     let ptt_post_delay = app_state.config.timing.ptt_post_delay_ms;
@@ -60,8 +66,13 @@ async fn process_tx_item(
     let ptt_signal: PttSignal = ptt_signal_str.parse().map_err(CoreError::PttSignalParseError)?;
 
     match item {
-        TxItem::GeneratedVoice { id: _, audio_data, priority: _ } => {
-            debug!(item_id = %item_id, task_id = %task_id_str, is_simulation, "Processing GeneratedVoice item");
+        TxItem::GeneratedVoice { id: _, audio_data, priority: _, framing } => {
+            debug!(item_id = %item_id, task_id = %task_id_str, is_simulation, ?framing, "Processing GeneratedVoice item");
+            let audio_data = match framing {
+                TxFraming::Voice => conditioned_tx_audio(&app_state, audio_data).await?,
+                TxFraming::AnalogData | TxFraming::Raw => audio_data,
+            };
+            let audio_data = framed_audio(&app_state.config, audio_data, framing)?;
 
             // --- Log TX Start to file (已有的代码) ---
             let start_entry = LogEntry {
@@ -87,38 +98,35 @@ async fn process_tx_item(
 
             if !is_simulation {
                 info!(item_id=%item_id, task_id=%task_id_str, "Performing real hardware transmission.");
-                let port = serial_port.ok_or(CoreError::PttPortNotConfigured)?;
 
-                // Activate PTT
-                elfradio_hardware::set_ptt(port, ptt_signal, true, ptt_pre_delay, ptt_post_delay).await?;
+                // Activate PTT. The guard unkeys if we bail out early or panic below.
+                let ptt = ptt_controller(&app_state, ptt_signal).await?;
+                let ptt_guard = tokio::task::spawn_blocking(move || ptt.key()).await??;
                 sleep(Duration::from_millis(ptt_pre_delay)).await;
 
-                // Send audio data
-                final_send_result = if let Some(sender) = app_state.audio_output_sender.lock().await.as_ref() {
-                    let estimated_duration_secs = audio_data.len() as f32 / 16000.0; // Assuming 16kHz
-                    let estimated_duration = Duration::from_secs_f32(estimated_duration_secs);
-                    debug!(item_id = %item_id, task_id=%task_id_str, duration_ms = estimated_duration.as_millis(), "Sending audio data...");
-
-                    sender.send(audio_data).map_err(|_| CoreError::AudioChannelClosed)?;
-                    sleep(estimated_duration).await; // Sleep for estimated duration
-                    Ok(())
+                // Send audio data and wait until the device has actually played it
+                let sender = app_state.audio_output_sender.lock().await.clone();
+                final_send_result = if let Some(sender) = sender {
+                    debug!(item_id = %item_id, task_id=%task_id_str, samples = audio_data.len(), device_rate = sender.sample_rate(), "Sending audio data...");
+                    sender.play(&audio_data, TX_AUDIO_SAMPLE_RATE).await.map_err(|e| {
+                        error!(item_id = %item_id, task_id=%task_id_str, "Audio playback failed: {}", e);
+                        CoreError::AudioChannelClosed
+                    })
                 } else {
                     error!(item_id = %item_id, task_id=%task_id_str, "Audio output sender is not available.");
                     Err(CoreError::AudioChannelClosed)
                 };
 
-                // Deactivate PTT
-                final_ptt_off_result = elfradio_hardware::set_ptt(
-                    port, ptt_signal, false, ptt_pre_delay, ptt_post_delay
-                )
-                .await
-                .map_err(CoreError::from);
-
+                // Deactivate PTT after the post-delay
                 sleep(Duration::from_millis(ptt_post_delay)).await;
+                final_ptt_off_result = match tokio::task::spawn_blocking(move || ptt_guard.release()).await {
+                    Ok(result) => result.map_err(CoreError::from),
+                    Err(e) => Err(CoreError::from(e)),
+                };
             } else {
                 info!(item_id=%item_id, task_id=%task_id_str, "Simulation mode: Skipping hardware PTT and audio output.");
                 // Simulate delay for timing consistency if needed
-                let estimated_duration_secs = audio_data.len() as f32 / 16000.0;
+                let estimated_duration_secs = audio_data.len() as f32 / TX_AUDIO_SAMPLE_RATE as f32;
                 let simulated_total_delay = Duration::from_secs_f32(estimated_duration_secs)
                     + Duration::from_millis(ptt_pre_delay)
                     + Duration::from_millis(ptt_post_delay);
@@ -174,10 +182,11 @@ async fn process_tx_item(
                 return Err(CoreError::AiNotConfigured); // 使用新的专用错误类型
             };
             
-            let (audio_f32, _wav_spec): (Vec<f32>, WavSpec) = decode_wav_data(&audio_bytes)?;
-            debug!("Decoded WAV data, samples count: {}", audio_f32.len());
+            let (audio_f32, wav_spec): (Vec<f32>, WavSpec) = decode_wav_data(&audio_bytes)?;
+            debug!("Decoded WAV data, samples count: {}, spec: {:?}", audio_f32.len(), wav_spec);
+            let audio_f32 = to_tx_format(&audio_f32, &wav_spec);
 
-            let generated_voice_item = TxItem::GeneratedVoice { id, audio_data: audio_f32, priority };
+            let generated_voice_item = TxItem::GeneratedVoice { id, audio_data: audio_f32, priority, framing: TxFraming::Voice };
             info!(item_id = %id, task_id=%task_id_str, "Created GeneratedVoice item from TTS result.");
 
             if let Err(e) = app_state.tx_queue.send(generated_voice_item) {
@@ -189,6 +198,78 @@ async fn process_tx_item(
             }
         }
 
+        TxItem::SstvImage { id, path, their_call, rst, priority } => {
+            // Encoding happens regardless of simulation mode, like TTS above.
+            let mode: SstvMode = app_state.config.sstv_settings.mode.parse()?;
+            let max_duration = app_state.config.timing.max_sstv_duration_s;
+            if mode.duration_secs() > max_duration as f64 {
+                warn!(item_id = %id, task_id=%task_id_str, %mode, "SSTV mode exceeds max_sstv_duration_s ({} s).", max_duration);
+                return Err(CoreError::ConfigError(format!(
+                    "SSTV mode {} takes {:.0} s, longer than max_sstv_duration_s ({} s)",
+                    mode,
+                    mode.duration_secs(),
+                    max_duration
+                )));
+            }
+            let template = match &app_state.config.sstv_settings.template {
+                Some(name) => elfradio_config::load_sstv_template(name).map_err(|e| {
+                    error!(item_id = %id, task_id=%task_id_str, "Failed to load SSTV template '{}': {}", name, e);
+                    CoreError::ConfigError(format!("Failed to load SSTV template '{}': {}", name, e))
+                })?,
+                None => SstvTemplate::default(),
+            };
+            let now = Utc::now();
+            let mut fields = HashMap::from([
+                ("mycall".to_string(), app_state.config.radio_etiquette.nickname.clone()),
+                ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+                ("time".to_string(), now.format("%H:%MZ").to_string()),
+                ("mode".to_string(), mode.name().to_string()),
+                ("task".to_string(), task_info.name.clone()),
+            ]);
+            fields.extend(their_call.map(|call| ("theircall".to_string(), call)));
+            fields.extend(rst.map(|rst| ("rst".to_string(), rst)));
+            info!(item_id = %id, task_id=%task_id_str, %mode, ?path, "Encoding SSTV picture.");
+
+            let audio_data = tokio::task::spawn_blocking(move || -> Result<Vec<f32>, DspError> {
+                let image = prepare_sstv_image(&image::open(&path)?, mode, &template, &fields);
+                encode_sstv(&image, mode, TX_AUDIO_SAMPLE_RATE)
+            })
+            .await??;
+
+            requeue_generated(&app_state, id, audio_data, priority, TxFraming::Raw, "SSTV")?;
+        }
+
+        TxItem::PskText { id, text, mode, priority } => {
+            let mode: PskMode = mode.parse()?;
+            let params = PskParams::new(mode, TX_AUDIO_SAMPLE_RATE);
+            info!(item_id = %id, task_id=%task_id_str, %mode, "Encoding PSK text: '{}'", text);
+            let audio_data = tokio::task::spawn_blocking(move || generate_psk_audio(&text, &params)).await??;
+
+            requeue_generated(&app_state, id, audio_data, priority, TxFraming::Raw, "PSK")?;
+        }
+
+        TxItem::PocsagPage { id, capcode, message, numeric, baud, priority } => {
+            let page = match (message.is_empty(), numeric) {
+                (true, _) => PocsagMessage::tone(capcode, 0),
+                (false, true) => PocsagMessage::numeric(capcode, &message),
+                (false, false) => PocsagMessage::alphanumeric(capcode, &message),
+            };
+            let params = PocsagParams::new(baud, TX_AUDIO_SAMPLE_RATE);
+            info!(item_id = %id, task_id=%task_id_str, capcode, baud, "Encoding POCSAG page: '{}'", message);
+            let audio_data = tokio::task::spawn_blocking(move || generate_pocsag_audio(&[page], &params)).await??;
+
+            requeue_generated(&app_state, id, audio_data, priority, TxFraming::Raw, "POCSAG")?;
+        }
+
+        TxItem::DtmfDigits { id, digits, priority } => {
+            let dtmf = &app_state.config.dtmf;
+            let params = DtmfParams::new(TX_AUDIO_SAMPLE_RATE).with_timing(dtmf.mark_ms, dtmf.space_ms);
+            info!(item_id = %id, task_id=%task_id_str, "Encoding DTMF digits: '{}'", digits);
+            let audio_data = tokio::task::spawn_blocking(move || generate_dtmf_audio(&digits, &params)).await??;
+
+            requeue_generated(&app_state, id, audio_data, priority, TxFraming::AnalogData, "DTMF")?;
+        }
+
         TxItem::ManualVoice { id, path, priority: _ } => {
              warn!(item_id = %id, task_id=%task_id_str, ?path, "Processing ManualVoice item - Not implemented yet.");
              // TODO: Implement logic (consider simulation flag here too if needed)
@@ -198,6 +279,153 @@ async fn process_tx_item(
     Ok(())
 }
 
+/// Queues encoder output for transmission as `GeneratedVoice`, framed as `framing`.
+/// `label` names the mode in the logs.
+fn requeue_generated(
+    app_state: &AppState,
+    id: Uuid,
+    audio_data: Vec<f32>,
+    priority: u8,
+    framing: TxFraming,
+    label: &str,
+) -> TxProcessingOutcome<()> {
+    let generated_voice_item = TxItem::GeneratedVoice { id, audio_data, priority, framing };
+    if let Err(e) = app_state.tx_queue.send(generated_voice_item) {
+        let failed_item_id = e.0.id();
+        error!(item_id = %failed_item_id, "Failed to re-queue {} audio: {}", label, e);
+        return Err(CoreError::TxQueueSendError(format!("Failed to send item {} to tx queue", failed_item_id)));
+    }
+    info!(item_id = %id, ?framing, "Queued {} audio as GeneratedVoice.", label);
+    Ok(())
+}
+
+/// Runs voice through the `tx_audio` chain, so every TTS provider reaches the radio at
+/// the same level without over-deviating.
+async fn conditioned_tx_audio(app_state: &AppState, audio_data: Vec<f32>) -> TxProcessingOutcome<Vec<f32>> {
+    let config = &app_state.config.tx_audio;
+    if !config.enabled {
+        return Ok(audio_data);
+    }
+    let params = TxChainParams::new(TX_AUDIO_SAMPLE_RATE)
+        .with_pre_emphasis(config.pre_emphasis)
+        .with_compressor(config.compressor_threshold_dbfs, config.compressor_ratio)
+        .with_levels(config.target_dbfs, config.limit_dbfs);
+    let params = if config.bandpass { params } else { params.with_bandpass(None) };
+    Ok(tokio::task::spawn_blocking(move || condition_tx_audio(&audio_data, &params)).await??)
+}
+
+/// Silence (milliseconds) between signal tones and the audio they frame.
+const SIGNAL_TONE_PAUSE_MS: u64 = 100;
+
+/// Adds what `framing` calls for: signal tones around voice and analog data, and the
+/// subtone under them. Raw digital-mode audio goes out exactly as its modulator made it.
+fn framed_audio(config: &Config, audio_data: Vec<f32>, framing: TxFraming) -> TxProcessingOutcome<Vec<f32>> {
+    if framing == TxFraming::Raw {
+        return Ok(audio_data);
+    }
+    let audio_data = with_signal_tones(&config.signal_tone, audio_data, framing)?;
+    with_tx_subtone(&config.subtone, audio_data)
+}
+
+/// Frames outgoing audio with the configured signal tones: the 1750 Hz burst and the
+/// start sequence before it, the end sequence or Roger beep after it. Analog data only
+/// gets the burst that opens the repeater; melodies and the beep belong to voice overs.
+fn with_signal_tones(config: &SignalToneConfig, audio_data: Vec<f32>, framing: TxFraming) -> TxProcessingOutcome<Vec<f32>> {
+    if !config.enabled {
+        return Ok(audio_data);
+    }
+    let voice = framing == TxFraming::Voice;
+    let params = SignalToneParams::new(TX_AUDIO_SAMPLE_RATE);
+    let pause = vec![0.0; (SIGNAL_TONE_PAUSE_MS * TX_AUDIO_SAMPLE_RATE as u64 / 1000) as usize];
+    let start = [
+        (config.tone_burst_ms > 0).then(|| ToneSequence::tone_burst(config.tone_burst_ms)),
+        (voice && !config.start_freqs_hz.is_empty()).then(|| ToneSequence::new(config.start_freqs_hz.clone(), config.duration_ms)),
+    ];
+    let end = if !voice {
+        None
+    } else if config.roger_beep {
+        Some(ToneSequence::roger_beep())
+    } else {
+        (!config.end_freqs_hz.is_empty()).then(|| ToneSequence::new(config.end_freqs_hz.clone(), config.duration_ms))
+    };
+
+    let mut framed = Vec::new();
+    for sequence in start.iter().flatten() {
+        framed.extend(generate_tone_sequence(sequence, &params)?);
+        framed.extend(&pause);
+    }
+    framed.extend(audio_data);
+    if let Some(sequence) = end {
+        framed.extend(&pause);
+        framed.extend(generate_tone_sequence(&sequence, &params)?);
+    }
+    Ok(framed)
+}
+
+/// Mixes the configured CTCSS tone or DCS code under outgoing audio, for radios fed
+/// flat audio that can't add it themselves.
+fn with_tx_subtone(config: &SubtoneConfig, audio_data: Vec<f32>) -> TxProcessingOutcome<Vec<f32>> {
+    let Some(tx) = config.tx.as_deref().filter(|tx| !tx.trim().is_empty()) else {
+        return Ok(audio_data);
+    };
+    let subtone: Subtone = tx.parse()?;
+    let params = SubtoneParams::new(subtone, TX_AUDIO_SAMPLE_RATE).with_level(config.level);
+    Ok(mix_subtone(&audio_data, &params)?)
+}
+
+/// Returns the persistent PTT controller, opening it on first use.
+///
+/// The controller keeps the serial port (or CAT connection) open between
+/// transmissions and unkeys on its own once `timing.max_tx_duration_s` expires.
+/// A failed open is not cached, so the next transmission retries.
+async fn ptt_controller(app_state: &AppState, ptt_signal: PttSignal) -> TxProcessingOutcome<Arc<PttController>> {
+    if let Some(ptt) = app_state.ptt_controller.read().await.clone() {
+        return Ok(ptt);
+    }
+
+    let mut slot = app_state.ptt_controller.write().await;
+    if let Some(ptt) = slot.clone() {
+        return Ok(ptt);
+    }
+
+    let rig = app_state.rig_control.read().await.clone();
+    match ptt_signal {
+        PttSignal::Cat if rig.is_none() => return Err(CoreError::RigNotConnected),
+        PttSignal::Rts | PttSignal::Dtr if app_state.config.hardware.serial_port.is_none() => {
+            return Err(CoreError::PttPortNotConfigured)
+        }
+        _ => {}
+    }
+
+    let hardware_config = app_state.config.hardware.clone();
+    let watchdog = Duration::from_secs(app_state.config.timing.max_tx_duration_s);
+    let controller = tokio::task::spawn_blocking(move || {
+        PttController::from_config(&hardware_config, rig, Some(watchdog))
+    })
+    .await??;
+
+    info!(line = controller.description(), "Opened persistent PTT controller.");
+    let controller = Arc::new(controller);
+    *slot = Some(controller.clone());
+    Ok(controller)
+}
+
+/// Converts decoded WAV samples to what the TX path works in: mono at
+/// `TX_AUDIO_SAMPLE_RATE`. Channels are averaged, then the result is resampled.
+fn to_tx_format(samples: &[f32], spec: &WavSpec) -> Vec<f32> {
+    let channels = spec.channels.max(1) as usize;
+    let mono: Vec<f32> = if channels == 1 {
+        samples.to_vec()
+    } else {
+        samples.chunks_exact(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32).collect()
+    };
+    if spec.sample_rate == TX_AUDIO_SAMPLE_RATE {
+        return mono;
+    }
+    debug!("Resampling TX audio from {} Hz to {} Hz", spec.sample_rate, TX_AUDIO_SAMPLE_RATE);
+    resample(&mono, spec.sample_rate, TX_AUDIO_SAMPLE_RATE)
+}
+
 /// Decodes WAV audio data (bytes) into a vector of f32 samples.
 pub fn decode_wav_data(wav_data: &[u8]) -> TxProcessingOutcome<(Vec<f32>, WavSpec)> {
     // 首先记录尝试解码的音频数据长度
@@ -950,6 +1178,7 @@ pub async fn queue_text_for_transmission(
              id: Uuid::new_v4(),
              audio_data: audio_f32,
             priority: 5, // Default priority for /api/send_text items
+            framing: TxFraming::Voice,
         };
         debug!(task_id = %task_id, "Created TxItem: {:?}", tx_item);
 
@@ -995,4 +1224,84 @@ async fn save_wav_file(path: &Path, samples: &[f32], sample_rate: u32) -> Result
     writer.finalize().map_err(|e| CoreError::AudioError(e.to_string()))?;
     debug!("Successfully saved WAV file: {:?}", path);
     Ok(())
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elfradio_dsp::decode_subtone;
+
+    /// A config that frames every over with a tone burst, start tones and a Roger beep,
+    /// with a DCS code under it.
+    fn framing_config() -> Config {
+        let mut config = Config::default();
+        config.subtone.tx = Some("D023N".to_string());
+        config.signal_tone.enabled = true;
+        config.signal_tone.start_freqs_hz = vec![1000.0];
+        config.signal_tone.roger_beep = true;
+        config.signal_tone.tone_burst_ms = 400;
+        config
+    }
+
+    #[test]
+    fn test_voice_is_framed() {
+        let voice = vec![0.25; TX_AUDIO_SAMPLE_RATE as usize];
+        let framed = framed_audio(&framing_config(), voice.clone(), TxFraming::Voice).unwrap();
+        // Tones around the voice, plus the DCS turn-off code after it.
+        assert!(framed.len() > voice.len());
+        // The subtone is mixed in, so the voice doesn't come through untouched.
+        assert!(!framed.windows(voice.len()).any(|window| window == voice.as_slice()));
+    }
+
+    #[test]
+    fn test_tts_audio_converted_to_tx_format() {
+        // One second of 24 kHz stereo: a 500 Hz tone on the left, silence on the right.
+        let spec = WavSpec { channels: 2, sample_rate: 24000, bits_per_sample: 16, sample_format: SampleFormat::Int };
+        let stereo: Vec<f32> = (0..24000)
+            .flat_map(|n| [(2.0 * std::f32::consts::PI * 500.0 * n as f32 / 24000.0).sin(), 0.0])
+            .collect();
+        let mono = to_tx_format(&stereo, &spec);
+        assert!((mono.len() as i64 - TX_AUDIO_SAMPLE_RATE as i64).abs() <= 2, "{} samples", mono.len());
+        // Same pitch at the new rate: 500 upward zero crossings in one second.
+        let crossings = mono.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        assert!((crossings as i64 - 500).abs() <= 2, "{} crossings", crossings);
+        let peak = mono.iter().fold(0.0f32, |acc, x| acc.max(x.abs()));
+        assert!((peak - 0.5).abs() < 0.05, "peak {}", peak);
+
+        let spec = WavSpec { channels: 1, sample_rate: TX_AUDIO_SAMPLE_RATE, ..spec };
+        assert_eq!(to_tx_format(&[0.1, 0.2], &spec), vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_requeued_data_is_sent_unframed() {
+        // What the SSTV, PSK and POCSAG arms requeue. Neither tones nor the subtone may
+        // touch it.
+        let data: Vec<f32> = (0..TX_AUDIO_SAMPLE_RATE).map(|n| if (n / 13) % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        assert_eq!(framed_audio(&framing_config(), data.clone(), TxFraming::Raw).unwrap(), data);
+    }
+
+    #[test]
+    fn test_dtmf_carries_subtone() {
+        // A CTCSS/DCS-gated repeater ignores DTMF commands without the subtone under them.
+        let params = DtmfParams::new(TX_AUDIO_SAMPLE_RATE);
+        let dtmf = generate_dtmf_audio("*1234567890#", &params).unwrap();
+        let framed = framed_audio(&framing_config(), dtmf, TxFraming::AnalogData).unwrap();
+        let expected: Subtone = "D023N".parse().unwrap();
+        let found = decode_subtone(&framed, TX_AUDIO_SAMPLE_RATE).unwrap();
+        assert!(found.is_some_and(|found| found.is_equivalent(&expected)), "found {:?}", found);
+    }
+
+    #[test]
+    fn test_dtmf_gets_tone_burst_only() {
+        // The 1750 Hz burst opens the repeater for the command; no start tones or Roger beep.
+        let config = framing_config().signal_tone;
+        let dtmf = generate_dtmf_audio("*12#", &DtmfParams::new(TX_AUDIO_SAMPLE_RATE)).unwrap();
+        let framed = with_signal_tones(&config, dtmf.clone(), TxFraming::AnalogData).unwrap();
+        let ms = |ms: u64| (ms * TX_AUDIO_SAMPLE_RATE as u64 / 1000) as usize;
+        assert_eq!(framed.len(), ms(config.tone_burst_ms) + ms(SIGNAL_TONE_PAUSE_MS) + dtmf.len());
+        assert!(framed.ends_with(&dtmf));
+        let burst = &framed[..ms(config.tone_burst_ms)];
+        let crossings = burst.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        assert!((crossings as i64 - 700).abs() <= 2, "{} crossings in the 400 ms burst", crossings);
+    }
+}
@@ -0,0 +1,326 @@
+// Bell 202 AFSK at 1200 baud: mark 1200 Hz, space 2200 Hz.
+//
+// Transmit: HDLC flags, frame bytes LSB first with a 0 stuffed after five 1s, flags →
+// NRZI (a 0 changes tone, a 1 keeps it) → phase-continuous tones.
+// Receive: mark and space filters (one bit long) → tone decision → PLL bit clock → NRZI →
+// HDLC deframer (flags, unstuffing, aborts) → FCS check → frame.
+
+use super::{Ax25Frame, MAX_FRAME_LEN, MIN_FRAME_LEN};
+use crate::error::DspError;
+use crate::rtty::ToneFilter;
+use std::f64::consts::PI;
+use tracing::{debug, info, trace};
+
+const BAUD: f64 = 1200.0;
+const MARK_HZ: f32 = 1200.0;
+const SPACE_HZ: f32 = 2200.0;
+const FLAG: u8 = 0x7E;
+/// Fraction of the timing error the bit clock corrects at each tone change.
+const PLL_GAIN: f64 = 0.3;
+
+/// AFSK1200 modem settings shared by the modulator and demodulator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AfskParams {
+    pub sample_rate: u32,
+    /// Peak amplitude of the generated audio (0.0 to 1.0).
+    pub amplitude: f32,
+    /// Flags sent before the frame (TXDELAY) so the receiver can lock on.
+    pub preamble_flags: usize,
+    /// Flags sent after the frame.
+    pub postamble_flags: usize,
+}
+
+impl Default for AfskParams {
+    /// About 250 ms of preamble, enough for most FM transceivers to key up.
+    fn default() -> Self {
+        Self { sample_rate: 48000, amplitude: 0.85, preamble_flags: 38, postamble_flags: 3 }
+    }
+}
+
+impl AfskParams {
+    /// Default settings at `sample_rate`.
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, ..Self::default() }
+    }
+
+    fn bit_samples(&self) -> f64 {
+        self.sample_rate as f64 / BAUD
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidAfskParameter(msg));
+        // The space tone and its keying sidebands need to stay below Nyquist.
+        if self.sample_rate < 6000 {
+            return invalid(format!("sample rate {} Hz is too low for Bell 202", self.sample_rate));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return invalid(format!("amplitude {} is outside 0..1", self.amplitude));
+        }
+        if self.preamble_flags == 0 || self.postamble_flags == 0 {
+            return invalid("at least one flag is needed on each side of the frame".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// HDLC bit stream for a frame: flags, bit-stuffed frame bytes (LSB first), flags.
+fn hdlc_bits(frame: &[u8], params: &AfskParams) -> Vec<bool> {
+    let flag_bits = |count: usize| (0..count * 8).map(|n| FLAG >> (n % 8) & 1 == 1);
+    let mut bits: Vec<bool> = flag_bits(params.preamble_flags).collect();
+    let mut ones = 0;
+    for &byte in frame {
+        for n in 0..8 {
+            let bit = byte >> n & 1 == 1;
+            bits.push(bit);
+            ones = if bit { ones + 1 } else { 0 };
+            if ones == 5 {
+                bits.push(false);
+                ones = 0;
+            }
+        }
+    }
+    bits.extend(flag_bits(params.postamble_flags));
+    bits
+}
+
+/// Generates Bell 202 AFSK audio for one frame.
+pub fn generate_afsk_audio(frame: &Ax25Frame, params: &AfskParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    frame.validate()?;
+    debug!("Generating AFSK1200 audio: {:?}, Frame='{}'", params, frame);
+
+    let bits = hdlc_bits(&frame.to_bytes(), params);
+    let step = |hz: f32| 2.0 * PI * hz as f64 / params.sample_rate as f64;
+    let bit_samples = params.bit_samples();
+    let mut audio = Vec::with_capacity((bits.len() as f64 * bit_samples).ceil() as usize);
+    let mut phase = 0.0f64;
+    let mut mark = true;
+    for (n, bit) in bits.into_iter().enumerate() {
+        if !bit {
+            mark = !mark;
+        }
+        let tone_step = if mark { step(MARK_HZ) } else { step(SPACE_HZ) };
+        let end = ((n + 1) as f64 * bit_samples).round() as usize;
+        while audio.len() < end {
+            audio.push(params.amplitude * phase.sin() as f32);
+            phase = (phase + tone_step) % (2.0 * PI);
+        }
+    }
+
+    info!(
+        "Generated AFSK1200 audio with {} samples (approx {:.2} seconds)",
+        audio.len(),
+        audio.len() as f64 / params.sample_rate as f64
+    );
+    Ok(audio)
+}
+
+/// Finds flag-delimited frames in the NRZI-decoded bit stream.
+#[derive(Default)]
+struct Deframer {
+    /// Last eight bits, newest in bit 7 (bytes arrive LSB first).
+    shift: u8,
+    ones: u32,
+    bits: Vec<bool>,
+    in_frame: bool,
+}
+
+impl Deframer {
+    /// Feeds one bit; returns the bytes between two flags when a frame ends.
+    fn push(&mut self, bit: bool) -> Option<Vec<u8>> {
+        self.shift = self.shift >> 1 | (bit as u8) << 7;
+        if self.shift == FLAG {
+            let frame = if self.in_frame { self.take_frame() } else { None };
+            self.in_frame = true;
+            self.bits.clear();
+            self.ones = 0;
+            return frame;
+        }
+        if !self.in_frame {
+            return None;
+        }
+        if bit {
+            self.ones += 1;
+            if self.ones >= 7 {
+                // Abort, or the idle carrier after a frame.
+                self.in_frame = false;
+                return None;
+            }
+        } else {
+            let stuffed = self.ones == 5;
+            self.ones = 0;
+            if stuffed {
+                return None;
+            }
+        }
+        self.bits.push(bit);
+        if self.bits.len() > (MAX_FRAME_LEN + 1) * 8 {
+            self.in_frame = false;
+        }
+        None
+    }
+
+    /// The bytes received before the closing flag, whose first seven bits were stored.
+    fn take_frame(&mut self) -> Option<Vec<u8>> {
+        let len = self.bits.len().checked_sub(7)?;
+        if len % 8 != 0 || len / 8 < MIN_FRAME_LEN {
+            return None;
+        }
+        let bytes = self.bits[..len]
+            .chunks(8)
+            .map(|byte| byte.iter().rev().fold(0u8, |acc, &bit| acc << 1 | bit as u8))
+            .collect();
+        Some(bytes)
+    }
+}
+
+/// Streaming AFSK1200 packet decoder.
+pub struct AfskDecoder {
+    mark: ToneFilter,
+    space: ToneFilter,
+    /// Bit clock advance per sample.
+    clock_step: f64,
+    /// Position within the current bit; the bit is sampled when this wraps.
+    clock: f64,
+    tone: bool,
+    /// Tone at the previous bit sample, for NRZI.
+    last_bit_tone: bool,
+    deframer: Deframer,
+    frames: Vec<Ax25Frame>,
+}
+
+impl AfskDecoder {
+    /// Creates a decoder for audio at `params.sample_rate`.
+    pub fn new(params: &AfskParams) -> Result<Self, DspError> {
+        params.validate()?;
+        let len = params.bit_samples().round() as usize;
+        Ok(Self {
+            mark: ToneFilter::new(MARK_HZ, params.sample_rate, len),
+            space: ToneFilter::new(SPACE_HZ, params.sample_rate, len),
+            clock_step: 1.0 / params.bit_samples(),
+            clock: 0.0,
+            tone: true,
+            last_bit_tone: true,
+            deframer: Deframer::default(),
+            frames: Vec::new(),
+        })
+    }
+
+    /// Feeds audio and returns any frames that passed the FCS check.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Ax25Frame> {
+        for &sample in samples {
+            self.process_sample(sample);
+        }
+        std::mem::take(&mut self.frames)
+    }
+
+    fn process_sample(&mut self, sample: f32) {
+        let tone = self.mark.process(sample) > self.space.process(sample);
+        if tone != self.tone {
+            // Tone changes belong half way between bit samples.
+            self.tone = tone;
+            self.clock += (0.5 - self.clock) * PLL_GAIN;
+        }
+        self.clock += self.clock_step;
+        if self.clock < 1.0 {
+            return;
+        }
+        self.clock -= 1.0;
+        let bit = tone == self.last_bit_tone;
+        self.last_bit_tone = tone;
+        let Some(bytes) = self.deframer.push(bit) else {
+            return;
+        };
+        match Ax25Frame::from_bytes(&bytes) {
+            Ok(frame) => {
+                debug!("Received AX.25 frame: {}", frame);
+                self.frames.push(frame);
+            }
+            Err(e) => trace!("Discarding {} byte AFSK frame: {}", bytes.len(), e),
+        }
+    }
+}
+
+/// Decodes every frame in a complete AFSK1200 recording.
+pub fn decode_afsk(samples: &[f32], params: &AfskParams) -> Result<Vec<Ax25Frame>, DspError> {
+    let mut decoder = AfskDecoder::new(params)?;
+    let frames = decoder.push(samples);
+    debug!("Decoded {} AX.25 frames", frames.len());
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::add_noise;
+    use assert_matches::assert_matches;
+
+    fn frame(monitor: &str) -> Ax25Frame {
+        monitor.parse().unwrap()
+    }
+
+    #[test]
+    fn test_bit_stuffing() {
+        let params = AfskParams { preamble_flags: 1, postamble_flags: 1, ..AfskParams::new(8000) };
+        let bits = hdlc_bits(&[0xFF, 0x00], &params);
+        let expected: Vec<bool> = [0, 1, 1, 1, 1, 1, 1, 0]
+            .into_iter()
+            .chain([1, 1, 1, 1, 1, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0])
+            .chain([0, 1, 1, 1, 1, 1, 1, 0])
+            .map(|b| b == 1)
+            .collect();
+        assert_eq!(bits, expected);
+
+        let mut deframer = Deframer::default();
+        let frames: Vec<Vec<u8>> = hdlc_bits(&[0xFF; MIN_FRAME_LEN], &params)
+            .into_iter()
+            .filter_map(|bit| deframer.push(bit))
+            .collect();
+        assert_eq!(frames, vec![vec![0xFF; MIN_FRAME_LEN]]);
+    }
+
+    #[test]
+    fn test_loopback() {
+        let frame = frame("VK7KSM-9>APRS,WIDE1-1,WIDE2-1:!4252.50S/14718.75E>Mobile ~~~~~ 73");
+        for rate in [8000, 11025, 22050, 44100, 48000] {
+            let params = AfskParams::new(rate);
+            let audio = generate_afsk_audio(&frame, &params).unwrap();
+            assert!(audio.iter().all(|s| s.abs() <= 0.85));
+            assert_eq!(decode_afsk(&audio, &params).unwrap(), vec![frame.clone()], "{} Hz", rate);
+        }
+    }
+
+    #[test]
+    fn test_loopback_with_noise_and_streaming() {
+        let params = AfskParams::new(22050);
+        let frames = [frame("VK7KSM>APRS:>On the air"), frame("VK7ABC-7>APRS,WIDE2-2::VK7KSM   :Hello{42")];
+        let mut audio = vec![0.0; 3000];
+        for frame in &frames {
+            audio.extend(generate_afsk_audio(frame, &params).unwrap());
+            audio.extend(vec![0.0; 2345]);
+        }
+        add_noise(&mut audio, 0.25, 3);
+        let mut decoder = AfskDecoder::new(&params).unwrap();
+        let received: Vec<Ax25Frame> = audio.chunks(441).flat_map(|chunk| decoder.push(chunk)).collect();
+        assert_eq!(received, frames);
+    }
+
+    #[test]
+    fn test_noise_decodes_nothing() {
+        let params = AfskParams::new(8000);
+        let mut noise = vec![0.0; 8000 * 5];
+        add_noise(&mut noise, 0.3, 9);
+        assert!(decode_afsk(&noise, &params).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let frame = frame("VK7KSM>APRS:>x");
+        assert_matches!(generate_afsk_audio(&frame, &AfskParams::new(4000)), Err(DspError::InvalidAfskParameter(_)));
+        assert_matches!(
+            generate_afsk_audio(&frame, &AfskParams { preamble_flags: 0, ..AfskParams::new(8000) }),
+            Err(DspError::InvalidAfskParameter(_))
+        );
+        assert_matches!(AfskDecoder::new(&AfskParams::new(0)).err(), Some(DspError::InvalidAfskParameter(_)));
+    }
+}
@@ -0,0 +1,249 @@
+// APRS information fields (APRS 1.0.1): position reports (plain and compressed, with or
+// without a timestamp), messages and status reports.
+
+use crate::error::DspError;
+use std::fmt;
+
+/// A position report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AprsPosition {
+    /// Degrees, north positive.
+    pub latitude: f64,
+    /// Degrees, east positive.
+    pub longitude: f64,
+    /// '/' for the primary table, '\\' for the alternate one, or an overlay character.
+    pub symbol_table: char,
+    pub symbol_code: char,
+    /// Timestamp as sent ("DDHHMMz", "DDHHMM/" or "HHMMSSh").
+    pub timestamp: Option<String>,
+    /// The station can receive APRS messages.
+    pub messaging: bool,
+    pub comment: String,
+}
+
+/// A message to a station, bulletin or group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AprsMessage {
+    pub addressee: String,
+    pub text: String,
+    /// Message number the sender wants acknowledged.
+    pub id: Option<String>,
+}
+
+/// A parsed APRS information field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AprsPacket {
+    Position(AprsPosition),
+    Message(AprsMessage),
+    Status { timestamp: Option<String>, text: String },
+    /// A report type this parser doesn't handle, by its data type identifier.
+    Other { data_type: char, data: String },
+}
+
+impl fmt::Display for AprsPacket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AprsPacket::Position(position) => {
+                write!(f, "Position {:.5}, {:.5}", position.latitude, position.longitude)?;
+                if !position.comment.is_empty() {
+                    write!(f, " ({})", position.comment)?;
+                }
+                Ok(())
+            }
+            AprsPacket::Message(message) => {
+                write!(f, "Message to {}: {}", message.addressee, message.text)?;
+                if let Some(id) = &message.id {
+                    write!(f, " [#{}]", id)?;
+                }
+                Ok(())
+            }
+            AprsPacket::Status { text, .. } => write!(f, "Status: {}", text),
+            AprsPacket::Other { data_type, .. } => write!(f, "Unsupported APRS data type '{}'", data_type),
+        }
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> DspError {
+    DspError::InvalidAprsPacket(msg.into())
+}
+
+/// Parses the information field of an APRS frame.
+pub fn parse_aprs(info: &[u8]) -> Result<AprsPacket, DspError> {
+    let text = String::from_utf8_lossy(info);
+    let text = text.trim_end_matches(['\r', '\n']);
+    let mut chars = text.chars();
+    let data_type = chars.next().ok_or_else(|| invalid("empty information field"))?;
+    let body = chars.as_str();
+    match data_type {
+        '!' | '=' => parse_position(body, None, data_type == '='),
+        '/' | '@' => {
+            let (timestamp, rest) = split_timestamp(body).ok_or_else(|| invalid("missing position timestamp"))?;
+            parse_position(rest, Some(timestamp), data_type == '@')
+        }
+        ':' => parse_message(body),
+        '>' => {
+            let (timestamp, text) = match split_timestamp(body) {
+                Some((timestamp, rest)) if timestamp.ends_with('z') => (Some(timestamp), rest),
+                _ => (None, body),
+            };
+            Ok(AprsPacket::Status { timestamp, text: text.to_string() })
+        }
+        _ => Ok(AprsPacket::Other { data_type, data: body.to_string() }),
+    }
+}
+
+/// Splits off a seven character timestamp: six digits and 'z', '/' or 'h'.
+fn split_timestamp(body: &str) -> Option<(String, &str)> {
+    let timestamp = body.get(..7)?;
+    let (digits, kind) = timestamp.split_at(6);
+    (digits.bytes().all(|b| b.is_ascii_digit()) && matches!(kind, "z" | "/" | "h"))
+        .then(|| (timestamp.to_string(), &body[7..]))
+}
+
+fn parse_position(body: &str, timestamp: Option<String>, messaging: bool) -> Result<AprsPacket, DspError> {
+    let first = body.chars().next().ok_or_else(|| invalid("missing position"))?;
+    let (latitude, longitude, symbol_table, symbol_code, comment) = if first.is_ascii_digit() || first == ' ' {
+        parse_uncompressed(body)?
+    } else {
+        parse_compressed(body)?
+    };
+    Ok(AprsPacket::Position(AprsPosition {
+        latitude,
+        longitude,
+        symbol_table,
+        symbol_code,
+        timestamp,
+        messaging,
+        comment: comment.trim().to_string(),
+    }))
+}
+
+/// "DDMM.hhN" + table + "DDDMM.hhE" + symbol + comment. Spaces left for position
+/// ambiguity count as zeros.
+fn parse_uncompressed(body: &str) -> Result<(f64, f64, char, char, &str), DspError> {
+    let field = body.get(..19).ok_or_else(|| invalid("position is too short"))?;
+    let chars: Vec<char> = field.chars().collect();
+    let angle = |digits: &[char], degree_digits: usize, max: f64, hemispheres: [char; 2], hemisphere: char| {
+        let digits: String = digits.iter().map(|&c| if c == ' ' { '0' } else { c }).collect();
+        let degrees: f64 = digits[..degree_digits].parse().map_err(|_| invalid(format!("bad position '{}'", field)))?;
+        let minutes: f64 = digits[degree_digits..].parse().map_err(|_| invalid(format!("bad position '{}'", field)))?;
+        let value = degrees + minutes / 60.0;
+        if !(minutes < 60.0 && value <= max) {
+            return Err(invalid(format!("position '{}' is out of range", field)));
+        }
+        match hemisphere {
+            h if h == hemispheres[0] => Ok(value),
+            h if h == hemispheres[1] => Ok(-value),
+            _ => Err(invalid(format!("bad hemisphere '{}'", hemisphere))),
+        }
+    };
+    let latitude = angle(&chars[0..7], 2, 90.0, ['N', 'S'], chars[7])?;
+    let longitude = angle(&chars[9..17], 3, 180.0, ['E', 'W'], chars[17])?;
+    Ok((latitude, longitude, chars[8], chars[18], &body[19..]))
+}
+
+/// Table + 4 base-91 latitude + 4 base-91 longitude + symbol + course/speed + type.
+fn parse_compressed(body: &str) -> Result<(f64, f64, char, char, &str), DspError> {
+    let field = body.get(..13).ok_or_else(|| invalid("compressed position is too short"))?;
+    let bytes = field.as_bytes();
+    let symbol_table = bytes[0] as char;
+    if !matches!(symbol_table, '/' | '\\' | 'A'..='Z' | 'a'..='j') {
+        return Err(invalid(format!("bad symbol table '{}'", symbol_table)));
+    }
+    let base91 = |digits: &[u8]| {
+        digits.iter().try_fold(0u32, |acc, &b| match b {
+            33..=123 => Ok(acc * 91 + (b - 33) as u32),
+            _ => Err(invalid(format!("bad compressed position '{}'", field))),
+        })
+    };
+    let latitude = 90.0 - base91(&bytes[1..5])? as f64 / 380926.0;
+    let longitude = -180.0 + base91(&bytes[5..9])? as f64 / 190463.0;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(invalid(format!("compressed position '{}' is out of range", field)));
+    }
+    Ok((latitude, longitude, symbol_table, bytes[9] as char, &body[13..]))
+}
+
+/// ":ADDRESSEE:text{id", with the addressee padded to nine characters.
+fn parse_message(body: &str) -> Result<AprsPacket, DspError> {
+    let (addressee, text) = match (body.get(..9), body.get(9..)) {
+        (Some(addressee), Some(rest)) if rest.starts_with(':') => (addressee.trim(), &rest[1..]),
+        _ => return Err(invalid(format!("bad message header ':{}'", body))),
+    };
+    if addressee.is_empty() {
+        return Err(invalid("message has no addressee"));
+    }
+    let (text, id) = match text.rsplit_once('{') {
+        Some((text, id)) if !id.is_empty() && id.len() <= 5 => (text, Some(id.to_string())),
+        _ => (text, None),
+    };
+    Ok(AprsPacket::Message(AprsMessage { addressee: addressee.to_string(), text: text.to_string(), id }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    fn position(info: &str) -> AprsPosition {
+        match parse_aprs(info.as_bytes()).unwrap() {
+            AprsPacket::Position(position) => position,
+            other => panic!("expected a position, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_uncompressed_position() {
+        let report = position("!4903.50N/07201.75W-Test 001234\r");
+        assert!((report.latitude - 49.058333).abs() < 1e-5);
+        assert!((report.longitude + 72.029167).abs() < 1e-5);
+        assert_eq!((report.symbol_table, report.symbol_code), ('/', '-'));
+        assert_eq!(report.comment, "Test 001234");
+        assert!(!report.messaging && report.timestamp.is_none());
+
+        let report = position("@092345z4252.50S\\14718.75E>");
+        assert_eq!(report.timestamp.as_deref(), Some("092345z"));
+        assert!(report.messaging);
+        assert!((report.latitude + 42.875).abs() < 1e-5 && (report.longitude - 147.3125).abs() < 1e-5);
+
+        // Ambiguity: the last two minute digits left blank.
+        let report = position("=4903.  N/07201.  W-");
+        assert!((report.latitude - 49.05).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_compressed_position() {
+        // Example from the APRS 1.0.1 specification: 49°30' N, 72°45' W.
+        let report = position("=/5L!!<*e7>7P[Compressed");
+        assert!((report.latitude - 49.5).abs() < 1e-4);
+        assert!((report.longitude + 72.75).abs() < 1e-4);
+        assert_eq!((report.symbol_table, report.symbol_code), ('/', '>'));
+        assert_eq!(report.comment, "Compressed");
+    }
+
+    #[test]
+    fn test_message_and_status() {
+        assert_eq!(
+            parse_aprs(b":VK7KSM   :Hello there{042").unwrap(),
+            AprsPacket::Message(AprsMessage {
+                addressee: "VK7KSM".to_string(),
+                text: "Hello there".to_string(),
+                id: Some("042".to_string()),
+            })
+        );
+        assert_matches!(parse_aprs(b":BLN1     :Net tonight").unwrap(), AprsPacket::Message(AprsMessage { id: None, .. }));
+        assert_eq!(
+            parse_aprs(b">092345zNet control").unwrap(),
+            AprsPacket::Status { timestamp: Some("092345z".to_string()), text: "Net control".to_string() }
+        );
+        assert_eq!(parse_aprs(b">QRV 146.500").unwrap().to_string(), "Status: QRV 146.500");
+        assert_matches!(parse_aprs(b"`(_fn\"Oj/").unwrap(), AprsPacket::Other { data_type: '`', .. });
+    }
+
+    #[test]
+    fn test_invalid_packets() {
+        for info in ["", "!4903.50N/0720", "!4903.50X/07201.75W-", "!9903.50N/07201.75W-", ":SHORT:x", "/4903.50N/07201.75W-"] {
+            assert_matches!(parse_aprs(info.as_bytes()), Err(DspError::InvalidAprsPacket(_)), "{:?}", info);
+        }
+    }
+}
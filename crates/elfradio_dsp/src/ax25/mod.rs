@@ -0,0 +1,330 @@
+// AX.25 packet radio: UI frames carried by the 1200 baud Bell 202 AFSK modem, and the
+// APRS reports found in their information fields.
+//
+// Frame: destination, source and up to eight digipeater addresses, control (UI),
+// PID, information field, FCS (CRC-16/X.25, low byte first).
+
+mod afsk;
+mod aprs;
+
+pub use afsk::{decode_afsk, generate_afsk_audio, AfskDecoder, AfskParams};
+pub use aprs::{parse_aprs, AprsMessage, AprsPacket, AprsPosition};
+
+use crate::error::DspError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Control field of a UI frame, ignoring the poll/final bit.
+const CONTROL_UI: u8 = 0x03;
+const POLL_FINAL: u8 = 0x10;
+/// Protocol ID for "no layer 3", as used by APRS.
+pub const AX25_PID_NO_LAYER3: u8 = 0xF0;
+/// Most digipeaters a frame can name.
+const MAX_DIGIPEATERS: usize = 8;
+/// Longest information field.
+const MAX_INFO_LEN: usize = 256;
+/// Shortest and longest frames on the air, FCS included.
+pub(crate) const MIN_FRAME_LEN: usize = 2 * 7 + 2 + 2;
+pub(crate) const MAX_FRAME_LEN: usize = (2 + MAX_DIGIPEATERS) * 7 + 2 + MAX_INFO_LEN + 2;
+
+/// Frame check sequence (CRC-16/X.25).
+pub(crate) fn fcs(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in bytes {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { crc >> 1 ^ 0x8408 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// A station address: callsign and SSID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ax25Address {
+    /// One to six upper case letters or digits.
+    pub callsign: String,
+    /// Secondary station identifier, 0 to 15.
+    pub ssid: u8,
+    /// H bit of a digipeater entry: the frame has been repeated by this station.
+    pub repeated: bool,
+}
+
+impl Ax25Address {
+    pub fn new(callsign: &str, ssid: u8) -> Result<Self, DspError> {
+        let callsign = callsign.trim().to_ascii_uppercase();
+        if callsign.is_empty() || callsign.len() > 6 || !callsign.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(DspError::InvalidAx25Address(format!(
+                "callsign '{}' must be 1 to 6 letters or digits",
+                callsign
+            )));
+        }
+        if ssid > 15 {
+            return Err(DspError::InvalidAx25Address(format!("SSID {} is outside 0..15", ssid)));
+        }
+        Ok(Self { callsign, ssid, repeated: false })
+    }
+
+    /// The seven address bytes. `high_bit` is the C bit (source/destination) or H bit
+    /// (digipeater); `last` sets the extension bit that ends the address field.
+    fn encode(&self, high_bit: bool, last: bool) -> [u8; 7] {
+        let mut bytes = [b' ' << 1; 7];
+        for (byte, c) in bytes.iter_mut().zip(self.callsign.bytes()) {
+            *byte = c << 1;
+        }
+        bytes[6] = 0x60 | (high_bit as u8) << 7 | (self.ssid & 0x0F) << 1 | last as u8;
+        bytes
+    }
+
+    /// Parses seven address bytes; returns the address and its C/H bit.
+    fn decode(bytes: &[u8]) -> Result<(Self, bool), DspError> {
+        if bytes[..6].iter().any(|&b| b & 1 != 0) {
+            return Err(DspError::InvalidAx25Frame("address field ends inside a callsign".to_string()));
+        }
+        let callsign: String = bytes[..6].iter().map(|&b| (b >> 1) as char).collect();
+        let address = Self::new(callsign.trim_end(), bytes[6] >> 1 & 0x0F)?;
+        Ok((address, bytes[6] & 0x80 != 0))
+    }
+}
+
+impl fmt::Display for Ax25Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.callsign)?;
+        if self.ssid != 0 {
+            write!(f, "-{}", self.ssid)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Ax25Address {
+    type Err = DspError;
+
+    /// Parses "CALL" or "CALL-SSID"; a trailing '*' marks a repeated digipeater.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, repeated) = match s.trim().strip_suffix('*') {
+            Some(rest) => (rest, true),
+            None => (s.trim(), false),
+        };
+        let (callsign, ssid) = match s.split_once('-') {
+            Some((callsign, ssid)) => {
+                let ssid = ssid
+                    .parse()
+                    .map_err(|_| DspError::InvalidAx25Address(format!("bad SSID in '{}'", s)))?;
+                (callsign, ssid)
+            }
+            None => (s, 0),
+        };
+        Ok(Self { repeated, ..Self::new(callsign, ssid)? })
+    }
+}
+
+/// An AX.25 UI (unnumbered information) frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ax25Frame {
+    pub destination: Ax25Address,
+    pub source: Ax25Address,
+    /// Digipeaters, in the order the frame visits them.
+    pub path: Vec<Ax25Address>,
+    pub pid: u8,
+    pub info: Vec<u8>,
+}
+
+impl Ax25Frame {
+    /// Builds a UI frame with no layer 3 protocol, as APRS uses.
+    pub fn ui(
+        destination: Ax25Address,
+        source: Ax25Address,
+        path: Vec<Ax25Address>,
+        info: impl Into<Vec<u8>>,
+    ) -> Result<Self, DspError> {
+        let frame = Self { destination, source, path, pid: AX25_PID_NO_LAYER3, info: info.into() };
+        frame.validate()?;
+        Ok(frame)
+    }
+
+    fn validate(&self) -> Result<(), DspError> {
+        if self.path.len() > MAX_DIGIPEATERS {
+            return Err(DspError::InvalidAx25Frame(format!(
+                "{} digipeaters, at most {} allowed",
+                self.path.len(),
+                MAX_DIGIPEATERS
+            )));
+        }
+        if self.info.len() > MAX_INFO_LEN {
+            return Err(DspError::InvalidAx25Frame(format!(
+                "{} byte information field, at most {} allowed",
+                self.info.len(),
+                MAX_INFO_LEN
+            )));
+        }
+        Ok(())
+    }
+
+    /// The frame as sent between flags, FCS included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MIN_FRAME_LEN + 7 * self.path.len() + self.info.len());
+        // Version 2 command frame: C bit set in the destination, clear in the source.
+        bytes.extend(self.destination.encode(true, false));
+        bytes.extend(self.source.encode(false, self.path.is_empty()));
+        for (n, digipeater) in self.path.iter().enumerate() {
+            bytes.extend(digipeater.encode(digipeater.repeated, n + 1 == self.path.len()));
+        }
+        bytes.extend([CONTROL_UI, self.pid]);
+        bytes.extend(&self.info);
+        bytes.extend(fcs(&bytes).to_le_bytes());
+        bytes
+    }
+
+    /// Parses a received frame (FCS included), rejecting bad checksums and non-UI frames.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DspError> {
+        if !(MIN_FRAME_LEN..=MAX_FRAME_LEN).contains(&bytes.len()) {
+            return Err(DspError::InvalidAx25Frame(format!("{} bytes is not a valid frame length", bytes.len())));
+        }
+        let (body, check) = bytes.split_at(bytes.len() - 2);
+        if fcs(body).to_le_bytes() != check {
+            return Err(DspError::InvalidAx25Frame("FCS mismatch".to_string()));
+        }
+
+        let mut addresses = Vec::new();
+        let mut offset = 0;
+        loop {
+            let Some(field) = body.get(offset..offset + 7) else {
+                return Err(DspError::InvalidAx25Frame("address field is not terminated".to_string()));
+            };
+            let (mut address, high_bit) = Ax25Address::decode(field)?;
+            offset += 7;
+            if addresses.len() >= 2 {
+                address.repeated = high_bit;
+            }
+            addresses.push(address);
+            if field[6] & 1 == 1 {
+                break;
+            }
+        }
+        if addresses.len() < 2 {
+            return Err(DspError::InvalidAx25Frame("missing source address".to_string()));
+        }
+        let (control, pid) = match body.get(offset..offset + 2) {
+            Some(&[control, pid]) => (control, pid),
+            _ => return Err(DspError::InvalidAx25Frame("missing control and PID fields".to_string())),
+        };
+        if control & !POLL_FINAL != CONTROL_UI {
+            return Err(DspError::InvalidAx25Frame(format!("control 0x{:02X} is not a UI frame", control)));
+        }
+
+        let mut addresses = addresses.into_iter();
+        let (Some(destination), Some(source)) = (addresses.next(), addresses.next()) else {
+            unreachable!("at least two addresses were checked above");
+        };
+        let frame = Self { destination, source, path: addresses.collect(), pid, info: body[offset + 2..].to_vec() };
+        frame.validate()?;
+        Ok(frame)
+    }
+
+    /// The information field as text, with a trailing CR/LF removed.
+    pub fn info_text(&self) -> String {
+        String::from_utf8_lossy(&self.info).trim_end_matches(['\r', '\n']).to_string()
+    }
+}
+
+impl fmt::Display for Ax25Frame {
+    /// Monitor (TNC2) format: "SOURCE>DEST,DIGI1*,DIGI2:info". The '*' follows the
+    /// last digipeater that has repeated the frame.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}>{}", self.source, self.destination)?;
+        let last_repeated = self.path.iter().rposition(|digipeater| digipeater.repeated);
+        for (n, digipeater) in self.path.iter().enumerate() {
+            write!(f, ",{}", digipeater)?;
+            if Some(n) == last_repeated {
+                f.write_str("*")?;
+            }
+        }
+        write!(f, ":{}", self.info_text())
+    }
+}
+
+impl FromStr for Ax25Frame {
+    type Err = DspError;
+
+    /// Parses monitor (TNC2) format. A '*' marks that digipeater and every one before it
+    /// as repeated.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DspError::InvalidAx25Frame(format!("'{}' is not SOURCE>DEST[,PATH]:info", s));
+        let (header, info) = s.split_once(':').ok_or_else(invalid)?;
+        let (source, rest) = header.split_once('>').ok_or_else(invalid)?;
+        let mut fields = rest.split(',');
+        let destination = fields.next().ok_or_else(invalid)?.parse()?;
+        let mut path = fields.map(str::parse).collect::<Result<Vec<Ax25Address>, _>>()?;
+        if let Some(last_repeated) = path.iter().rposition(|digipeater| digipeater.repeated) {
+            path[..last_repeated].iter_mut().for_each(|digipeater| digipeater.repeated = true);
+        }
+        Self::ui(destination, source.parse()?, path, info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_fcs() {
+        assert_eq!(fcs(b"123456789"), 0x906E);
+    }
+
+    #[test]
+    fn test_address_encoding() {
+        let address: Ax25Address = "vk7ksm-9".parse().unwrap();
+        assert_eq!(address.to_string(), "VK7KSM-9");
+        assert_eq!(address.encode(false, true), [0xAC, 0x96, 0x6E, 0x96, 0xA6, 0x9A, 0x73]);
+        let short = Ax25Address::new("APRS", 0).unwrap();
+        assert_eq!(short.encode(true, false), [0x82, 0xA0, 0xA4, 0xA6, 0x40, 0x40, 0xE0]);
+        assert_eq!(Ax25Address::decode(&short.encode(true, false)).unwrap(), (short, true));
+
+        assert_matches!("TOOLONG1".parse::<Ax25Address>(), Err(DspError::InvalidAx25Address(_)));
+        assert_matches!("VK7KSM-16".parse::<Ax25Address>(), Err(DspError::InvalidAx25Address(_)));
+        assert_matches!("VK/7".parse::<Ax25Address>(), Err(DspError::InvalidAx25Address(_)));
+        assert!("WIDE1-1*".parse::<Ax25Address>().unwrap().repeated);
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let monitor = "VK7KSM-9>APRS,VK7RAA*,WIDE2-1:!4252.50S/14718.75E>Hobart";
+        let frame: Ax25Frame = monitor.parse().unwrap();
+        assert_eq!(frame.path.len(), 2);
+        assert!(frame.path[0].repeated && !frame.path[1].repeated);
+        assert_eq!(frame.to_string(), monitor);
+
+        let bytes = frame.to_bytes();
+        assert_eq!(bytes.len(), 4 * 7 + 2 + frame.info.len() + 2);
+        assert_eq!(&bytes[28..30], &[CONTROL_UI, AX25_PID_NO_LAYER3]);
+        assert_eq!(Ax25Frame::from_bytes(&bytes).unwrap(), frame);
+
+        let direct = Ax25Frame::ui("CQ".parse().unwrap(), "VK7KSM".parse().unwrap(), vec![], "hi").unwrap();
+        assert_eq!(Ax25Frame::from_bytes(&direct.to_bytes()).unwrap().to_string(), "VK7KSM>CQ:hi");
+    }
+
+    #[test]
+    fn test_rejects_bad_frames() {
+        let frame: Ax25Frame = "VK7KSM>APRS:>status".parse().unwrap();
+        let mut bytes = frame.to_bytes();
+        bytes[20] ^= 0x04;
+        assert_matches!(Ax25Frame::from_bytes(&bytes), Err(DspError::InvalidAx25Frame(_)));
+        assert_matches!(Ax25Frame::from_bytes(&bytes[..10]), Err(DspError::InvalidAx25Frame(_)));
+
+        // An I frame (control 0x00) with a correct FCS.
+        let mut body = frame.to_bytes();
+        body.truncate(body.len() - 2);
+        body[14] = 0x00;
+        body.extend(fcs(&body).to_le_bytes());
+        assert_matches!(Ax25Frame::from_bytes(&body), Err(DspError::InvalidAx25Frame(_)));
+
+        let path = vec![Ax25Address::new("WIDE1", 1).unwrap(); 9];
+        assert_matches!(
+            Ax25Frame::ui(frame.destination.clone(), frame.source.clone(), path, "x"),
+            Err(DspError::InvalidAx25Frame(_))
+        );
+        assert_matches!("no separators".parse::<Ax25Frame>(), Err(DspError::InvalidAx25Frame(_)));
+    }
+}
@@ -0,0 +1,85 @@
+// Audio conditioning for the radio: the transmit chain that levels voice before it
+// reaches the modulator, the receive chain that cleans up noisy audio before it is
+// transcribed, and the filters they share with the other modules.
+
+mod rx;
+mod tx;
+
+pub use rx::{condition_rx_audio, RxChainParams, RxConditioner};
+pub use tx::{condition_tx_audio, TxChainParams};
+
+use std::f64::consts::PI;
+
+/// Q of each biquad section in an `order`th-order Butterworth filter (`order` even).
+pub(crate) fn butterworth_q(order: usize) -> Vec<f64> {
+    (1..=order / 2).map(|k| 1.0 / (2.0 * (PI * (2 * k - 1) as f64 / (2 * order) as f64).cos())).collect()
+}
+
+/// RBJ cookbook biquad, direct form I.
+#[derive(Debug, Clone)]
+pub(crate) struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn from_coefficients(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self { b: b.map(|b| b / a[0]), a: [a[1] / a[0], a[2] / a[0]], x: [0.0; 2], y: [0.0; 2] }
+    }
+
+    pub(crate) fn high_pass(cutoff: f64, sample_rate: f64, q: f64) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let (cos, alpha) = (w0.cos(), w0.sin() / (2.0 * q));
+        Self::from_coefficients([(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    pub(crate) fn low_pass(cutoff: f64, sample_rate: f64, q: f64) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let (cos, alpha) = (w0.cos(), w0.sin() / (2.0 * q));
+        Self::from_coefficients([(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// Notch `bandwidth` Hz wide (at -3 dB) centred on `freq`.
+    pub(crate) fn notch(freq: f64, bandwidth: f64, sample_rate: f64) -> Self {
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (cos, alpha) = (w0.cos(), w0.sin() / (2.0 * freq / bandwidth));
+        Self::from_coefficients([1.0, -2.0 * cos, 1.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// Takes `other`'s coefficients but keeps this filter's history, so a retuned
+    /// filter doesn't click.
+    pub(crate) fn retune(&mut self, other: Biquad) {
+        self.b = other.b;
+        self.a = other.a;
+    }
+
+    pub(crate) fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1] - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+pub(crate) fn db_to_gain(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+/// One-pole smoothing coefficient for a time constant.
+pub(crate) fn smoothing(ms: f64, sample_rate: f64) -> f64 {
+    1.0 - (-1000.0 / (ms * sample_rate)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_butterworth_q() {
+        let q = butterworth_q(4);
+        assert!((q[0] - 0.5412).abs() < 1e-4 && (q[1] - 1.3066).abs() < 1e-4, "{:?}", q);
+        assert_eq!(butterworth_q(8).len(), 4);
+    }
+}
@@ -0,0 +1,558 @@
+// Receive audio chain: STFT noise reduction → heterodyne notches → AGC.
+//
+// Noise reduction is a Wiener filter over half-overlapped frames of about 32 ms. The noise
+// profile is learned while audio plays, from the lowest smoothed power each bin has
+// shown over the last 1.5 s, so it follows a changing band and needs no noise-only
+// sample. Heterodynes are found in the same spectra: a bin that stands well clear of
+// its neighbours for a couple of seconds gets a narrow notch, tuned from the bin's phase
+// advance. The AGC runs last, so it levels what is left.
+//
+// The chain streams: `RxConditioner` hands back as many samples as it is given, one
+// frame late.
+
+use super::{db_to_gain, smoothing, Biquad};
+use crate::error::DspError;
+use rustfft::num_complex::Complex64;
+use rustfft::{Fft, FftPlanner};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Analysis frame length, rounded up to a power of two.
+const FRAME_MS: f64 = 32.0;
+/// Smoothing of each bin's power before its minimum is tracked.
+const NOISE_SMOOTHING: f64 = 0.85;
+/// The noise profile is the lowest smoothed power over this long, tracked in sub-windows
+/// so it can rise again one sub-window after the noise does.
+const NOISE_WINDOW_SECONDS: f64 = 1.5;
+const NOISE_SUBWINDOWS: usize = 8;
+/// Minimum tracking underestimates the mean noise power by about this much.
+const NOISE_BIAS: f64 = 2.0;
+/// Weight of the previous frame in the decision-directed a priori SNR; high values keep
+/// the residual noise from turning into musical tones.
+const PRIOR_SNR_WEIGHT: f64 = 0.98;
+/// How far noise is pulled down at full strength.
+const MAX_NOISE_REDUCTION_DB: f64 = 24.0;
+/// A heterodyne stands this far above the median of the bins around it...
+const HETERODYNE_PEAK_DB: f64 = 15.0;
+/// ...bins 2 to 8 either side of it...
+const HETERODYNE_NEIGHBOURS: usize = 8;
+/// ...in nearly every frame, scored with this time constant. Speech partials glide and
+/// pause, so they never score high enough.
+const HETERODYNE_SECONDS: f64 = 1.0;
+const HETERODYNE_ON: f64 = 0.9;
+const HETERODYNE_OFF: f64 = 0.5;
+/// Smoothing of the per-frame frequency estimates.
+const FREQUENCY_SMOOTHING: f64 = 0.2;
+const MAX_NOTCHES: usize = 4;
+const NOTCH_BANDWIDTH_HZ: f64 = 50.0;
+/// Drift a heterodyne is allowed before its notch is retuned.
+const NOTCH_RETUNE_HZ: f64 = 0.5;
+/// The AGC follows the mean square over this long, not each waveform peak.
+const AGC_AVERAGE_MS: f64 = 20.0;
+const AGC_ATTACK_MS: f64 = 10.0;
+const AGC_RELEASE_MS: f64 = 400.0;
+/// Gain comes back up this slowly, so pauses don't pump up the noise.
+const AGC_RECOVERY_MS: f64 = 1000.0;
+
+/// Receive audio chain settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RxChainParams {
+    pub sample_rate: u32,
+    /// Noise reduction strength, from 0.0 (off) to 1.0 (noise pulled down by 24 dB).
+    pub noise_reduction: f32,
+    /// Find heterodynes and notch them out.
+    pub notch: bool,
+    /// Level (dBFS RMS) the AGC holds audio at; None turns the AGC off.
+    pub agc_target_dbfs: Option<f32>,
+    /// Most gain the AGC applies, so it doesn't lift the noise floor up to the target.
+    pub agc_max_gain_db: f32,
+}
+
+impl RxChainParams {
+    /// Half-strength noise reduction, heterodyne notches and AGC to -20 dBFS with at
+    /// most 30 dB of gain.
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, noise_reduction: 0.5, notch: true, agc_target_dbfs: Some(-20.0), agc_max_gain_db: 30.0 }
+    }
+
+    pub fn with_noise_reduction(mut self, strength: f32) -> Self {
+        self.noise_reduction = strength;
+        self
+    }
+
+    pub fn with_notch(mut self, notch: bool) -> Self {
+        self.notch = notch;
+        self
+    }
+
+    pub fn with_agc(mut self, target_dbfs: Option<f32>) -> Self {
+        self.agc_target_dbfs = target_dbfs;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidRxAudioParameter(msg));
+        if self.sample_rate < 8000 {
+            return invalid(format!("sample rate {} Hz is below 8000 Hz", self.sample_rate));
+        }
+        if !(0.0..=1.0).contains(&self.noise_reduction) {
+            return invalid(format!("noise reduction strength {} is outside 0..1", self.noise_reduction));
+        }
+        if let Some(target) = self.agc_target_dbfs
+            && target >= 0.0
+        {
+            return invalid(format!("AGC target {} dBFS isn't below full scale", target));
+        }
+        if self.agc_max_gain_db < 0.0 {
+            return invalid(format!("maximum AGC gain {} dB is negative", self.agc_max_gain_db));
+        }
+        Ok(())
+    }
+}
+
+/// Wiener filter with a noise profile learned by minimum statistics.
+struct NoiseReducer {
+    /// Lowest gain applied to any bin.
+    floor: f64,
+    frames_per_subwindow: usize,
+    /// Frames into the current sub-window; None until the first frame.
+    frame_count: Option<usize>,
+    smoothed: Vec<f64>,
+    current_min: Vec<f64>,
+    /// Minima of the finished sub-windows, oldest first.
+    subwindow_mins: VecDeque<Vec<f64>>,
+    /// Previous frame's clean power estimate in each bin.
+    clean: Vec<f64>,
+}
+
+impl NoiseReducer {
+    fn new(bins: usize, strength: f64, frames_per_second: f64) -> Self {
+        let frames = NOISE_WINDOW_SECONDS * frames_per_second / NOISE_SUBWINDOWS as f64;
+        Self {
+            floor: db_to_gain(-MAX_NOISE_REDUCTION_DB * strength),
+            frames_per_subwindow: (frames.ceil() as usize).max(1),
+            frame_count: None,
+            smoothed: vec![0.0; bins],
+            current_min: vec![f64::INFINITY; bins],
+            subwindow_mins: VecDeque::with_capacity(NOISE_SUBWINDOWS),
+            clean: vec![0.0; bins],
+        }
+    }
+
+    fn noise(&self, bin: usize) -> f64 {
+        let lowest = self.subwindow_mins.iter().fold(self.current_min[bin], |lowest, mins| lowest.min(mins[bin]));
+        (lowest * NOISE_BIAS).max(1e-20)
+    }
+
+    fn apply(&mut self, spectrum: &mut [Complex64]) {
+        let len = spectrum.len();
+        let started = self.frame_count.is_some();
+        for bin in 0..self.smoothed.len() {
+            let power = spectrum[bin].norm_sqr();
+            self.smoothed[bin] =
+                if started { NOISE_SMOOTHING * self.smoothed[bin] + (1.0 - NOISE_SMOOTHING) * power } else { power };
+            self.current_min[bin] = self.current_min[bin].min(self.smoothed[bin]);
+
+            let noise = self.noise(bin);
+            let posterior_snr = power / noise;
+            let prior_snr =
+                PRIOR_SNR_WEIGHT * self.clean[bin] / noise + (1.0 - PRIOR_SNR_WEIGHT) * (posterior_snr - 1.0).max(0.0);
+            let gain = (prior_snr / (1.0 + prior_snr)).max(self.floor);
+            self.clean[bin] = gain * gain * power;
+            spectrum[bin] *= gain;
+            if bin > 0 && bin < len / 2 {
+                spectrum[len - bin] *= gain;
+            }
+        }
+
+        let count = self.frame_count.map_or(1, |count| count + 1);
+        self.frame_count = Some(count % self.frames_per_subwindow);
+        if count == self.frames_per_subwindow {
+            if self.subwindow_mins.len() == NOISE_SUBWINDOWS - 1 {
+                self.subwindow_mins.pop_front();
+            }
+            self.subwindow_mins.push_back(std::mem::replace(&mut self.current_min, self.smoothed.clone()));
+        }
+    }
+}
+
+struct Notch {
+    bin: usize,
+    hz: f64,
+    filter: Biquad,
+}
+
+/// Finds steady carriers in the spectrum and notches them out of the output.
+struct HeterodyneNotches {
+    sample_rate: f64,
+    bin_hz: f64,
+    score_rate: f64,
+    /// How often each bin has stood out lately (0 to 1).
+    score: Vec<f64>,
+    /// Long-term power in each bin.
+    level: Vec<f64>,
+    phase: Vec<f64>,
+    /// Frequency of the strongest component near each bin.
+    hz: Vec<f64>,
+    notches: Vec<Notch>,
+}
+
+impl HeterodyneNotches {
+    fn new(bins: usize, sample_rate: f64, bin_hz: f64, frames_per_second: f64) -> Self {
+        Self {
+            sample_rate,
+            bin_hz,
+            score_rate: smoothing(HETERODYNE_SECONDS * 1000.0, frames_per_second),
+            score: vec![0.0; bins],
+            level: vec![0.0; bins],
+            phase: vec![0.0; bins],
+            hz: (0..bins).map(|bin| bin as f64 * bin_hz).collect(),
+            notches: Vec::new(),
+        }
+    }
+
+    fn observe(&mut self, spectrum: &[Complex64]) {
+        let bins = self.score.len();
+        let power: Vec<f64> = spectrum[..bins].iter().map(|x| x.norm_sqr()).collect();
+        let peak_ratio = 10f64.powf(HETERODYNE_PEAK_DB / 10.0);
+        let mut neighbours = Vec::with_capacity(2 * HETERODYNE_NEIGHBOURS);
+        for bin in 0..bins {
+            // Frames are half a frame apart, so a tone in the middle of the bin advances
+            // by π·bin; what's left over gives its offset from the middle.
+            let phase = spectrum[bin].arg();
+            let offset = (phase - self.phase[bin] - PI * bin as f64 + PI).rem_euclid(2.0 * PI) - PI;
+            self.phase[bin] = phase;
+            self.hz[bin] += ((bin as f64 + offset / PI) * self.bin_hz - self.hz[bin]) * FREQUENCY_SMOOTHING;
+
+            let peak = bin >= HETERODYNE_NEIGHBOURS && bin + HETERODYNE_NEIGHBOURS < bins && {
+                neighbours.clear();
+                neighbours.extend_from_slice(&power[bin - HETERODYNE_NEIGHBOURS..bin - 1]);
+                neighbours.extend_from_slice(&power[bin + 2..=bin + HETERODYNE_NEIGHBOURS]);
+                neighbours.sort_by(f64::total_cmp);
+                power[bin] > neighbours[neighbours.len() / 2] * peak_ratio
+            };
+            self.score[bin] += (if peak { 1.0 } else { 0.0 } - self.score[bin]) * self.score_rate;
+            self.level[bin] += (power[bin] - self.level[bin]) * self.score_rate;
+        }
+
+        // A carrier may sit between two bins, so a notch holds while either neighbour scores.
+        let (score, hz) = (&self.score, &self.hz);
+        self.notches.retain(|notch| {
+            let held = score[notch.bin - 1..=notch.bin + 1].iter().any(|&score| score >= HETERODYNE_OFF);
+            if !held {
+                debug!("Heterodyne at {:.1} Hz has gone, removing its notch", notch.hz);
+            }
+            held
+        });
+        for notch in &mut self.notches {
+            if (hz[notch.bin] - notch.hz).abs() > NOTCH_RETUNE_HZ {
+                notch.hz = hz[notch.bin];
+                notch.filter.retune(Biquad::notch(notch.hz, NOTCH_BANDWIDTH_HZ, self.sample_rate));
+            }
+        }
+
+        let level = &self.level;
+        let mut carriers: Vec<usize> = (HETERODYNE_NEIGHBOURS..bins - HETERODYNE_NEIGHBOURS)
+            .filter(|&bin| score[bin] >= HETERODYNE_ON && level[bin] >= level[bin - 1] && level[bin] >= level[bin + 1])
+            .filter(|&bin| !self.notches.iter().any(|notch| notch.bin.abs_diff(bin) <= 1))
+            .collect();
+        carriers.sort_by(|&a, &b| level[b].total_cmp(&level[a]));
+        for bin in carriers.into_iter().take(MAX_NOTCHES.saturating_sub(self.notches.len())) {
+            debug!("Notching heterodyne at {:.1} Hz", hz[bin]);
+            let filter = Biquad::notch(hz[bin], NOTCH_BANDWIDTH_HZ, self.sample_rate);
+            self.notches.push(Notch { bin, hz: hz[bin], filter });
+        }
+    }
+
+    fn filter(&mut self, x: f64) -> f64 {
+        self.notches.iter_mut().fold(x, |x, notch| notch.filter.process(x))
+    }
+}
+
+/// RMS AGC: gain drops as fast as the level rises and recovers slowly.
+struct Agc {
+    target_power: f64,
+    max_gain: f64,
+    average: f64,
+    attack: f64,
+    release: f64,
+    recovery: f64,
+    power: f64,
+    /// Envelope of `power`.
+    level: f64,
+    gain: f64,
+}
+
+impl Agc {
+    fn new(target_dbfs: f64, max_gain_db: f64, sample_rate: f64) -> Self {
+        Self {
+            target_power: db_to_gain(target_dbfs).powi(2),
+            max_gain: db_to_gain(max_gain_db),
+            average: smoothing(AGC_AVERAGE_MS, sample_rate),
+            attack: smoothing(AGC_ATTACK_MS, sample_rate),
+            release: smoothing(AGC_RELEASE_MS, sample_rate),
+            recovery: smoothing(AGC_RECOVERY_MS, sample_rate),
+            power: 0.0,
+            level: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.power += (x * x - self.power) * self.average;
+        self.level += (self.power - self.level) * if self.power > self.level { self.attack } else { self.release };
+        let wanted = (self.target_power / self.level.max(1e-20)).sqrt().min(self.max_gain);
+        self.gain += (wanted - self.gain) * if wanted < self.gain { self.attack } else { self.recovery };
+        (x * self.gain).clamp(-1.0, 1.0)
+    }
+}
+
+/// Streaming receive chain. Keeps its noise profile, notches and AGC gain from one
+/// chunk to the next, so feed it one receiver's audio in order.
+pub struct RxConditioner {
+    fft: Arc<dyn Fft<f64>>,
+    ifft: Arc<dyn Fft<f64>>,
+    /// Square-root periodic Hann, used on analysis and synthesis: the two together sum
+    /// to one at half-frame overlap.
+    window: Vec<f64>,
+    /// The last frame of input.
+    frame: Vec<f64>,
+    /// Input received since the last frame was processed.
+    fresh: Vec<f64>,
+    overlap: Vec<f64>,
+    output: VecDeque<f64>,
+    noise_reducer: Option<NoiseReducer>,
+    notches: Option<HeterodyneNotches>,
+    agc: Option<Agc>,
+}
+
+impl RxConditioner {
+    pub fn new(params: &RxChainParams) -> Result<Self, DspError> {
+        params.validate()?;
+        let rate = params.sample_rate as f64;
+        let len = ((FRAME_MS / 1000.0 * rate).round() as usize).next_power_of_two();
+        let hop = len / 2;
+        let bins = len / 2 + 1;
+        let frames_per_second = rate / hop as f64;
+        debug!("Starting RX conditioner: {:?}, {}-sample frames", params, len);
+
+        let mut planner = FftPlanner::new();
+        Ok(Self {
+            fft: planner.plan_fft_forward(len),
+            ifft: planner.plan_fft_inverse(len),
+            window: (0..len).map(|n| (0.5 - 0.5 * (2.0 * PI * n as f64 / len as f64).cos()).sqrt()).collect(),
+            frame: vec![0.0; len],
+            fresh: Vec::with_capacity(hop),
+            overlap: vec![0.0; len],
+            // One hop of lead-in, so every call can hand back as much as it was given.
+            output: vec![0.0; hop].into(),
+            noise_reducer: (params.noise_reduction > 0.0)
+                .then(|| NoiseReducer::new(bins, params.noise_reduction as f64, frames_per_second)),
+            notches: params.notch.then(|| HeterodyneNotches::new(bins, rate, rate / len as f64, frames_per_second)),
+            agc: params.agc_target_dbfs.map(|target| Agc::new(target as f64, params.agc_max_gain_db as f64, rate)),
+        })
+    }
+
+    /// Samples between audio going in and coming back out.
+    pub fn latency(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Frequencies (Hz) of the heterodynes currently notched out.
+    pub fn notch_frequencies(&self) -> Vec<f32> {
+        self.notches.as_ref().map_or(Vec::new(), |notches| notches.notches.iter().map(|notch| notch.hz as f32).collect())
+    }
+
+    /// Conditions the next chunk of received audio. Returns the same number of samples,
+    /// `latency()` samples behind the input.
+    pub fn process(&mut self, chunk: &[f32]) -> Vec<f32> {
+        for &sample in chunk {
+            self.fresh.push(sample as f64);
+            if self.fresh.len() == self.frame.len() / 2 {
+                self.process_frame();
+            }
+        }
+        self.output.drain(..chunk.len()).map(|x| x as f32).collect()
+    }
+
+    fn process_frame(&mut self) {
+        let (len, hop) = (self.frame.len(), self.frame.len() / 2);
+        self.frame.drain(..hop);
+        self.frame.append(&mut self.fresh);
+
+        let mut spectrum: Vec<Complex64> =
+            self.frame.iter().zip(&self.window).map(|(&x, &w)| Complex64::new(x * w, 0.0)).collect();
+        self.fft.process(&mut spectrum);
+        if let Some(notches) = &mut self.notches {
+            notches.observe(&spectrum);
+        }
+        if let Some(noise_reducer) = &mut self.noise_reducer {
+            noise_reducer.apply(&mut spectrum);
+        }
+        self.ifft.process(&mut spectrum);
+        for ((sum, bin), &w) in self.overlap.iter_mut().zip(&spectrum).zip(&self.window) {
+            *sum += bin.re * w / len as f64;
+        }
+
+        for mut x in self.overlap.drain(..hop) {
+            if let Some(notches) = &mut self.notches {
+                x = notches.filter(x);
+            }
+            if let Some(agc) = &mut self.agc {
+                x = agc.process(x);
+            }
+            self.output.push_back(x);
+        }
+        self.overlap.resize(len, 0.0);
+    }
+}
+
+/// Runs a whole recording through the receive chain. The output lines up with the
+/// input; the first 1.5 s or so is processed while the noise profile is still learned.
+pub fn condition_rx_audio(audio: &[f32], params: &RxChainParams) -> Result<Vec<f32>, DspError> {
+    let mut conditioner = RxConditioner::new(params)?;
+    let latency = conditioner.latency();
+    let mut output = conditioner.process(audio);
+    output.extend(conditioner.process(&vec![0.0; latency]));
+    output.drain(..latency);
+
+    info!(
+        "Conditioned RX audio with {} samples (approx {:.2} seconds)",
+        output.len(),
+        output.len() as f64 / params.sample_rate as f64
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{gliding_speech, mean_power, si_snr_db, white_noise, with_noise, with_tone};
+    use crate::goertzel::goertzel_power;
+    use assert_matches::assert_matches;
+
+    /// Skipped when scoring, while the noise profile and notches settle.
+    const SETTLE: usize = 2 * 16000;
+
+    /// RMS level (dBFS) of the 50 ms blocks within 20 dB of the loudest.
+    fn active_dbfs(samples: &[f32]) -> f64 {
+        let blocks: Vec<f64> = samples.chunks(800).map(mean_power).collect();
+        let loudest = blocks.iter().fold(0.0f64, |acc, &power| acc.max(power));
+        let active: Vec<f64> = blocks.into_iter().filter(|&power| power > loudest / 100.0).collect();
+        10.0 * (active.iter().sum::<f64>() / active.len() as f64).log10()
+    }
+
+    /// A/B: the noisy input (A) and the chain's output (B) are both scored against the
+    /// clean speech.
+    #[test]
+    fn test_noise_reduction_ab() {
+        let clean = gliding_speech(6.0, 0.3, 16000);
+        for snr_db in [0.0, 5.0, 10.0] {
+            let noisy = with_noise(&clean, snr_db, 7);
+            let unprocessed = si_snr_db(&clean[SETTLE..], &noisy[SETTLE..]);
+            let improvement = |strength: f32| {
+                let params = RxChainParams::new(16000).with_noise_reduction(strength).with_notch(false).with_agc(None);
+                let processed = condition_rx_audio(&noisy, &params).unwrap();
+                assert_eq!(processed.len(), noisy.len());
+                si_snr_db(&clean[SETTLE..], &processed[SETTLE..]) - unprocessed
+            };
+            for strength in [0.5, 1.0] {
+                let better = improvement(strength);
+                assert!(better > 4.0, "{} dB SNR, strength {}: only {:.1} dB better", snr_db, strength, better);
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_floor_pulled_down() {
+        let noise = white_noise(4 * 16000, 0.05, 3);
+        for strength in [0.5, 1.0] {
+            let params = RxChainParams::new(16000).with_noise_reduction(strength).with_notch(false).with_agc(None);
+            let processed = condition_rx_audio(&noise, &params).unwrap();
+            let reduction_db = 10.0 * (mean_power(&noise[SETTLE..]) / mean_power(&processed[SETTLE..])).log10();
+            assert!(reduction_db > 18.0 * strength as f64, "strength {}: noise down {:.1} dB", strength, reduction_db);
+        }
+    }
+
+    #[test]
+    fn test_heterodyne_notched() {
+        let clean = gliding_speech(6.0, 0.3, 16000);
+        let noisy = with_noise(&clean, 20.0, 11);
+        let params = RxChainParams::new(16000).with_noise_reduction(0.0).with_agc(None);
+
+        let mut conditioner = RxConditioner::new(&params).unwrap();
+        conditioner.process(&noisy);
+        assert!(conditioner.notch_frequencies().is_empty(), "speech notched at {:?}", conditioner.notch_frequencies());
+
+        let whistle = with_tone(&noisy, 1234.5, 0.3, 16000);
+        let processed = condition_rx_audio(&whistle, &params).unwrap();
+        let before = goertzel_power(&whistle[3 * 16000..], 1234.5, 16000.0);
+        let after = goertzel_power(&processed[3 * 16000..], 1234.5, 16000.0);
+        assert!(after < before * 1e-3, "heterodyne only down {:.1} dB", 10.0 * (before / after).log10());
+        assert!(si_snr_db(&clean[3 * 16000..], &processed[3 * 16000..]) > 15.0);
+
+        let mut conditioner = RxConditioner::new(&params).unwrap();
+        conditioner.process(&whistle);
+        assert_matches!(conditioner.notch_frequencies()[..], [hz] if (hz - 1234.5).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_agc_levels() {
+        let params = RxChainParams::new(16000).with_noise_reduction(0.0).with_notch(false);
+        for amplitude in [0.02, 0.9] {
+            let processed = condition_rx_audio(&gliding_speech(6.0, amplitude, 16000), &params).unwrap();
+            let level = active_dbfs(&processed[3 * 16000..]);
+            assert!((level + 20.0).abs() < 3.0, "amplitude {}: {:.1} dBFS", amplitude, level);
+            assert!(processed.iter().all(|x| x.abs() <= 1.0));
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let noisy = with_tone(&with_noise(&gliding_speech(3.0, 0.3, 8000), 5.0, 5), 800.0, 0.2, 8000);
+        let params = RxChainParams::new(8000).with_noise_reduction(0.8);
+        let batch = condition_rx_audio(&noisy, &params).unwrap();
+
+        let mut conditioner = RxConditioner::new(&params).unwrap();
+        let latency = conditioner.latency();
+        let mut streamed = Vec::new();
+        let mut rest: &[f32] = &noisy;
+        for size in [1, 37, 160, 1000].iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            let output = conditioner.process(chunk);
+            assert_eq!(output.len(), chunk.len());
+            streamed.extend(output);
+            rest = tail;
+        }
+        assert!(streamed[..latency].iter().all(|&x| x.abs() < 1e-9));
+        assert_eq!(streamed[latency..], batch[..noisy.len() - latency]);
+
+        // With everything off the chain hands the input back untouched.
+        let params = params.with_noise_reduction(0.0).with_notch(false).with_agc(None);
+        let passed = condition_rx_audio(&noisy, &params).unwrap();
+        assert!(passed.iter().zip(&noisy).all(|(a, b)| (a - b).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        for params in [
+            RxChainParams::new(4000),
+            RxChainParams::new(16000).with_noise_reduction(1.5),
+            RxChainParams::new(16000).with_noise_reduction(-0.1),
+            RxChainParams::new(16000).with_agc(Some(3.0)),
+        ] {
+            assert_matches!(
+                condition_rx_audio(&[0.0], &params),
+                Err(DspError::InvalidRxAudioParameter(_)),
+                "{:?}",
+                params
+            );
+        }
+        assert!(condition_rx_audio(&[], &RxChainParams::new(16000)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+// Transmit audio chain: voice band-pass → optional pre-emphasis → RMS compressor →
+// gain to the target loudness → look-ahead brick-wall limiter.
+//
+// The chain works on whole transmissions, so loudness is measured over the entire clip
+// (pauses excluded) instead of being tracked as it plays. The limiter runs last so no
+// later gain can push peaks past its ceiling.
+
+use super::{butterworth_q, db_to_gain, smoothing, Biquad};
+use crate::error::DspError;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use tracing::{debug, info};
+
+/// Pre-emphasis time constant: +6 dB/octave above about 210 Hz, across the voice band.
+const PRE_EMPHASIS_US: f64 = 750.0;
+/// Frequency at which pre-emphasis leaves the level unchanged.
+const PRE_EMPHASIS_REFERENCE_HZ: f64 = 1000.0;
+const COMPRESSOR_ATTACK_MS: f64 = 10.0;
+const COMPRESSOR_RELEASE_MS: f64 = 150.0;
+/// Loudness is measured over blocks this long, ignoring blocks below the gate.
+const LOUDNESS_BLOCK_MS: f64 = 50.0;
+const LOUDNESS_GATE_DBFS: f64 = -50.0;
+const LIMITER_LOOKAHEAD_MS: f64 = 5.0;
+const LIMITER_RELEASE_MS: f64 = 50.0;
+
+/// Transmit audio chain settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxChainParams {
+    pub sample_rate: u32,
+    /// Band-pass edges (Hz); None leaves the band alone.
+    pub bandpass_hz: Option<(f32, f32)>,
+    /// 6 dB/octave pre-emphasis, for transmitters fed flat audio.
+    pub pre_emphasis: bool,
+    /// Compressor threshold (dBFS RMS).
+    pub compressor_threshold_dbfs: f32,
+    /// Compression ratio above the threshold; 1.0 turns the compressor off.
+    pub compressor_ratio: f32,
+    /// Loudness (dBFS RMS, pauses excluded) the audio is brought to.
+    pub target_dbfs: f32,
+    /// Most gain used to reach the target, so near-silence isn't pumped up into noise.
+    pub max_gain_db: f32,
+    /// Limiter ceiling (dBFS peak).
+    pub limit_dbfs: f32,
+}
+
+impl TxChainParams {
+    /// 300–2700 Hz band-pass, no pre-emphasis, 3:1 compression above -24 dBFS, -18 dBFS
+    /// loudness and a -1 dBFS ceiling.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            bandpass_hz: Some((300.0, 2700.0)),
+            pre_emphasis: false,
+            compressor_threshold_dbfs: -24.0,
+            compressor_ratio: 3.0,
+            target_dbfs: -18.0,
+            max_gain_db: 24.0,
+            limit_dbfs: -1.0,
+        }
+    }
+
+    pub fn with_bandpass(mut self, bandpass_hz: Option<(f32, f32)>) -> Self {
+        self.bandpass_hz = bandpass_hz;
+        self
+    }
+
+    pub fn with_pre_emphasis(mut self, pre_emphasis: bool) -> Self {
+        self.pre_emphasis = pre_emphasis;
+        self
+    }
+
+    pub fn with_compressor(mut self, threshold_dbfs: f32, ratio: f32) -> Self {
+        self.compressor_threshold_dbfs = threshold_dbfs;
+        self.compressor_ratio = ratio;
+        self
+    }
+
+    pub fn with_levels(mut self, target_dbfs: f32, limit_dbfs: f32) -> Self {
+        self.target_dbfs = target_dbfs;
+        self.limit_dbfs = limit_dbfs;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidTxAudioParameter(msg));
+        if self.sample_rate < 8000 {
+            return invalid(format!("sample rate {} Hz is below 8000 Hz", self.sample_rate));
+        }
+        if let Some((low, high)) = self.bandpass_hz
+            && !(low > 0.0 && low < high && high < self.sample_rate as f32 / 2.0)
+        {
+            return invalid(format!("band-pass {}–{} Hz doesn't fit below {} Hz", low, high, self.sample_rate / 2));
+        }
+        if self.compressor_ratio < 1.0 {
+            return invalid(format!("compressor ratio {} is below 1", self.compressor_ratio));
+        }
+        if self.limit_dbfs > 0.0 {
+            return invalid(format!("limiter ceiling {} dBFS is above full scale", self.limit_dbfs));
+        }
+        if self.target_dbfs >= self.limit_dbfs {
+            return invalid(format!(
+                "target loudness {} dBFS isn't below the {} dBFS ceiling",
+                self.target_dbfs, self.limit_dbfs
+            ));
+        }
+        if self.max_gain_db < 0.0 {
+            return invalid(format!("maximum gain {} dB is negative", self.max_gain_db));
+        }
+        Ok(())
+    }
+}
+
+fn bandpass(samples: &mut [f64], low: f64, high: f64, sample_rate: f64) {
+    let q = butterworth_q(4);
+    let mut filters: Vec<Biquad> = q
+        .iter()
+        .map(|&q| Biquad::high_pass(low, sample_rate, q))
+        .chain(q.iter().map(|&q| Biquad::low_pass(high, sample_rate, q)))
+        .collect();
+    for sample in samples {
+        *sample = filters.iter_mut().fold(*sample, |x, f| f.process(x));
+    }
+}
+
+/// First-order pre-emphasis, 1 + sτ, scaled to unity gain at the reference frequency.
+fn pre_emphasis(samples: &mut [f64], sample_rate: f64) {
+    let a = (-1.0 / (PRE_EMPHASIS_US * 1e-6 * sample_rate)).exp();
+    let w = 2.0 * PI * PRE_EMPHASIS_REFERENCE_HZ / sample_rate;
+    let reference_gain = ((1.0 - a * w.cos()).powi(2) + (a * w.sin()).powi(2)).sqrt();
+    let mut previous = 0.0;
+    for sample in samples {
+        let x = *sample;
+        *sample = (x - a * previous) / reference_gain;
+        previous = x;
+    }
+}
+
+fn compress(samples: &mut [f64], threshold_dbfs: f64, ratio: f64, sample_rate: f64) {
+    let (attack, release) = (smoothing(COMPRESSOR_ATTACK_MS, sample_rate), smoothing(COMPRESSOR_RELEASE_MS, sample_rate));
+    // Mean square tracker; a full-scale sine sits at -3 dBFS.
+    let mut power = 0.0;
+    for sample in samples {
+        let square = *sample * *sample;
+        power += (square - power) * if square > power { attack } else { release };
+        let level_dbfs = 10.0 * (power + 1e-12).log10();
+        if level_dbfs > threshold_dbfs {
+            *sample *= db_to_gain((threshold_dbfs - level_dbfs) * (1.0 - 1.0 / ratio));
+        }
+    }
+}
+
+/// RMS level (dBFS) of the blocks above the gate; None if everything is below it.
+fn gated_loudness(samples: &[f64], sample_rate: f64) -> Option<f64> {
+    let block = ((LOUDNESS_BLOCK_MS / 1000.0 * sample_rate) as usize).max(1);
+    let gate = 10f64.powf(LOUDNESS_GATE_DBFS / 10.0);
+    let (sum, count) = samples
+        .chunks(block)
+        .map(|chunk| chunk.iter().map(|x| x * x).sum::<f64>() / chunk.len() as f64)
+        .filter(|&power| power > gate)
+        .fold((0.0, 0usize), |(sum, count), power| (sum + power, count + 1));
+    (count > 0).then(|| 10.0 * (sum / count as f64).log10())
+}
+
+/// Look-ahead peak limiter. The gain needed by each sample is spread back over the
+/// look-ahead window and averaged, so it is always reached by the time the peak plays.
+fn limit(samples: &mut [f64], ceiling: f64, sample_rate: f64) {
+    let lookahead = ((LIMITER_LOOKAHEAD_MS / 1000.0 * sample_rate) as usize).max(1);
+    let release = smoothing(LIMITER_RELEASE_MS, sample_rate);
+    let needed: Vec<f64> = samples.iter().map(|x| if x.abs() > ceiling { ceiling / x.abs() } else { 1.0 }).collect();
+
+    // Lowest gain needed from each sample to the end of the look-ahead (monotonic deque).
+    let mut window: VecDeque<usize> = VecDeque::new();
+    let mut held = Vec::with_capacity(samples.len());
+    let mut gain: f64 = 1.0;
+    for n in 0..samples.len() + lookahead - 1 {
+        if n < samples.len() {
+            while window.back().is_some_and(|&back| needed[back] >= needed[n]) {
+                window.pop_back();
+            }
+            window.push_back(n);
+        }
+        if n + 1 < lookahead {
+            continue;
+        }
+        let start = n + 1 - lookahead;
+        while window.front().is_some_and(|&front| front < start) {
+            window.pop_front();
+        }
+        let lowest = window.front().map_or(1.0, |&front| needed[front]);
+        gain = lowest.min(gain + (1.0 - gain) * release);
+        held.push(gain);
+    }
+
+    let mut sum = 0.0;
+    for n in 0..samples.len() {
+        sum += held[n];
+        if n >= lookahead {
+            sum -= held[n - lookahead];
+        }
+        samples[n] = (samples[n] * sum / (n + 1).min(lookahead) as f64).clamp(-ceiling, ceiling);
+    }
+}
+
+/// Runs `audio` through the transmit chain and returns audio at the target loudness
+/// with no peak above the ceiling. Silence stays silent.
+pub fn condition_tx_audio(audio: &[f32], params: &TxChainParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Conditioning TX audio: {:?}, {} samples", params, audio.len());
+
+    let rate = params.sample_rate as f64;
+    let mut samples: Vec<f64> = audio.iter().map(|&x| x as f64).collect();
+    let input_loudness = gated_loudness(&samples, rate);
+    if let Some((low, high)) = params.bandpass_hz {
+        bandpass(&mut samples, low as f64, high as f64, rate);
+    }
+    if params.pre_emphasis {
+        pre_emphasis(&mut samples, rate);
+    }
+    if params.compressor_ratio > 1.0 {
+        compress(&mut samples, params.compressor_threshold_dbfs as f64, params.compressor_ratio as f64, rate);
+    }
+    if let Some(loudness) = gated_loudness(&samples, rate) {
+        let gain = db_to_gain((params.target_dbfs as f64 - loudness).min(params.max_gain_db as f64));
+        samples.iter_mut().for_each(|x| *x *= gain);
+    }
+    limit(&mut samples, db_to_gain(params.limit_dbfs as f64), rate);
+
+    let output: Vec<f32> = samples.iter().map(|&x| x as f32).collect();
+    let loudness = |level: Option<f64>| level.map_or("silence".to_string(), |dbfs| format!("{:.1} dBFS", dbfs));
+    info!(
+        "Conditioned TX audio from {} to {}: {} samples (approx {:.2} seconds)",
+        loudness(input_loudness),
+        loudness(gated_loudness(&samples, rate)),
+        output.len(),
+        output.len() as f64 / rate
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::speech;
+    use crate::goertzel::goertzel_power;
+    use assert_matches::assert_matches;
+
+    fn peak_dbfs(samples: &[f32]) -> f64 {
+        20.0 * samples.iter().fold(0.0f32, |acc, s| acc.max(s.abs())).log10() as f64
+    }
+
+    fn loudness(samples: &[f32], sample_rate: u32) -> f64 {
+        let samples: Vec<f64> = samples.iter().map(|&x| x as f64).collect();
+        gated_loudness(&samples, sample_rate as f64).unwrap()
+    }
+
+    #[test]
+    fn test_levels_evened_out() {
+        let params = TxChainParams::new(16000);
+        // A provider that whispers and one that clips.
+        for amplitude in [0.05, 0.3, 1.6] {
+            let mut input = speech(4.0, amplitude, 16000);
+            input.iter_mut().for_each(|x| *x = x.clamp(-1.0, 1.0));
+            let output = condition_tx_audio(&input, &params).unwrap();
+            assert_eq!(output.len(), input.len());
+            let level = loudness(&output, 16000);
+            assert!((level - params.target_dbfs as f64).abs() < 1.5, "amplitude {}: {:.1} dBFS", amplitude, level);
+            assert!(peak_dbfs(&output) <= params.limit_dbfs as f64 + 1e-3, "amplitude {}", amplitude);
+        }
+    }
+
+    #[test]
+    fn test_bandpass() {
+        let rate = 16000.0;
+        let params = TxChainParams::new(16000).with_compressor(-24.0, 1.0);
+        let input: Vec<f32> = (0..16000)
+            .map(|n| {
+                let t = n as f64 / rate;
+                (0.2 * ((2.0 * PI * 100.0 * t).sin() + (2.0 * PI * 1000.0 * t).sin() + (2.0 * PI * 6000.0 * t).sin()))
+                    as f32
+            })
+            .collect();
+        let output = condition_tx_audio(&input, &params).unwrap();
+        let voice = goertzel_power(&output[4000..], 1000.0, rate);
+        assert!(goertzel_power(&output[4000..], 100.0, rate) < voice * 1e-3);
+        assert!(goertzel_power(&output[4000..], 6000.0, rate) < voice * 1e-2);
+    }
+
+    #[test]
+    fn test_pre_emphasis() {
+        let rate = 16000.0;
+        let input: Vec<f32> = (0..16000)
+            .map(|n| {
+                let t = n as f64 / rate;
+                (0.1 * ((2.0 * PI * 500.0 * t).sin() + (2.0 * PI * 2000.0 * t).sin())) as f32
+            })
+            .collect();
+        let ratio_db = |pre_emphasis: bool| {
+            let params = TxChainParams::new(16000).with_compressor(-24.0, 1.0).with_pre_emphasis(pre_emphasis);
+            let output = condition_tx_audio(&input, &params).unwrap();
+            10.0 * (goertzel_power(&output[4000..], 2000.0, rate) / goertzel_power(&output[4000..], 500.0, rate)).log10()
+        };
+        // Two octaves at 6 dB/octave, a little less this close to the corner.
+        let lift = ratio_db(true) - ratio_db(false);
+        assert!((9.0..13.0).contains(&lift), "lift {:.1} dB", lift);
+    }
+
+    #[test]
+    fn test_limiter_is_brick_wall() {
+        let mut samples: Vec<f64> = (0..8000).map(|n| 0.3 * (n as f64 * 0.05).sin()).collect();
+        for n in [10, 2000, 2003, 5000, 7999] {
+            samples[n] = if n % 2 == 0 { 3.0 } else { -2.0 };
+        }
+        limit(&mut samples, 0.5, 8000.0);
+        assert!(samples.iter().all(|x| x.abs() <= 0.5));
+        // Away from the peaks the gain has recovered.
+        assert!((samples[4000] - 0.3 * (4000.0f64 * 0.05).sin()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_silence_and_invalid_params() {
+        let output = condition_tx_audio(&vec![0.0; 8000], &TxChainParams::new(16000)).unwrap();
+        assert!(output.iter().all(|&x| x == 0.0));
+        assert!(condition_tx_audio(&[], &TxChainParams::new(16000)).unwrap().is_empty());
+
+        for params in [
+            TxChainParams::new(4000),
+            TxChainParams::new(16000).with_bandpass(Some((2700.0, 300.0))),
+            TxChainParams::new(16000).with_bandpass(Some((300.0, 9000.0))),
+            TxChainParams::new(16000).with_compressor(-20.0, 0.5),
+            TxChainParams::new(16000).with_levels(-18.0, 3.0),
+            TxChainParams::new(16000).with_levels(-0.5, -1.0),
+        ] {
+            assert_matches!(
+                condition_tx_audio(&[0.0], &params),
+                Err(DspError::InvalidTxAudioParameter(_)),
+                "{:?}",
+                params
+            );
+        }
+    }
+}
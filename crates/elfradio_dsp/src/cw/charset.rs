@@ -0,0 +1,150 @@
+// 非拉丁字母的莫尔斯电码表：日文和文（Wabun）、俄文西里尔字母、希腊字母。
+
+use super::get_morse_map;
+use std::collections::HashMap;
+
+/// 和文开始信号 DO（`-..---`，连写），之后的电码按和文表解读。
+pub(crate) const WABUN_DO: &str = "-..---";
+/// 和文结束信号 SN（`...-.`，连写），回到欧文。
+pub(crate) const WABUN_SN: &str = "...-.";
+
+/// 浊音符和半浊音符（单独发送的字符）。
+const DAKUTEN: char = '゛';
+const HANDAKUTEN: char = '゜';
+
+const WABUN: &[(char, &str)] = &[
+    ('ア', "--.--"), ('イ', ".-"), ('ウ', "..-"), ('エ', "-.---"), ('オ', ".-..."),
+    ('カ', ".-.."), ('キ', "-.-.."), ('ク', "...-"), ('ケ', "-.--"), ('コ', "----"),
+    ('サ', "-.-.-"), ('シ', "--.-."), ('ス', "---.-"), ('セ', ".---."), ('ソ', "---."),
+    ('タ', "-."), ('チ', "..-."), ('ツ', ".--."), ('テ', ".-.--"), ('ト', "..-.."),
+    ('ナ', ".-."), ('ニ', "-.-."), ('ヌ', "...."), ('ネ', "--.-"), ('ノ', "..--"),
+    ('ハ', "-..."), ('ヒ', "--..-"), ('フ', "--.."), ('ヘ', "."), ('ホ', "-.."),
+    ('マ', "-..-"), ('ミ', "..-.-"), ('ム', "-"), ('メ', "-...-"), ('モ', "-..-."),
+    ('ヤ', ".--"), ('ユ', "-..--"), ('ヨ', "--"),
+    ('ラ', "..."), ('リ', "--."), ('ル', "-.--."), ('レ', "---"), ('ロ', ".-.-"),
+    ('ワ', "-.-"), ('ヰ', ".-..-"), ('ヱ', ".--.."), ('ヲ', ".---"), ('ン', ".-.-."),
+    (DAKUTEN, ".."), (HANDAKUTEN, "..--."), ('ー', ".--.-"), ('、', ".-.-.-"),
+    ('（', "-.--.-"), ('）', ".-..-."),
+];
+
+const CYRILLIC: &[(char, &str)] = &[
+    ('А', ".-"), ('Б', "-..."), ('В', ".--"), ('Г', "--."), ('Д', "-.."), ('Е', "."),
+    ('Ж', "...-"), ('З', "--.."), ('И', ".."), ('Й', ".---"), ('К', "-.-"), ('Л', ".-.."),
+    ('М', "--"), ('Н', "-."), ('О', "---"), ('П', ".--."), ('Р', ".-."), ('С', "..."),
+    ('Т', "-"), ('У', "..-"), ('Ф', "..-."), ('Х', "...."), ('Ц', "-.-."), ('Ч', "---."),
+    ('Ш', "----"), ('Щ', "--.-"), ('Ъ', "--.--"), ('Ы', "-.--"), ('Ь', "-..-"), ('Э', "..-.."),
+    ('Ю', "..--"), ('Я', ".-.-"),
+];
+
+const GREEK: &[(char, &str)] = &[
+    ('Α', ".-"), ('Β', "-..."), ('Γ', "--."), ('Δ', "-.."), ('Ε', "."), ('Ζ', "--.."),
+    ('Η', "...."), ('Θ', "-.-."), ('Ι', ".."), ('Κ', "-.-"), ('Λ', ".-.."), ('Μ', "--"),
+    ('Ν', "-."), ('Ξ', "-..-"), ('Ο', "---"), ('Π', ".--."), ('Ρ', ".-."), ('Σ', "..."),
+    ('Τ', "-"), ('Υ', "-.--"), ('Φ', "..-."), ('Χ', "----"), ('Ψ', "--.-"), ('Ω', ".--"),
+];
+
+/// 浊音假名与对应的清音假名
+const VOICED: [(&str, &str); 2] = [
+    ("ガギグゲゴザジズゼゾダヂヅデドバビブベボヴ", "カキクケコサシスセソタチツテトハヒフヘホウ"),
+    ("パピプペポ", "ハヒフヘホ"),
+];
+/// 小写假名与对应的大写假名
+const SMALL_KANA: (&str, &str) = ("ァィゥェォッャュョヮヵヶ", "アイウエオツヤユヨワカケ");
+/// 带重音的希腊字母
+const GREEK_TONOS: (&str, &str) = ("ΆΈΉΊΌΎΏΪΫ", "ΑΕΗΙΟΥΩΙΥ");
+
+/// CW 收发使用的字符集，每次调用单独选择。
+///
+/// 数字和标点在所有字符集中都按欧文电码发送。和文段落以 DO 开始、以 SN 结束。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MorseCharset {
+    /// 拉丁字母（国际莫尔斯电码）
+    #[default]
+    Latin,
+    /// 日文和文电码（片假名；平假名发送时转换为片假名）
+    Wabun,
+    /// 俄文西里尔字母
+    Cyrillic,
+    /// 希腊字母
+    Greek,
+}
+
+impl MorseCharset {
+    /// 本字符集的字母表（不含数字和标点）。
+    pub(crate) fn letters(self) -> HashMap<char, &'static str> {
+        match self {
+            MorseCharset::Latin => get_morse_map().into_iter().filter(|(c, _)| c.is_ascii_alphabetic()).collect(),
+            MorseCharset::Wabun => WABUN.iter().copied().collect(),
+            MorseCharset::Cyrillic => CYRILLIC.iter().copied().collect(),
+            MorseCharset::Greek => GREEK.iter().copied().collect(),
+        }
+    }
+
+    /// 电码到字符的解码表：字母优先于编码相同的欧文标点。
+    pub(crate) fn decode_table(self) -> HashMap<&'static str, char> {
+        let mut table: HashMap<&'static str, char> =
+            get_morse_map().into_iter().filter(|(c, _)| !c.is_ascii_alphabetic()).map(|(c, code)| (code, c)).collect();
+        table.extend(self.letters().into_iter().map(|(c, code)| (code, c)));
+        table
+    }
+
+    /// 把输入字符转换成电码表中的形式（大写、去重音、片假名），
+    /// 和文的浊音/半浊音假名拆成清音假名加浊点。
+    pub(crate) fn normalize(self, character: char) -> (char, Option<char>) {
+        let upper = character.to_uppercase().next().unwrap_or(character);
+        match self {
+            MorseCharset::Latin => (upper, None),
+            MorseCharset::Cyrillic => (if upper == 'Ё' { 'Е' } else { upper }, None),
+            MorseCharset::Greek => {
+                let upper = if character == 'ς' { 'Σ' } else { upper };
+                (swap(upper, GREEK_TONOS).unwrap_or(upper), None)
+            }
+            MorseCharset::Wabun => {
+                // 平假名 → 片假名
+                let kana = match character {
+                    'ぁ'..='ゖ' => char::from_u32(character as u32 + 0x60).unwrap_or(character),
+                    _ => character,
+                };
+                let kana = swap(kana, SMALL_KANA).unwrap_or(kana);
+                for (marks, (voiced, plain)) in [DAKUTEN, HANDAKUTEN].into_iter().zip(VOICED) {
+                    if let Some(base) = swap(kana, (voiced, plain)) {
+                        return (base, Some(marks));
+                    }
+                }
+                (kana, None)
+            }
+        }
+    }
+}
+
+/// 在 `from` 中找到 `c` 时返回 `to` 中同一位置的字符。
+fn swap(c: char, (from, to): (&str, &str)) -> Option<char> {
+    from.chars().position(|f| f == c).and_then(|i| to.chars().nth(i))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_have_unique_codes() {
+        for charset in [MorseCharset::Latin, MorseCharset::Wabun, MorseCharset::Cyrillic, MorseCharset::Greek] {
+            let letters = charset.letters();
+            let codes: std::collections::HashSet<_> = letters.values().collect();
+            assert_eq!(codes.len(), letters.len(), "{:?}", charset);
+            assert!(!codes.contains(&WABUN_DO) && !codes.contains(&WABUN_SN));
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(MorseCharset::Latin.normalize('q'), ('Q', None));
+        assert_eq!(MorseCharset::Cyrillic.normalize('ё'), ('Е', None));
+        assert_eq!(MorseCharset::Greek.normalize('ς'), ('Σ', None));
+        assert_eq!(MorseCharset::Greek.normalize('ώ'), ('Ω', None));
+        assert_eq!(MorseCharset::Wabun.normalize('ガ'), ('カ', Some('゛')));
+        assert_eq!(MorseCharset::Wabun.normalize('ぽ'), ('ホ', Some('゜')));
+        assert_eq!(MorseCharset::Wabun.normalize('っ'), ('ツ', None));
+        assert_eq!(MorseCharset::Wabun.normalize('ヴ'), ('ウ', Some('゛')));
+    }
+}
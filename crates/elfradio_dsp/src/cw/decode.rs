@@ -0,0 +1,485 @@
+// CW (Morse) receive decoder.
+//
+// Audio → Goertzel tone level per 5 ms block → adaptive mark/space threshold →
+// key-down/key-up runs → dot length estimate (follows the sender's speed) → text.
+
+use super::charset::{MorseCharset, WABUN_DO, WABUN_SN};
+use super::{get_morse_map, prosign_code, DECODED_PROSIGNS};
+use crate::error::DspError;
+use crate::goertzel::goertzel_power;
+use std::collections::{HashMap, VecDeque};
+use tracing::{debug, trace};
+
+/// Length of one detector block.
+const BLOCK_MS: f64 = 5.0;
+/// A change of key state must last this many blocks; shorter blips are noise.
+const DEBOUNCE_BLOCKS: u32 = 2;
+/// Marks kept for the speed estimate.
+const MARK_HISTORY: usize = 12;
+/// Speed assumed until the first marks are heard.
+const DEFAULT_WPM: f32 = 20.0;
+const MIN_WPM: f32 = 5.0;
+const MAX_WPM: f32 = 60.0;
+/// The tone peak must stand this far above the noise level.
+const MIN_SNR: f32 = 6.0;
+/// Key-down / key-up thresholds, as a fraction of the way from noise to peak.
+const MARK_THRESHOLD: f32 = 0.5;
+const SPACE_THRESHOLD: f32 = 0.35;
+/// Time constants of the peak and noise trackers (ms).
+const PEAK_DECAY_MS: f64 = 2000.0;
+const NOISE_TRACK_MS: f64 = 500.0;
+/// The noise estimate falls this many times faster than it rises.
+const NOISE_FALL_RATE: f32 = 8.0;
+/// Written for element sequences that aren't in the Morse table.
+const UNKNOWN_CHAR: char = '*';
+
+/// Goertzel filter measuring the tone amplitude over one block.
+///
+/// The block is Hann windowed so strong signals a few hundred hertz away don't leak in.
+struct Goertzel {
+    tone_hz: f64,
+    sample_rate: f64,
+    window: Vec<f32>,
+    block: Vec<f32>,
+}
+
+impl Goertzel {
+    fn new(tone_hz: f32, sample_rate: u32, len: usize) -> Self {
+        let window: Vec<f32> = (0..len)
+            .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * (n as f32 + 0.5) / len as f32).cos())
+            .collect();
+        Self { tone_hz: tone_hz as f64, sample_rate: sample_rate as f64, window, block: Vec::with_capacity(len) }
+    }
+
+    /// Feeds one sample; returns the tone amplitude when a block completes.
+    fn process(&mut self, sample: f32) -> Option<f32> {
+        self.block.push(sample * self.window[self.block.len()]);
+        if self.block.len() < self.window.len() {
+            return None;
+        }
+        let power = goertzel_power(&self.block, self.tone_hz, self.sample_rate);
+        self.block.clear();
+        // A sine of amplitude A reads A²/2, halved in amplitude by the window (mean 1/2).
+        Some((2.0 * (2.0 * power).sqrt()) as f32)
+    }
+}
+
+/// Streaming CW decoder for a single tone frequency.
+pub struct CwDecoder {
+    goertzel: Goertzel,
+    block_secs: f64,
+    peak: f32,
+    noise: Option<f32>,
+    /// Blocks seen so far, for averaging the first noise estimate.
+    blocks: u32,
+    peak_decay: f32,
+    noise_alpha: f32,
+    key_down: bool,
+    /// Blocks in the current (committed) key state.
+    run: u32,
+    /// Consecutive blocks disagreeing with the key state.
+    pending: u32,
+    /// Estimated dot length in blocks.
+    dot_blocks: f32,
+    marks: VecDeque<u32>,
+    /// Mark lengths (blocks) of the character being received.
+    elements: Vec<u32>,
+    /// Whether a word space may be written (something was decoded since the last one).
+    word_open: bool,
+    table: HashMap<&'static str, char>,
+    /// Element sequences written as `<AR>` etc. instead of the matching punctuation.
+    prosigns: HashMap<String, &'static str>,
+    /// Kana table when listening for Wabun, and whether a DO has switched to it.
+    wabun: Option<HashMap<&'static str, char>>,
+    wabun_active: bool,
+    output: String,
+}
+
+impl CwDecoder {
+    /// Creates a decoder listening for a CW tone at `tone_hz`.
+    pub fn new(sample_rate: u32, tone_hz: f32) -> Result<Self, DspError> {
+        if sample_rate == 0 {
+            return Err(DspError::InvalidCwParameter("sample rate cannot be zero".to_string()));
+        }
+        if !(tone_hz > 0.0 && tone_hz < sample_rate as f32 / 2.0) {
+            return Err(DspError::InvalidCwParameter(format!(
+                "tone {} Hz is outside 0..{} Hz",
+                tone_hz,
+                sample_rate / 2
+            )));
+        }
+        let block_len = ((sample_rate as f64 * BLOCK_MS / 1000.0).round() as usize).max(8);
+        let block_secs = block_len as f64 / sample_rate as f64;
+        let block_ms = block_secs * 1000.0;
+        let morse_map = get_morse_map();
+        let prosigns = DECODED_PROSIGNS
+            .iter()
+            .filter_map(|&name| Some((prosign_code(name, &morse_map)?, name)))
+            .collect();
+        let table = MorseCharset::Latin.decode_table();
+        let mut decoder = Self {
+            goertzel: Goertzel::new(tone_hz, sample_rate, block_len),
+            block_secs,
+            peak: 0.0,
+            noise: None,
+            blocks: 0,
+            peak_decay: (-block_ms / PEAK_DECAY_MS).exp() as f32,
+            noise_alpha: (1.0 - (-block_ms / NOISE_TRACK_MS).exp()) as f32,
+            key_down: false,
+            run: 0,
+            pending: 0,
+            dot_blocks: 0.0,
+            marks: VecDeque::with_capacity(MARK_HISTORY),
+            elements: Vec::new(),
+            word_open: false,
+            table,
+            prosigns,
+            wabun: None,
+            wabun_active: false,
+            output: String::new(),
+        };
+        decoder.dot_blocks = decoder.wpm_to_dot_blocks(DEFAULT_WPM);
+        Ok(decoder)
+    }
+
+    /// Starts from `wpm` instead of the default 20 WPM, so the first characters decode cleanly.
+    pub fn with_wpm(mut self, wpm: f32) -> Self {
+        self.dot_blocks = self.wpm_to_dot_blocks(wpm);
+        self
+    }
+
+    /// Decodes letters of `charset`. For Wabun the decoder starts in Latin and
+    /// switches on DO / SN.
+    pub fn with_charset(mut self, charset: MorseCharset) -> Self {
+        if charset == MorseCharset::Wabun {
+            self.table = MorseCharset::Latin.decode_table();
+            self.wabun = Some(charset.decode_table());
+        } else {
+            self.table = charset.decode_table();
+            self.wabun = None;
+        }
+        self.wabun_active = false;
+        self
+    }
+
+    /// Current speed estimate in words per minute (PARIS timing).
+    pub fn wpm(&self) -> f32 {
+        (1.2 / (self.dot_blocks as f64 * self.block_secs)) as f32
+    }
+
+    /// Feeds audio and returns any text decoded from it.
+    pub fn push(&mut self, samples: &[f32]) -> String {
+        for &sample in samples {
+            if let Some(level) = self.goertzel.process(sample) {
+                self.process_block(level);
+            }
+        }
+        std::mem::take(&mut self.output)
+    }
+
+    /// Ends the current mark or character and returns the remaining text.
+    pub fn flush(&mut self) -> String {
+        if self.key_down {
+            self.end_mark(self.run + self.pending);
+            self.key_down = false;
+        }
+        self.run = 0;
+        self.pending = 0;
+        self.end_char();
+        std::mem::take(&mut self.output)
+    }
+
+    fn wpm_to_dot_blocks(&self, wpm: f32) -> f32 {
+        let wpm = wpm.clamp(MIN_WPM, MAX_WPM);
+        (1.2 / (wpm as f64 * self.block_secs)) as f32
+    }
+
+    fn process_block(&mut self, level: f32) {
+        // Peak follows the tone at once and decays slowly. The noise level drops
+        // faster than it rises (so a signal at start-up doesn't hide the next one)
+        // and only rises between marks.
+        self.peak = if level > self.peak { level } else { self.peak * self.peak_decay };
+        let noise = self.noise.unwrap_or(level);
+        self.blocks = self.blocks.saturating_add(1);
+        // Plain average until the tracker's time constant is reached.
+        let warm_up = 1.0 / self.blocks as f32;
+        let (rise, fall) = if warm_up > self.noise_alpha {
+            (warm_up, warm_up)
+        } else {
+            (self.noise_alpha, (self.noise_alpha * NOISE_FALL_RATE).min(1.0))
+        };
+        let range = self.peak - noise;
+        let is_mark = if self.key_down {
+            level >= noise + SPACE_THRESHOLD * range
+        } else {
+            level > noise + MARK_THRESHOLD * range && self.peak > MIN_SNR * noise
+        };
+        if level < noise {
+            self.noise = Some(noise + (level - noise) * fall);
+        } else if !self.key_down && !is_mark {
+            self.noise = Some(noise + (level - noise) * rise);
+        }
+
+        if is_mark == self.key_down {
+            self.run += 1 + self.pending;
+            self.pending = 0;
+        } else {
+            self.pending += 1;
+            if self.pending >= DEBOUNCE_BLOCKS {
+                if self.key_down {
+                    self.end_mark(self.run);
+                }
+                self.key_down = is_mark;
+                self.run = self.pending;
+                self.pending = 0;
+            }
+        }
+
+        if !self.key_down {
+            // Characters and word spaces are written as soon as the gap is long enough.
+            let gap = self.run as f32 / self.dot_blocks;
+            if gap >= 2.0 {
+                self.end_char();
+            }
+            if gap >= 5.0 && self.word_open {
+                self.output.push(' ');
+                self.word_open = false;
+            }
+        }
+    }
+
+    fn end_mark(&mut self, blocks: u32) {
+        if self.marks.len() == MARK_HISTORY {
+            self.marks.pop_front();
+        }
+        self.marks.push_back(blocks);
+        self.update_speed();
+        trace!(blocks, dot_blocks = self.dot_blocks, "CW mark");
+        self.elements.push(blocks);
+    }
+
+    fn end_char(&mut self) {
+        if self.elements.is_empty() {
+            return;
+        }
+        // Classified only now, so the first marks use the estimate they helped make.
+        let elements: String = self
+            .elements
+            .iter()
+            .map(|&blocks| if (blocks as f32) < 2.0 * self.dot_blocks { '.' } else { '-' })
+            .collect();
+        let shift = if self.wabun_active { WABUN_SN } else { WABUN_DO };
+        if self.wabun.is_some() && elements == shift {
+            self.wabun_active = !self.wabun_active;
+            debug!(wabun = self.wabun_active, "CW Wabun shift");
+            self.elements.clear();
+            return;
+        }
+        let table = match &self.wabun {
+            Some(kana) if self.wabun_active => kana,
+            _ => &self.table,
+        };
+        if let Some(name) = self.prosigns.get(&elements).filter(|_| !self.wabun_active) {
+            debug!(%elements, prosign = name, wpm = self.wpm(), "CW prosign");
+            self.output.push_str(&format!("<{}>", name));
+        } else {
+            let c = table.get(elements.as_str()).copied().unwrap_or(UNKNOWN_CHAR);
+            debug!(%elements, %c, wpm = self.wpm(), "CW character");
+            self.output.push(c);
+        }
+        self.elements.clear();
+        self.word_open = true;
+    }
+
+    /// Re-estimates the dot length from recent marks.
+    ///
+    /// The marks are split into a short and a long group (in log scale). If the
+    /// groups are at least 2:1 apart they are dots and dashes; otherwise all
+    /// recent marks are the same kind, judged against the previous estimate.
+    fn update_speed(&mut self) {
+        let mut marks: Vec<f32> = self.marks.iter().map(|&m| m as f32).collect();
+        marks.sort_by(f32::total_cmp);
+        let logs: Vec<f32> = marks.iter().map(|m| m.ln()).collect();
+        let spread = |group: &[f32]| {
+            let mean = group.iter().sum::<f32>() / group.len() as f32;
+            group.iter().map(|v| (v - mean).powi(2)).sum::<f32>()
+        };
+        let split = (1..marks.len()).min_by(|&a, &b| {
+            let cost = |k: usize| spread(&logs[..k]) + spread(&logs[k..]);
+            cost(a).total_cmp(&cost(b))
+        });
+        let mean = |group: &[f32]| group.iter().sum::<f32>() / group.len() as f32;
+
+        let dot = match split {
+            Some(k) if mean(&marks[k..]) >= 2.0 * mean(&marks[..k]) => {
+                (marks[..k].iter().sum::<f32>() + marks[k..].iter().sum::<f32>() / 3.0) / marks.len() as f32
+            }
+            _ => {
+                let all = mean(&marks);
+                if all < 2.0 * self.dot_blocks { all } else { all / 3.0 }
+            }
+        };
+        let (min, max) = (self.wpm_to_dot_blocks(MAX_WPM), self.wpm_to_dot_blocks(MIN_WPM));
+        self.dot_blocks = dot.clamp(min, max);
+    }
+}
+
+/// Decodes a complete CW recording with a tone at `tone_hz`.
+pub fn decode_cw(samples: &[f32], sample_rate: u32, tone_hz: f32, charset: MorseCharset) -> Result<String, DspError> {
+    let mut decoder = CwDecoder::new(sample_rate, tone_hz)?.with_charset(charset);
+    let mut text = decoder.push(samples);
+    text.push_str(&decoder.flush());
+    debug!(wpm = decoder.wpm(), "Decoded {} CW characters", text.len());
+    Ok(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cw::{generate_cw_audio, CwParams};
+    use crate::fixtures::add_noise;
+    use assert_matches::assert_matches;
+
+    fn silence(secs: f32, rate: u32) -> Vec<f32> {
+        vec![0.0; (secs * rate as f32) as usize]
+    }
+
+    /// `text` keyed word by word with the standard 7-dot word gap, so these tests don't
+    /// depend on how the encoder spaces words.
+    fn keyed(text: &str, params: &CwParams) -> Vec<f32> {
+        let word_gap = silence(7.0 * 1.2 / params.wpm as f32, params.sample_rate);
+        let mut audio = Vec::new();
+        for (n, word) in text.split(' ').enumerate() {
+            if n > 0 {
+                audio.extend(&word_gap);
+            }
+            audio.extend(generate_cw_audio(word, params).unwrap());
+        }
+        audio
+    }
+
+    /// Keyed `text` with some quiet before and after, as a receiver hears it.
+    fn transmission(text: &str, wpm: u32, freq: f32, rate: u32) -> Vec<f32> {
+        let mut audio = silence(0.3, rate);
+        audio.extend(keyed(text, &CwParams::new(wpm, freq, rate)));
+        audio.extend(silence(0.3, rate));
+        audio
+    }
+
+    #[test]
+    fn test_round_trip_at_several_speeds_and_rates() {
+        let text = "CQ CQ DE VK7KSM K";
+        for (wpm, rate) in [(12, 8000), (20, 48000), (35, 8000), (45, 16000)] {
+            let audio = transmission(text, wpm, 700.0, rate);
+            let mut decoder = CwDecoder::new(rate, 700.0).unwrap();
+            let mut decoded = decoder.push(&audio);
+            decoded.push_str(&decoder.flush());
+            assert_eq!(decoded.trim(), text, "{} WPM at {} Hz", wpm, rate);
+            // The keying edges make marks read a little short, most of all at high speed.
+            assert!((decoder.wpm() - wpm as f32).abs() < wpm as f32 * 0.15, "{} WPM estimated as {}", wpm, decoder.wpm());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_with_noise() {
+        let text = "VK7KSM DE VK3ABC UR RST 599 73 TU";
+        let rate = 8000;
+        let mut audio = transmission(text, 22, 650.0, rate);
+        add_noise(&mut audio, 0.4, 0x5eed);
+        assert_eq!(decode_cw(&audio, rate, 650.0, MorseCharset::Latin).unwrap(), text);
+    }
+
+    #[test]
+    fn test_tracks_speed_changes() {
+        let rate = 8000;
+        // Gradual drift, one word per speed step, starting well below the default guess.
+        let words = ["CQ", "CQ", "DE", "VK7KSM", "VK7KSM", "PSE", "K"];
+        let mut audio = silence(0.3, rate);
+        for (n, word) in words.iter().enumerate() {
+            let wpm = 12 + 3 * n as u32;
+            audio.extend(generate_cw_audio(word, &CwParams::new(wpm, 700.0, rate)).unwrap());
+            audio.extend(silence(7.0 * 1.2 / wpm as f32, rate));
+        }
+        add_noise(&mut audio, 0.2, 7);
+        assert_eq!(decode_cw(&audio, rate, 700.0, MorseCharset::Latin).unwrap(), words.join(" "));
+
+        // An abrupt jump from 15 to 30 WPM settles within a couple of characters.
+        let mut audio = transmission("VVV DE VK7KSM", 15, 700.0, rate);
+        audio.extend(transmission("TEST TEST 599 BK", 30, 700.0, rate));
+        let mut decoder = CwDecoder::new(rate, 700.0).unwrap().with_wpm(15.0);
+        let mut decoded = decoder.push(&audio);
+        decoded.push_str(&decoder.flush());
+        assert!(decoded.starts_with("VVV DE VK7KSM "), "{:?}", decoded);
+        assert!(decoded.ends_with("TEST 599 BK "), "{:?}", decoded);
+        assert!((decoder.wpm() - 30.0).abs() < 3.0, "estimated {} WPM", decoder.wpm());
+    }
+
+    #[test]
+    fn test_streaming_emits_characters_during_gaps() {
+        let rate = 8000;
+        let audio = transmission("HI HI", 20, 700.0, rate);
+        let mut decoder = CwDecoder::new(rate, 700.0).unwrap();
+        let decoded: String = audio.chunks(97).map(|chunk| decoder.push(chunk)).collect();
+        // The trailing silence is long enough to finish the last character and the word.
+        assert_eq!(decoded, "HI HI ");
+        assert_eq!(decoder.flush(), "");
+    }
+
+    #[test]
+    fn test_noise_alone_decodes_nothing() {
+        let rate = 8000;
+        let mut audio = silence(5.0, rate);
+        add_noise(&mut audio, 0.3, 42);
+        assert_eq!(decode_cw(&audio, rate, 700.0, MorseCharset::Latin).unwrap(), "");
+        // A different tone isn't copied either.
+        let mut other = transmission("TEST", 20, 1500.0, rate);
+        add_noise(&mut other, 0.05, 43);
+        assert_eq!(decode_cw(&other, rate, 700.0, MorseCharset::Latin).unwrap(), "");
+    }
+
+    #[test]
+    fn test_prosigns() {
+        let text = "TNX QSO <BT> 73 <SK>";
+        assert_eq!(decode_cw(&transmission(text, 25, 700.0, 8000), 8000, 700.0, MorseCharset::Latin).unwrap(), text);
+        // '+' and <AR> are the same sound; the decoder writes the prosign.
+        assert_eq!(decode_cw(&transmission("QRT +", 25, 700.0, 8000), 8000, 700.0, MorseCharset::Latin).unwrap(), "QRT <AR>");
+    }
+
+    #[test]
+    fn test_non_latin_charsets() {
+        let rate = 8000;
+        let round_trip = |text: &str, charset| {
+            let params = CwParams::new(22, 700.0, rate).with_charset(charset);
+            let mut audio = silence(0.3, rate);
+            audio.extend(keyed(text, &params));
+            audio.extend(silence(0.3, rate));
+            decode_cw(&audio, rate, 700.0, charset).unwrap()
+        };
+        assert_eq!(round_trip("ПРИВЕТ ИЗ МОСКВЫ 73", MorseCharset::Cyrillic), "ПРИВЕТ ИЗ МОСКВЫ 73");
+        assert_eq!(round_trip("Καλημέρα ΨΩ", MorseCharset::Greek), "ΚΑΛΗΜΕΡΑ ΨΩ");
+        // Latin call signs around a Wabun section; the voicing mark is its own character.
+        assert_eq!(
+            round_trip("JA1ABC DE JH1XYZ ワタシハ やまだ デス 5NN", MorseCharset::Wabun),
+            "JA1ABC DE JH1XYZ ワタシハ ヤマタ゛ テ゛ス 5NN"
+        );
+        // Without DO the same sounds are Latin.
+        let params = CwParams::new(22, 700.0, rate);
+        let mut audio = silence(0.3, rate);
+        audio.extend(generate_cw_audio("ANT", &params).unwrap());
+        audio.extend(silence(0.3, rate));
+        assert_eq!(decode_cw(&audio, rate, 700.0, MorseCharset::Wabun).unwrap(), "ANT");
+    }
+
+    #[test]
+    fn test_unknown_sequence_and_invalid_parameters() {
+        // Eight dots isn't a character (it's the "error" prosign): close up the gap in "HH".
+        let dot = generate_cw_audio("E", &CwParams::new(20, 700.0, 8000)).unwrap().len();
+        let mut audio = transmission("HH", 20, 700.0, 8000);
+        let h_end = silence(0.3, 8000).len() + 7 * dot;
+        audio.splice(h_end..h_end + 3 * dot, std::iter::repeat_n(0.0, dot));
+        assert_eq!(decode_cw(&audio, 8000, 700.0, MorseCharset::Latin).unwrap(), "*");
+
+        assert_matches!(CwDecoder::new(0, 700.0).err(), Some(DspError::InvalidCwParameter(_)));
+        assert_matches!(CwDecoder::new(8000, 4000.0).err(), Some(DspError::InvalidCwParameter(_)));
+    }
+}
@@ -0,0 +1,279 @@
+// DTMF (dual-tone multi-frequency) signalling: each key is one low "row" tone plus
+// one high "column" tone.
+//
+// Detection: 20 ms Goertzel blocks every 10 ms → strongest row and column, checked for
+// level, purity, group margin and twist → two agreeing blocks to press or release a key.
+
+use crate::error::DspError;
+use crate::goertzel::goertzel_power;
+use std::f64::consts::PI;
+use tracing::{debug, info, trace, warn};
+
+const ROW_HZ: [f64; 4] = [697.0, 770.0, 852.0, 941.0];
+const COLUMN_HZ: [f64; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+const KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Shortest tone and gap the detector is expected to resolve (ITU-T Q.24 minimum).
+const MIN_TIMING_MS: u64 = 40;
+/// Raised-cosine ramp at each end of a generated tone pair.
+const RAMP_MS: f64 = 2.0;
+const BLOCK_MS: f64 = 20.0;
+const HOP_MS: f64 = 10.0;
+/// Lowest tone pair power the detector accepts (about -50 dBFS).
+const MIN_POWER: f64 = 1e-5;
+/// Share of the block's power that must be in the two tones.
+const MIN_PURITY: f64 = 0.75;
+/// How far (dB) the other tones of each group must be below the strongest.
+const MIN_GROUP_MARGIN_DB: f64 = 6.0;
+/// Largest level difference (dB) between the row and column tones.
+const MAX_TWIST_DB: f64 = 8.0;
+/// Consecutive blocks that must agree before a key press or release counts.
+const CONFIRM_BLOCKS: u32 = 2;
+
+fn key_position(key: char) -> Option<(usize, usize)> {
+    let key = key.to_ascii_uppercase();
+    KEYS.iter().enumerate().find_map(|(row, keys)| keys.iter().position(|&k| k == key).map(|column| (row, column)))
+}
+
+/// DTMF generator settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DtmfParams {
+    pub sample_rate: u32,
+    /// Tone length of each key (milliseconds).
+    pub mark_ms: u64,
+    /// Silence between keys (milliseconds).
+    pub space_ms: u64,
+    /// Peak amplitude of the tone pair (0.0 to 1.0); each tone gets half.
+    pub amplitude: f32,
+}
+
+impl DtmfParams {
+    /// 100 ms tones with 100 ms gaps.
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, mark_ms: 100, space_ms: 100, amplitude: 0.85 }
+    }
+
+    pub fn with_timing(mut self, mark_ms: u64, space_ms: u64) -> Self {
+        self.mark_ms = mark_ms;
+        self.space_ms = space_ms;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidDtmfParameter(msg));
+        // The highest column tone and its ramp sidebands need to stay below Nyquist.
+        if self.sample_rate < 4000 {
+            return invalid(format!("sample rate {} Hz is too low for DTMF", self.sample_rate));
+        }
+        if self.mark_ms < MIN_TIMING_MS || self.space_ms < MIN_TIMING_MS {
+            return invalid(format!(
+                "{} ms tones with {} ms gaps are shorter than the {} ms minimum",
+                self.mark_ms, self.space_ms, MIN_TIMING_MS
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return invalid(format!("amplitude {} is outside 0..1", self.amplitude));
+        }
+        Ok(())
+    }
+}
+
+/// Generates DTMF audio for `digits` (0-9, A-D, * and #).
+///
+/// Each key is a tone pair of `mark_ms` followed by `space_ms` of silence, except the last.
+pub fn generate_dtmf_audio(digits: &str, params: &DtmfParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Generating DTMF audio: {:?}, Digits='{}'", params, digits);
+
+    let rate = params.sample_rate as f64;
+    let mark = (params.mark_ms as f64 * rate / 1000.0).round() as usize;
+    let space = (params.space_ms as f64 * rate / 1000.0).round() as usize;
+    let ramp = (RAMP_MS * rate / 1000.0).round() as usize;
+    let mut audio = Vec::new();
+    for key in digits.chars().filter(|c| !c.is_whitespace()) {
+        let Some((row, column)) = key_position(key) else {
+            warn!("Unsupported character for DTMF encoding: '{}'. Skipping.", key);
+            continue;
+        };
+        if !audio.is_empty() {
+            audio.resize(audio.len() + space, 0.0);
+        }
+        let steps = [ROW_HZ[row], COLUMN_HZ[column]].map(|hz| 2.0 * PI * hz / rate);
+        for n in 0..mark {
+            let edge = n.min(mark - 1 - n);
+            let envelope = if edge < ramp { 0.5 - 0.5 * (PI * edge as f64 / ramp as f64).cos() } else { 1.0 };
+            let tones = steps.iter().map(|step| (step * n as f64).sin()).sum::<f64>();
+            audio.push((params.amplitude as f64 * 0.5 * envelope * tones) as f32);
+        }
+    }
+
+    info!(
+        "Generated DTMF audio with {} samples (approx {:.2} seconds)",
+        audio.len(),
+        audio.len() as f64 / rate
+    );
+    Ok(audio)
+}
+
+/// Index and power of the strongest tone, if every other tone is far enough below it.
+fn strongest(powers: &[f64; 4]) -> Option<(usize, f64)> {
+    let (index, &max) = powers.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1))?;
+    let margin = 10f64.powf(MIN_GROUP_MARGIN_DB / 10.0);
+    powers.iter().enumerate().all(|(n, &p)| n == index || p * margin <= max).then_some((index, max))
+}
+
+/// Streaming DTMF detector.
+pub struct DtmfDetector {
+    sample_rate: f64,
+    block_len: usize,
+    hop_len: usize,
+    buffer: Vec<f32>,
+    /// Key reported for the tone now present, if any.
+    current: Option<char>,
+    candidate: Option<char>,
+    candidate_blocks: u32,
+    output: String,
+}
+
+impl DtmfDetector {
+    pub fn new(sample_rate: u32) -> Result<Self, DspError> {
+        DtmfParams::new(sample_rate).validate()?;
+        let rate = sample_rate as f64;
+        let block_len = (BLOCK_MS * rate / 1000.0).round() as usize;
+        Ok(Self {
+            sample_rate: rate,
+            block_len,
+            hop_len: (HOP_MS * rate / 1000.0).round() as usize,
+            buffer: Vec::with_capacity(block_len * 2),
+            current: None,
+            candidate: None,
+            candidate_blocks: 0,
+            output: String::new(),
+        })
+    }
+
+    /// Feeds audio and returns the keys pressed in it, one character per press.
+    pub fn push(&mut self, samples: &[f32]) -> String {
+        self.buffer.extend_from_slice(samples);
+        let mut start = 0;
+        while self.buffer.len() - start >= self.block_len {
+            let key = self.detect(&self.buffer[start..start + self.block_len]);
+            self.update(key);
+            start += self.hop_len;
+        }
+        self.buffer.drain(..start);
+        std::mem::take(&mut self.output)
+    }
+
+    fn detect(&self, block: &[f32]) -> Option<char> {
+        let total = block.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / block.len() as f64;
+        let powers = |freqs: &[f64; 4]| freqs.map(|freq| goertzel_power(block, freq, self.sample_rate));
+        let (row, row_power) = strongest(&powers(&ROW_HZ))?;
+        let (column, column_power) = strongest(&powers(&COLUMN_HZ))?;
+        let pair = row_power + column_power;
+        let twist_db = 10.0 * (row_power / column_power).log10();
+        if pair < MIN_POWER || pair < MIN_PURITY * total || twist_db.abs() > MAX_TWIST_DB {
+            return None;
+        }
+        Some(KEYS[row][column])
+    }
+
+    fn update(&mut self, key: Option<char>) {
+        if key == self.candidate {
+            self.candidate_blocks += 1;
+        } else {
+            self.candidate = key;
+            self.candidate_blocks = 1;
+        }
+        if self.candidate_blocks >= CONFIRM_BLOCKS && self.candidate != self.current {
+            self.current = self.candidate;
+            if let Some(key) = self.current {
+                trace!("DTMF key {}", key);
+                self.output.push(key);
+            }
+        }
+    }
+}
+
+/// Decodes the keys in a complete recording.
+pub fn decode_dtmf(samples: &[f32], sample_rate: u32) -> Result<String, DspError> {
+    let mut detector = DtmfDetector::new(sample_rate)?;
+    let digits = detector.push(samples);
+    debug!("Decoded {} DTMF keys", digits.len());
+    Ok(digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::add_noise;
+    use assert_matches::assert_matches;
+
+    fn tones(freqs: &[f64], ms: u64, rate: u32) -> Vec<f32> {
+        let len = (ms * rate as u64 / 1000) as usize;
+        (0..len)
+            .map(|n| freqs.iter().map(|f| (2.0 * PI * f * n as f64 / rate as f64).sin() as f32 * 0.3).sum())
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let keys = "123A456B789C*0#D";
+        for rate in [8000, 16000, 44100, 48000] {
+            let params = DtmfParams::new(rate).with_timing(50, 50);
+            let audio = generate_dtmf_audio(&keys.to_lowercase(), &params).unwrap();
+            assert_eq!(decode_dtmf(&audio, rate).unwrap(), keys, "{} Hz", rate);
+        }
+    }
+
+    #[test]
+    fn test_repeated_keys_noise_and_streaming() {
+        let params = DtmfParams::new(16000).with_timing(60, 45);
+        let mut audio = vec![0.0; 1234];
+        audio.extend(generate_dtmf_audio("1 1 9 9 #", &params).unwrap());
+        audio.extend(vec![0.0; 800]);
+        add_noise(&mut audio, 0.1, 4);
+        let mut detector = DtmfDetector::new(16000).unwrap();
+        let keys: String = audio.chunks(333).map(|chunk| detector.push(chunk)).collect();
+        assert_eq!(keys, "1199#");
+    }
+
+    #[test]
+    fn test_rejects_non_dtmf() {
+        let rate = 8000;
+        // A lone row tone, a third tone on top of a key, a short blip and a 12 dB twist.
+        assert_eq!(decode_dtmf(&tones(&[697.0], 200, rate), rate).unwrap(), "");
+        assert_eq!(decode_dtmf(&tones(&[697.0, 1209.0, 1000.0], 200, rate), rate).unwrap(), "");
+        assert_eq!(decode_dtmf(&tones(&[697.0, 1209.0], 15, rate), rate).unwrap(), "");
+        let mut twisted = tones(&[697.0], 200, rate);
+        twisted.iter_mut().zip(tones(&[1209.0], 200, rate)).for_each(|(s, c)| *s = *s * 0.25 + c);
+        assert_eq!(decode_dtmf(&twisted, rate).unwrap(), "");
+
+        let mut noise = vec![0.0; rate as usize * 3];
+        add_noise(&mut noise, 0.3, 8);
+        assert_eq!(decode_dtmf(&noise, rate).unwrap(), "");
+    }
+
+    #[test]
+    fn test_generator() {
+        let params = DtmfParams::new(8000);
+        let audio = generate_dtmf_audio("5x5", &params).unwrap();
+        // Two keys of 800 samples with one 800 sample gap; 'x' is skipped.
+        assert_eq!(audio.len(), 3 * 800);
+        assert!(audio.iter().all(|s| s.abs() <= 0.85));
+        assert!(audio[..4].iter().all(|s| s.abs() < 0.05));
+        assert!(audio[800..1600].iter().all(|&s| s == 0.0));
+
+        assert_matches!(
+            generate_dtmf_audio("1", &params.clone().with_timing(20, 100)),
+            Err(DspError::InvalidDtmfParameter(_))
+        );
+        assert_matches!(generate_dtmf_audio("1", &DtmfParams::new(3000)), Err(DspError::InvalidDtmfParameter(_)));
+        assert_matches!(DtmfDetector::new(0).err(), Some(DspError::InvalidDtmfParameter(_)));
+    }
+}
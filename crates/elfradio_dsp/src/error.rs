@@ -25,6 +25,12 @@ pub enum DspError {
     #[error("SSTV encoding failed: {0}")]
     SstvEncodeError(String),
 
+    #[error("SSTV decoding failed: {0}")]
+    SstvDecodeError(String),
+
+    #[error("Unsupported SSTV mode: {0}")]
+    UnsupportedSstvMode(String),
+
     #[error("I/O error: {0}")]
     IoError(#[from] io::Error),
 
@@ -41,4 +47,44 @@ pub enum DspError {
     InvalidWpm(u32),
     #[error("Unsupported character for CW encoding: '{0}'")]
     UnsupportedCharacter(char),
+    #[error("Invalid CW parameter: {0}")]
+    InvalidCwParameter(String),
+
+    // --- Digital Mode Errors ---
+    #[error("Invalid RTTY parameter: {0}")]
+    InvalidRttyParameter(String),
+    #[error("Invalid PSK parameter: {0}")]
+    InvalidPskParameter(String),
+    #[error("Invalid DTMF parameter: {0}")]
+    InvalidDtmfParameter(String),
+
+    // --- Packet Radio Errors ---
+    #[error("Invalid AX.25 address: {0}")]
+    InvalidAx25Address(String),
+    #[error("Invalid AX.25 frame: {0}")]
+    InvalidAx25Frame(String),
+    #[error("Invalid AFSK parameter: {0}")]
+    InvalidAfskParameter(String),
+    #[error("Invalid APRS packet: {0}")]
+    InvalidAprsPacket(String),
+    #[error("Invalid POCSAG parameter: {0}")]
+    InvalidPocsagParameter(String),
+
+    // --- Squelch & Signal Tone Errors ---
+    #[error("Invalid CTCSS/DCS parameter: {0}")]
+    InvalidSubtoneParameter(String),
+    #[error("Invalid signal tone parameter: {0}")]
+    InvalidSignalToneParameter(String),
+
+    // --- Audio Conditioning Errors ---
+    #[error("Invalid TX audio chain parameter: {0}")]
+    InvalidTxAudioParameter(String),
+    #[error("Invalid RX audio chain parameter: {0}")]
+    InvalidRxAudioParameter(String),
+
+    // --- SDR Demodulation Errors ---
+    #[error("Unsupported demodulation mode: {0}")]
+    UnsupportedDemodMode(String),
+    #[error("Invalid demodulator parameter: {0}")]
+    InvalidDemodParameter(String),
 }
@@ -0,0 +1,29 @@
+// Goertzel tone measurement: the power of a single frequency over a block, cheaper than
+// an FFT when a detector only cares about a handful of known tones.
+
+use std::f64::consts::PI;
+
+/// Mean power of the `hz` component of `samples` (A²/2 for a sine of amplitude A).
+pub(crate) fn goertzel_power(samples: &[f32], hz: f64, sample_rate: f64) -> f64 {
+    let coeff = 2.0 * (2.0 * PI * hz / sample_rate).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &sample in samples {
+        let s0 = sample as f64 + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    2.0 * (s1 * s1 + s2 * s2 - coeff * s1 * s2) / (samples.len() as f64).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_power() {
+        let rate = 8000.0;
+        let sine: Vec<f32> = (0..800).map(|n| (0.5 * (2.0 * PI * 1000.0 * n as f64 / rate).sin()) as f32).collect();
+        assert!((goertzel_power(&sine, 1000.0, rate) - 0.125).abs() < 1e-4);
+        assert!(goertzel_power(&sine, 1500.0, rate) < 1e-6);
+    }
+}
@@ -18,12 +18,45 @@ mod error;
 pub mod vad;
 mod sstv;
 mod cw;
+mod rtty;
+mod psk;
+mod ax25;
+mod pocsag;
+mod dtmf;
+mod subtone;
+mod signal_tone;
+mod conditioning;
+mod demod;
+mod resample;
+mod goertzel;
+#[cfg(test)]
+mod fixtures;
 
 // Re-exports
 pub use error::{DspError, VadError};
 pub use vad::VadProcessor;
-pub use sstv::encode_sstv_martin_m1;
-pub use cw::generate_cw_audio;
+pub use sstv::{
+    decode_sstv, encode_sstv, encode_sstv_martin_m1, prepare_sstv_image, DecodedSstvImage, SstvDecoder, SstvMode, SstvReceiver,
+    SSTV_DEFAULT_SAMPLE_RATE,
+};
+pub use cw::{decode_cw, generate_cw_audio, CwDecoder, CwParams, MorseCharset};
+pub use rtty::{decode_rtty, generate_rtty_audio, RttyDecoder, RttyParams};
+pub use psk::{decode_psk, generate_psk_audio, PskDecoder, PskMode, PskParams, PSK_DEFAULT_TONE_HZ};
+pub use ax25::{
+    decode_afsk, generate_afsk_audio, parse_aprs, AfskDecoder, AfskParams, AprsMessage, AprsPacket, AprsPosition, Ax25Address,
+    Ax25Frame, AX25_PID_NO_LAYER3,
+};
+pub use pocsag::{
+    decode_pocsag, encode_pocsag, generate_pocsag_audio, PocsagContent, PocsagDecoder, PocsagMessage, PocsagParams,
+    POCSAG_MAX_CAPCODE,
+};
+pub use dtmf::{decode_dtmf, generate_dtmf_audio, DtmfDetector, DtmfParams};
+pub use subtone::{decode_subtone, mix_subtone, Subtone, SubtoneDetector, SubtoneParams, CTCSS_TONES, DCS_CODES};
+pub use conditioning::{condition_rx_audio, condition_tx_audio, RxChainParams, RxConditioner, TxChainParams};
+pub use signal_tone::{generate_tone_sequence, SignalToneParams, ToneSequence, TONE_BURST_HZ};
+pub use demod::{DemodMode, Demodulator};
+pub use resample::{resample, LinearResampler};
+pub use num_complex::Complex32;
 
 // Keep necessary top-level imports if used by other potential functions in lib.rs
 // For now, only tracing seems potentially relevant if lib-level logging is added later.
@@ -0,0 +1,273 @@
+// POCSAG receive decoder.
+//
+// Baseband (FM discriminator output) → DC removal and half-bit smoothing → slicer →
+// PLL bit clock → sync codeword search (either polarity) → batches of codewords →
+// BCH correction → pages.
+
+use super::{
+    correct_codeword, PocsagContent, PocsagMessage, PocsagParams, BATCH_CODEWORDS, IDLE_CODEWORD, MESSAGE_FLAG,
+    NUMERIC_CHARS, SYNC_CODEWORD,
+};
+use crate::error::DspError;
+use std::collections::VecDeque;
+use tracing::{debug, trace};
+
+/// Bit errors allowed in the sync codeword when searching for it and between batches.
+const SYNC_SEARCH_ERRORS: u32 = 2;
+const SYNC_TRACK_ERRORS: u32 = 4;
+/// Fraction of the timing error the bit clock corrects at each level change.
+const PLL_GAIN: f64 = 0.2;
+/// DC tracker time constant, in bits.
+const DC_TIME_BITS: f64 = 64.0;
+
+/// Collects the codewords of one page.
+struct Page {
+    capcode: u32,
+    function: u8,
+    data: Vec<u32>,
+}
+
+impl Page {
+    /// The 20-bit data fields as one bit stream, read in `width`-bit characters LSB first.
+    fn characters(&self, width: usize) -> impl Iterator<Item = u8> + '_ {
+        let bits: Vec<bool> =
+            self.data.iter().flat_map(|&data| (0..20).rev().map(move |n| data >> n & 1 == 1)).collect();
+        (0..bits.len() / width)
+            .map(move |n| bits[n * width..(n + 1) * width].iter().rev().fold(0u8, |acc, &bit| acc << 1 | bit as u8))
+    }
+
+    fn into_message(self) -> PocsagMessage {
+        let content = if self.data.is_empty() {
+            PocsagContent::Tone
+        } else if self.function == 0 {
+            let digits: String = self.characters(4).map(|code| NUMERIC_CHARS[code as usize]).collect();
+            PocsagContent::Numeric(digits.trim_end().to_string())
+        } else {
+            let text: String = self.characters(7).map(|code| code as char).collect();
+            // Padding and end-of-text markers.
+            PocsagContent::Alphanumeric(text.trim_end_matches(['\0', '\u{3}', '\u{4}']).to_string())
+        };
+        PocsagMessage { capcode: self.capcode, function: self.function, content }
+    }
+}
+
+enum SyncState {
+    Hunt,
+    /// `slot` counts codewords since the sync codeword; slot 16 is the next sync.
+    Batch { inverted: bool, slot: usize, word: u32, bits: u32 },
+}
+
+/// Streaming POCSAG decoder for one baud rate.
+pub struct PocsagDecoder {
+    clock_step: f64,
+    clock: f64,
+    dc: f64,
+    dc_rate: f64,
+    smoother: VecDeque<f32>,
+    smoother_len: usize,
+    smoother_sum: f64,
+    level: bool,
+    /// Last 32 bits, newest in bit 0, while hunting for sync.
+    shift: u32,
+    state: SyncState,
+    page: Option<Page>,
+    messages: Vec<PocsagMessage>,
+}
+
+impl PocsagDecoder {
+    /// Creates a decoder for baseband at `params.sample_rate` and `params.baud`.
+    /// Polarity is detected from the sync codeword, so `params.invert` doesn't matter.
+    pub fn new(params: &PocsagParams) -> Result<Self, DspError> {
+        params.validate()?;
+        let bit_samples = params.bit_samples();
+        let smoother_len = (bit_samples / 2.0).round().max(1.0) as usize;
+        Ok(Self {
+            clock_step: 1.0 / bit_samples,
+            clock: 0.0,
+            dc: 0.0,
+            dc_rate: 1.0 / (DC_TIME_BITS * bit_samples),
+            smoother: VecDeque::with_capacity(smoother_len + 1),
+            smoother_len,
+            smoother_sum: 0.0,
+            level: false,
+            shift: 0,
+            state: SyncState::Hunt,
+            page: None,
+            messages: Vec::new(),
+        })
+    }
+
+    /// Feeds baseband and returns any pages completed by it.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<PocsagMessage> {
+        for &sample in samples {
+            self.process_sample(sample);
+        }
+        std::mem::take(&mut self.messages)
+    }
+
+    /// Ends any page still being received and returns the remaining pages.
+    pub fn flush(&mut self) -> Vec<PocsagMessage> {
+        self.finish_page();
+        self.state = SyncState::Hunt;
+        std::mem::take(&mut self.messages)
+    }
+
+    fn process_sample(&mut self, sample: f32) {
+        self.dc += (sample as f64 - self.dc) * self.dc_rate;
+        self.smoother.push_back(sample);
+        self.smoother_sum += sample as f64;
+        if self.smoother.len() > self.smoother_len {
+            self.smoother_sum -= self.smoother.pop_front().unwrap_or_default() as f64;
+        }
+        // Binary 1 is the lower frequency, the negative level.
+        let level = self.smoother_sum / (self.smoother.len() as f64) < self.dc;
+        if level != self.level {
+            self.level = level;
+            self.clock += (0.5 - self.clock) * PLL_GAIN;
+        }
+        self.clock += self.clock_step;
+        if self.clock >= 1.0 {
+            self.clock -= 1.0;
+            self.receive_bit(level);
+        }
+    }
+
+    fn receive_bit(&mut self, bit: bool) {
+        match &mut self.state {
+            SyncState::Hunt => {
+                self.shift = self.shift << 1 | bit as u32;
+                let errors = (self.shift ^ SYNC_CODEWORD).count_ones();
+                if errors <= SYNC_SEARCH_ERRORS || 32 - errors <= SYNC_SEARCH_ERRORS {
+                    let inverted = errors > SYNC_SEARCH_ERRORS;
+                    debug!(inverted, "POCSAG sync acquired");
+                    self.state = SyncState::Batch { inverted, slot: 0, word: 0, bits: 0 };
+                }
+            }
+            SyncState::Batch { inverted, slot, word, bits } => {
+                *word = *word << 1 | (bit != *inverted) as u32;
+                *bits += 1;
+                if *bits < 32 {
+                    return;
+                }
+                let (codeword, index) = (*word, *slot);
+                *word = 0;
+                *bits = 0;
+                if index < BATCH_CODEWORDS {
+                    *slot += 1;
+                    self.receive_codeword(codeword, index / 2);
+                } else if (codeword ^ SYNC_CODEWORD).count_ones() <= SYNC_TRACK_ERRORS {
+                    *slot = 0;
+                } else {
+                    debug!("POCSAG sync lost");
+                    self.finish_page();
+                    self.shift = codeword;
+                    self.state = SyncState::Hunt;
+                }
+            }
+        }
+    }
+
+    fn receive_codeword(&mut self, received: u32, frame: usize) {
+        let Some(codeword) = correct_codeword(received) else {
+            trace!("Uncorrectable POCSAG codeword {:08X}", received);
+            // Keep the raw data of a damaged message codeword so the rest of the text lines up.
+            match &mut self.page {
+                Some(page) if received & MESSAGE_FLAG != 0 => page.data.push(received >> 11 & 0xF_FFFF),
+                _ => self.finish_page(),
+            }
+            return;
+        };
+        if codeword == IDLE_CODEWORD {
+            self.finish_page();
+        } else if codeword & MESSAGE_FLAG == 0 {
+            self.finish_page();
+            self.page = Some(Page {
+                capcode: (codeword >> 13) << 3 | frame as u32,
+                function: (codeword >> 11 & 3) as u8,
+                data: Vec::new(),
+            });
+        } else if let Some(page) = &mut self.page {
+            page.data.push(codeword >> 11 & 0xF_FFFF);
+        }
+    }
+
+    fn finish_page(&mut self) {
+        if let Some(page) = self.page.take() {
+            let message = page.into_message();
+            debug!("Received POCSAG page: {:?}", message);
+            self.messages.push(message);
+        }
+    }
+}
+
+/// Decodes every page in a complete POCSAG baseband recording.
+pub fn decode_pocsag(samples: &[f32], params: &PocsagParams) -> Result<Vec<PocsagMessage>, DspError> {
+    let mut decoder = PocsagDecoder::new(params)?;
+    let mut messages = decoder.push(samples);
+    messages.extend(decoder.flush());
+    debug!("Decoded {} POCSAG pages", messages.len());
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::add_noise;
+    use crate::pocsag::{encode_pocsag, generate_pocsag_audio, PREAMBLE_BITS};
+
+    fn pages() -> Vec<PocsagMessage> {
+        vec![
+            PocsagMessage::alphanumeric(1234567, "Meet at the repeater site, 0900 Saturday."),
+            PocsagMessage::numeric(200000, "0362-555-1234"),
+            PocsagMessage::tone(8, 2),
+            PocsagMessage::alphanumeric(9, "Short"),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (baud, rate) in [(512, 8000), (1200, 22050), (2400, 48000), (2400, 11025)] {
+            let params = PocsagParams::new(baud, rate);
+            let audio = generate_pocsag_audio(&pages(), &params).unwrap();
+            assert_eq!(decode_pocsag(&audio, &params).unwrap(), pages(), "{} baud at {} Hz", baud, rate);
+        }
+    }
+
+    #[test]
+    fn test_inverted_offset_and_noisy() {
+        let params = PocsagParams { invert: true, ..PocsagParams::new(1200, 16000) };
+        let mut audio = vec![0.0; 777];
+        audio.extend(generate_pocsag_audio(&pages(), &params).unwrap());
+        audio.extend(vec![0.0; 1000]);
+        // A small DC offset, as from a slightly mistuned receiver.
+        audio.iter_mut().for_each(|s| *s += 0.1);
+        add_noise(&mut audio, 0.45, 17);
+        let mut decoder = PocsagDecoder::new(&PocsagParams::new(1200, 16000)).unwrap();
+        let mut received: Vec<PocsagMessage> = audio.chunks(500).flat_map(|chunk| decoder.push(chunk)).collect();
+        received.extend(decoder.flush());
+        assert_eq!(received, pages());
+    }
+
+    #[test]
+    fn test_corrects_bit_errors() {
+        let params = PocsagParams::new(1200, 9600);
+        let mut codewords = encode_pocsag(&pages()).unwrap();
+        // Two errors in every codeword, sync words included.
+        for (n, codeword) in codewords.iter_mut().enumerate() {
+            *codeword ^= 1 << (n % 32) | 1 << ((n * 7 + 3) % 32);
+        }
+        let bits = (0..PREAMBLE_BITS)
+            .map(|n| n % 2 == 0)
+            .chain(codewords.iter().flat_map(|&codeword| (0..32).rev().map(move |n| codeword >> n & 1 == 1)));
+        let audio: Vec<f32> = bits.flat_map(|bit| [if bit { -0.8 } else { 0.8 }; 8]).collect();
+        assert_eq!(decode_pocsag(&audio, &params).unwrap(), pages());
+    }
+
+    #[test]
+    fn test_noise_decodes_nothing() {
+        let params = PocsagParams::new(1200, 16000);
+        let mut noise = vec![0.0; 16000 * 3];
+        add_noise(&mut noise, 0.5, 23);
+        assert!(decode_pocsag(&noise, &params).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+// POCSAG encoder: messages → codewords in batches → NRZ baseband.
+
+use super::{
+    make_codeword, PocsagContent, PocsagMessage, PocsagParams, BATCH_CODEWORDS, IDLE_CODEWORD, MESSAGE_FLAG,
+    NUMERIC_CHARS, PREAMBLE_BITS, SYNC_CODEWORD,
+};
+use crate::error::DspError;
+use tracing::{debug, info, warn};
+
+/// Message bits, each character LSB first: 4-bit BCD for numeric pages, 7-bit ASCII
+/// for alphanumeric ones.
+fn content_bits(content: &PocsagContent) -> Vec<bool> {
+    let mut bits = Vec::new();
+    let push = |bits: &mut Vec<bool>, value: u8, width: usize| bits.extend((0..width).map(|n| value >> n & 1 == 1));
+    match content {
+        PocsagContent::Tone => {}
+        PocsagContent::Numeric(digits) => {
+            for c in digits.to_uppercase().chars() {
+                match NUMERIC_CHARS.iter().position(|&n| n == c) {
+                    Some(code) => push(&mut bits, code as u8, 4),
+                    None => warn!("Unsupported character for POCSAG numeric message: '{}'. Skipping.", c),
+                }
+            }
+            // Fill the last codeword with spaces.
+            while bits.len() % 20 != 0 {
+                push(&mut bits, 0xC, 4);
+            }
+        }
+        PocsagContent::Alphanumeric(text) => {
+            for c in text.chars() {
+                if c.is_ascii() {
+                    push(&mut bits, c as u8, 7);
+                } else {
+                    warn!("Unsupported character for POCSAG alphanumeric message: '{}'. Skipping.", c);
+                }
+            }
+        }
+    }
+    bits
+}
+
+/// Address codeword followed by the message codewords for one page.
+fn message_codewords(message: &PocsagMessage) -> Vec<u32> {
+    let address = (message.capcode >> 3) << 2 | message.function as u32;
+    let mut codewords = vec![make_codeword(address)];
+    for chunk in content_bits(&message.content).chunks(20) {
+        let data = (0..20).fold(0u32, |acc, n| acc << 1 | chunk.get(n).copied().unwrap_or(false) as u32);
+        codewords.push(make_codeword(MESSAGE_FLAG >> 11 | data));
+    }
+    codewords
+}
+
+/// Builds the batches for `messages`, sync codewords included.
+///
+/// Each address goes in its capcode's frame, padding with idle codewords as needed.
+/// The last batch is filled with idle codewords.
+pub fn encode_pocsag(messages: &[PocsagMessage]) -> Result<Vec<u32>, DspError> {
+    let mut slots = Vec::new();
+    for message in messages {
+        message.validate()?;
+        let frame = (message.capcode & 7) as usize;
+        while slots.len() % BATCH_CODEWORDS / 2 != frame {
+            slots.push(IDLE_CODEWORD);
+        }
+        slots.extend(message_codewords(message));
+    }
+    // At least one idle codeword ends the last message.
+    slots.push(IDLE_CODEWORD);
+    while slots.len() % BATCH_CODEWORDS != 0 {
+        slots.push(IDLE_CODEWORD);
+    }
+
+    let mut codewords = Vec::with_capacity(slots.len() / BATCH_CODEWORDS * (BATCH_CODEWORDS + 1));
+    for batch in slots.chunks(BATCH_CODEWORDS) {
+        codewords.push(SYNC_CODEWORD);
+        codewords.extend(batch);
+    }
+    Ok(codewords)
+}
+
+/// Generates POCSAG NRZ baseband for `messages`, to drive an FM transmitter's
+/// modulator directly (flat audio or data port).
+pub fn generate_pocsag_audio(messages: &[PocsagMessage], params: &PocsagParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Generating POCSAG baseband: {:?}, {} messages", params, messages.len());
+
+    let codewords = encode_pocsag(messages)?;
+    let preamble = (0..PREAMBLE_BITS).map(|n| n % 2 == 0);
+    let bits = preamble.chain(codewords.iter().flat_map(|&codeword| (0..32).rev().map(move |n| codeword >> n & 1 == 1)));
+
+    let bit_samples = params.bit_samples();
+    let mut audio = Vec::new();
+    for (n, bit) in bits.enumerate() {
+        let level = if bit != params.invert { -params.amplitude } else { params.amplitude };
+        let end = ((n + 1) as f64 * bit_samples).round() as usize;
+        audio.resize(end, level);
+    }
+
+    info!(
+        "Generated POCSAG {} baud baseband with {} samples (approx {:.2} seconds)",
+        params.baud,
+        audio.len(),
+        audio.len() as f64 / params.sample_rate as f64
+    );
+    Ok(audio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pocsag::is_valid;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_numeric_packing() {
+        // "1" is BCD 0001, sent LSB first; padding spaces are 1100 → 0011.
+        let codewords = message_codewords(&PocsagMessage::numeric(8, "1"));
+        assert_eq!(codewords.len(), 2);
+        assert_eq!(codewords[1] >> 11, 0x100000 | 0b1000_0011_0011_0011_0011);
+        assert!(codewords.iter().all(|&codeword| is_valid(codeword)));
+        // Address: capcode 8 → address bits 1, function 0.
+        assert_eq!(codewords[0] >> 11, 1 << 2);
+    }
+
+    #[test]
+    fn test_batch_layout() {
+        // Capcode 1234567 is in frame 7, the last frame of the batch.
+        let codewords = encode_pocsag(&[PocsagMessage::alphanumeric(1234567, "Hello")]).unwrap();
+        assert_eq!(codewords.len(), 2 * 17);
+        assert_eq!(codewords[0], SYNC_CODEWORD);
+        assert!(codewords[1..15].iter().all(|&codeword| codeword == IDLE_CODEWORD));
+        assert_eq!(codewords[15] >> 13, 1234567 >> 3);
+        // 35 bits of text need two message codewords; the second carries into the next batch.
+        assert_eq!(codewords[16] & MESSAGE_FLAG, MESSAGE_FLAG);
+        assert_eq!(codewords[17], SYNC_CODEWORD);
+        assert_eq!(codewords[18] & MESSAGE_FLAG, MESSAGE_FLAG);
+        assert!(codewords[19..].iter().all(|&codeword| codeword == IDLE_CODEWORD));
+
+        let tone = encode_pocsag(&[PocsagMessage::tone(2, 1)]).unwrap();
+        assert_eq!(tone.len(), 17);
+        assert_eq!(tone[5], make_codeword(1));
+    }
+
+    #[test]
+    fn test_baseband() {
+        let params = PocsagParams::new(1200, 48000);
+        let audio = generate_pocsag_audio(&[PocsagMessage::numeric(100, "5551234")], &params).unwrap();
+        assert_eq!(audio.len(), (576 + 17 * 32) * 40);
+        // Preamble starts with a 1, the lower frequency.
+        assert_eq!(audio[0], -0.85);
+        assert_eq!(audio[40], 0.85);
+        let inverted = generate_pocsag_audio(&[], &PocsagParams { invert: true, ..params }).unwrap();
+        assert_eq!(inverted[0], 0.85);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let params = PocsagParams::new(1200, 48000);
+        assert_matches!(
+            generate_pocsag_audio(&[], &PocsagParams::new(9600, 48000)),
+            Err(DspError::InvalidPocsagParameter(_))
+        );
+        assert_matches!(
+            generate_pocsag_audio(&[], &PocsagParams::new(2400, 8000)),
+            Err(DspError::InvalidPocsagParameter(_))
+        );
+        assert_matches!(
+            generate_pocsag_audio(&[PocsagMessage::numeric(1 << 21, "1")], &params),
+            Err(DspError::InvalidPocsagParameter(_))
+        );
+        assert_matches!(encode_pocsag(&[PocsagMessage::tone(1, 4)]), Err(DspError::InvalidPocsagParameter(_)));
+    }
+}
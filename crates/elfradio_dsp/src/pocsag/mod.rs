@@ -0,0 +1,180 @@
+// POCSAG paging: 32-bit codewords protected by BCH(31,21) plus even parity, sent in
+// batches of a sync codeword and eight two-codeword frames after a 576-bit preamble.
+//
+// Address codeword: 0, capcode bits 20..3, two function bits, parity. A pager only
+// listens in frame (capcode & 7). Message codeword: 1, 20 data bits, parity. A message
+// runs until the next address or idle codeword, carrying on across batches.
+
+mod decode;
+mod encode;
+
+pub use decode::{decode_pocsag, PocsagDecoder};
+pub use encode::{encode_pocsag, generate_pocsag_audio};
+
+use crate::error::DspError;
+
+pub(crate) const SYNC_CODEWORD: u32 = 0x7CD2_15D8;
+pub(crate) const IDLE_CODEWORD: u32 = 0x7A89_C197;
+pub(crate) const PREAMBLE_BITS: usize = 576;
+/// Codewords in a batch, not counting the sync codeword.
+pub(crate) const BATCH_CODEWORDS: usize = 16;
+/// Largest capcode (21 bits).
+pub const POCSAG_MAX_CAPCODE: u32 = (1 << 21) - 1;
+/// BCH(31,21) generator polynomial x^10 + x^9 + x^8 + x^6 + x^5 + x^3 + 1.
+const BCH_GENERATOR: u32 = 0x769;
+const MESSAGE_FLAG: u32 = 0x8000_0000;
+/// Numeric characters by BCD value. 0xA is the spare code, shown as '.'.
+const NUMERIC_CHARS: [char; 16] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '.', 'U', ' ', '-', ']', '['];
+
+/// Function bits used when a message doesn't ask for anything else.
+const NUMERIC_FUNCTION: u8 = 0;
+const ALPHANUMERIC_FUNCTION: u8 = 3;
+
+/// BCH remainder of the 31 bits above the parity bit.
+fn bch_syndrome(codeword: u32) -> u32 {
+    let mut remainder = codeword >> 1;
+    for bit in (10..31).rev() {
+        if remainder & 1 << bit != 0 {
+            remainder ^= BCH_GENERATOR << (bit - 10);
+        }
+    }
+    remainder
+}
+
+/// Completes a codeword from its top 21 bits: BCH check bits and even parity.
+pub(crate) fn make_codeword(data: u32) -> u32 {
+    let mut codeword = data << 11;
+    codeword |= bch_syndrome(codeword) << 1;
+    codeword | codeword.count_ones() & 1
+}
+
+fn is_valid(codeword: u32) -> bool {
+    bch_syndrome(codeword) == 0 && codeword.count_ones().is_multiple_of(2)
+}
+
+/// Corrects up to two bit errors; None if the codeword is beyond repair.
+pub(crate) fn correct_codeword(codeword: u32) -> Option<u32> {
+    if is_valid(codeword) {
+        return Some(codeword);
+    }
+    for i in 0..32 {
+        let single = codeword ^ 1 << i;
+        if is_valid(single) {
+            return Some(single);
+        }
+        for j in i + 1..32 {
+            if is_valid(single ^ 1 << j) {
+                return Some(single ^ 1 << j);
+            }
+        }
+    }
+    None
+}
+
+/// What a page carries after its address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PocsagContent {
+    /// Address only: the pager just beeps.
+    Tone,
+    /// Digits and the few symbols in the numeric character set.
+    Numeric(String),
+    /// 7-bit ASCII text.
+    Alphanumeric(String),
+}
+
+/// One page: who it is for and what it says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PocsagMessage {
+    /// Pager address (RIC), 0 to 2097151.
+    pub capcode: u32,
+    /// Function bits (0 to 3); pagers use them to pick an alert. The decoder reads
+    /// messages with function 0 as numeric and the rest as alphanumeric.
+    pub function: u8,
+    pub content: PocsagContent,
+}
+
+impl PocsagMessage {
+    pub fn numeric(capcode: u32, digits: &str) -> Self {
+        Self { capcode, function: NUMERIC_FUNCTION, content: PocsagContent::Numeric(digits.to_string()) }
+    }
+
+    pub fn alphanumeric(capcode: u32, text: &str) -> Self {
+        Self { capcode, function: ALPHANUMERIC_FUNCTION, content: PocsagContent::Alphanumeric(text.to_string()) }
+    }
+
+    pub fn tone(capcode: u32, function: u8) -> Self {
+        Self { capcode, function, content: PocsagContent::Tone }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        if self.capcode > POCSAG_MAX_CAPCODE {
+            return Err(DspError::InvalidPocsagParameter(format!(
+                "capcode {} is above {}",
+                self.capcode, POCSAG_MAX_CAPCODE
+            )));
+        }
+        if self.function > 3 {
+            return Err(DspError::InvalidPocsagParameter(format!("function {} is outside 0..3", self.function)));
+        }
+        Ok(())
+    }
+}
+
+/// POCSAG baseband settings shared by the encoder and decoder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PocsagParams {
+    /// 512, 1200 or 2400.
+    pub baud: u32,
+    pub sample_rate: u32,
+    /// Peak level of the generated baseband (0.0 to 1.0).
+    pub amplitude: f32,
+    /// Send binary 1 as the positive level. POCSAG sends 1 as the lower frequency,
+    /// which is the negative level on an FM modulator that isn't inverted.
+    pub invert: bool,
+}
+
+impl PocsagParams {
+    pub fn new(baud: u32, sample_rate: u32) -> Self {
+        Self { baud, sample_rate, amplitude: 0.85, invert: false }
+    }
+
+    pub(crate) fn bit_samples(&self) -> f64 {
+        self.sample_rate as f64 / self.baud as f64
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidPocsagParameter(msg));
+        if !matches!(self.baud, 512 | 1200 | 2400) {
+            return invalid(format!("{} baud is not a POCSAG rate (512, 1200 or 2400)", self.baud));
+        }
+        if self.bit_samples() < 4.0 {
+            return invalid(format!("sample rate {} Hz is too low for {} baud", self.sample_rate, self.baud));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return invalid(format!("amplitude {} is outside 0..1", self.amplitude));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codewords() {
+        // The sync and idle codewords are themselves valid BCH codewords.
+        assert_eq!(make_codeword(SYNC_CODEWORD >> 11), SYNC_CODEWORD);
+        assert_eq!(make_codeword(IDLE_CODEWORD >> 11), IDLE_CODEWORD);
+        assert!(is_valid(make_codeword(0x1A_BCDE)));
+    }
+
+    #[test]
+    fn test_error_correction() {
+        let codeword = make_codeword(0x12_3456 & 0x1F_FFFF);
+        assert_eq!(correct_codeword(codeword ^ 1 << 17), Some(codeword));
+        assert_eq!(correct_codeword(codeword ^ 1), Some(codeword));
+        assert_eq!(correct_codeword(codeword ^ (1 << 31 | 1 << 4)), Some(codeword));
+        assert_eq!(correct_codeword(codeword ^ 0b111 << 20), None);
+    }
+}
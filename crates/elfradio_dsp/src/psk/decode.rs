@@ -0,0 +1,310 @@
+// PSK31 demodulator.
+//
+// Audio → NCO mix to baseband (AFC-steered) → decimate to ~500 Hz → low-pass →
+// Gardner symbol timing → decision-directed Costas loop → differential detection →
+// (QPSK: Viterbi) → Varicode → text.
+
+use super::varicode::{decode_bits, MAX_CODE_BITS};
+use super::{convolve, symbol_rotation, PskMode, PskParams, PSK31_BAUD};
+use crate::demod::{lowpass_taps, BoxcarDecimator, Fir};
+use crate::error::DspError;
+use num_complex::{Complex32, Complex64};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use tracing::{debug, trace};
+
+/// Rate the filtered baseband runs at (approximately; it's an integer fraction of the input).
+const BASEBAND_RATE: f64 = 500.0;
+/// Symbol timing correction per symbol, as a fraction of the timing error.
+const TIMING_GAIN: f64 = 0.1;
+/// Costas loop gains: phase (per symbol) and frequency (per symbol, per radian of error).
+const PHASE_GAIN: f64 = 0.2;
+const FREQ_GAIN: f64 = 0.01;
+/// Share of the measured symbol-to-symbol drift the AFC removes each symbol.
+const AFC_GAIN: f64 = 0.1;
+/// The AFC doesn't pull further than this from the nominal tone.
+const AFC_RANGE_HZ: f64 = 15.0;
+/// Signal quality (0..1) above which characters are written.
+const SQUELCH_LEVEL: f64 = 0.7;
+/// Weight of each symbol in the quality average.
+const QUALITY_ALPHA: f64 = 0.1;
+/// QPSK decoding delay in bits.
+const VITERBI_DEPTH: u32 = 32;
+
+/// Soft-decision Viterbi decoder for the QPSK31 convolutional code (16 states).
+struct Viterbi {
+    metrics: [f64; 16],
+    /// Survivor bits of each state, newest in bit 0.
+    paths: [u64; 16],
+    steps: u32,
+}
+
+impl Viterbi {
+    fn new() -> Self {
+        Self { metrics: [0.0; 16], paths: [0; 16], steps: 0 }
+    }
+
+    /// Takes the received phase change; returns the bit decided `VITERBI_DEPTH` steps ago.
+    fn push(&mut self, change: Complex64) -> Option<bool> {
+        let change = change / change.norm().max(1e-12);
+        let mut metrics = [f64::INFINITY; 16];
+        let mut paths = [0u64; 16];
+        for (state, (&metric, &path)) in self.metrics.iter().zip(&self.paths).enumerate() {
+            for bit in 0..2u8 {
+                let register = ((state as u8) << 1 | bit) & 0x1F;
+                let next = (register & 0x0F) as usize;
+                let cost = metric + (change - symbol_rotation(convolve(register))).norm_sqr();
+                if cost < metrics[next] {
+                    metrics[next] = cost;
+                    paths[next] = path << 1 | bit as u64;
+                }
+            }
+        }
+        let best = metrics.iter().copied().fold(f64::INFINITY, f64::min);
+        metrics.iter_mut().for_each(|m| *m -= best);
+        self.metrics = metrics;
+        self.paths = paths;
+        self.steps += 1;
+        (self.steps >= VITERBI_DEPTH).then(|| self.best_path() >> (VITERBI_DEPTH - 1) & 1 == 1)
+    }
+
+    fn best_path(&self) -> u64 {
+        let best = (0..16).min_by(|&a, &b| self.metrics[a].total_cmp(&self.metrics[b])).unwrap_or(0);
+        self.paths[best]
+    }
+
+    /// Bits still held back, oldest first.
+    fn flush(&mut self) -> Vec<bool> {
+        let held = self.steps.min(VITERBI_DEPTH - 1);
+        let path = self.best_path();
+        *self = Self::new();
+        (0..held).rev().map(|n| path >> n & 1 == 1).collect()
+    }
+}
+
+/// Streaming PSK31 decoder.
+pub struct PskDecoder {
+    mode: PskMode,
+    nco_phase: f64,
+    /// NCO step in radians per input sample, and the nominal step for the tone.
+    nco_step: f64,
+    nominal_step: f64,
+    max_offset_step: f64,
+    input_symbol_samples: f64,
+    decimator: BoxcarDecimator,
+    filter: Fir<Complex32>,
+    /// Filtered baseband samples per symbol.
+    symbol_samples: f64,
+    clock: f64,
+    history: VecDeque<Complex32>,
+    prev_symbol: Complex64,
+    quality: f64,
+    viterbi: Option<Viterbi>,
+    /// Varicode bits since the last character gap, newest in bit 0.
+    bits: u32,
+    output: String,
+}
+
+impl PskDecoder {
+    /// Creates a decoder listening around `params.tone_hz`.
+    pub fn new(params: &PskParams) -> Result<Self, DspError> {
+        params.validate()?;
+        let factor = (params.sample_rate as f64 / BASEBAND_RATE).round().max(1.0) as usize;
+        let baseband_rate = params.sample_rate as f64 / factor as f64;
+        let symbol_samples = baseband_rate / PSK31_BAUD;
+        let nominal_step = 2.0 * PI * params.tone_hz as f64 / params.sample_rate as f64;
+        Ok(Self {
+            mode: params.mode,
+            nco_phase: 0.0,
+            nco_step: nominal_step,
+            nominal_step,
+            max_offset_step: 2.0 * PI * AFC_RANGE_HZ / params.sample_rate as f64,
+            input_symbol_samples: params.symbol_samples(),
+            decimator: BoxcarDecimator::new(factor),
+            filter: Fir::new(lowpass_taps(35.0, baseband_rate as f32, 50.0)),
+            symbol_samples,
+            clock: 0.0,
+            history: VecDeque::with_capacity(symbol_samples.ceil() as usize + 2),
+            prev_symbol: Complex64::default(),
+            quality: 0.0,
+            viterbi: (params.mode == PskMode::Qpsk31).then(Viterbi::new),
+            bits: 0,
+            output: String::new(),
+        })
+    }
+
+    /// How far the AFC has moved from the nominal tone, in Hz.
+    pub fn frequency_offset_hz(&self) -> f32 {
+        ((self.nco_step - self.nominal_step) * self.input_symbol_samples * PSK31_BAUD / (2.0 * PI)) as f32
+    }
+
+    /// Feeds audio and returns any text decoded from it.
+    pub fn push(&mut self, samples: &[f32]) -> String {
+        for &sample in samples {
+            let lo = Complex64::from_polar(1.0, -self.nco_phase);
+            self.nco_phase = (self.nco_phase + self.nco_step) % (2.0 * PI);
+            let mixed = Complex32::new((sample as f64 * lo.re) as f32, (sample as f64 * lo.im) as f32);
+            if let Some(baseband) = self.decimator.process(mixed) {
+                let filtered = self.filter.process(baseband);
+                self.process_baseband(filtered);
+            }
+        }
+        std::mem::take(&mut self.output)
+    }
+
+    /// Releases the bits the QPSK decoder is holding back and returns the remaining text.
+    pub fn flush(&mut self) -> String {
+        if let Some(viterbi) = &mut self.viterbi {
+            for bit in viterbi.flush() {
+                self.receive_bit(bit);
+            }
+        }
+        std::mem::take(&mut self.output)
+    }
+
+    fn process_baseband(&mut self, sample: Complex32) {
+        self.history.push_back(sample);
+        if self.history.len() > self.symbol_samples.ceil() as usize + 2 {
+            self.history.pop_front();
+        }
+        self.clock += 1.0;
+        if self.clock < self.symbol_samples {
+            return;
+        }
+        self.clock -= self.symbol_samples;
+
+        let to64 = |z: Complex32| Complex64::new(z.re as f64, z.im as f64);
+        let symbol = to64(sample);
+        let half = (self.symbol_samples / 2.0).round() as usize;
+        if let Some(&mid) = self.history.iter().rev().nth(half) {
+            // Gardner detector: the mid-point sample leans towards the later symbol when
+            // strobing late. Negative error means late, so the next strobe comes sooner.
+            let power = self.prev_symbol.norm_sqr() + symbol.norm_sqr();
+            if power > 0.0 {
+                let error = ((self.prev_symbol - symbol) * to64(mid).conj()).re / power;
+                self.clock -= TIMING_GAIN * error.clamp(-1.0, 1.0) * self.symbol_samples / 2.0;
+            }
+        }
+
+        let order = if self.mode == PskMode::Qpsk31 { 4.0 } else { 2.0 };
+        let change = symbol * self.prev_symbol.conj();
+        self.prev_symbol = symbol;
+        if symbol.norm_sqr() == 0.0 || change.norm_sqr() == 0.0 {
+            return;
+        }
+        // Quality: how well the phase changes sit on the constellation (1 = perfectly).
+        let drift = (change.powf(order)).arg() / order;
+        self.quality += QUALITY_ALPHA * ((order * change.arg()).cos() - self.quality);
+
+        // Costas loop (decision directed) plus AFC on the symbol-to-symbol drift.
+        let decision = if order == 4.0 {
+            let quadrant = (symbol.arg() / (PI / 2.0)).round();
+            Complex64::from_polar(1.0, quadrant * PI / 2.0)
+        } else {
+            Complex64::new(symbol.re.signum(), 0.0)
+        };
+        let phase_error = (symbol * decision.conj()).arg();
+        let per_sample = 1.0 / self.input_symbol_samples;
+        self.nco_phase += PHASE_GAIN * phase_error;
+        self.nco_step += (FREQ_GAIN * phase_error + AFC_GAIN * drift) * per_sample;
+        self.nco_step =
+            self.nco_step.clamp(self.nominal_step - self.max_offset_step, self.nominal_step + self.max_offset_step);
+        trace!(quality = self.quality, offset_hz = self.frequency_offset_hz(), "PSK symbol");
+
+        match &mut self.viterbi {
+            None => self.receive_bit(change.re > 0.0),
+            Some(viterbi) => {
+                if let Some(bit) = viterbi.push(change) {
+                    self.receive_bit(bit);
+                }
+            }
+        }
+    }
+
+    fn receive_bit(&mut self, bit: bool) {
+        self.bits = self.bits << 1 | bit as u32;
+        if self.bits & 0b11 == 0 {
+            let code = self.bits >> 2;
+            self.bits = 0;
+            if code == 0 || self.quality < SQUELCH_LEVEL {
+                return;
+            }
+            match decode_bits(code) {
+                Some(c) if c == '\n' || c == '\t' || !c.is_ascii_control() => self.output.push(c),
+                Some(_) => {}
+                None => debug!(code = format!("{:b}", code), "Unknown Varicode"),
+            }
+        } else if self.bits >> (MAX_CODE_BITS + 2) != 0 {
+            // Too long for any code: noise. Keep the last bit so the next gap still counts.
+            self.bits &= 1;
+        }
+    }
+}
+
+/// Decodes a complete PSK31 recording.
+pub fn decode_psk(samples: &[f32], params: &PskParams) -> Result<String, DspError> {
+    let mut decoder = PskDecoder::new(params)?;
+    let mut text = decoder.push(samples);
+    text.push_str(&decoder.flush());
+    debug!(offset_hz = decoder.frequency_offset_hz(), "Decoded {} PSK characters", text.chars().count());
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::add_noise;
+    use crate::psk::generate_psk_audio;
+
+    const TEXT: &str = "CQ CQ de VK7KSM VK7KSM pse k\nThe quick brown fox: 0123456789!";
+
+    #[test]
+    fn test_round_trip() {
+        for mode in [PskMode::Bpsk31, PskMode::Qpsk31] {
+            for rate in [8000, 11025, 48000] {
+                let params = PskParams::new(mode, rate);
+                let audio = generate_psk_audio(TEXT, &params).unwrap();
+                assert_eq!(decode_psk(&audio, &params).unwrap(), TEXT, "{} at {} Hz", mode, rate);
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_and_frequency_offset() {
+        let rate = 8000;
+        for (mode, offset, sigma) in [(PskMode::Bpsk31, 6.0, 0.8), (PskMode::Qpsk31, -3.0, 0.5)] {
+            let sent = PskParams { tone_hz: 1000.0 + offset, ..PskParams::new(mode, rate) };
+            let mut audio = vec![0.0; 1234];
+            audio.extend(generate_psk_audio(TEXT, &sent).unwrap());
+            add_noise(&mut audio, sigma, 99);
+
+            let params = PskParams::new(mode, rate);
+            let mut decoder = PskDecoder::new(&params).unwrap();
+            let mut text = decoder.push(&audio);
+            text.push_str(&decoder.flush());
+            assert_eq!(text, TEXT, "{}", mode);
+            let afc = decoder.frequency_offset_hz();
+            assert!((afc - offset).abs() < 1.0, "{}: AFC at {} Hz, signal at {} Hz", mode, afc, offset);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let params = PskParams::new(PskMode::Qpsk31, 8000);
+        let audio = generate_psk_audio("73 de VK7KSM", &params).unwrap();
+        let mut decoder = PskDecoder::new(&params).unwrap();
+        let mut streamed: String = audio.chunks(101).map(|chunk| decoder.push(chunk)).collect();
+        streamed.push_str(&decoder.flush());
+        assert_eq!(streamed, "73 de VK7KSM");
+    }
+
+    #[test]
+    fn test_noise_alone_decodes_nothing() {
+        for mode in [PskMode::Bpsk31, PskMode::Qpsk31] {
+            let params = PskParams::new(mode, 8000);
+            let mut noise = vec![0.0; 8000 * 5];
+            add_noise(&mut noise, 0.3, 3);
+            assert_eq!(decode_psk(&noise, &params).unwrap(), "", "{}", mode);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// PSK31 modulator.
+
+use super::varicode::encode_char;
+use super::{convolve, symbol_rotation, PskMode, PskParams};
+use crate::error::DspError;
+use num_complex::Complex64;
+use std::f64::consts::PI;
+use tracing::{debug, info, warn};
+
+/// Reversals sent before the text so the receiver can find the carrier and symbol timing.
+const PREAMBLE_SYMBOLS: usize = 32;
+/// Steady carrier after the text, which also flushes the QPSK decoder.
+const POSTAMBLE_SYMBOLS: usize = 32;
+
+/// Varicode bit stream for `text`, framed by the preamble (zeros) and postamble (ones).
+///
+/// Each character is followed by "00". A newline is sent as CR LF; characters outside
+/// ASCII are skipped.
+fn text_bits(text: &str) -> Vec<bool> {
+    let mut bits = vec![false; PREAMBLE_SYMBOLS];
+    for character in text.chars() {
+        if character == '\r' {
+            continue;
+        }
+        let chars: &[char] = if character == '\n' { &['\r', '\n'] } else { &[character] };
+        for &c in chars {
+            match encode_char(c) {
+                Some(code) => {
+                    bits.extend(code);
+                    bits.extend([false, false]);
+                }
+                None => warn!("Unsupported character for PSK31 encoding: '{}'. Skipping.", c),
+            }
+        }
+    }
+    bits.extend(std::iter::repeat_n(true, POSTAMBLE_SYMBOLS));
+    bits
+}
+
+/// Two-bit symbols for `bits`: one per bit for BPSK, convolutionally coded for QPSK.
+fn bits_to_symbols(bits: &[bool], mode: PskMode) -> Vec<u8> {
+    match mode {
+        PskMode::Bpsk31 => bits.iter().map(|&bit| if bit { 2 } else { 0 }).collect(),
+        PskMode::Qpsk31 => {
+            let mut register = 0u8;
+            bits.iter()
+                .map(|&bit| {
+                    register = (register << 1 | bit as u8) & 0x1F;
+                    convolve(register)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Generates PSK31 audio for `text`.
+///
+/// Phase changes follow a raised-cosine envelope across the symbol, so the signal
+/// stays within its ~60 Hz bandwidth. The carrier fades in and out over one symbol.
+pub fn generate_psk_audio(text: &str, params: &PskParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Generating PSK audio: {:?}, Text='{}'", params, text);
+
+    let symbols = bits_to_symbols(&text_bits(text), params.mode);
+    // Symbol vectors with a silent one at each end for the fade in and out.
+    let mut vectors = vec![Complex64::default()];
+    let mut phase = Complex64::new(1.0, 0.0);
+    vectors.push(phase);
+    for &symbol in &symbols {
+        phase *= symbol_rotation(symbol);
+        vectors.push(phase);
+    }
+    vectors.push(Complex64::default());
+
+    let symbol_samples = params.symbol_samples();
+    let step = 2.0 * PI * params.tone_hz as f64 / params.sample_rate as f64;
+    let total = ((vectors.len() - 1) as f64 * symbol_samples).round() as usize;
+    let mut audio = Vec::with_capacity(total);
+    for n in 0..total {
+        let position = n as f64 / symbol_samples;
+        let index = (position.floor() as usize).min(vectors.len() - 2);
+        let t = position - index as f64;
+        let weight = 0.5 - 0.5 * (PI * t).cos();
+        let baseband = vectors[index] * (1.0 - weight) + vectors[index + 1] * weight;
+        let carrier = Complex64::from_polar(1.0, step * n as f64);
+        audio.push(params.amplitude * (baseband * carrier).re as f32);
+    }
+
+    info!(
+        "Generated {} audio with {} samples (approx {:.2} seconds)",
+        params.mode,
+        audio.len(),
+        audio.len() as f64 / params.sample_rate as f64
+    );
+    Ok(audio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_and_symbols() {
+        let bits = text_bits("e");
+        assert_eq!(bits.len(), PREAMBLE_SYMBOLS + 4 + POSTAMBLE_SYMBOLS);
+        assert_eq!(&bits[PREAMBLE_SYMBOLS..PREAMBLE_SYMBOLS + 4], &[true, true, false, false]);
+        // QPSK idle: zeros give reversals and ones a steady carrier, like BPSK.
+        assert!(bits_to_symbols(&[false; 8], PskMode::Qpsk31).iter().all(|&s| s == 0));
+        assert!(bits_to_symbols(&[true; 8], PskMode::Qpsk31)[4..].iter().all(|&s| s == 2));
+        assert_eq!(text_bits("a\nb").len(), text_bits("a\r\nb").len());
+    }
+
+    #[test]
+    fn test_envelope() {
+        let params = PskParams::new(PskMode::Bpsk31, 8000);
+        let audio = generate_psk_audio("", &params).unwrap();
+        // Fade in, 64 symbols, fade out.
+        assert_eq!(audio.len(), 66 * 256);
+        assert!(audio[..8].iter().all(|s| s.abs() < 0.01));
+        assert!(audio[audio.len() - 8..].iter().all(|s| s.abs() < 0.01));
+        // Reversals pass through zero half way between symbol peaks; the steady carrier doesn't.
+        let peak = |range: std::ops::Range<usize>| audio[range].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(10 * 256 - 8..10 * 256 + 8) > 0.8);
+        assert!(peak(10 * 256 + 124..10 * 256 + 132) < 0.06);
+        assert!(peak(50 * 256 - 8..50 * 256 + 8) > 0.8);
+    }
+}
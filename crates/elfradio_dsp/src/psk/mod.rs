@@ -0,0 +1,111 @@
+// PSK31: Varicode text at 31.25 baud, as differential BPSK or as QPSK carrying a
+// rate 1/2, K=5 convolutional code.
+
+mod decode;
+mod encode;
+mod varicode;
+
+pub use decode::{decode_psk, PskDecoder};
+pub use encode::generate_psk_audio;
+
+use crate::error::DspError;
+use num_complex::Complex64;
+use std::fmt;
+use std::str::FromStr;
+
+/// PSK31 symbol rate.
+pub const PSK31_BAUD: f64 = 31.25;
+/// Audio frequency PSK31 is usually sent and listened for on.
+pub const PSK_DEFAULT_TONE_HZ: f32 = 1000.0;
+
+/// Convolutional code generator polynomials (K = 5).
+const POLY_A: u8 = 0x17;
+const POLY_B: u8 = 0x19;
+
+/// Phase change for each two-bit symbol. BPSK uses 0 (a reversal, bit 0) and 2 (no
+/// change, bit 1).
+fn symbol_rotation(symbol: u8) -> Complex64 {
+    match symbol & 3 {
+        0 => Complex64::new(-1.0, 0.0),
+        1 => Complex64::new(0.0, -1.0),
+        2 => Complex64::new(1.0, 0.0),
+        _ => Complex64::new(0.0, 1.0),
+    }
+}
+
+/// Encoder output for the register holding the newest bit in bit 0.
+fn convolve(register: u8) -> u8 {
+    let parity = |poly: u8| (register & poly).count_ones() as u8 & 1;
+    parity(POLY_A) | parity(POLY_B) << 1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PskMode {
+    #[default]
+    Bpsk31,
+    Qpsk31,
+}
+
+impl PskMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PskMode::Bpsk31 => "BPSK31",
+            PskMode::Qpsk31 => "QPSK31",
+        }
+    }
+}
+
+impl fmt::Display for PskMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for PskMode {
+    type Err = DspError;
+
+    /// Accepts "BPSK31" / "QPSK31" in any case; "PSK31" means BPSK31.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "BPSK31" | "PSK31" => Ok(PskMode::Bpsk31),
+            "QPSK31" => Ok(PskMode::Qpsk31),
+            _ => Err(DspError::InvalidPskParameter(format!("unknown PSK mode '{}'", s))),
+        }
+    }
+}
+
+/// PSK modem settings shared by the modulator and demodulator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PskParams {
+    pub mode: PskMode,
+    /// Carrier (audio) frequency in Hz.
+    pub tone_hz: f32,
+    pub sample_rate: u32,
+    /// Peak amplitude of the generated audio (0.0 to 1.0).
+    pub amplitude: f32,
+}
+
+impl PskParams {
+    pub fn new(mode: PskMode, sample_rate: u32) -> Self {
+        Self { mode, tone_hz: PSK_DEFAULT_TONE_HZ, sample_rate, amplitude: 0.85 }
+    }
+
+    pub(crate) fn symbol_samples(&self) -> f64 {
+        self.sample_rate as f64 / PSK31_BAUD
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        if self.sample_rate < 1000 {
+            return Err(DspError::InvalidPskParameter(format!("sample rate {} Hz is too low", self.sample_rate)));
+        }
+        // Leave room for the signal's own bandwidth on both sides.
+        let max = self.sample_rate as f32 / 2.0 - 100.0;
+        if !(self.tone_hz >= 100.0 && self.tone_hz <= max) {
+            return Err(DspError::InvalidPskParameter(format!("tone {} Hz is outside 100..{} Hz", self.tone_hz, max)));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return Err(DspError::InvalidPskParameter(format!("amplitude {} is outside 0..1", self.amplitude)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// PSK31 Varicode: ASCII characters as variable-length codes with no "00" inside,
+// so two zero bits mark the gap between characters.
+
+/// Codes for ASCII 0-127, most significant bit sent first.
+const VARICODE: [&str; 128] = [
+    "1010101011", "1011011011", "1011101101", "1101110111", "1011101011", "1101011111", "1011101111", "1011111101",
+    "1011111111", "11101111", "11101", "1101101111", "1011011101", "11111", "1101110101", "1110101011",
+    "1011110111", "1011110101", "1110101101", "1110101111", "1101011011", "1101101011", "1101101101", "1101010111",
+    "1101111011", "1101111101", "1110110111", "1101010101", "1101011101", "1110111011", "1011111011", "1101111111",
+    "1", "111111111", "101011111", "111110101", "111011011", "1011010101", "1010111011", "101111111",
+    "11111011", "11110111", "101101111", "111011111", "1110101", "110101", "1010111", "110101111",
+    "10110111", "10111101", "11101101", "11111111", "101110111", "101011011", "101101011", "110101101",
+    "110101011", "110110111", "11110101", "110111101", "111101101", "1010101", "111010111", "1010101111",
+    "1010111101", "1111101", "11101011", "10101101", "10110101", "1110111", "11011011", "11111101",
+    "101010101", "1111111", "111111101", "101111101", "11010111", "10111011", "11011101", "10101011",
+    "11010101", "111011101", "10101111", "1101111", "1101101", "101010111", "110110101", "101011101",
+    "101110101", "101111011", "1010101101", "111110111", "111101111", "111111011", "1010111111", "101101101",
+    "1011011111", "1011", "1011111", "101111", "101101", "11", "111101", "1011011",
+    "101011", "1101", "111101011", "10111111", "11011", "111011", "1111", "111",
+    "111111", "110111111", "10101", "10111", "101", "110111", "1111011", "1101011",
+    "11011111", "1011101", "111010101", "1010110111", "110111011", "1010110101", "1011010111", "1110110101",
+];
+
+/// Bits of `c`'s code, first bit first; None outside ASCII.
+pub(crate) fn encode_char(c: char) -> Option<impl Iterator<Item = bool>> {
+    let code = VARICODE.get(c as usize).filter(|_| c.is_ascii())?;
+    Some(code.bytes().map(|b| b == b'1'))
+}
+
+/// Character for a received code (its bits packed MSB first).
+pub(crate) fn decode_bits(bits: u32) -> Option<char> {
+    VARICODE
+        .iter()
+        .position(|code| u32::from_str_radix(code, 2).ok() == Some(bits))
+        .map(|ascii| ascii as u8 as char)
+}
+
+/// Longest code in the table, in bits.
+pub(crate) const MAX_CODE_BITS: u32 = 10;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_valid_and_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for (ascii, code) in VARICODE.iter().enumerate() {
+            assert!(code.starts_with('1') && code.ends_with('1') && !code.contains("00"), "{}: {}", ascii, code);
+            assert!(code.len() <= MAX_CODE_BITS as usize);
+            assert!(seen.insert(*code), "duplicate code {}", code);
+            let bits = u32::from_str_radix(code, 2).unwrap();
+            assert_eq!(decode_bits(bits), Some(ascii as u8 as char));
+        }
+        // The common letters get the short codes.
+        assert_eq!(VARICODE[b'e' as usize], "11");
+        assert_eq!(VARICODE[b' ' as usize], "1");
+        assert!(encode_char('é').is_none());
+    }
+}
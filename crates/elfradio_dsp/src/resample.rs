@@ -0,0 +1,143 @@
+// Streaming sample-rate conversion for the audio backends and the speech path.
+//
+// Linear interpolation is plenty for speech and narrowband modes. When
+// downsampling, an 8th-order Butterworth low-pass at 0.4 × the output rate
+// runs first so e.g. 48 kHz → 16 kHz doesn't fold hiss into the voice band.
+
+use crate::conditioning::{butterworth_q, Biquad};
+
+/// Converts a continuous stream of mono samples from one rate to another.
+///
+/// Feed it consecutive blocks with [`LinearResampler::process`]; interpolation
+/// state carries over between blocks, so block boundaries are seamless.
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    from_rate: u32,
+    to_rate: u32,
+    /// Input samples advanced per output sample.
+    step: f64,
+    /// Position of the next output sample; -1.0 refers to `last`, 0.0 to the first sample of the next block.
+    position: f64,
+    last: f32,
+    anti_alias: Vec<Biquad>,
+}
+
+impl LinearResampler {
+    /// Creates a resampler from `from_rate` Hz to `to_rate` Hz.
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let (from, to) = (from_rate.max(1), to_rate.max(1));
+        let anti_alias = if to < from {
+            let cutoff = 0.4 * to as f64;
+            butterworth_q(8).into_iter().map(|q| Biquad::low_pass(cutoff, from as f64, q)).collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            from_rate: from,
+            to_rate: to,
+            step: from as f64 / to as f64,
+            position: 0.0,
+            last: 0.0,
+            anti_alias,
+        }
+    }
+
+    /// Returns `true` if input and output rates are equal.
+    pub fn is_passthrough(&self) -> bool {
+        self.from_rate == self.to_rate
+    }
+
+    /// Input rate in Hz.
+    pub fn from_rate(&self) -> u32 {
+        self.from_rate
+    }
+
+    /// Output rate in Hz.
+    pub fn to_rate(&self) -> u32 {
+        self.to_rate
+    }
+
+    /// Resamples the next block of the stream.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() || input.is_empty() {
+            return input.to_vec();
+        }
+
+        let filtered: Vec<f32> = if self.anti_alias.is_empty() {
+            input.to_vec()
+        } else {
+            input
+                .iter()
+                .map(|&s| self.anti_alias.iter_mut().fold(s as f64, |acc, f| f.process(acc)) as f32)
+                .collect()
+        };
+
+        let n = filtered.len();
+        let sample_at = |index: isize| if index < 0 { self.last } else { filtered[index as usize] };
+        let mut output = Vec::with_capacity((n as f64 / self.step).ceil() as usize + 1);
+        while self.position < (n - 1) as f64 {
+            let index = self.position.floor();
+            let frac = (self.position - index) as f32;
+            let (a, b) = (sample_at(index as isize), sample_at(index as isize + 1));
+            output.push(a + (b - a) * frac);
+            self.position += self.step;
+        }
+
+        self.position -= n as f64;
+        self.last = filtered[n - 1];
+        output
+    }
+}
+
+/// One-shot resampling of a complete buffer.
+pub fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    LinearResampler::new(from_rate, to_rate).process(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goertzel::goertzel_power;
+
+    fn sine(freq: f32, rate: u32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin()).collect()
+    }
+
+    /// Amplitude of the `freq` component.
+    fn tone_level(samples: &[f32], freq: f32, rate: u32) -> f64 {
+        (2.0 * goertzel_power(samples, freq as f64, rate as f64)).sqrt()
+    }
+
+    #[test]
+    fn test_length_and_passthrough() {
+        let input = sine(1000.0, 48000, 48000);
+        assert_eq!(resample(&input, 48000, 48000), input);
+        let down = resample(&input, 48000, 16000);
+        assert!((down.len() as i64 - 16000).abs() <= 1, "got {}", down.len());
+        let up = resample(&input[..16000], 16000, 44100);
+        assert!((up.len() as i64 - 44100).abs() <= 3, "got {}", up.len());
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let input = sine(700.0, 44100, 44100);
+        let one_shot = resample(&input, 44100, 16000);
+        let mut streaming = LinearResampler::new(44100, 16000);
+        let blocks: Vec<f32> = input.chunks(441).flat_map(|block| streaming.process(block)).collect();
+        assert!((blocks.len() as i64 - one_shot.len() as i64).abs() <= 1);
+        for (a, b) in blocks.iter().zip(&one_shot).skip(10) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_tone_preserved_and_alias_suppressed() {
+        // 1 kHz survives 48k → 16k at nearly full level.
+        let voice = resample(&sine(1000.0, 48000, 48000), 48000, 16000);
+        assert!(tone_level(&voice[1000..], 1000.0, 16000) > 0.9);
+
+        // 12 kHz would alias to 4 kHz at 16 kHz; the anti-alias filter must kill it.
+        let hiss = resample(&sine(12000.0, 48000, 48000), 48000, 16000);
+        assert!(tone_level(&hiss[1000..], 4000.0, 16000) < 0.04);
+    }
+}
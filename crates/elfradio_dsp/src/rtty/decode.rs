@@ -0,0 +1,237 @@
+// RTTY receive decoder.
+//
+// Audio → mark and space filters (one bit long, matched to the symbol) → discriminator →
+// start-edge search and mid-bit sampling of each frame → ITA2 with LTRS/FIGS tracking → text.
+
+use super::{RttyParams, FIGS, FIGURES, LETTERS, LTRS};
+use crate::error::DspError;
+use num_complex::Complex64;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use tracing::{debug, trace};
+
+/// Fraction of the audio power that must be in the two tones for a frame to start.
+const SQUELCH_RATIO: f64 = 0.3;
+
+/// Integrate-and-dump filter for one tone over a sliding window.
+pub(crate) struct ToneFilter {
+    phase: f64,
+    step: f64,
+    sum: Complex64,
+    history: VecDeque<Complex64>,
+    len: usize,
+}
+
+impl ToneFilter {
+    pub(crate) fn new(hz: f32, sample_rate: u32, len: usize) -> Self {
+        Self {
+            phase: 0.0,
+            step: 2.0 * PI * hz as f64 / sample_rate as f64,
+            sum: Complex64::default(),
+            history: VecDeque::with_capacity(len + 1),
+            len,
+        }
+    }
+
+    /// Feeds one sample; returns the tone's mean power over the window (A²/2 for a full-window sine).
+    pub(crate) fn process(&mut self, sample: f32) -> f64 {
+        let mixed = Complex64::from_polar(sample as f64, -self.phase);
+        self.phase = (self.phase + self.step) % (2.0 * PI);
+        self.sum += mixed;
+        self.history.push_back(mixed);
+        if self.history.len() > self.len {
+            self.sum -= self.history.pop_front().unwrap_or_default();
+        }
+        2.0 * (self.sum.norm() / self.len as f64).powi(2)
+    }
+}
+
+enum FrameState {
+    /// Waiting for a mark-to-space edge; the flag records that mark has been heard.
+    Hunt { mark_seen: bool },
+    /// Receiving a frame; `elapsed` counts samples since the detected edge.
+    Frame { elapsed: f64, bit: u32, code: u8 },
+}
+
+/// Streaming RTTY decoder.
+pub struct RttyDecoder {
+    mark: ToneFilter,
+    space: ToneFilter,
+    /// Sliding sum of squared samples over the same window as the tone filters.
+    power: f64,
+    power_history: VecDeque<f64>,
+    bit_samples: f64,
+    state: FrameState,
+    figures: bool,
+    unshift_on_space: bool,
+    output: String,
+}
+
+impl RttyDecoder {
+    /// Creates a decoder for the tones and speed in `params`.
+    pub fn new(params: &RttyParams) -> Result<Self, DspError> {
+        params.validate()?;
+        let bit_samples = params.bit_samples();
+        let len = bit_samples.round() as usize;
+        Ok(Self {
+            mark: ToneFilter::new(params.mark_hz, params.sample_rate, len),
+            space: ToneFilter::new(params.space_hz(), params.sample_rate, len),
+            power: 0.0,
+            power_history: VecDeque::with_capacity(len + 1),
+            bit_samples,
+            state: FrameState::Hunt { mark_seen: false },
+            figures: false,
+            unshift_on_space: params.unshift_on_space,
+            output: String::new(),
+        })
+    }
+
+    /// Feeds audio and returns any text decoded from it.
+    pub fn push(&mut self, samples: &[f32]) -> String {
+        for &sample in samples {
+            self.process_sample(sample);
+        }
+        std::mem::take(&mut self.output)
+    }
+
+    /// Drops any partly received frame and returns the remaining text.
+    pub fn flush(&mut self) -> String {
+        self.state = FrameState::Hunt { mark_seen: false };
+        std::mem::take(&mut self.output)
+    }
+
+    fn process_sample(&mut self, sample: f32) {
+        let mark = self.mark.process(sample);
+        let space = self.space.process(sample);
+        let energy = (sample as f64).powi(2);
+        self.power += energy;
+        self.power_history.push_back(energy);
+        if self.power_history.len() > self.mark.len {
+            self.power -= self.power_history.pop_front().unwrap_or_default();
+        }
+        let mean_power = self.power.max(0.0) / self.mark.len as f64;
+        let is_mark = mark > space;
+
+        match &mut self.state {
+            FrameState::Hunt { mark_seen } => {
+                if mark + space < SQUELCH_RATIO * mean_power || mean_power == 0.0 {
+                    *mark_seen = false;
+                } else if is_mark {
+                    *mark_seen = true;
+                } else if *mark_seen {
+                    // The filters are a bit long, so they cross over half a bit after the edge.
+                    self.state = FrameState::Frame { elapsed: 0.0, bit: 0, code: 0 };
+                }
+            }
+            FrameState::Frame { elapsed, bit, code } => {
+                *elapsed += 1.0;
+                // The window covers bit `n` exactly half a bit after the crossover plus n bits.
+                if *elapsed < (*bit as f64 + 0.5) * self.bit_samples {
+                    return;
+                }
+                match *bit {
+                    0 if is_mark => {
+                        trace!("RTTY start bit too short");
+                        self.state = FrameState::Hunt { mark_seen: true };
+                    }
+                    1..=5 if is_mark => *code |= 1 << (*bit - 1),
+                    6 => {
+                        let code = *code;
+                        self.state = FrameState::Hunt { mark_seen: is_mark };
+                        if is_mark {
+                            self.receive(code);
+                        } else {
+                            debug!(code, "RTTY framing error");
+                        }
+                        return;
+                    }
+                    _ => {}
+                }
+                if let FrameState::Frame { bit, .. } = &mut self.state {
+                    *bit += 1;
+                }
+            }
+        }
+    }
+
+    fn receive(&mut self, code: u8) {
+        trace!(code, figures = self.figures, "RTTY character");
+        match code {
+            LTRS => self.figures = false,
+            FIGS => self.figures = true,
+            _ => {
+                let table = if self.figures { &FIGURES } else { &LETTERS };
+                match table[code as usize] {
+                    Some(' ') => {
+                        self.output.push(' ');
+                        self.figures &= !self.unshift_on_space;
+                    }
+                    Some('\r') | None => {}
+                    Some(c) => self.output.push(c),
+                }
+            }
+        }
+    }
+}
+
+/// Decodes a complete RTTY recording.
+pub fn decode_rtty(samples: &[f32], params: &RttyParams) -> Result<String, DspError> {
+    let mut decoder = RttyDecoder::new(params)?;
+    let mut text = decoder.push(samples);
+    text.push_str(&decoder.flush());
+    debug!("Decoded {} RTTY characters", text.chars().count());
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtty::generate_rtty_audio;
+    use crate::fixtures::add_noise;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "RYRYRY CQ CQ DE VK7KSM VK7KSM K\nUR 599 599 IN HOBART, 73!";
+        for params in [
+            RttyParams::new(8000),
+            RttyParams::new(48000),
+            RttyParams { baud: 50.0, shift_hz: 850.0, mark_hz: 1275.0, stop_bits: 1.0, ..RttyParams::new(11025) },
+            RttyParams { baud: 75.0, unshift_on_space: false, ..RttyParams::new(8000) },
+        ] {
+            let audio = generate_rtty_audio(text, &params).unwrap();
+            assert_eq!(decode_rtty(&audio, &params).unwrap(), text, "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_with_noise_and_offset_start() {
+        let params = RttyParams::new(8000);
+        let text = "THE QUICK BROWN FOX 1234567890";
+        // Start part way into the leading carrier, and add noise.
+        let mut audio = generate_rtty_audio(text, &params).unwrap().split_off(357);
+        add_noise(&mut audio, 0.3, 11);
+        assert_eq!(decode_rtty(&audio, &params).unwrap(), text);
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let params = RttyParams::new(8000);
+        let audio = generate_rtty_audio("DE VK7KSM 73", &params).unwrap();
+        let mut decoder = RttyDecoder::new(&params).unwrap();
+        let streamed: String = audio.chunks(113).map(|chunk| decoder.push(chunk)).collect();
+        assert_eq!(streamed, "DE VK7KSM 73");
+    }
+
+    #[test]
+    fn test_noise_and_wrong_shift_decode_nothing() {
+        let params = RttyParams::new(8000);
+        let mut noise = vec![0.0; 8000 * 3];
+        add_noise(&mut noise, 0.3, 5);
+        assert_eq!(decode_rtty(&noise, &params).unwrap(), "");
+
+        // 850 Hz shift audio puts its space tone far outside a 170 Hz receiver.
+        let wide = RttyParams { shift_hz: 850.0, ..params.clone() };
+        let audio = generate_rtty_audio("RYRYRYRY", &wide).unwrap();
+        assert_eq!(decode_rtty(&audio, &params).unwrap(), "");
+    }
+}
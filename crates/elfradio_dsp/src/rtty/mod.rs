@@ -0,0 +1,261 @@
+// RTTY: ITA2 (Baudot) characters sent as asynchronous frames on two AFSK tones.
+//
+// Frame: one start bit (space), five data bits LSB first (1 = mark), 1.5 stop bits (mark).
+
+mod decode;
+
+pub use decode::{decode_rtty, RttyDecoder};
+pub(crate) use decode::ToneFilter;
+
+use crate::error::DspError;
+use std::f64::consts::PI;
+use tracing::{debug, info, warn};
+
+/// ITA2 control codes.
+pub(crate) const LTRS: u8 = 0x1F;
+pub(crate) const FIGS: u8 = 0x1B;
+const SPACE: u8 = 0x04;
+const CR: u8 = 0x08;
+const LF: u8 = 0x02;
+
+/// Letters case, indexed by code. Shift codes are None.
+pub(crate) const LETTERS: [Option<char>; 32] = [
+    None, Some('E'), Some('\n'), Some('A'), Some(' '), Some('S'), Some('I'), Some('U'),
+    Some('\r'), Some('D'), Some('R'), Some('J'), Some('N'), Some('F'), Some('C'), Some('K'),
+    Some('T'), Some('Z'), Some('L'), Some('W'), Some('H'), Some('Y'), Some('P'), Some('Q'),
+    Some('O'), Some('B'), Some('G'), None, Some('M'), Some('X'), Some('V'), None,
+];
+
+/// Figures case (US TTY variant used on the amateur bands). The bell is left out.
+pub(crate) const FIGURES: [Option<char>; 32] = [
+    None, Some('3'), Some('\n'), Some('-'), Some(' '), None, Some('8'), Some('7'),
+    Some('\r'), Some('$'), Some('4'), Some('\''), Some(','), Some('!'), Some(':'), Some('('),
+    Some('5'), Some('"'), Some(')'), Some('2'), Some('#'), Some('6'), Some('0'), Some('1'),
+    Some('9'), Some('?'), Some('&'), None, Some('.'), Some('/'), Some(';'), None,
+];
+
+/// Mark carrier sent before and after the text so the receiver can settle.
+const IDLE_SECS: f64 = 0.15;
+
+/// RTTY modem settings shared by the encoder and decoder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RttyParams {
+    /// Mark tone in Hz.
+    pub mark_hz: f32,
+    /// Space tone offset from mark in Hz (space = mark + shift).
+    pub shift_hz: f32,
+    /// Symbol rate.
+    pub baud: f32,
+    /// Stop bit length in bits (at least 1).
+    pub stop_bits: f32,
+    pub sample_rate: u32,
+    /// Peak amplitude of the generated audio (0.0 to 1.0).
+    pub amplitude: f32,
+    /// Return to letters case after a space ("unshift on space").
+    pub unshift_on_space: bool,
+}
+
+impl Default for RttyParams {
+    /// The amateur standard: 45.45 baud, 170 Hz shift, 2125 Hz mark.
+    fn default() -> Self {
+        Self {
+            mark_hz: 2125.0,
+            shift_hz: 170.0,
+            baud: 45.45,
+            stop_bits: 1.5,
+            sample_rate: 48000,
+            amplitude: 0.85,
+            unshift_on_space: true,
+        }
+    }
+}
+
+impl RttyParams {
+    /// Standard settings at `sample_rate`.
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, ..Self::default() }
+    }
+
+    pub fn space_hz(&self) -> f32 {
+        self.mark_hz + self.shift_hz
+    }
+
+    pub(crate) fn bit_samples(&self) -> f64 {
+        self.sample_rate as f64 / self.baud as f64
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidRttyParameter(msg));
+        if self.sample_rate == 0 {
+            return invalid("sample rate cannot be zero".to_string());
+        }
+        let nyquist = self.sample_rate as f32 / 2.0;
+        for (name, hz) in [("mark", self.mark_hz), ("space", self.space_hz())] {
+            if !(hz > 0.0 && hz < nyquist) {
+                return invalid(format!("{} tone {} Hz is outside 0..{} Hz", name, hz, nyquist));
+            }
+        }
+        if !(self.baud > 0.0 && self.baud.is_finite()) || self.bit_samples() < 8.0 {
+            return invalid(format!("{} baud is not usable at {} Hz", self.baud, self.sample_rate));
+        }
+        if self.shift_hz.abs() < self.baud {
+            return invalid(format!("shift {} Hz is narrower than the {} baud symbol rate", self.shift_hz, self.baud));
+        }
+        if !(self.stop_bits >= 1.0 && self.stop_bits.is_finite()) {
+            return invalid(format!("{} stop bits", self.stop_bits));
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return invalid(format!("amplitude {} is outside 0..1", self.amplitude));
+        }
+        Ok(())
+    }
+}
+
+/// Converts text to ITA2 codes, inserting LTRS/FIGS where the case changes.
+///
+/// Starts with LTRS so the receiver's case is known. A newline is sent as CR LF;
+/// characters without an ITA2 code are skipped.
+fn encode_ita2(text: &str, unshift_on_space: bool) -> Vec<u8> {
+    let mut codes = vec![LTRS];
+    let mut figures = false;
+    for character in text.to_uppercase().chars() {
+        match character {
+            ' ' => {
+                codes.push(SPACE);
+                figures &= !unshift_on_space;
+                continue;
+            }
+            '\n' => {
+                codes.extend([CR, LF]);
+                continue;
+            }
+            '\r' => continue,
+            _ => {}
+        }
+        let position = |table: &[Option<char>; 32]| table.iter().position(|&c| c == Some(character));
+        let (code, needs_figures) = match (position(&LETTERS), position(&FIGURES)) {
+            (Some(code), _) => (code as u8, false),
+            (None, Some(code)) => (code as u8, true),
+            (None, None) => {
+                warn!("Unsupported character for RTTY encoding: '{}'. Skipping.", character);
+                continue;
+            }
+        };
+        if needs_figures != figures {
+            codes.push(if needs_figures { FIGS } else { LTRS });
+            figures = needs_figures;
+        }
+        codes.push(code);
+    }
+    codes
+}
+
+/// Phase-continuous two-tone writer with fractional bit timing.
+struct FskWriter {
+    phase: f64,
+    mark_step: f64,
+    space_step: f64,
+    amplitude: f32,
+    sample_rate: f64,
+    /// Time written so far, in seconds.
+    time: f64,
+    buffer: Vec<f32>,
+}
+
+impl FskWriter {
+    fn new(params: &RttyParams) -> Self {
+        let step = |hz: f32| 2.0 * PI * hz as f64 / params.sample_rate as f64;
+        Self {
+            phase: 0.0,
+            mark_step: step(params.mark_hz),
+            space_step: step(params.space_hz()),
+            amplitude: params.amplitude,
+            sample_rate: params.sample_rate as f64,
+            time: 0.0,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn tone(&mut self, mark: bool, secs: f64) {
+        self.time += secs;
+        let end = (self.time * self.sample_rate).round() as usize;
+        let step = if mark { self.mark_step } else { self.space_step };
+        while self.buffer.len() < end {
+            self.buffer.push(self.amplitude * self.phase.sin() as f32);
+            self.phase = (self.phase + step) % (2.0 * PI);
+        }
+    }
+}
+
+/// Generates RTTY AFSK audio for `text`.
+///
+/// Letters are sent upper case. The audio starts and ends with a short mark carrier.
+pub fn generate_rtty_audio(text: &str, params: &RttyParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Generating RTTY audio: {:?}, Text='{}'", params, text);
+
+    let bit = 1.0 / params.baud as f64;
+    let mut writer = FskWriter::new(params);
+    writer.tone(true, IDLE_SECS);
+    for code in encode_ita2(text, params.unshift_on_space) {
+        writer.tone(false, bit);
+        for n in 0..5 {
+            writer.tone(code >> n & 1 == 1, bit);
+        }
+        writer.tone(true, bit * params.stop_bits as f64);
+    }
+    writer.tone(true, IDLE_SECS);
+
+    info!(
+        "Generated RTTY audio with {} samples (approx {:.2} seconds)",
+        writer.buffer.len(),
+        writer.buffer.len() as f64 / params.sample_rate as f64
+    );
+    Ok(writer.buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_encode_shifts() {
+        // LTRS, C, Q, space, FIGS, 5, 9, 9, space (back to letters), FIGS, 7, 3
+        assert_eq!(
+            encode_ita2("cq 599 73", true),
+            vec![LTRS, 0x0E, 0x17, SPACE, FIGS, 0x10, 0x18, 0x18, SPACE, FIGS, 0x07, 0x01]
+        );
+        // Without unshift-on-space the figures case carries over the space.
+        assert_eq!(encode_ita2("1 2", false), vec![LTRS, FIGS, 0x17, SPACE, 0x13]);
+        assert_eq!(encode_ita2("A\nB", true), vec![LTRS, 0x03, CR, LF, 0x19]);
+        assert_eq!(encode_ita2("A%B", true), vec![LTRS, 0x03, 0x19]);
+    }
+
+    #[test]
+    fn test_frame_length() {
+        let params = RttyParams { baud: 50.0, stop_bits: 1.5, ..RttyParams::new(8000) };
+        // Two characters (LTRS + E) of 7.5 bits at 160 samples, plus the idle carrier.
+        let audio = generate_rtty_audio("E", &params).unwrap();
+        assert_eq!(audio.len(), 2 * 1200 + 2 * 1200);
+        assert!(audio.iter().all(|s| s.abs() <= 0.85));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let params = RttyParams::new(8000);
+        assert_matches!(generate_rtty_audio("E", &RttyParams::new(0)), Err(DspError::InvalidRttyParameter(_)));
+        assert_matches!(
+            generate_rtty_audio("E", &RttyParams { mark_hz: 3900.0, ..params.clone() }),
+            Err(DspError::InvalidRttyParameter(_))
+        );
+        assert_matches!(
+            generate_rtty_audio("E", &RttyParams { shift_hz: 20.0, ..params.clone() }),
+            Err(DspError::InvalidRttyParameter(_))
+        );
+        assert_matches!(
+            generate_rtty_audio("E", &RttyParams { stop_bits: 0.5, ..params }),
+            Err(DspError::InvalidRttyParameter(_))
+        );
+    }
+}
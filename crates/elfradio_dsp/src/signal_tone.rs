@@ -0,0 +1,190 @@
+// Signal tones that frame a transmission: start and end tone sequences, the Roger beep
+// (courtesy tone) and the 1750 Hz burst that opens many European repeaters.
+//
+// Back-to-back tones keep their phase, so a sequence steps from note to note without a
+// click; a raised-cosine ramp shapes every edge next to silence.
+
+use crate::error::DspError;
+use std::f64::consts::PI;
+use tracing::{debug, info};
+
+/// Tone burst frequency used to open European repeaters.
+pub const TONE_BURST_HZ: f32 = 1750.0;
+/// Raised-cosine ramp at each edge next to silence.
+const RAMP_MS: f64 = 5.0;
+
+/// A run of tones of equal length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToneSequence {
+    pub freqs_hz: Vec<f32>,
+    /// Length of each tone (milliseconds).
+    pub tone_ms: u64,
+    /// Silence between tones (milliseconds). Without one the tones follow each other
+    /// phase-continuously.
+    pub gap_ms: u64,
+}
+
+impl ToneSequence {
+    pub fn new(freqs_hz: Vec<f32>, tone_ms: u64) -> Self {
+        Self { freqs_hz, tone_ms, gap_ms: 0 }
+    }
+
+    pub fn with_gap(mut self, gap_ms: u64) -> Self {
+        self.gap_ms = gap_ms;
+        self
+    }
+
+    /// Roger beep: a quick falling two-note chirp that marks the end of an over.
+    pub fn roger_beep() -> Self {
+        Self::new(vec![2000.0, 1500.0], 75)
+    }
+
+    /// A 1750 Hz tone burst; most repeaters want 300 to 500 ms.
+    pub fn tone_burst(duration_ms: u64) -> Self {
+        Self::new(vec![TONE_BURST_HZ], duration_ms)
+    }
+}
+
+/// Signal tone generator settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalToneParams {
+    pub sample_rate: u32,
+    /// Peak amplitude (0.0 to 1.0).
+    pub amplitude: f32,
+}
+
+impl SignalToneParams {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, amplitude: 0.5 }
+    }
+
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    pub(crate) fn validate(&self, sequence: &ToneSequence) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidSignalToneParameter(msg));
+        if self.sample_rate == 0 {
+            return invalid("sample rate must be greater than 0".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.amplitude) {
+            return invalid(format!("amplitude {} is outside 0..1", self.amplitude));
+        }
+        let nyquist = self.sample_rate as f32 / 2.0;
+        if let Some(hz) = sequence.freqs_hz.iter().find(|&&hz| !(hz > 0.0 && hz < nyquist)) {
+            return invalid(format!("{} Hz is outside 0..{} Hz", hz, nyquist));
+        }
+        if sequence.tone_ms == 0 && !sequence.freqs_hz.is_empty() {
+            return invalid("tone length must be greater than 0 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn ramp_gain(n: usize, ramp: usize) -> f64 {
+    if n < ramp { 0.5 - 0.5 * (PI * n as f64 / ramp as f64).cos() } else { 1.0 }
+}
+
+/// Generates `sequence`: each tone for `tone_ms`, with `gap_ms` of silence between tones.
+pub fn generate_tone_sequence(sequence: &ToneSequence, params: &SignalToneParams) -> Result<Vec<f32>, DspError> {
+    params.validate(sequence)?;
+    debug!("Generating signal tones: {:?}, {:?}", sequence, params);
+
+    let rate = params.sample_rate as f64;
+    let tone = (sequence.tone_ms as f64 * rate / 1000.0).round() as usize;
+    let gap = (sequence.gap_ms as f64 * rate / 1000.0).round() as usize;
+    let ramp = ((RAMP_MS * rate / 1000.0).round() as usize).min(tone / 2);
+    let last = sequence.freqs_hz.len().saturating_sub(1);
+    let mut audio = Vec::with_capacity(sequence.freqs_hz.len() * (tone + gap));
+    let mut phase = 0.0f64;
+    for (index, &hz) in sequence.freqs_hz.iter().enumerate() {
+        if index > 0 && gap > 0 {
+            audio.resize(audio.len() + gap, 0.0);
+            phase = 0.0;
+        }
+        let fade_in = index == 0 || gap > 0;
+        let fade_out = index == last || gap > 0;
+        let step = 2.0 * PI * hz as f64 / rate;
+        for n in 0..tone {
+            let rise = if fade_in { ramp_gain(n, ramp) } else { 1.0 };
+            let fall = if fade_out { ramp_gain(tone - 1 - n, ramp) } else { 1.0 };
+            audio.push((params.amplitude as f64 * rise.min(fall) * phase.sin()) as f32);
+            phase = (phase + step) % (2.0 * PI);
+        }
+    }
+
+    info!(
+        "Generated signal tones with {} samples (approx {:.2} seconds)",
+        audio.len(),
+        audio.len() as f64 / rate
+    );
+    Ok(audio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    /// Frequency estimate from upward zero crossings.
+    fn frequency(samples: &[f32], sample_rate: f64) -> f64 {
+        let crossings = samples.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        crossings as f64 * sample_rate / samples.len() as f64
+    }
+
+    #[test]
+    fn test_sequence_layout() {
+        let params = SignalToneParams::new(16000);
+        let audio = generate_tone_sequence(&ToneSequence::new(vec![1000.0, 1500.0], 100).with_gap(50), &params).unwrap();
+        assert_eq!(audio.len(), 1600 + 800 + 1600);
+        assert!((frequency(&audio[100..1500], 16000.0) - 1000.0).abs() < 20.0);
+        assert!(audio[1600..2400].iter().all(|&s| s == 0.0));
+        assert!((frequency(&audio[2500..3900], 16000.0) - 1500.0).abs() < 20.0);
+        // Ramped edges, full level in between.
+        assert!(audio[0].abs() < 1e-6 && audio[3999].abs() < 0.01);
+        let peak = audio.iter().fold(0.0f32, |acc, s| acc.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_back_to_back_tones_are_continuous() {
+        let params = SignalToneParams::new(8000).with_amplitude(1.0);
+        let audio = generate_tone_sequence(&ToneSequence::roger_beep(), &params).unwrap();
+        assert_eq!(audio.len(), 2 * 600);
+        // No sample-to-sample step larger than the highest tone allows.
+        let max_step = 2.0 * PI * 2000.0 / 8000.0;
+        assert!(audio.windows(2).all(|pair| ((pair[1] - pair[0]).abs() as f64) <= max_step + 1e-3));
+        // The join isn't ramped down.
+        assert!(audio[595..605].iter().any(|s| s.abs() > 0.5));
+    }
+
+    #[test]
+    fn test_tone_burst() {
+        let audio = generate_tone_sequence(&ToneSequence::tone_burst(400), &SignalToneParams::new(16000)).unwrap();
+        assert_eq!(audio.len(), 6400);
+        assert!((frequency(&audio, 16000.0) - 1750.0).abs() < 10.0);
+        assert!(generate_tone_sequence(&ToneSequence::new(vec![], 100), &SignalToneParams::new(16000)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let params = SignalToneParams::new(8000);
+        for sequence in [
+            ToneSequence::new(vec![4000.0], 100),
+            ToneSequence::new(vec![0.0], 100),
+            ToneSequence::new(vec![1000.0], 0),
+        ] {
+            assert_matches!(
+                generate_tone_sequence(&sequence, &params),
+                Err(DspError::InvalidSignalToneParameter(_)),
+                "{:?}",
+                sequence
+            );
+        }
+        assert_matches!(
+            generate_tone_sequence(&ToneSequence::tone_burst(100), &params.clone().with_amplitude(1.5)),
+            Err(DspError::InvalidSignalToneParameter(_))
+        );
+    }
+}
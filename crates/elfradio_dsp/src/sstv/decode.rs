@@ -0,0 +1,765 @@
+// SSTV receive decoder.
+//
+// Audio → instantaneous frequency track → VIS header → sync pulses → line
+// timing fit (which absorbs sound-card clock error, i.e. slant) → pixels.
+
+use super::modes::{
+    frequency_to_level, ycrcb_to_rgb, Channel, ColorSpace, Segment, SstvMode, BLACK_HZ, LEADER_HZ, SYNC_HZ,
+    VIS_BIT_MS, VIS_ONE_HZ, VIS_ZERO_HZ, WHITE_HZ,
+};
+use crate::demod::{lowpass_taps, BoxcarDecimator, Fir, Nco};
+use crate::error::DspError;
+use image::{Rgb, RgbImage};
+use num_complex::Complex32;
+use tracing::{debug, info, warn};
+
+/// The frequency track is computed at roughly this rate.
+const TRACK_RATE: u32 = 11025;
+/// Frequencies below this count as sync (halfway between sync and black).
+const SYNC_THRESHOLD_HZ: f32 = (SYNC_HZ + BLACK_HZ) / 2.0;
+/// Largest clock error the line timing fit will accept.
+const MAX_CLOCK_ERROR: f64 = 0.03;
+
+/// A decoded picture.
+#[derive(Debug, Clone)]
+pub struct DecodedSstvImage {
+    pub mode: SstvMode,
+    pub image: RgbImage,
+    /// VIS code from the header, if one was read with valid parity.
+    pub vis_code: Option<u8>,
+    /// Measured line period over the nominal one (1.0 = no slant).
+    pub clock_ratio: f64,
+    /// Image lines actually received; the rest of the picture is black.
+    pub lines_received: u32,
+}
+
+/// Instantaneous frequency of the audio, one value per track sample.
+pub(crate) struct FrequencyTrack {
+    pub(crate) freq: Vec<f32>,
+    pub(crate) rate: f64,
+}
+
+impl FrequencyTrack {
+    pub(crate) fn new(samples: &[f32], sample_rate: u32) -> Self {
+        let factor = (sample_rate / TRACK_RATE).max(1) as usize;
+        let rate = sample_rate as f64 / factor as f64;
+        // Mixing the leader tone to DC puts the whole SSTV band (1100–2300 Hz) within ±800 Hz.
+        let mut mixer = Nco::new(LEADER_HZ as f64, sample_rate as f64);
+        let mut decimator = BoxcarDecimator::new(factor);
+        let taps = lowpass_taps(1300.0, rate as f32, 700.0);
+        // Flushing the filter keeps the tail of a transmission that ends right with the audio.
+        let flush = taps.len();
+        let mut filter = Fir::new(taps);
+        let scale = (rate / (2.0 * std::f64::consts::PI)) as f32;
+
+        let mut prev = Complex32::default();
+        let mut freq = Vec::with_capacity(samples.len() / factor + 1);
+        let basebands = samples.iter().filter_map(|&sample| decimator.process(mixer.mix(Complex32::new(sample, 0.0))));
+        for baseband in basebands.chain(std::iter::repeat_n(Complex32::default(), flush)) {
+            let filtered = filter.process(baseband);
+            freq.push(LEADER_HZ + (filtered * prev.conj()).arg() * scale);
+            prev = filtered;
+        }
+        Self { freq, rate }
+    }
+
+    pub(crate) fn samples(&self, ms: f64) -> f64 {
+        ms * self.rate / 1000.0
+    }
+
+    /// Centered moving average over `ms`.
+    fn smoothed(&self, ms: f64) -> Vec<f32> {
+        let half = (self.samples(ms) / 2.0).round().max(1.0) as usize;
+        let mut prefix = Vec::with_capacity(self.freq.len() + 1);
+        prefix.push(0.0f64);
+        for &f in &self.freq {
+            prefix.push(prefix[prefix.len() - 1] + f as f64);
+        }
+        (0..self.freq.len())
+            .map(|i| {
+                let (a, b) = (i.saturating_sub(half), (i + half + 1).min(self.freq.len()));
+                ((prefix[b] - prefix[a]) / (b - a) as f64) as f32
+            })
+            .collect()
+    }
+
+    fn median(&self, from: f64, to: f64) -> Option<f32> {
+        let (a, b) = (from.max(0.0) as usize, to.max(0.0) as usize);
+        if b > self.freq.len() || a >= b {
+            return None;
+        }
+        let mut window = self.freq[a..b].to_vec();
+        window.sort_unstable_by(f32::total_cmp);
+        Some(window[window.len() / 2])
+    }
+
+    fn mean(&self, from: f64, to: f64) -> Option<f32> {
+        let a = from.round().max(0.0) as usize;
+        let b = (to.round() as usize).max(a + 1);
+        if b > self.freq.len() {
+            return None;
+        }
+        Some(self.freq[a..b].iter().sum::<f32>() / (b - a) as f32)
+    }
+}
+
+/// A VIS header found in the track.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VisHeader {
+    pub(crate) code: u8,
+    pub(crate) parity_ok: bool,
+    /// Track index of the start bit.
+    pub(crate) start: usize,
+    /// Track index just after the stop bit.
+    pub(crate) end: f64,
+}
+
+impl VisHeader {
+    pub(crate) fn mode(&self) -> Option<SstvMode> {
+        if self.parity_ok { SstvMode::from_vis_code(self.code) } else { None }
+    }
+}
+
+/// Finds the first leader + VIS header at or after track index `from`.
+pub(crate) fn find_vis(track: &FrequencyTrack, from: usize) -> Option<VisHeader> {
+    let smooth = track.smoothed(5.0);
+    let bit = track.samples(VIS_BIT_MS);
+    let leader = track.samples(150.0) as usize;
+    let header_len = (bit * 10.0) as usize;
+    let start = from.max(leader + 1);
+    if smooth.len() < header_len + 1 {
+        return None;
+    }
+    let midpoint = (LEADER_HZ + SYNC_HZ) / 2.0;
+
+    for i in start..smooth.len() - header_len {
+        // Leader → start bit edge
+        if !(smooth[i - 1] >= midpoint && smooth[i] < midpoint) {
+            continue;
+        }
+        let in_leader = smooth[i - leader..i].iter().filter(|f| (*f - LEADER_HZ).abs() < 150.0).count();
+        if (in_leader as f64) < 0.8 * leader as f64 {
+            continue;
+        }
+        let bit_center = |n: f64| i as f64 + bit * (n + 0.5);
+        let window = bit / 3.0;
+        let Some(start_bit) = track.median(bit_center(0.0) - window, bit_center(0.0) + window) else { continue };
+        if (start_bit - SYNC_HZ).abs() > 100.0 {
+            continue;
+        }
+
+        let mut bits = [false; 8];
+        let mut valid = true;
+        for (n, value) in bits.iter_mut().enumerate() {
+            let center = bit_center(n as f64 + 1.0);
+            let Some(freq) = track.median(center - window, center + window) else {
+                valid = false;
+                break;
+            };
+            // The data bits must be clean; the parity slot is checked through the parity itself.
+            if n < 7 && !(VIS_ONE_HZ - 150.0..VIS_ZERO_HZ + 150.0).contains(&freq) {
+                valid = false;
+                break;
+            }
+            *value = freq < SYNC_HZ;
+        }
+        if !valid {
+            continue;
+        }
+        let code = bits[..7].iter().enumerate().fold(0u8, |acc, (n, &b)| acc | ((b as u8) << n));
+        let parity_ok = bits.iter().filter(|b| **b).count() % 2 == 0;
+        debug!(code, parity_ok, at_secs = i as f64 / track.rate, "Found VIS header");
+        return Some(VisHeader { code, parity_ok, start: i, end: i as f64 + bit * 10.0 });
+    }
+    None
+}
+
+/// A run of sync tone, identified by where it ends.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SyncPulse {
+    /// Track position (fractional) where the frequency rises back through the threshold.
+    pub(crate) end: f64,
+    pub(crate) len: f64,
+}
+
+/// Finds sync-tone runs between 2 and 40 ms long.
+pub(crate) fn find_sync_pulses(track: &FrequencyTrack, from: usize, to: usize) -> Vec<SyncPulse> {
+    let smooth = track.smoothed(1.0);
+    let to = to.min(smooth.len());
+    let (min_len, max_len) = (track.samples(2.0), track.samples(40.0));
+    let mut pulses = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for i in from.max(1)..to {
+        let below = smooth[i] < SYNC_THRESHOLD_HZ;
+        match (run_start, below) {
+            (None, true) => run_start = Some(i),
+            (Some(start), false) => {
+                // Interpolate the rising edge for sub-sample timing.
+                let (a, b) = (smooth[i - 1], smooth[i]);
+                let end = (i - 1) as f64 + ((SYNC_THRESHOLD_HZ - a) / (b - a)).clamp(0.0, 1.0) as f64;
+                let len = end - start as f64;
+                if (min_len..=max_len).contains(&len) {
+                    pulses.push(SyncPulse { end, len });
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    pulses
+}
+
+/// Line timing fitted to the sync pulses: sync `k` ends at `origin + k * period`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LineTiming {
+    pub(crate) origin: f64,
+    pub(crate) period: f64,
+    pub(crate) nominal_period: f64,
+    pub(crate) first_line: i64,
+    pub(crate) last_line: i64,
+    pub(crate) inliers: usize,
+}
+
+impl LineTiming {
+    /// Fraction of the lines between the first and last matched pulse that had a pulse.
+    fn coverage(&self) -> f64 {
+        self.inliers as f64 / (self.last_line - self.first_line + 1) as f64
+    }
+
+    fn score(&self) -> f64 {
+        self.inliers as f64 * self.coverage()
+    }
+
+    pub(crate) fn clock_ratio(&self) -> f64 {
+        self.period / self.nominal_period
+    }
+}
+
+/// Incremental least-squares line fit.
+#[derive(Default)]
+struct LineFit {
+    points: Vec<(i64, f64)>,
+}
+
+impl LineFit {
+    fn solve(&self) -> Option<(f64, f64)> {
+        let n = self.points.len() as f64;
+        if self.points.len() < 2 {
+            return None;
+        }
+        let (sk, se) = self.points.iter().fold((0.0, 0.0), |(sk, se), &(k, e)| (sk + k as f64, se + e));
+        let (mk, me) = (sk / n, se / n);
+        let (mut skk, mut ske) = (0.0, 0.0);
+        for &(k, e) in &self.points {
+            skk += (k as f64 - mk).powi(2);
+            ske += (k as f64 - mk) * (e - me);
+        }
+        if skk == 0.0 {
+            return None;
+        }
+        let period = ske / skk;
+        Some((me - period * mk, period))
+    }
+}
+
+/// Fits the line timing of `mode` to `pulses` (only those of the mode's sync length are used).
+pub(crate) fn fit_line_timing(track: &FrequencyTrack, mode: SstvMode, pulses: &[SyncPulse]) -> Option<LineTiming> {
+    let spec = mode.spec();
+    let nominal = track.samples(spec.line_ms());
+    let sync = track.samples(spec.sync_ms());
+    // Longer runs are kept: the first sync often merges with the VIS stop bit.
+    let ends: Vec<f64> = pulses
+        .iter()
+        .filter(|p| p.len >= 0.6 * sync && p.len <= sync + track.samples(32.0))
+        .map(|p| p.end)
+        .collect();
+    if ends.len() < 3 {
+        return None;
+    }
+
+    // Anchor on the first pulse that has company at the expected spacing.
+    let near = |a: f64, b: f64| (1..=3).any(|m| ((b - a) - m as f64 * nominal).abs() < MAX_CLOCK_ERROR * m as f64 * nominal);
+    let anchor = (0..ends.len().min(20))
+        .find(|&a| ends.iter().filter(|&&b| b > ends[a] && near(ends[a], b)).count() >= 2)
+        .unwrap_or(0);
+
+    let tight = track.samples(4.0);
+    let mut fit = LineFit { points: vec![(0, ends[anchor])] };
+    let (mut origin, mut period) = (ends[anchor], nominal);
+    let accept = |fit: &mut LineFit, origin: &mut f64, period: &mut f64, e: f64| {
+        let k = ((e - *origin) / *period).round() as i64;
+        if fit.points.iter().any(|&(pk, _)| pk == k) {
+            return;
+        }
+        let tolerance = if fit.points.len() < 2 { MAX_CLOCK_ERROR * nominal * k.unsigned_abs() as f64 + tight } else { tight };
+        if (e - (*origin + k as f64 * *period)).abs() < tolerance {
+            fit.points.push((k, e));
+            if let Some((o, p)) = fit.solve() {
+                (*origin, *period) = (o, p);
+            }
+        }
+    };
+    for &e in &ends[anchor + 1..] {
+        accept(&mut fit, &mut origin, &mut period, e);
+    }
+    for &e in ends[..anchor].iter().rev() {
+        accept(&mut fit, &mut origin, &mut period, e);
+    }
+
+    // Drop stragglers and refit.
+    let (o, p) = fit.solve()?;
+    fit.points.retain(|&(k, e)| (e - (o + k as f64 * p)).abs() < track.samples(1.5));
+    let (origin, period) = fit.solve()?;
+    if (period / nominal - 1.0).abs() > MAX_CLOCK_ERROR {
+        return None;
+    }
+    let first_line = fit.points.iter().map(|p| p.0).min()?;
+    let last_line = fit.points.iter().map(|p| p.0).max()?;
+    Some(LineTiming { origin, period, nominal_period: nominal, first_line, last_line, inliers: fit.points.len() })
+}
+
+/// Decodes SSTV pictures from recorded audio.
+///
+/// # Example
+/// ```no_run
+/// # fn main() -> Result<(), elfradio_dsp::DspError> {
+/// let samples: Vec<f32> = Vec::new(); // 48 kHz mono audio
+/// let picture = elfradio_dsp::SstvDecoder::new(48000)?.decode(&samples)?;
+/// picture.image.save("received.png")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SstvDecoder {
+    sample_rate: u32,
+    mode: Option<SstvMode>,
+}
+
+impl SstvDecoder {
+    pub fn new(sample_rate: u32) -> Result<Self, DspError> {
+        if sample_rate < 6000 {
+            return Err(DspError::SstvDecodeError(format!("Sample rate {} Hz is too low for SSTV", sample_rate)));
+        }
+        Ok(Self { sample_rate, mode: None })
+    }
+
+    /// Uses `mode` when no valid VIS header is found, instead of guessing from the line rate.
+    pub fn with_mode(mut self, mode: SstvMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Decodes the first picture in `samples`.
+    pub fn decode(&self, samples: &[f32]) -> Result<DecodedSstvImage, DspError> {
+        let track = FrequencyTrack::new(samples, self.sample_rate);
+        let vis = find_vis(&track, 0);
+        let vis_mode = vis.and_then(|v| v.mode());
+        match (&vis, vis_mode) {
+            (Some(v), None) => warn!(code = v.code, parity_ok = v.parity_ok, "Unrecognised VIS header, detecting mode from line timing"),
+            (None, _) => debug!("No VIS header found, detecting mode from line timing"),
+            _ => {}
+        }
+
+        // Keep to the picture that follows the header.
+        let from = vis.map_or(0, |v| (v.end - track.samples(50.0)).max(0.0) as usize);
+        let pulses = find_sync_pulses(&track, from, track.freq.len());
+        let candidates: Vec<SstvMode> = match vis_mode.or(self.mode) {
+            Some(mode) => vec![mode],
+            None => SstvMode::ALL.to_vec(),
+        };
+        let (mode, timing) = candidates
+            .into_iter()
+            .filter_map(|mode| {
+                let span = track.samples(mode.spec().line_ms()) * (mode.spec().sync_periods() as f64 * 1.05 + 2.0);
+                let in_picture: Vec<SyncPulse> = pulses.iter().copied().filter(|p| p.end < from as f64 + span).collect();
+                fit_line_timing(&track, mode, &in_picture).map(|timing| (mode, timing))
+            })
+            .max_by(|a, b| a.1.score().total_cmp(&b.1.score()))
+            .ok_or_else(|| DspError::SstvDecodeError("No SSTV line sync found".to_string()))?;
+
+        let spec = mode.spec();
+        let scale = timing.clock_ratio();
+        let sync_end = track.samples(spec.sync_end_ms()) * scale;
+        // The first line is the one starting closest to the end of the header.
+        let first = match vis {
+            Some(v) => ((v.end - timing.origin + sync_end) / timing.period).round() as i64,
+            None => timing.first_line,
+        };
+        info!(
+            %mode,
+            vis_code = vis_mode.map(|m| m.vis_code()),
+            clock_ratio = scale,
+            sync_pulses = timing.inliers,
+            "Decoding SSTV picture"
+        );
+
+        // Lines past the last sync pulse heard are noise.
+        let available = (timing.last_line - first + 1).clamp(0, spec.sync_periods() as i64);
+        let mut lines = Vec::with_capacity(spec.sync_periods() as usize);
+        for line in 0..available {
+            let start = timing.origin + (first + line) as f64 * timing.period - sync_end;
+            match read_line(&track, mode, start, scale) {
+                Some(scan) => lines.push(scan),
+                None => break,
+            }
+        }
+        let lines_received = lines.len() as u32 * spec.lines_per_sync;
+        if lines_received == 0 {
+            return Err(DspError::SstvDecodeError(format!("{} picture has no complete lines", mode)));
+        }
+        Ok(DecodedSstvImage {
+            mode,
+            image: assemble(mode, &lines),
+            vis_code: vis_mode.map(|m| m.vis_code()),
+            clock_ratio: scale,
+            lines_received,
+        })
+    }
+}
+
+/// Pixel levels of one sync period, per scan segment in layout order.
+struct ScanLine {
+    channels: Vec<(Channel, Vec<u8>)>,
+    /// Robot 36: the chroma separator was white, so the chroma is B-Y.
+    blue_chroma: bool,
+}
+
+impl ScanLine {
+    fn channel(&self, channel: Channel) -> Option<&[u8]> {
+        self.channels.iter().find(|(c, _)| *c == channel).map(|(_, v)| v.as_slice())
+    }
+}
+
+fn read_line(track: &FrequencyTrack, mode: SstvMode, start: f64, scale: f64) -> Option<ScanLine> {
+    let spec = mode.spec();
+    let mut line = ScanLine { channels: Vec::new(), blue_chroma: false };
+    let mut t = start;
+    for segment in spec.layout {
+        let len = track.samples(segment.ms()) * scale;
+        match *segment {
+            Segment::Scan(channel, _) => {
+                let pixel = len / spec.width as f64;
+                let values = (0..spec.width)
+                    .map(|x| track.mean(t + x as f64 * pixel, t + (x + 1) as f64 * pixel).map(frequency_to_level))
+                    .collect::<Option<Vec<u8>>>()?;
+                line.channels.push((channel, values));
+            }
+            Segment::ChromaSeparator(_) => {
+                line.blue_chroma = track.mean(t + len * 0.2, t + len * 0.8)? > (BLACK_HZ + WHITE_HZ) / 2.0;
+            }
+            Segment::Sync(_) | Segment::Tone(..) => {}
+        }
+        t += len;
+    }
+    Some(line)
+}
+
+fn assemble(mode: SstvMode, lines: &[ScanLine]) -> RgbImage {
+    let spec = mode.spec();
+    let mut image = RgbImage::new(spec.width, spec.height);
+    match spec.color {
+        ColorSpace::Rgb => {
+            for (y, line) in lines.iter().enumerate() {
+                let (Some(r), Some(g), Some(b)) = (line.channel(Channel::Red), line.channel(Channel::Green), line.channel(Channel::Blue)) else {
+                    continue;
+                };
+                for x in 0..spec.width as usize {
+                    image.put_pixel(x as u32, y as u32, Rgb([r[x], g[x], b[x]]));
+                }
+            }
+        }
+        ColorSpace::YCrCb if spec.layout.iter().any(|s| matches!(s, Segment::Scan(Channel::Chroma, _))) => {
+            // Robot 36 sends R-Y and B-Y on alternate lines; each pair shares both.
+            for (pair, lines) in lines.chunks(2).enumerate() {
+                let chroma = |blue: bool| {
+                    lines
+                        .iter()
+                        .find(|l| l.blue_chroma == blue)
+                        .or_else(|| lines.get(usize::from(blue)))
+                        .or(lines.first())
+                        .and_then(|l| l.channel(Channel::Chroma))
+                };
+                let (Some(cr), Some(cb)) = (chroma(false), chroma(true)) else { continue };
+                for (offset, line) in lines.iter().enumerate() {
+                    let Some(luma) = line.channel(Channel::Luma) else { continue };
+                    let y = (pair * 2 + offset) as u32;
+                    for x in 0..spec.width as usize {
+                        image.put_pixel(x as u32, y, Rgb(ycrcb_to_rgb(luma[x], cr[x], cb[x])));
+                    }
+                }
+            }
+        }
+        ColorSpace::YCrCb => {
+            // Robot 72 and PD: full chroma with every sync; PD shares it between two luma lines.
+            for (period, line) in lines.iter().enumerate() {
+                let (Some(cr), Some(cb)) = (line.channel(Channel::RedDiff), line.channel(Channel::BlueDiff)) else {
+                    continue;
+                };
+                for (offset, channel) in [Channel::Luma, Channel::LumaOdd].into_iter().enumerate() {
+                    let Some(luma) = line.channel(channel) else { continue };
+                    let y = period as u32 * spec.lines_per_sync + offset as u32;
+                    for x in 0..spec.width as usize {
+                        image.put_pixel(x as u32, y, Rgb(ycrcb_to_rgb(luma[x], cr[x], cb[x])));
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Decodes the first SSTV picture in `samples`, detecting the mode from its VIS header or line rate.
+pub fn decode_sstv(samples: &[f32], sample_rate: u32) -> Result<DecodedSstvImage, DspError> {
+    SstvDecoder::new(sample_rate)?.decode(samples)
+}
+
+/// Watches a live audio stream for SSTV pictures.
+///
+/// Audio is pushed in arbitrary chunks. Once a VIS header for a supported
+/// mode is heard, audio is buffered until the picture is complete (or the
+/// signal disappears) and the decoded picture is returned.
+pub struct SstvReceiver {
+    decoder: SstvDecoder,
+    sample_rate: u32,
+    buffer: Vec<f32>,
+    /// Samples at the start of `buffer` already searched for a header.
+    searched: usize,
+    receiving: Option<Reception>,
+}
+
+struct Reception {
+    mode: SstvMode,
+    /// Buffer index where the picture should be complete.
+    expected_end: usize,
+    /// Buffer index of the last signal-loss check.
+    checked: usize,
+}
+
+impl SstvReceiver {
+    pub fn new(sample_rate: u32) -> Result<Self, DspError> {
+        Ok(Self { decoder: SstvDecoder::new(sample_rate)?, sample_rate, buffer: Vec::new(), searched: 0, receiving: None })
+    }
+
+    /// The mode being received, if a picture is in progress.
+    pub fn receiving(&self) -> Option<SstvMode> {
+        self.receiving.as_ref().map(|r| r.mode)
+    }
+
+    fn samples(&self, secs: f64) -> usize {
+        (secs * self.sample_rate as f64) as usize
+    }
+
+    /// Feeds audio; returns a picture when one has been completed.
+    pub fn push(&mut self, samples: &[f32]) -> Option<DecodedSstvImage> {
+        self.buffer.extend_from_slice(samples);
+        let len = self.buffer.len();
+        let Some(reception) = &mut self.receiving else {
+            if len - self.searched >= self.samples(1.0) {
+                self.search_for_header();
+            }
+            return None;
+        };
+
+        if len >= reception.expected_end {
+            return self.finish();
+        }
+        if len - reception.checked >= self.sample_rate as usize {
+            reception.checked = len;
+            if self.signal_lost() {
+                info!("SSTV signal lost, decoding the partial picture");
+                return self.finish();
+            }
+        }
+        None
+    }
+
+    /// Decodes whatever has been received so far, e.g. when the receiver is stopped.
+    pub fn flush(&mut self) -> Option<DecodedSstvImage> {
+        if self.receiving.is_some() { self.finish() } else { None }
+    }
+
+    fn search_for_header(&mut self) {
+        // Overlap with the previous search so a header split across searches is still seen whole.
+        let overlap = self.samples(0.8);
+        let from = self.searched.saturating_sub(overlap);
+        let track = FrequencyTrack::new(&self.buffer[from..], self.sample_rate);
+        let to_buffer = |index: f64| from + (index / track.rate * self.sample_rate as f64) as usize;
+
+        match find_vis(&track, 0).and_then(|v| v.mode().map(|mode| (v, mode))) {
+            Some((vis, mode)) => {
+                info!(%mode, "SSTV picture starting");
+                // Keep a little leader in front so the decoder sees the header again.
+                let keep_from = to_buffer(vis.start as f64).saturating_sub(self.samples(0.3));
+                self.buffer.drain(..keep_from);
+                let header_end = to_buffer(vis.end) - keep_from;
+                let expected_end = header_end + self.samples(mode.duration_secs() * (1.0 + MAX_CLOCK_ERROR) + 0.2);
+                self.receiving = Some(Reception { mode, expected_end, checked: header_end });
+                self.searched = 0;
+            }
+            None => {
+                let keep_from = self.buffer.len().saturating_sub(overlap);
+                self.buffer.drain(..keep_from);
+                self.searched = self.buffer.len();
+            }
+        }
+    }
+
+    /// `true` if the last few lines carried no sync pulses of the expected kind.
+    fn signal_lost(&self) -> bool {
+        let Some(mode) = self.receiving() else { return false };
+        let window = self.samples(mode.spec().line_ms() * 4.0 / 1000.0 + 0.1);
+        if self.buffer.len() < window * 2 {
+            return false;
+        }
+        let track = FrequencyTrack::new(&self.buffer[self.buffer.len() - window..], self.sample_rate);
+        let sync = track.samples(mode.spec().sync_ms());
+        !find_sync_pulses(&track, 0, track.freq.len()).iter().any(|p| p.len >= 0.6 * sync && p.len <= 1.6 * sync)
+    }
+
+    fn finish(&mut self) -> Option<DecodedSstvImage> {
+        let reception = self.receiving.take()?;
+        let end = reception.expected_end.min(self.buffer.len());
+        let result = self.decoder.clone().with_mode(reception.mode).decode(&self.buffer[..end]);
+        self.buffer.drain(..end);
+        self.searched = 0;
+        match result {
+            Ok(picture) => Some(picture),
+            Err(e) => {
+                warn!("Failed to decode {} picture: {}", reception.mode, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sstv::encode::{write_lines, write_vis, ToneWriter};
+
+    const COLOURS: [[u8; 3]; 4] = [[230, 40, 40], [40, 200, 60], [50, 60, 220], [240, 230, 80]];
+
+    /// Four vertical colour bars.
+    pub(crate) fn colour_bars(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, _| Rgb(COLOURS[(x * 4 / width) as usize]))
+    }
+
+    /// Synthesizes the first `lines` sync periods of `image` in `mode`, optionally with a VIS header.
+    fn synthesize(image: &RgbImage, mode: SstvMode, rate: u32, lines: u32, vis: bool) -> Vec<f32> {
+        let mut writer = ToneWriter::new(rate, 10.0);
+        writer.tone(0.0, 500.0);
+        if vis {
+            write_vis(&mut writer, mode);
+        }
+        write_lines(&mut writer, mode, image, 0..lines);
+        writer.tone(0.0, 300.0);
+        writer.finish()
+    }
+
+    /// Checks the middle of each colour bar on the given rows.
+    pub(crate) fn assert_bars(image: &RgbImage, rows: std::ops::Range<u32>, tolerance: i32) {
+        let width = image.width();
+        for y in rows {
+            for (bar, colour) in COLOURS.iter().enumerate() {
+                let x = width * (2 * bar as u32 + 1) / 8;
+                let got = image.get_pixel(x, y).0;
+                for c in 0..3 {
+                    assert!(
+                        (got[c] as i32 - colour[c] as i32).abs() <= tolerance,
+                        "row {} bar {}: got {:?}, want {:?}",
+                        y,
+                        bar,
+                        got,
+                        colour
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decodes_each_mode_from_vis() {
+        for mode in SstvMode::ALL {
+            let (width, height) = mode.resolution();
+            let audio = synthesize(&colour_bars(width, height), mode, 11025, 24, true);
+            let picture = decode_sstv(&audio, 11025).unwrap();
+            assert_eq!(picture.mode, mode);
+            assert_eq!(picture.vis_code, Some(mode.vis_code()));
+            assert_eq!(picture.lines_received, 24 * mode.spec().lines_per_sync, "{}", mode);
+            assert!((picture.clock_ratio - 1.0).abs() < 0.001, "{}: {}", mode, picture.clock_ratio);
+            // Robot 36 chroma is half resolution and lowpassed, so allow more error there.
+            let tolerance = if mode == SstvMode::Robot36 { 30 } else { 16 };
+            assert_bars(&picture.image, 0..picture.lines_received, tolerance);
+        }
+    }
+
+    #[test]
+    fn test_corrects_slant_from_clock_error() {
+        // Transmitter clock 0.6% fast relative to the receiver (11091 Hz played back at 11025 Hz).
+        let image = colour_bars(320, 256);
+        let audio = synthesize(&image, SstvMode::ScottieS2, 11091, 60, true);
+        let picture = SstvDecoder::new(11025).unwrap().decode(&audio).unwrap();
+        assert_eq!(picture.mode, SstvMode::ScottieS2);
+        assert!((picture.clock_ratio - 1.006).abs() < 0.001, "clock ratio {}", picture.clock_ratio);
+        assert_bars(&picture.image, 0..60, 16);
+        // Without correction the bars would have drifted by over a line's worth of time by now.
+        assert_bars(&picture.image, 55..60, 16);
+    }
+
+    #[test]
+    fn test_detects_mode_without_vis() {
+        let image = colour_bars(320, 256);
+        for mode in [SstvMode::MartinM2, SstvMode::Robot36] {
+            let audio = synthesize(&image, mode, 8000, 30, false);
+            let picture = decode_sstv(&audio, 8000).unwrap();
+            assert_eq!(picture.mode, mode);
+            assert_eq!(picture.vis_code, None);
+            assert!(picture.lines_received >= 28, "{}: {} lines", mode, picture.lines_received);
+        }
+        // A hint only applies when there's no VIS.
+        let audio = synthesize(&image, SstvMode::MartinM2, 8000, 8, true);
+        let picture = SstvDecoder::new(8000).unwrap().with_mode(SstvMode::ScottieS1).decode(&audio).unwrap();
+        assert_eq!(picture.mode, SstvMode::MartinM2);
+    }
+
+    #[test]
+    fn test_rejects_audio_without_sstv() {
+        let tone: Vec<f32> = (0..44100).map(|i| (i as f32 * 0.1).sin()).collect();
+        assert!(matches!(decode_sstv(&tone, 44100), Err(DspError::SstvDecodeError(_))));
+        assert!(SstvDecoder::new(4000).is_err());
+    }
+
+    #[test]
+    fn test_receiver_emits_picture_from_stream() {
+        let image = colour_bars(320, 240);
+        let mut audio = vec![0.0f32; 8000 * 3];
+        audio.extend(synthesize(&image, SstvMode::Robot36, 8000, 240, true));
+        audio.extend(vec![0.0f32; 8000 * 3]);
+
+        let mut receiver = SstvReceiver::new(8000).unwrap();
+        let mut pictures = Vec::new();
+        let mut saw_receiving = false;
+        for chunk in audio.chunks(160) {
+            pictures.extend(receiver.push(chunk));
+            saw_receiving |= receiver.receiving() == Some(SstvMode::Robot36);
+        }
+        assert!(saw_receiving);
+        assert_eq!(pictures.len(), 1);
+        assert_eq!(pictures[0].lines_received, 240);
+        assert_bars(&pictures[0].image, 0..240, 30);
+        assert!(receiver.receiving().is_none());
+    }
+
+    #[test]
+    fn test_receiver_decodes_partial_picture_when_signal_stops() {
+        let image = colour_bars(320, 256);
+        let mut audio = synthesize(&image, SstvMode::MartinM2, 8000, 40, true);
+        audio.extend(vec![0.0f32; 8000 * 3]);
+
+        let mut receiver = SstvReceiver::new(8000).unwrap();
+        let pictures: Vec<_> = audio.chunks(320).filter_map(|chunk| receiver.push(chunk)).collect();
+        assert_eq!(pictures.len(), 1);
+        assert!((38..=41).contains(&pictures[0].lines_received), "{} lines", pictures[0].lines_received);
+        assert_bars(&pictures[0].image, 0..38, 16);
+    }
+}
@@ -0,0 +1,187 @@
+// SSTV transmit encoder for every mode in the mode table.
+
+use super::modes::{
+    level_to_frequency, rgb_to_ycrcb, Channel, Segment, SstvMode, BLACK_HZ, LEADER_HZ, SYNC_HZ,
+    VIS_BIT_MS, VIS_ONE_HZ, VIS_ZERO_HZ, WHITE_HZ,
+};
+use crate::error::DspError;
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+use std::ops::Range;
+use tracing::{debug, info};
+
+/// Peak amplitude of the generated tone.
+const AMPLITUDE: f32 = 0.85;
+
+/// Phase-continuous FM tone generator.
+///
+/// Tone lengths are tracked in fractional samples, so rounding never
+/// accumulates into line timing errors (slant) at any sample rate.
+pub(crate) struct ToneWriter {
+    rate: f64,
+    phase: f64,
+    /// Ideal end of everything written so far, in samples.
+    clock: f64,
+    samples: Vec<f32>,
+}
+
+impl ToneWriter {
+    pub(crate) fn new(sample_rate: u32, capacity_secs: f64) -> Self {
+        let rate = sample_rate as f64;
+        Self { rate, phase: 0.0, clock: 0.0, samples: Vec::with_capacity((capacity_secs * rate) as usize + 1) }
+    }
+
+    pub(crate) fn tone(&mut self, freq: f32, ms: f64) {
+        self.clock += ms * self.rate / 1000.0;
+        let step = 2.0 * std::f64::consts::PI * freq as f64 / self.rate;
+        while (self.samples.len() as f64) < self.clock {
+            self.samples.push(AMPLITUDE * self.phase.sin() as f32);
+            self.phase = (self.phase + step) % (2.0 * std::f64::consts::PI);
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<f32> {
+        self.samples
+    }
+}
+
+/// Writes the leader, break and VIS code (7 bits LSB first, even parity).
+pub(crate) fn write_vis(writer: &mut ToneWriter, mode: SstvMode) {
+    writer.tone(LEADER_HZ, 300.0);
+    writer.tone(SYNC_HZ, 10.0);
+    writer.tone(LEADER_HZ, 300.0);
+    writer.tone(SYNC_HZ, VIS_BIT_MS); // Start bit
+    let code = mode.vis_code();
+    let parity = code.count_ones() % 2 == 1;
+    for bit in (0..7).map(|n| (code >> n) & 1 == 1).chain([parity]) {
+        writer.tone(if bit { VIS_ONE_HZ } else { VIS_ZERO_HZ }, VIS_BIT_MS);
+    }
+    writer.tone(SYNC_HZ, VIS_BIT_MS); // Stop bit
+}
+
+/// Writes the given sync periods of `image`, which must already be at the mode's resolution.
+pub(crate) fn write_lines(writer: &mut ToneWriter, mode: SstvMode, image: &RgbImage, periods: Range<u32>) {
+    let spec = mode.spec();
+    if periods.start == 0 && spec.leading_sync_ms > 0.0 {
+        writer.tone(SYNC_HZ, spec.leading_sync_ms);
+    }
+    let pixel = |x: u32, y: u32| image.get_pixel(x, y.min(spec.height - 1)).0;
+    // Chroma shared by two lines is averaged over both.
+    let chroma = |x: u32, y: u32, index: usize| {
+        let pair = y & !1;
+        ((rgb_to_ycrcb(pixel(x, pair))[index] as u16 + rgb_to_ycrcb(pixel(x, pair + 1))[index] as u16) / 2) as u8
+    };
+
+    for period in periods {
+        let y = period * spec.lines_per_sync;
+        for segment in spec.layout {
+            match *segment {
+                Segment::Sync(ms) => writer.tone(SYNC_HZ, ms),
+                Segment::Tone(freq, ms) => writer.tone(freq, ms),
+                Segment::ChromaSeparator(ms) => writer.tone(if y.is_multiple_of(2) { BLACK_HZ } else { WHITE_HZ }, ms),
+                Segment::Scan(channel, ms) => {
+                    let pixel_ms = ms / spec.width as f64;
+                    for x in 0..spec.width {
+                        let level = match channel {
+                            Channel::Red => pixel(x, y)[0],
+                            Channel::Green => pixel(x, y)[1],
+                            Channel::Blue => pixel(x, y)[2],
+                            Channel::Luma => rgb_to_ycrcb(pixel(x, y))[0],
+                            Channel::LumaOdd => rgb_to_ycrcb(pixel(x, y + 1))[0],
+                            // Robot 72 sends full chroma on every line.
+                            Channel::RedDiff if spec.lines_per_sync == 1 => rgb_to_ycrcb(pixel(x, y))[1],
+                            Channel::BlueDiff if spec.lines_per_sync == 1 => rgb_to_ycrcb(pixel(x, y))[2],
+                            Channel::RedDiff => chroma(x, y, 1),
+                            Channel::BlueDiff => chroma(x, y, 2),
+                            Channel::Chroma => chroma(x, y, if y.is_multiple_of(2) { 1 } else { 2 }),
+                        };
+                        writer.tone(level_to_frequency(level), pixel_ms);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Encodes `image` as an SSTV transmission in `mode` at `sample_rate`.
+///
+/// The image is scaled to the mode's native resolution if needed. The output
+/// starts with the calibration leader and VIS header.
+///
+/// # 参数
+/// * `image` - 要发送的图片。
+/// * `mode` - SSTV 模式。
+/// * `sample_rate` - 输出音频采样率 (Hz)，通常是 TX 音频路径的采样率。
+pub fn encode_sstv(image: &DynamicImage, mode: SstvMode, sample_rate: u32) -> Result<Vec<f32>, DspError> {
+    if sample_rate < 6000 {
+        return Err(DspError::SstvEncodeError(format!("Sample rate {} Hz is too low for SSTV", sample_rate)));
+    }
+    let spec = mode.spec();
+    let rgb = if image.width() == spec.width && image.height() == spec.height {
+        image.to_rgb8()
+    } else {
+        debug!(
+            "Scaling {}x{} image to {}x{} for {}",
+            image.width(),
+            image.height(),
+            spec.width,
+            spec.height,
+            mode
+        );
+        image.resize_exact(spec.width, spec.height, FilterType::Triangle).to_rgb8()
+    };
+
+    info!(%mode, sample_rate, "Encoding SSTV picture ({:.1} s)", mode.duration_secs());
+    let mut writer = ToneWriter::new(sample_rate, mode.duration_secs() + 1.0);
+    write_vis(&mut writer, mode);
+    write_lines(&mut writer, mode, &rgb, 0..spec.sync_periods());
+    let samples = writer.finish();
+    debug!("Generated {} SSTV samples", samples.len());
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sstv::decode::tests::{assert_bars, colour_bars};
+    use crate::sstv::decode_sstv;
+
+    #[test]
+    fn test_round_trip_at_tx_and_soundcard_rates() {
+        // A non-native size is scaled to 320x240 first.
+        let image = DynamicImage::ImageRgb8(colour_bars(640, 480));
+        for rate in [16000, 48000] {
+            let samples = encode_sstv(&image, SstvMode::Robot36, rate).unwrap();
+            let picture = decode_sstv(&samples, rate).unwrap();
+            assert_eq!(picture.mode, SstvMode::Robot36);
+            assert_eq!(picture.vis_code, Some(8));
+            assert_eq!(picture.lines_received, 240, "{} Hz", rate);
+            assert!((picture.clock_ratio - 1.0).abs() < 0.001, "{} Hz: {}", rate, picture.clock_ratio);
+            assert_bars(&picture.image, 0..240, 30);
+        }
+    }
+
+    #[test]
+    fn test_length_matches_mode_timing_at_any_rate() {
+        let image = DynamicImage::ImageRgb8(colour_bars(320, 256));
+        for (mode, rate) in [(SstvMode::Robot36, 8000), (SstvMode::Robot36, 44100), (SstvMode::Pd90, 11025)] {
+            let samples = encode_sstv(&image, mode, rate).unwrap();
+            let expected = (mode.duration_secs() + 0.91) * rate as f64; // Header: 610 ms leader + 300 ms VIS
+            assert!((samples.len() as f64 - expected).abs() <= 1.0, "{} at {}: {} samples", mode, rate, samples.len());
+            assert!(samples.iter().all(|s| s.abs() <= AMPLITUDE));
+        }
+        assert!(encode_sstv(&image, SstvMode::Robot36, 4000).is_err());
+    }
+
+    #[test]
+    fn test_vis_parity() {
+        // PD90 = 99 = 0b1100011: four ones, so the parity bit is 0 (1300 Hz).
+        let mut writer = ToneWriter::new(8000, 1.0);
+        write_vis(&mut writer, SstvMode::Pd90);
+        let samples = writer.finish();
+        let bit_len = 240; // 30 ms at 8 kHz
+        let parity_start = (610 + 30 + 7 * 30) * 8;
+        let crossings = samples[parity_start..parity_start + bit_len].windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count();
+        assert!((38..=40).contains(&crossings), "{} cycles in 30 ms", crossings); // 1300 Hz * 30 ms = 39
+    }
+}
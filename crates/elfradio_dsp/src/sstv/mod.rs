@@ -1,71 +1,36 @@
+mod decode;
+mod encode;
+mod modes;
+mod prepare;
+
+pub use decode::{decode_sstv, DecodedSstvImage, SstvDecoder, SstvReceiver};
+pub use encode::encode_sstv;
+pub use modes::SstvMode;
+pub use prepare::prepare_sstv_image;
+
 use crate::error::DspError;
-use image::{self, DynamicImage};
-use rsstv::{
-    common::SSTVMode,
-    martinm1::MartinM1,
-};
 use std::path::Path;
 use tracing::{debug, info};
 
 // --- SSTV Encoding Implementation ---
 
-// Constants might be specific to modes, perhaps keep them here for now
-// Or potentially move to a shared `modes` module if more modes are added.
+/// Sample rate of `encode_sstv_martin_m1` output, kept at the rate the old `rsstv` encoder used.
+pub const SSTV_DEFAULT_SAMPLE_RATE: u32 = 44100;
 
-/// Encodes an image file into SSTV Martin M1 audio samples using the `rsstv` crate (v0.2.1 API).
+/// Encodes an image file into SSTV Martin M1 audio samples.
 ///
 /// # Arguments
 /// * `image_path` - Path to the image file (e.g., PNG, JPG).
 ///
 /// # Returns
 /// A `Vec<f32>` containing the raw audio samples (-1.0 to 1.0) for the SSTV signal
-/// at the sample rate defined by `rsstv` (44100 Hz in v0.2.1),
-/// or a `DspError` if image loading or processing fails.
+/// at `SSTV_DEFAULT_SAMPLE_RATE` (44100 Hz), or a `DspError` if image loading or
+/// processing fails. Use [`encode_sstv`] for other modes and sample rates.
 pub fn encode_sstv_martin_m1(image_path: &Path) -> Result<Vec<f32>, DspError> {
-    info!("Starting SSTV Martin M1 encoding using rsstv v0.2.1 for: {:?}", image_path);
-
-    // 1. Load Image into DynamicImage
-    debug!("Loading image...");
-    let loaded_image: DynamicImage = image::open(image_path)?; // Implicitly uses DspError::ImageError(#[from] ImageError)
+    info!("Starting SSTV Martin M1 encoding for: {:?}", image_path);
+    let loaded_image = image::open(image_path)?; // Implicitly uses DspError::ImageError(#[from] ImageError)
     debug!("Image loaded successfully. Dimensions: {}x{}", loaded_image.width(), loaded_image.height());
-
-    // Note: rsstv 0.2.1 MartinM1 encoder seems to handle resizing internally.
-    // If specific dimensions were strictly required by the library *before* encoding,
-    // we would add a check here:
-    // if loaded_image.width() != MARTIN_M1_WIDTH || loaded_image.height() != MARTIN_M1_HEIGHT {
-    //     warn!(
-    //         "Image dimensions ({}x{}) do not match Martin M1 ({}x{}). rsstv will resize.",
-    //         loaded_image.width(), loaded_image.height(),
-    //         MARTIN_M1_WIDTH, MARTIN_M1_HEIGHT
-    //     );
-    //     // Potentially return an error if strict dimensions are needed:
-    //     // return Err(DspError::UnsupportedDimensions { ... });
-    // }
-
-
-    // 2. Create MartinM1 Encoder instance
-    let mut encoder = MartinM1::new();
-    debug!("rsstv MartinM1 encoder created.");
-
-    // 3. Encode the loaded image
-    info!("Encoding image to SSTV signal...");
-    let signal = encoder.encode(loaded_image); // rsstv handles resizing internally
-    debug!("Image encoded to signal.");
-
-    // 4. Convert Signal to Audio Samples
-    info!("Converting signal to audio samples...");
-    let samples: Vec<f32> = signal.to_samples(); // This doesn't return Result in rsstv 0.2.1
-    let sstv_sample_rate = rsstv::SAMPLE_RATE as u32; // Use the const directly
-
-    info!(
-        "rsstv successfully generated {} SSTV audio samples (approx {:.2} seconds at {} Hz)",
-        samples.len(),
-        samples.len() as f32 / sstv_sample_rate as f32,
-        sstv_sample_rate
-    );
-
-    // 5. Return Samples
-    Ok(samples)
+    encode_sstv(&loaded_image, SstvMode::MartinM1, SSTV_DEFAULT_SAMPLE_RATE)
 }
 
 
@@ -201,4 +166,24 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_decode_round_trip_with_martin_m1_encoder() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let input_path = temp_dir.path().join("bars.png");
+        decode::tests::colour_bars(320, 256).save(&input_path).expect("Failed to save test image");
+
+        let samples = encode_sstv_martin_m1(&input_path).expect("Encoding failed");
+        let picture = decode_sstv(&samples, SSTV_DEFAULT_SAMPLE_RATE).expect("Decoding failed");
+
+        assert_eq!(picture.mode, SstvMode::MartinM1);
+        assert_eq!(picture.vis_code, Some(44));
+        assert_eq!(picture.lines_received, 256);
+        assert!((picture.clock_ratio - 1.0).abs() < 0.001, "clock ratio {}", picture.clock_ratio);
+        decode::tests::assert_bars(&picture.image, 0..256, 16);
+
+        let output_path = temp_dir.path().join("decoded.png");
+        picture.image.save(&output_path).expect("Failed to save decoded image");
+        assert_eq!(image::open(&output_path).unwrap().to_rgb8().dimensions(), (320, 256));
+    }
 }
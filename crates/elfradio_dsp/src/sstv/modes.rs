@@ -0,0 +1,460 @@
+// SSTV mode definitions: VIS codes, resolutions and line layouts.
+//
+// A layout lists one sync period of the signal in transmission order. The
+// decoder walks it backwards from each detected sync pulse.
+
+use crate::error::DspError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Sync pulse frequency.
+pub(crate) const SYNC_HZ: f32 = 1200.0;
+/// Frequency of a black pixel (and of most porches).
+pub(crate) const BLACK_HZ: f32 = 1500.0;
+/// Frequency of a white pixel.
+pub(crate) const WHITE_HZ: f32 = 2300.0;
+/// Calibration leader tone ahead of the VIS code.
+pub(crate) const LEADER_HZ: f32 = 1900.0;
+/// VIS bit tones.
+pub(crate) const VIS_ONE_HZ: f32 = 1100.0;
+pub(crate) const VIS_ZERO_HZ: f32 = 1300.0;
+/// Length of each VIS bit (and of the start/stop bits).
+pub(crate) const VIS_BIT_MS: f64 = 30.0;
+
+/// Supported SSTV modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SstvMode {
+    MartinM1,
+    MartinM2,
+    ScottieS1,
+    ScottieS2,
+    ScottieDx,
+    Robot36,
+    Robot72,
+    Pd90,
+    Pd120,
+}
+
+/// Colour component carried by a scan segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Channel {
+    Red,
+    Green,
+    Blue,
+    Luma,
+    /// PD: luma of the second image line carried by the same sync period.
+    LumaOdd,
+    RedDiff,
+    BlueDiff,
+    /// Robot 36: R-Y on even lines, B-Y on odd lines.
+    Chroma,
+}
+
+/// One piece of a line.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Segment {
+    /// 1200 Hz sync pulse (ms).
+    Sync(f64),
+    /// Fixed tone: frequency (Hz), length (ms).
+    Tone(f32, f64),
+    /// Pixel data for one channel across the full width (ms).
+    Scan(Channel, f64),
+    /// Robot 36 chroma marker (ms): black before R-Y, white before B-Y.
+    ChromaSeparator(f64),
+}
+
+impl Segment {
+    pub(crate) fn ms(&self) -> f64 {
+        match *self {
+            Segment::Sync(ms) | Segment::Tone(_, ms) | Segment::Scan(_, ms) | Segment::ChromaSeparator(ms) => ms,
+        }
+    }
+}
+
+/// How the scan channels map to RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorSpace {
+    Rgb,
+    /// BT.601 Y/R-Y/B-Y in studio swing (16..235), as MMSSTV sends it.
+    YCrCb,
+}
+
+pub(crate) struct ModeSpec {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// Image lines sent per sync period (2 for PD).
+    pub(crate) lines_per_sync: u32,
+    /// Extra sync pulse sent once before the first line (Scottie), in ms.
+    pub(crate) leading_sync_ms: f64,
+    pub(crate) color: ColorSpace,
+    pub(crate) layout: &'static [Segment],
+}
+
+impl ModeSpec {
+    /// Number of sync periods in a picture.
+    pub(crate) fn sync_periods(&self) -> u32 {
+        self.height / self.lines_per_sync
+    }
+
+    /// Length of one sync period (ms).
+    pub(crate) fn line_ms(&self) -> f64 {
+        self.layout.iter().map(Segment::ms).sum()
+    }
+
+    /// Length of the sync pulse (ms).
+    pub(crate) fn sync_ms(&self) -> f64 {
+        self.layout.iter().find_map(|s| if let Segment::Sync(ms) = s { Some(*ms) } else { None }).unwrap_or(0.0)
+    }
+
+    /// Time from the start of the line to the end of its sync pulse (ms).
+    pub(crate) fn sync_end_ms(&self) -> f64 {
+        let mut t = 0.0;
+        for segment in self.layout {
+            t += segment.ms();
+            if matches!(segment, Segment::Sync(_)) {
+                break;
+            }
+        }
+        t
+    }
+}
+
+const MARTIN_PORCH: Segment = Segment::Tone(BLACK_HZ, 0.572);
+const SCOTTIE_PORCH: Segment = Segment::Tone(BLACK_HZ, 1.5);
+
+static MARTIN_M1: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 1,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::Rgb,
+    layout: &[
+        Segment::Sync(4.862),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Green, 146.432),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Blue, 146.432),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Red, 146.432),
+        MARTIN_PORCH,
+    ],
+};
+
+static MARTIN_M2: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 1,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::Rgb,
+    layout: &[
+        Segment::Sync(4.862),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Green, 73.216),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Blue, 73.216),
+        MARTIN_PORCH,
+        Segment::Scan(Channel::Red, 73.216),
+        MARTIN_PORCH,
+    ],
+};
+
+// Scottie puts the sync pulse between blue and red, so each line starts with green.
+static SCOTTIE_S1: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 1,
+    leading_sync_ms: 9.0,
+    color: ColorSpace::Rgb,
+    layout: &[
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Green, 138.240),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Blue, 138.240),
+        Segment::Sync(9.0),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Red, 138.240),
+    ],
+};
+
+static SCOTTIE_S2: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 1,
+    leading_sync_ms: 9.0,
+    color: ColorSpace::Rgb,
+    layout: &[
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Green, 88.064),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Blue, 88.064),
+        Segment::Sync(9.0),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Red, 88.064),
+    ],
+};
+
+static ROBOT_36: ModeSpec = ModeSpec {
+    width: 320,
+    height: 240,
+    lines_per_sync: 1,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::YCrCb,
+    layout: &[
+        Segment::Sync(9.0),
+        Segment::Tone(BLACK_HZ, 3.0),
+        Segment::Scan(Channel::Luma, 88.0),
+        Segment::ChromaSeparator(4.5),
+        Segment::Tone(LEADER_HZ, 1.5),
+        Segment::Scan(Channel::Chroma, 44.0),
+    ],
+};
+
+static SCOTTIE_DX: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 1,
+    leading_sync_ms: 9.0,
+    color: ColorSpace::Rgb,
+    layout: &[
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Green, 345.6),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Blue, 345.6),
+        Segment::Sync(9.0),
+        SCOTTIE_PORCH,
+        Segment::Scan(Channel::Red, 345.6),
+    ],
+};
+
+static ROBOT_72: ModeSpec = ModeSpec {
+    width: 320,
+    height: 240,
+    lines_per_sync: 1,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::YCrCb,
+    layout: &[
+        Segment::Sync(9.0),
+        Segment::Tone(BLACK_HZ, 3.0),
+        Segment::Scan(Channel::Luma, 138.0),
+        Segment::Tone(BLACK_HZ, 4.5),
+        Segment::Tone(LEADER_HZ, 1.5),
+        Segment::Scan(Channel::RedDiff, 69.0),
+        Segment::Tone(WHITE_HZ, 4.5),
+        Segment::Tone(LEADER_HZ, 1.5),
+        Segment::Scan(Channel::BlueDiff, 69.0),
+    ],
+};
+
+// PD modes send two image lines per sync: Y(even), R-Y, B-Y (shared), Y(odd).
+static PD_90: ModeSpec = ModeSpec {
+    width: 320,
+    height: 256,
+    lines_per_sync: 2,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::YCrCb,
+    layout: &[
+        Segment::Sync(20.0),
+        Segment::Tone(BLACK_HZ, 2.08),
+        Segment::Scan(Channel::Luma, 170.24),
+        Segment::Scan(Channel::RedDiff, 170.24),
+        Segment::Scan(Channel::BlueDiff, 170.24),
+        Segment::Scan(Channel::LumaOdd, 170.24),
+    ],
+};
+
+static PD_120: ModeSpec = ModeSpec {
+    width: 640,
+    height: 496,
+    lines_per_sync: 2,
+    leading_sync_ms: 0.0,
+    color: ColorSpace::YCrCb,
+    layout: &[
+        Segment::Sync(20.0),
+        Segment::Tone(BLACK_HZ, 2.08),
+        Segment::Scan(Channel::Luma, 121.6),
+        Segment::Scan(Channel::RedDiff, 121.6),
+        Segment::Scan(Channel::BlueDiff, 121.6),
+        Segment::Scan(Channel::LumaOdd, 121.6),
+    ],
+};
+
+impl SstvMode {
+    /// Every supported mode, used when the mode has to be guessed from the line rate.
+    pub const ALL: [SstvMode; 9] = [
+        SstvMode::MartinM1,
+        SstvMode::MartinM2,
+        SstvMode::ScottieS1,
+        SstvMode::ScottieS2,
+        SstvMode::ScottieDx,
+        SstvMode::Robot36,
+        SstvMode::Robot72,
+        SstvMode::Pd90,
+        SstvMode::Pd120,
+    ];
+
+    /// 7-bit VIS code announcing this mode.
+    pub fn vis_code(&self) -> u8 {
+        match self {
+            SstvMode::MartinM1 => 44,
+            SstvMode::MartinM2 => 40,
+            SstvMode::ScottieS1 => 60,
+            SstvMode::ScottieS2 => 56,
+            SstvMode::ScottieDx => 76,
+            SstvMode::Robot36 => 8,
+            SstvMode::Robot72 => 12,
+            SstvMode::Pd90 => 99,
+            SstvMode::Pd120 => 95,
+        }
+    }
+
+    pub fn from_vis_code(code: u8) -> Option<SstvMode> {
+        Self::ALL.into_iter().find(|mode| mode.vis_code() == code)
+    }
+
+    /// Human readable name, e.g. "Martin M1".
+    pub fn name(&self) -> &'static str {
+        match self {
+            SstvMode::MartinM1 => "Martin M1",
+            SstvMode::MartinM2 => "Martin M2",
+            SstvMode::ScottieS1 => "Scottie S1",
+            SstvMode::ScottieS2 => "Scottie S2",
+            SstvMode::ScottieDx => "Scottie DX",
+            SstvMode::Robot36 => "Robot 36",
+            SstvMode::Robot72 => "Robot 72",
+            SstvMode::Pd90 => "PD90",
+            SstvMode::Pd120 => "PD120",
+        }
+    }
+
+    /// Native image size (width, height).
+    pub fn resolution(&self) -> (u32, u32) {
+        let spec = self.spec();
+        (spec.width, spec.height)
+    }
+
+    /// Time to send the picture, excluding the VIS header.
+    pub fn duration_secs(&self) -> f64 {
+        let spec = self.spec();
+        (spec.leading_sync_ms + spec.line_ms() * spec.sync_periods() as f64) / 1000.0
+    }
+
+    pub(crate) fn spec(&self) -> &'static ModeSpec {
+        match self {
+            SstvMode::MartinM1 => &MARTIN_M1,
+            SstvMode::MartinM2 => &MARTIN_M2,
+            SstvMode::ScottieS1 => &SCOTTIE_S1,
+            SstvMode::ScottieS2 => &SCOTTIE_S2,
+            SstvMode::ScottieDx => &SCOTTIE_DX,
+            SstvMode::Robot36 => &ROBOT_36,
+            SstvMode::Robot72 => &ROBOT_72,
+            SstvMode::Pd90 => &PD_90,
+            SstvMode::Pd120 => &PD_120,
+        }
+    }
+}
+
+impl fmt::Display for SstvMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses `sstv_settings.mode`: "Martin M1", "scottie_dx", "Robot36", "PD 120", "S2", ...
+impl FromStr for SstvMode {
+    type Err = DspError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key: String = s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+        match key.as_str() {
+            "martinm1" | "m1" => Ok(SstvMode::MartinM1),
+            "martinm2" | "m2" => Ok(SstvMode::MartinM2),
+            "scotties1" | "s1" => Ok(SstvMode::ScottieS1),
+            "scotties2" | "s2" => Ok(SstvMode::ScottieS2),
+            "scottiedx" | "sdx" => Ok(SstvMode::ScottieDx),
+            "robot36" | "r36" => Ok(SstvMode::Robot36),
+            "robot72" | "r72" => Ok(SstvMode::Robot72),
+            "pd90" => Ok(SstvMode::Pd90),
+            "pd120" => Ok(SstvMode::Pd120),
+            _ => Err(DspError::UnsupportedSstvMode(s.to_string())),
+        }
+    }
+}
+
+/// Maps a tone frequency to an 8-bit pixel level.
+pub(crate) fn frequency_to_level(freq: f32) -> u8 {
+    ((freq - BLACK_HZ) / (WHITE_HZ - BLACK_HZ) * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Maps an 8-bit pixel level to its tone frequency.
+pub(crate) fn level_to_frequency(level: u8) -> f32 {
+    BLACK_HZ + (WHITE_HZ - BLACK_HZ) * level as f32 / 255.0
+}
+
+/// RGB to BT.601 studio-swing Y/R-Y/B-Y.
+pub(crate) fn rgb_to_ycrcb([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(16.0 + (65.738 * r + 129.057 * g + 25.064 * b) / 256.0),
+        clamp(128.0 + (112.439 * r - 94.154 * g - 18.285 * b) / 256.0),
+        clamp(128.0 + (-37.945 * r - 74.494 * g + 112.439 * b) / 256.0),
+    ]
+}
+
+/// BT.601 studio-swing Y/R-Y/B-Y back to RGB.
+pub(crate) fn ycrcb_to_rgb(y: u8, cr: u8, cb: u8) -> [u8; 3] {
+    let y = 1.164 * (y as f32 - 16.0);
+    let (cr, cb) = (cr as f32 - 128.0, cb as f32 - 128.0);
+    let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    [clamp(y + 1.596 * cr), clamp(y - 0.813 * cr - 0.392 * cb), clamp(y + 2.017 * cb)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_timing_matches_published_values() {
+        let expected = [
+            (SstvMode::MartinM1, 446.446),
+            (SstvMode::MartinM2, 226.798),
+            (SstvMode::ScottieS1, 428.22),
+            (SstvMode::ScottieS2, 277.692),
+            (SstvMode::ScottieDx, 1050.3),
+            (SstvMode::Robot36, 150.0),
+            (SstvMode::Robot72, 300.0),
+            (SstvMode::Pd90, 703.04),
+            (SstvMode::Pd120, 508.48),
+        ];
+        for (mode, line_ms) in expected {
+            assert!((mode.spec().line_ms() - line_ms).abs() < 1e-6, "{}", mode);
+            assert_eq!(SstvMode::from_vis_code(mode.vis_code()), Some(mode));
+        }
+        assert!((SstvMode::MartinM1.duration_secs() - 114.29).abs() < 0.01);
+        assert!((SstvMode::Pd90.duration_secs() - 89.99).abs() < 0.01);
+        assert!((SstvMode::Pd120.duration_secs() - 126.1).abs() < 0.01);
+        assert!((SstvMode::ScottieS1.spec().sync_end_ms() - (1.5 + 138.24 + 1.5 + 138.24 + 9.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_mode_names() {
+        for mode in SstvMode::ALL {
+            assert_eq!(mode.name().parse::<SstvMode>().unwrap(), mode);
+        }
+        assert_eq!("scottie_dx".parse::<SstvMode>().unwrap(), SstvMode::ScottieDx);
+        assert_eq!("PD 120".parse::<SstvMode>().unwrap(), SstvMode::Pd120);
+        assert_eq!("robot36".parse::<SstvMode>().unwrap(), SstvMode::Robot36);
+        assert!(matches!("Wraase SC2-180".parse::<SstvMode>(), Err(DspError::UnsupportedSstvMode(_))));
+    }
+
+    #[test]
+    fn test_ycrcb_round_trip() {
+        for rgb in [[0, 0, 0], [255, 255, 255], [200, 30, 90], [12, 180, 240]] {
+            let [y, cr, cb] = rgb_to_ycrcb(rgb);
+            let back = ycrcb_to_rgb(y, cr, cb);
+            for c in 0..3 {
+                assert!((back[c] as i32 - rgb[c] as i32).abs() <= 2, "{:?} -> {:?}", rgb, back);
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// SSTV picture preparation: fit to the mode's frame and stamp template text on it.
+
+use super::modes::SstvMode;
+use elfradio_types::{SstvImageFit, SstvTemplate, SstvTextAnchor, SstvTextOverlay};
+use embedded_graphics::mono_font::ascii::FONT_10X20;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgb, RgbImage};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use tracing::debug;
+
+/// Template coordinates are in pixels of a picture this wide.
+const REFERENCE_WIDTH: u32 = 320;
+
+/// Fits `image` to `mode`'s native resolution and draws the template's text overlays.
+///
+/// `fields` supplies the `{name}` placeholder values; an overlay that refers to a
+/// field without a (non-empty) value is left out. The built-in font covers ASCII
+/// only, other characters are drawn as `?`.
+pub fn prepare_sstv_image(
+    image: &DynamicImage,
+    mode: SstvMode,
+    template: &SstvTemplate,
+    fields: &HashMap<String, String>,
+) -> DynamicImage {
+    let (width, height) = mode.resolution();
+    let mut frame = fit_image(image, width, height, template);
+    let unit = (width / REFERENCE_WIDTH).max(1);
+    for overlay in &template.text {
+        match fill_placeholders(&overlay.text, fields) {
+            Some(text) if !text.trim().is_empty() => draw_text(&mut frame, &text, overlay, unit),
+            _ => debug!("Skipping SSTV overlay {:?}: missing field value", overlay.text),
+        }
+    }
+    DynamicImage::ImageRgb8(frame)
+}
+
+fn fit_image(image: &DynamicImage, width: u32, height: u32, template: &SstvTemplate) -> RgbImage {
+    match template.fit {
+        SstvImageFit::Crop => image.resize_to_fill(width, height, FilterType::Triangle).to_rgb8(),
+        SstvImageFit::Letterbox => {
+            let scaled = image.resize(width, height, FilterType::Triangle).to_rgb8();
+            let mut frame = RgbImage::from_pixel(width, height, Rgb(template.background));
+            let x = (width - scaled.width()) / 2;
+            let y = (height - scaled.height()) / 2;
+            imageops::replace(&mut frame, &scaled, x as i64, y as i64);
+            frame
+        }
+    }
+}
+
+/// Replaces every `{name}` in `text`; None if a field has no value.
+fn fill_placeholders(text: &str, fields: &HashMap<String, String>) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('}')? + open;
+        let value = fields.get(&rest[open + 1..close]).filter(|v| !v.is_empty())?;
+        out.push_str(value);
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// One-bit canvas the font is rendered into before scaling.
+struct GlyphMask {
+    size: Size,
+    bits: Vec<bool>,
+}
+
+impl GlyphMask {
+    fn render(text: &str) -> Self {
+        let glyph = FONT_10X20.character_size;
+        let size = Size::new(glyph.width * text.chars().count() as u32, glyph.height);
+        let mut mask = Self { size, bits: vec![false; (size.width * size.height) as usize] };
+        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        let Ok(_) = Text::with_baseline(text, Point::zero(), style, Baseline::Top).draw(&mut mask);
+        mask
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as u32) < self.size.width
+            && (y as u32) < self.size.height
+            && self.bits[(y as u32 * self.size.width + x as u32) as usize]
+    }
+}
+
+impl OriginDimensions for GlyphMask {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for GlyphMask {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let Ok((x, y)) = <(u32, u32)>::try_from(point)
+                && x < self.size.width
+                && y < self.size.height
+            {
+                self.bits[(y * self.size.width + x) as usize] = color.is_on();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn draw_text(frame: &mut RgbImage, text: &str, overlay: &SstvTextOverlay, unit: u32) {
+    let mask = GlyphMask::render(text);
+    let scale = overlay.scale.max(1) * unit;
+    let (text_w, text_h) = ((mask.size.width * scale) as i64, (mask.size.height * scale) as i64);
+    let (frame_w, frame_h) = (frame.width() as i64, frame.height() as i64);
+    let (dx, dy) = ((overlay.x * unit) as i64, (overlay.y * unit) as i64);
+
+    let left = match overlay.anchor {
+        SstvTextAnchor::TopLeft | SstvTextAnchor::BottomLeft => dx,
+        SstvTextAnchor::TopCenter | SstvTextAnchor::BottomCenter => (frame_w - text_w) / 2,
+        SstvTextAnchor::TopRight | SstvTextAnchor::BottomRight => frame_w - text_w - dx,
+    };
+    let top = match overlay.anchor {
+        SstvTextAnchor::TopLeft | SstvTextAnchor::TopCenter | SstvTextAnchor::TopRight => dy,
+        _ => frame_h - text_h - dy,
+    };
+
+    // The outline is one font pixel wide: the unset font pixels next to a set one.
+    let border = overlay.outline.map_or(0, |_| scale as i64);
+    for y in (top - border).max(0)..(top + text_h + border).min(frame_h) {
+        for x in (left - border).max(0)..(left + text_w + border).min(frame_w) {
+            let (mx, my) = ((x - left).div_euclid(scale as i64), (y - top).div_euclid(scale as i64));
+            let color = match overlay.outline {
+                _ if mask.get(mx, my) => overlay.color,
+                Some(outline) if (-1..=1).any(|oy| (-1..=1).any(|ox| mask.get(mx + ox, my + oy))) => outline,
+                _ => continue,
+            };
+            frame.put_pixel(x as u32, y as u32, Rgb(color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> HashMap<String, String> {
+        [("mycall", "VK7KSM"), ("theircall", "VK3ABC"), ("rst", "595")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn overlay(text: &str, anchor: SstvTextAnchor) -> SstvTextOverlay {
+        SstvTextOverlay { text: text.to_string(), anchor, x: 0, y: 0, scale: 1, color: [255, 0, 0], outline: None }
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let fields = fields();
+        assert_eq!(fill_placeholders("{theircall} de {mycall}", &fields).as_deref(), Some("VK3ABC de VK7KSM"));
+        assert_eq!(fill_placeholders("RST {rst}", &fields).as_deref(), Some("RST 595"));
+        assert_eq!(fill_placeholders("{time}", &fields), None);
+        assert_eq!(fill_placeholders("no fields", &fields).as_deref(), Some("no fields"));
+        assert_eq!(fill_placeholders("{unclosed", &fields), None);
+    }
+
+    #[test]
+    fn test_crop_and_letterbox_to_native_resolution() {
+        // 2:1 white picture into a 320x256 frame.
+        let wide = DynamicImage::ImageRgb8(RgbImage::from_pixel(640, 320, Rgb([255, 255, 255])));
+        let mut template = SstvTemplate { background: [0, 0, 64], ..Default::default() };
+
+        let cropped = prepare_sstv_image(&wide, SstvMode::MartinM1, &template, &fields()).to_rgb8();
+        assert_eq!(cropped.dimensions(), (320, 256));
+        assert_eq!(cropped.get_pixel(160, 2).0, [255, 255, 255]);
+
+        template.fit = SstvImageFit::Letterbox;
+        let boxed = prepare_sstv_image(&wide, SstvMode::MartinM1, &template, &fields()).to_rgb8();
+        assert_eq!(boxed.dimensions(), (320, 256));
+        assert_eq!(boxed.get_pixel(160, 2).0, [0, 0, 64]); // 160 px picture, 48 px bars
+        assert_eq!(boxed.get_pixel(160, 128).0, [255, 255, 255]);
+
+        let pd120 = prepare_sstv_image(&wide, SstvMode::Pd120, &template, &fields());
+        assert_eq!((pd120.width(), pd120.height()), (640, 496));
+    }
+
+    #[test]
+    fn test_overlays_are_anchored_and_skipped_without_values() {
+        let black = DynamicImage::ImageRgb8(RgbImage::new(320, 256));
+        let template = SstvTemplate {
+            text: vec![
+                overlay("{theircall}", SstvTextAnchor::TopLeft),
+                overlay("{rst}", SstvTextAnchor::BottomRight),
+                SstvTextOverlay { outline: Some([0, 255, 0]), ..overlay("{time}", SstvTextAnchor::BottomLeft) },
+            ],
+            ..Default::default()
+        };
+        let image = prepare_sstv_image(&black, SstvMode::MartinM1, &template, &fields()).to_rgb8();
+        let red_in = |x0: u32, y0: u32, x1: u32, y1: u32| {
+            (y0..y1).flat_map(|y| (x0..x1).map(move |x| (x, y))).filter(|&(x, y)| image.get_pixel(x, y).0 == [255, 0, 0]).count()
+        };
+        assert!(red_in(0, 0, 60, 20) > 50, "callsign drawn top left");
+        assert_eq!(red_in(60, 0, 320, 20), 0, "six 10-pixel glyphs");
+        assert!(red_in(290, 236, 320, 256) > 20, "report drawn bottom right");
+        // No {time} value, so no outline either.
+        assert!(image.pixels().all(|p| p.0 != [0, 255, 0]));
+    }
+
+    #[test]
+    fn test_outline_surrounds_text() {
+        let black = DynamicImage::ImageRgb8(RgbImage::new(320, 256));
+        let template = SstvTemplate {
+            text: vec![SstvTextOverlay {
+                x: 20,
+                y: 20,
+                scale: 2,
+                outline: Some([0, 255, 0]),
+                ..overlay("{mycall}", SstvTextAnchor::TopLeft)
+            }],
+            ..Default::default()
+        };
+        let image = prepare_sstv_image(&black, SstvMode::ScottieS1, &template, &fields()).to_rgb8();
+        let count = |rgb: [u8; 3]| image.pixels().filter(|p| p.0 == rgb).count();
+        assert!(count([255, 0, 0]) > 200);
+        assert!(count([0, 255, 0]) > 200);
+        // Nothing outside the text box grown by the 2 px outline.
+        for (x, y, p) in image.enumerate_pixels() {
+            if p.0 != [0, 0, 0] {
+                assert!((18..20 + 120 + 2).contains(&x) && (18..20 + 40 + 2).contains(&y), "({}, {})", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+// Subtone detector, for squelch gating and tone scanning.
+//
+// Audio → boxcar decimation to about 4 kHz → 300 Hz low-pass → decimation to about
+// 1 kHz. CTCSS: Goertzel filters for every standard tone over 400 ms, every 100 ms; a
+// tone counts when it carries most of the sub-audible power and peaks on its own
+// frequency. DCS: slicer → PLL bit clock → 23-bit shift register, looked up among the
+// rotations of every standard code.
+
+use super::{dcs_codeword, rotate_word, Subtone, CTCSS_TONES, DCS_BIT_RATE, DCS_CODES, DCS_WORD_BITS};
+use crate::demod::{lowpass_taps, Fir};
+use crate::error::DspError;
+use crate::goertzel::goertzel_power;
+use std::collections::{HashMap, VecDeque};
+use tracing::debug;
+
+const FIRST_STAGE_RATE: f64 = 4000.0;
+const SECOND_STAGE_RATE: f64 = 1000.0;
+const LOWPASS_HZ: f32 = 300.0;
+const LOWPASS_TRANSITION_HZ: f32 = 80.0;
+const CTCSS_WINDOW_S: f64 = 0.4;
+const CTCSS_HOP_S: f64 = 0.1;
+/// Share of the sub-audible power the strongest tone must carry.
+const CTCSS_PURITY: f64 = 0.3;
+/// Weakest tone detected, as mean power (a sine of about 0.0045 peak).
+const CTCSS_MIN_POWER: f64 = 1e-5;
+/// A tone must be stronger on its own frequency than this far either side of it,
+/// which keeps a voice's pitch sweeping past from counting.
+const CTCSS_PEAK_OFFSET_HZ: f64 = 1.0;
+/// Windows that must agree before a tone is reported, and missed windows before it's dropped.
+const CTCSS_CONFIRM: u32 = 2;
+const CTCSS_HOLD: u32 = 3;
+/// Bits in a row that must match a code before it's reported, and missed bits before it's dropped.
+const DCS_CONFIRM_BITS: u32 = DCS_WORD_BITS;
+const DCS_HOLD_BITS: u32 = 2 * DCS_WORD_BITS;
+/// Fraction of the timing error the bit clock corrects at each level change.
+const PLL_GAIN: f64 = 0.2;
+/// DC tracker time constant, in bits.
+const DC_TIME_BITS: f64 = 64.0;
+
+/// Turns a guess per step into a steady detection.
+struct Tracker {
+    confirm: u32,
+    hold: u32,
+    candidate: Option<Subtone>,
+    count: u32,
+    misses: u32,
+    detected: Option<Subtone>,
+}
+
+impl Tracker {
+    fn new(confirm: u32, hold: u32) -> Self {
+        Self { confirm, hold, candidate: None, count: 0, misses: 0, detected: None }
+    }
+
+    fn update(&mut self, guess: Option<Subtone>) {
+        if let Some(detected) = self.detected {
+            if guess == Some(detected) {
+                self.misses = 0;
+                return;
+            }
+            self.misses += 1;
+            if self.misses < self.hold {
+                return;
+            }
+            self.detected = None;
+            self.candidate = None;
+        }
+        if guess.is_some() && guess == self.candidate {
+            self.count += 1;
+        } else {
+            self.candidate = guess;
+            self.count = guess.is_some() as u32;
+        }
+        if self.count >= self.confirm {
+            self.detected = self.candidate;
+            self.misses = 0;
+        }
+    }
+}
+
+/// Streaming CTCSS and DCS detector. Every standard tone and code is listened for at
+/// once, so the same detector serves squelch gating and scanning.
+pub struct SubtoneDetector {
+    first_decimation: usize,
+    first_sum: f32,
+    first_count: usize,
+    lowpass: Fir<f32>,
+    second_decimation: usize,
+    second_count: usize,
+    sample_rate: f64,
+    window: VecDeque<f32>,
+    window_len: usize,
+    hop_len: usize,
+    since_eval: usize,
+    ctcss: Tracker,
+    /// Every rotation of every standard word, both polarities, by the code it is heard as.
+    dcs_words: HashMap<u32, Subtone>,
+    clock: f64,
+    clock_step: f64,
+    dc: f64,
+    dc_rate: f64,
+    level: bool,
+    /// Last 23 bits, the oldest in bit 0.
+    shift: u32,
+    dcs: Tracker,
+}
+
+impl SubtoneDetector {
+    /// Creates a detector for audio at `sample_rate` (at least 2000 Hz).
+    pub fn new(sample_rate: u32) -> Result<Self, DspError> {
+        if sample_rate < 2000 {
+            return Err(DspError::InvalidSubtoneParameter(format!("sample rate {} Hz is below 2000 Hz", sample_rate)));
+        }
+        let first_decimation = ((sample_rate as f64 / FIRST_STAGE_RATE).round() as usize).max(1);
+        let first_rate = sample_rate as f64 / first_decimation as f64;
+        let second_decimation = ((first_rate / SECOND_STAGE_RATE) as usize).max(1);
+        let rate = first_rate / second_decimation as f64;
+
+        let mut dcs_words = HashMap::new();
+        for inverted in [false, true] {
+            for &code in &DCS_CODES {
+                let word = dcs_codeword(code, inverted);
+                for n in 0..DCS_WORD_BITS {
+                    dcs_words.entry(rotate_word(word, n)).or_insert(Subtone::Dcs { code, inverted });
+                }
+            }
+        }
+
+        let window_len = (CTCSS_WINDOW_S * rate).round() as usize;
+        let bit_samples = rate / DCS_BIT_RATE;
+        debug!(sample_rate, detector_rate = rate, "Creating subtone detector");
+        Ok(Self {
+            first_decimation,
+            first_sum: 0.0,
+            first_count: 0,
+            lowpass: Fir::new(lowpass_taps(LOWPASS_HZ, first_rate as f32, LOWPASS_TRANSITION_HZ)),
+            second_decimation,
+            second_count: 0,
+            sample_rate: rate,
+            window: VecDeque::with_capacity(window_len + 1),
+            window_len,
+            hop_len: (CTCSS_HOP_S * rate).round() as usize,
+            since_eval: 0,
+            ctcss: Tracker::new(CTCSS_CONFIRM, CTCSS_HOLD),
+            dcs_words,
+            clock: 0.0,
+            clock_step: 1.0 / bit_samples,
+            dc: 0.0,
+            dc_rate: 1.0 / (DC_TIME_BITS * bit_samples),
+            level: false,
+            shift: 0,
+            dcs: Tracker::new(DCS_CONFIRM_BITS, DCS_HOLD_BITS),
+        })
+    }
+
+    /// The tone or code heard right now. DCS codes are reported by their first
+    /// equivalent standard code (see `Subtone::canonical`).
+    pub fn detected(&self) -> Option<Subtone> {
+        self.dcs.detected.or(self.ctcss.detected)
+    }
+
+    /// Feeds audio and returns the tone or code heard at the end of it.
+    pub fn push(&mut self, samples: &[f32]) -> Option<Subtone> {
+        let before = self.detected();
+        for &sample in samples {
+            self.first_sum += sample;
+            self.first_count += 1;
+            if self.first_count < self.first_decimation {
+                continue;
+            }
+            let filtered = self.lowpass.process(self.first_sum / self.first_count as f32);
+            self.first_sum = 0.0;
+            self.first_count = 0;
+            self.second_count += 1;
+            if self.second_count == self.second_decimation {
+                self.second_count = 0;
+                self.process_sample(filtered);
+            }
+        }
+        let after = self.detected();
+        if after != before {
+            debug!("Subtone changed: {:?} -> {:?}", before, after);
+        }
+        after
+    }
+
+    fn process_sample(&mut self, sample: f32) {
+        self.window.push_back(sample);
+        if self.window.len() > self.window_len {
+            self.window.pop_front();
+        }
+        self.since_eval += 1;
+        if self.since_eval >= self.hop_len && self.window.len() == self.window_len {
+            self.since_eval = 0;
+            let guess = self.ctcss_guess();
+            self.ctcss.update(guess);
+        }
+
+        self.dc += (sample as f64 - self.dc) * self.dc_rate;
+        let level = sample as f64 > self.dc;
+        if level != self.level {
+            self.level = level;
+            self.clock += (0.5 - self.clock) * PLL_GAIN;
+        }
+        self.clock += self.clock_step;
+        if self.clock >= 1.0 {
+            self.clock -= 1.0;
+            self.shift = self.shift >> 1 | (level as u32) << (DCS_WORD_BITS - 1);
+            let guess = self.dcs_words.get(&self.shift).copied();
+            self.dcs.update(guess);
+        }
+    }
+
+    /// The standard tone dominating the current window, if any.
+    fn ctcss_guess(&mut self) -> Option<Subtone> {
+        let window = self.window.make_contiguous();
+        let mean = window.iter().sum::<f32>() / window.len() as f32;
+        let centred: Vec<f32> = window.iter().map(|&x| x - mean).collect();
+        let total = centred.iter().map(|&x| (x as f64).powi(2)).sum::<f64>() / centred.len() as f64;
+        let (hz, power) = CTCSS_TONES
+            .iter()
+            .map(|&hz| (hz, goertzel_power(&centred, hz as f64, self.sample_rate)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        if power < CTCSS_MIN_POWER || power < CTCSS_PURITY * total {
+            return None;
+        }
+        let peaked = [-CTCSS_PEAK_OFFSET_HZ, CTCSS_PEAK_OFFSET_HZ]
+            .iter()
+            .all(|offset| goertzel_power(&centred, hz as f64 + offset, self.sample_rate) < power);
+        peaked.then_some(Subtone::Ctcss(hz))
+    }
+}
+
+/// Scans a recording and returns the tone or code heard in it (the last one, if the
+/// station changed).
+pub fn decode_subtone(samples: &[f32], sample_rate: u32) -> Result<Option<Subtone>, DspError> {
+    let mut detector = SubtoneDetector::new(sample_rate)?;
+    let mut heard = None;
+    for chunk in samples.chunks(sample_rate as usize / 10) {
+        heard = detector.push(chunk).or(heard);
+    }
+    debug!("Subtone scan result: {:?}", heard);
+    Ok(heard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::add_noise;
+    use crate::subtone::{mix_subtone, SubtoneParams};
+    use assert_matches::assert_matches;
+    use std::f64::consts::PI;
+
+    /// Rough stand-in for speech: a few partials with a wandering pitch.
+    fn voice(len: usize, sample_rate: u32) -> Vec<f32> {
+        let mut phase = 0.0f64;
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / sample_rate as f64;
+                let pitch = 140.0 + 30.0 * (2.0 * PI * 0.7 * t).sin();
+                phase += 2.0 * PI * pitch / sample_rate as f64;
+                let partials: f64 = [1.0, 3.0, 5.0, 8.0].iter().map(|&k| (k * phase).sin() * 0.12).sum();
+                (partials * (0.6 + 0.4 * (2.0 * PI * 3.0 * t).sin())) as f32
+            })
+            .collect()
+    }
+
+    fn transmit(subtone: Subtone, seconds: f64, sample_rate: u32, seed: u64) -> Vec<f32> {
+        let audio = voice((seconds * sample_rate as f64) as usize, sample_rate);
+        let mut mixed = mix_subtone(&audio, &SubtoneParams::new(subtone, sample_rate)).unwrap();
+        add_noise(&mut mixed, 0.03, seed);
+        mixed
+    }
+
+    #[test]
+    fn test_ctcss_tones() {
+        for (n, (hz, rate)) in
+            [(67.0, 16000), (69.3, 16000), (100.0, 8000), (159.8, 44100), (162.2, 16000), (254.1, 48000)].into_iter().enumerate()
+        {
+            let audio = transmit(Subtone::Ctcss(hz), 2.0, rate, n as u64 + 1);
+            assert_eq!(decode_subtone(&audio, rate).unwrap(), Some(Subtone::Ctcss(hz)), "{} Hz at {} Hz", hz, rate);
+        }
+    }
+
+    #[test]
+    fn test_dcs_codes() {
+        for (n, (code, inverted)) in [(0o023, false), (0o047, false), (0o754, false), (0o411, true)].into_iter().enumerate() {
+            let subtone = Subtone::Dcs { code, inverted };
+            let audio = transmit(subtone, 2.0, 16000, n as u64 + 11);
+            assert_eq!(decode_subtone(&audio, 16000).unwrap(), Some(subtone.canonical()), "{}", subtone);
+        }
+    }
+
+    #[test]
+    fn test_gating() {
+        let rate = 16000;
+        let mut audio = vec![0.0; rate as usize];
+        add_noise(&mut audio, 0.2, 5);
+        audio.extend(transmit(Subtone::Ctcss(88.5), 2.0, rate, 6));
+        let mut tail = vec![0.0; rate as usize];
+        add_noise(&mut tail, 0.2, 7);
+        audio.extend(tail);
+
+        let mut detector = SubtoneDetector::new(rate).unwrap();
+        let heard: Vec<Option<Subtone>> = audio.chunks(800).map(|chunk| detector.push(chunk)).collect();
+        // 20 chunks per second: noise, then the tone (found within 0.7 s), then noise.
+        assert!(heard[..20].iter().all(Option::is_none));
+        assert!(heard[34..60].iter().all(|&tone| tone == Some(Subtone::Ctcss(88.5))));
+        assert!(heard[75..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_noise_and_voice_detect_nothing() {
+        let mut noise = vec![0.0; 16000 * 3];
+        add_noise(&mut noise, 0.3, 9);
+        assert_eq!(decode_subtone(&noise, 16000).unwrap(), None);
+
+        // Unfiltered speech, plus a steady hum between two tones.
+        let mut speech = voice(16000 * 3, 16000);
+        for (n, sample) in speech.iter_mut().enumerate() {
+            *sample += 0.2 * (2.0 * PI * 120.0 * n as f64 / 16000.0).sin() as f32;
+        }
+        assert_eq!(decode_subtone(&speech, 16000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        assert_matches!(SubtoneDetector::new(1000).err(), Some(DspError::InvalidSubtoneParameter(_)));
+    }
+}
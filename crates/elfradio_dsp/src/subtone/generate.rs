@@ -0,0 +1,155 @@
+// Subtone mixing: program audio → 300 Hz high-pass, to clear the subtone band → scaled
+// and summed with the CTCSS sine or the smoothed DCS bit stream. DCS ends with the
+// 134.4 Hz turn-off code so receivers close their squelch without a noise burst.
+
+use super::{dcs_codeword, Subtone, SubtoneParams, DCS_BIT_RATE, DCS_WORD_BITS};
+use crate::conditioning::{butterworth_q, Biquad};
+use crate::error::DspError;
+use std::f64::consts::PI;
+use tracing::{debug, info};
+
+const HIGH_PASS_HZ: f64 = 300.0;
+const DCS_TURN_OFF_HZ: f64 = 134.4;
+const DCS_TURN_OFF_MS: u64 = 200;
+/// Subtone fade in and out, so it starts and stops without a click.
+const RAMP_MS: f64 = 10.0;
+/// Two passes of a moving average this long keep DCS edges out of the voice band.
+const DCS_SMOOTH_MS: f64 = 2.5;
+
+/// Centred moving average over `len` samples, shortened at the ends.
+fn moving_average(samples: &[f64], len: usize) -> Vec<f64> {
+    let mut prefix = Vec::with_capacity(samples.len() + 1);
+    prefix.push(0.0);
+    for &sample in samples {
+        prefix.push(prefix[prefix.len() - 1] + sample);
+    }
+    (0..samples.len())
+        .map(|n| {
+            let start = n.saturating_sub(len / 2);
+            let end = (n + len - len / 2).min(samples.len());
+            (prefix[end] - prefix[start]) / (end - start) as f64
+        })
+        .collect()
+}
+
+/// `len` samples of the subtone at unit amplitude, then the DCS turn-off code.
+fn subtone_wave(subtone: Subtone, len: usize, sample_rate: f64) -> Vec<f64> {
+    let tone = |hz: f64, n: usize| (2.0 * PI * hz * n as f64 / sample_rate).sin();
+    match subtone {
+        Subtone::Ctcss(hz) => (0..len).map(|n| tone(hz as f64, n)).collect(),
+        Subtone::Dcs { code, inverted } => {
+            let word = dcs_codeword(code, inverted);
+            let bits: Vec<f64> = (0..len)
+                .map(|n| {
+                    let bit = (n as f64 * DCS_BIT_RATE / sample_rate) as u64 % DCS_WORD_BITS as u64;
+                    if word >> bit & 1 == 1 { 1.0 } else { -1.0 }
+                })
+                .collect();
+            let smooth_len = ((DCS_SMOOTH_MS / 1000.0 * sample_rate).round() as usize).max(1);
+            let mut wave = moving_average(&moving_average(&bits, smooth_len), smooth_len);
+            let tail = (DCS_TURN_OFF_MS as f64 / 1000.0 * sample_rate).round() as usize;
+            wave.extend((0..tail).map(|n| tone(DCS_TURN_OFF_HZ, n)));
+            wave
+        }
+    }
+}
+
+/// Mixes `params.subtone` under `audio`, which is high-passed at 300 Hz first so it
+/// can't disturb tone decoders. DCS output runs 200 ms longer, for the turn-off code.
+pub fn mix_subtone(audio: &[f32], params: &SubtoneParams) -> Result<Vec<f32>, DspError> {
+    params.validate()?;
+    debug!("Mixing subtone: {:?}, {} samples", params, audio.len());
+    if audio.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sample_rate = params.sample_rate as f64;
+    let mut wave = subtone_wave(params.subtone, audio.len(), sample_rate);
+    let ramp = ((RAMP_MS / 1000.0 * sample_rate) as usize).min(wave.len() / 2);
+    let len = wave.len();
+    for n in 0..ramp {
+        let gain = 0.5 - 0.5 * (PI * n as f64 / ramp as f64).cos();
+        wave[n] *= gain;
+        wave[len - 1 - n] *= gain;
+    }
+
+    let mut filters: Vec<Biquad> = butterworth_q(4).into_iter().map(|q| Biquad::high_pass(HIGH_PASS_HZ, sample_rate, q)).collect();
+    let program_gain = 1.0 - params.level as f64;
+    let mixed: Vec<f32> = wave
+        .iter()
+        .enumerate()
+        .map(|(n, &subtone)| {
+            let program = filters.iter_mut().fold(audio.get(n).copied().unwrap_or(0.0) as f64, |x, f| f.process(x));
+            (program * program_gain + subtone * params.level as f64) as f32
+        })
+        .collect();
+
+    info!(
+        "Mixed {} under audio: {} samples (approx {:.2} seconds)",
+        params.subtone,
+        mixed.len(),
+        mixed.len() as f64 / sample_rate
+    );
+    Ok(mixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goertzel::goertzel_power;
+    use assert_matches::assert_matches;
+
+    fn sine(hz: f64, amplitude: f64, len: usize, sample_rate: f64) -> Vec<f32> {
+        (0..len).map(|n| (amplitude * (2.0 * PI * hz * n as f64 / sample_rate).sin()) as f32).collect()
+    }
+
+    #[test]
+    fn test_ctcss_level() {
+        let params = SubtoneParams::new(Subtone::Ctcss(88.5), 16000);
+        let mixed = mix_subtone(&vec![0.0; 16000], &params).unwrap();
+        assert_eq!(mixed.len(), 16000);
+        // Mean power of a sine is A²/2.
+        let power = goertzel_power(&mixed[4000..12000], 88.5, 16000.0);
+        assert!((power - 0.1f64.powi(2) / 2.0).abs() < 1e-4, "power {}", power);
+        assert!(mixed[0].abs() < 1e-3 && mixed[15999].abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_high_pass() {
+        let mut audio = sine(120.0, 0.5, 16000, 16000.0);
+        audio.iter_mut().zip(sine(1000.0, 0.5, 16000, 16000.0)).for_each(|(a, b)| *a += b);
+        let params = SubtoneParams::new(Subtone::Ctcss(67.0), 16000).with_level(0.0);
+        let mixed = mix_subtone(&audio, &params).unwrap();
+        let voice = goertzel_power(&mixed[4000..], 1000.0, 16000.0);
+        let hum = goertzel_power(&mixed[4000..], 120.0, 16000.0);
+        assert!((voice - 0.125).abs() < 0.01, "voice {}", voice);
+        assert!(hum < 0.125 * 1e-3, "hum {}", hum);
+    }
+
+    #[test]
+    fn test_dcs_turn_off() {
+        let params = SubtoneParams::new(Subtone::Dcs { code: 0o023, inverted: false }, 8000);
+        let mixed = mix_subtone(&vec![0.0; 8000], &params).unwrap();
+        assert_eq!(mixed.len(), 8000 + 1600);
+        let turn_off = goertzel_power(&mixed[8100..9500], 134.4, 8000.0);
+        assert!(turn_off > 0.5 * 0.1f64.powi(2) / 2.0, "turn-off {}", turn_off);
+        assert!(mix_subtone(&[], &params).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let ctcss = Subtone::Ctcss(88.5);
+        assert_matches!(
+            mix_subtone(&[0.0], &SubtoneParams::new(ctcss, 1000)),
+            Err(DspError::InvalidSubtoneParameter(_))
+        );
+        assert_matches!(
+            mix_subtone(&[0.0], &SubtoneParams::new(ctcss, 16000).with_level(0.8)),
+            Err(DspError::InvalidSubtoneParameter(_))
+        );
+        assert_matches!(
+            mix_subtone(&[0.0], &SubtoneParams::new(Subtone::Ctcss(150.0), 16000)),
+            Err(DspError::InvalidSubtoneParameter(_))
+        );
+    }
+}
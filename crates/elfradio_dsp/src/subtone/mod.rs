@@ -0,0 +1,231 @@
+// Sub-audible squelch signalling mixed under the program audio: CTCSS, one continuous
+// tone from 67.0 to 254.1 Hz, and DCS, a 23-bit codeword repeated at 134.4 bit/s.
+//
+// DCS word, first bit sent first: the nine bits of the octal code (LSB first), 0, 0,
+// 1, then the eleven Golay(23,12) check bits. An inverted code sends the complement.
+// Every rotation of a word is heard the same way, so a rotation of one code's word
+// can be another code's word (023N, 340N and 766N sound alike, as do 023N and 047I).
+
+mod detect;
+mod generate;
+
+pub use detect::{decode_subtone, SubtoneDetector};
+pub use generate::mix_subtone;
+
+use crate::error::DspError;
+use std::fmt;
+use std::str::FromStr;
+
+/// The 50 standard CTCSS tones (Hz).
+pub const CTCSS_TONES: [f32; 50] = [
+    67.0, 69.3, 71.9, 74.4, 77.0, 79.7, 82.5, 85.4, 88.5, 91.5, 94.8, 97.4, 100.0, 103.5, 107.2, 110.9, 114.8, 118.8,
+    123.0, 127.3, 131.8, 136.5, 141.3, 146.2, 151.4, 156.7, 159.8, 162.2, 165.5, 167.9, 171.3, 173.8, 177.3, 179.9,
+    183.5, 186.2, 189.9, 192.8, 196.6, 199.5, 203.5, 206.5, 210.7, 218.1, 225.7, 229.1, 233.6, 241.8, 250.3, 254.1,
+];
+
+/// The 104 standard DCS codes (octal).
+pub const DCS_CODES: [u16; 104] = [
+    0o023, 0o025, 0o026, 0o031, 0o032, 0o036, 0o043, 0o047, 0o051, 0o053, 0o054, 0o065, 0o071, 0o072, 0o073, 0o074,
+    0o114, 0o115, 0o116, 0o122, 0o125, 0o131, 0o132, 0o134, 0o143, 0o145, 0o152, 0o155, 0o156, 0o162, 0o165, 0o172,
+    0o174, 0o205, 0o212, 0o223, 0o225, 0o226, 0o243, 0o244, 0o245, 0o246, 0o251, 0o252, 0o255, 0o261, 0o263, 0o265,
+    0o266, 0o271, 0o274, 0o306, 0o311, 0o315, 0o325, 0o331, 0o332, 0o343, 0o346, 0o351, 0o356, 0o364, 0o365, 0o371,
+    0o411, 0o412, 0o413, 0o423, 0o431, 0o432, 0o445, 0o446, 0o452, 0o454, 0o455, 0o462, 0o464, 0o465, 0o466, 0o503,
+    0o506, 0o516, 0o523, 0o526, 0o532, 0o546, 0o565, 0o606, 0o612, 0o624, 0o627, 0o631, 0o632, 0o654, 0o662, 0o664,
+    0o703, 0o712, 0o723, 0o731, 0o732, 0o734, 0o743, 0o754,
+];
+
+pub(crate) const DCS_BIT_RATE: f64 = 134.4;
+pub(crate) const DCS_WORD_BITS: u32 = 23;
+const DCS_WORD_MASK: u32 = (1 << DCS_WORD_BITS) - 1;
+/// Golay(23,12) generator polynomial x^11 + x^10 + x^6 + x^5 + x^4 + x^2 + 1.
+const GOLAY_GENERATOR: u32 = 0xC75;
+/// Written tones are matched to the table within this many Hz.
+const CTCSS_TOLERANCE_HZ: f32 = 0.05;
+
+/// The DCS word for `code`, first bit sent in bit 0.
+pub(crate) fn dcs_codeword(code: u16, inverted: bool) -> u32 {
+    let data = code as u32 & 0o777 | 1 << 11;
+    let mut remainder = data << 11;
+    for bit in (11..23).rev() {
+        if remainder & 1 << bit != 0 {
+            remainder ^= GOLAY_GENERATOR << (bit - 11);
+        }
+    }
+    let word = data | remainder << 12;
+    if inverted { !word & DCS_WORD_MASK } else { word }
+}
+
+/// `word` rotated right by `n` bits, as heard when reception starts `n` bits in.
+pub(crate) fn rotate_word(word: u32, n: u32) -> u32 {
+    (word >> n | word << (DCS_WORD_BITS - n)) & DCS_WORD_MASK
+}
+
+/// A CTCSS tone or DCS code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subtone {
+    /// Tone frequency (Hz), one of `CTCSS_TONES`.
+    Ctcss(f32),
+    /// Code from `DCS_CODES`, normal (N) or inverted (I).
+    Dcs { code: u16, inverted: bool },
+}
+
+impl Subtone {
+    /// The same code with the other polarity, as heard through an inverting receiver.
+    pub fn inverted(self) -> Self {
+        match self {
+            Subtone::Dcs { code, inverted } => Subtone::Dcs { code, inverted: !inverted },
+            ctcss => ctcss,
+        }
+    }
+
+    /// Whether a receiver can tell the two apart. DCS codes whose words are rotations
+    /// of each other can't be.
+    pub fn is_equivalent(&self, other: &Subtone) -> bool {
+        match (*self, *other) {
+            (Subtone::Dcs { code: a, inverted: a_inv }, Subtone::Dcs { code: b, inverted: b_inv }) => {
+                let word = dcs_codeword(b, b_inv);
+                (0..DCS_WORD_BITS).any(|n| rotate_word(dcs_codeword(a, a_inv), n) == word)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The name a receiver reports: the first equivalent standard code, normal codes
+    /// before inverted ones.
+    pub fn canonical(self) -> Self {
+        if let Subtone::Ctcss(_) = self {
+            return self;
+        }
+        [false, true]
+            .into_iter()
+            .flat_map(|inverted| DCS_CODES.iter().map(move |&code| Subtone::Dcs { code, inverted }))
+            .find(|candidate| candidate.is_equivalent(&self))
+            .unwrap_or(self)
+    }
+}
+
+impl fmt::Display for Subtone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subtone::Ctcss(hz) => write!(f, "CTCSS {:.1} Hz", hz),
+            Subtone::Dcs { code, inverted } => write!(f, "DCS {:03o}{}", code, if *inverted { 'I' } else { 'N' }),
+        }
+    }
+}
+
+impl FromStr for Subtone {
+    type Err = DspError;
+
+    /// Parses "88.5", "CTCSS 88.5 Hz", "D023N", "023I" or "DCS 023". Codes without a
+    /// polarity are normal.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DspError::InvalidSubtoneParameter(format!("'{}' is not a CTCSS tone or DCS code", s));
+        let upper = s.trim().to_ascii_uppercase();
+        let ctcss = upper.strip_prefix("CTCSS").unwrap_or(&upper).trim();
+        let ctcss = ctcss.strip_suffix("HZ").unwrap_or(ctcss).trim();
+        if ctcss.contains('.') || upper.starts_with("CTCSS") {
+            let hz: f32 = ctcss.parse().map_err(|_| invalid())?;
+            return CTCSS_TONES
+                .iter()
+                .find(|&&tone| (tone - hz).abs() <= CTCSS_TOLERANCE_HZ)
+                .map(|&tone| Subtone::Ctcss(tone))
+                .ok_or_else(|| DspError::InvalidSubtoneParameter(format!("{} Hz is not a standard CTCSS tone", hz)));
+        }
+
+        let dcs = upper.strip_prefix("DCS").or_else(|| upper.strip_prefix('D')).unwrap_or(&upper).trim();
+        let (digits, inverted) = match dcs.strip_suffix('I') {
+            Some(digits) => (digits, true),
+            None => (dcs.strip_suffix('N').unwrap_or(dcs), false),
+        };
+        if digits.len() != 3 {
+            return Err(invalid());
+        }
+        let code = u16::from_str_radix(digits, 8).map_err(|_| invalid())?;
+        if !DCS_CODES.contains(&code) {
+            return Err(DspError::InvalidSubtoneParameter(format!("{:03o} is not a standard DCS code", code)));
+        }
+        Ok(Subtone::Dcs { code, inverted })
+    }
+}
+
+/// Settings for mixing a subtone under transmitted audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtoneParams {
+    pub subtone: Subtone,
+    pub sample_rate: u32,
+    /// Subtone share of full scale (0.0 to 0.5); the program audio gets the rest.
+    /// Around 0.1 gives the usual 500 Hz or so of deviation on a 5 kHz channel.
+    pub level: f32,
+}
+
+impl SubtoneParams {
+    pub fn new(subtone: Subtone, sample_rate: u32) -> Self {
+        Self { subtone, sample_rate, level: 0.1 }
+    }
+
+    pub fn with_level(mut self, level: f32) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), DspError> {
+        let invalid = |msg: String| Err(DspError::InvalidSubtoneParameter(msg));
+        if self.sample_rate < 2000 {
+            return invalid(format!("sample rate {} Hz is below 2000 Hz", self.sample_rate));
+        }
+        if !(0.0..=0.5).contains(&self.level) {
+            return invalid(format!("level {} is outside 0..0.5", self.level));
+        }
+        match self.subtone {
+            Subtone::Ctcss(hz) if !CTCSS_TONES.contains(&hz) => invalid(format!("{} Hz is not a standard CTCSS tone", hz)),
+            Subtone::Dcs { code, .. } if !DCS_CODES.contains(&code) => {
+                invalid(format!("{:03o} is not a standard DCS code", code))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_tables() {
+        assert!(CTCSS_TONES.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(DCS_CODES.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!CTCSS_TONES.contains(&150.0));
+    }
+
+    #[test]
+    fn test_dcs_codewords() {
+        // Code bits, then 100, then the check bits.
+        assert_eq!(dcs_codeword(0o023, false), 0x76_3813);
+        assert_eq!(dcs_codeword(0o023, false) & 0xFFF, 0o023 | 1 << 11);
+        assert_eq!(dcs_codeword(0o023, true), !0x76_3813 & DCS_WORD_MASK);
+
+        // Well-known aliases.
+        let d023 = Subtone::Dcs { code: 0o023, inverted: false };
+        assert!(d023.is_equivalent(&Subtone::Dcs { code: 0o340, inverted: false }));
+        assert!(d023.is_equivalent(&Subtone::Dcs { code: 0o766, inverted: false }));
+        assert!(d023.is_equivalent(&Subtone::Dcs { code: 0o047, inverted: true }));
+        assert!(!d023.is_equivalent(&Subtone::Dcs { code: 0o025, inverted: false }));
+        assert_eq!(Subtone::Dcs { code: 0o047, inverted: true }.canonical(), d023);
+        assert_eq!(d023.inverted().canonical(), Subtone::Dcs { code: 0o047, inverted: false });
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("88.5".parse::<Subtone>().unwrap(), Subtone::Ctcss(88.5));
+        assert_eq!("ctcss 100 Hz".parse::<Subtone>().unwrap(), Subtone::Ctcss(100.0));
+        assert_eq!("D023N".parse::<Subtone>().unwrap(), Subtone::Dcs { code: 0o023, inverted: false });
+        assert_eq!("DCS 754I".parse::<Subtone>().unwrap(), Subtone::Dcs { code: 0o754, inverted: true });
+        assert_eq!("265".parse::<Subtone>().unwrap(), Subtone::Dcs { code: 0o265, inverted: false });
+        assert_eq!(Subtone::Ctcss(67.0).to_string(), "CTCSS 67.0 Hz");
+        assert_eq!(Subtone::Dcs { code: 0o023, inverted: true }.to_string(), "DCS 023I");
+        for text in ["150.0", "88.4", "D024N", "D08", "DCS 0234", "", "tone"] {
+            assert_matches!(text.parse::<Subtone>(), Err(DspError::InvalidSubtoneParameter(_)), "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Input format conversion: any cpal sample type, any channel layout, any rate
+// → mono f32 at the rate the RX pipeline expects.
+
+use crate::HardwareError;
+use cpal::{FromSample, Sample, SampleFormat, SampleRate, SupportedStreamConfig, SupportedStreamConfigRange};
+use elfradio_dsp::LinearResampler;
+use std::str::FromStr;
+
+/// Which channel of a multi-channel input feeds the (mono) RX pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputChannel {
+    Left,
+    Right,
+    /// Average of all channels.
+    #[default]
+    Mix,
+}
+
+impl FromStr for InputChannel {
+    type Err = HardwareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "left" | "l" => Ok(InputChannel::Left),
+            "right" | "r" => Ok(InputChannel::Right),
+            "mix" | "mono" | "both" => Ok(InputChannel::Mix),
+            other => Err(HardwareError::GenericError(format!("Unknown input channel: {}", other))),
+        }
+    }
+}
+
+impl InputChannel {
+    /// Reduces one interleaved frame to a single sample.
+    pub fn select(&self, frame: &[f32]) -> f32 {
+        match (self, frame.len()) {
+            (_, 0) => 0.0,
+            (_, 1) => frame[0],
+            (InputChannel::Left, _) => frame[0],
+            (InputChannel::Right, _) => frame[1],
+            (InputChannel::Mix, n) => frame.iter().sum::<f32>() / n as f32,
+        }
+    }
+}
+
+/// How input audio is shaped before it reaches the RX pipeline.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputOptions {
+    /// Channel that feeds the mono pipeline.
+    pub channel: InputChannel,
+    /// Rate delivered to the pipeline. `None` keeps the device rate.
+    pub target_sample_rate: Option<u32>,
+}
+
+/// Converts interleaved device buffers into mono f32 at the target rate.
+pub struct InputPipeline {
+    channels: usize,
+    channel: InputChannel,
+    resampler: LinearResampler,
+}
+
+impl InputPipeline {
+    pub fn new(channels: u16, channel: InputChannel, device_rate: u32, target_rate: u32) -> Self {
+        Self {
+            channels: channels.max(1) as usize,
+            channel,
+            resampler: LinearResampler::new(device_rate, target_rate),
+        }
+    }
+
+    /// Converts one callback buffer of any supported sample type.
+    pub fn process<T>(&mut self, data: &[T]) -> Vec<f32>
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let mut frame = Vec::with_capacity(self.channels);
+        let mono: Vec<f32> = data
+            .chunks(self.channels)
+            .map(|raw| {
+                frame.clear();
+                frame.extend(raw.iter().map(|&s| f32::from_sample(s)));
+                self.channel.select(&frame)
+            })
+            .collect();
+        self.resampler.process(&mono)
+    }
+}
+
+/// Sample formats the input callback can convert, best first.
+fn format_rank(format: SampleFormat) -> Option<u8> {
+    match format {
+        SampleFormat::F32 => Some(4),
+        SampleFormat::I16 => Some(3),
+        SampleFormat::I32 => Some(2),
+        SampleFormat::U16 => Some(1),
+        _ => None,
+    }
+}
+
+/// Picks the best input configuration for `target_rate`.
+///
+/// Configurations that can run at `target_rate` natively win; among those the
+/// sample format (F32 > I16 > I32 > U16) and then fewer channels decide. If no
+/// range contains the rate, the closest supported rate is used and the caller
+/// resamples.
+pub fn choose_input_config(
+    ranges: impl IntoIterator<Item = SupportedStreamConfigRange>,
+    target_rate: u32,
+) -> Option<SupportedStreamConfig> {
+    let rate_distance = |range: &SupportedStreamConfigRange| {
+        let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+        if target_rate < min { min - target_rate } else { target_rate.saturating_sub(max) }
+    };
+
+    let best = ranges
+        .into_iter()
+        .filter_map(|range| format_rank(range.sample_format()).map(|rank| (range, rank)))
+        .min_by_key(|(range, rank)| (rate_distance(range), std::cmp::Reverse(*rank), range.channels()))?
+        .0;
+
+    let rate = target_rate.clamp(best.min_sample_rate().0, best.max_sample_rate().0);
+    Some(best.with_sample_rate(SampleRate(rate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::SupportedBufferSize;
+
+    fn range(channels: u16, min: u32, max: u32, format: SampleFormat) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(channels, SampleRate(min), SampleRate(max), SupportedBufferSize::Unknown, format)
+    }
+
+    #[test]
+    fn test_choose_prefers_native_rate_then_format_then_mono() {
+        let ranges = vec![
+            range(2, 48000, 48000, SampleFormat::F32),
+            range(2, 8000, 48000, SampleFormat::I16),
+            range(1, 8000, 48000, SampleFormat::I16),
+            range(1, 8000, 48000, SampleFormat::U8),
+        ];
+        let chosen = choose_input_config(ranges, 16000).unwrap();
+        assert_eq!(chosen.sample_format(), SampleFormat::I16);
+        assert_eq!(chosen.channels(), 1);
+        assert_eq!(chosen.sample_rate().0, 16000);
+    }
+
+    #[test]
+    fn test_choose_falls_back_to_closest_rate() {
+        let ranges = vec![range(2, 44100, 44100, SampleFormat::I16), range(2, 96000, 96000, SampleFormat::F32)];
+        let chosen = choose_input_config(ranges, 16000).unwrap();
+        assert_eq!(chosen.sample_rate().0, 44100);
+        assert!(choose_input_config(vec![range(1, 8000, 8000, SampleFormat::U8)], 8000).is_none());
+    }
+
+    #[test]
+    fn test_pipeline_converts_formats_and_channels() {
+        // Stereo I16: left full scale positive-ish, right silent.
+        let stereo_i16: Vec<i16> = (0..8).flat_map(|_| [i16::MAX, 0]).collect();
+        let mut left = InputPipeline::new(2, InputChannel::Left, 16000, 16000);
+        assert!(left.process(&stereo_i16).iter().all(|&s| (s - 1.0).abs() < 1e-3));
+        let mut right = InputPipeline::new(2, InputChannel::Right, 16000, 16000);
+        assert!(right.process(&stereo_i16).iter().all(|&s| s == 0.0));
+        let mut mix = InputPipeline::new(2, InputChannel::Mix, 16000, 16000);
+        assert!(mix.process(&stereo_i16).iter().all(|&s| (s - 0.5).abs() < 1e-3));
+
+        // U16 midpoint is silence; I32 keeps its scale.
+        let mut mono = InputPipeline::new(1, InputChannel::Mix, 16000, 16000);
+        assert!(mono.process(&[32768u16; 4]).iter().all(|&s| s.abs() < 1e-4));
+        assert!(mono.process(&[i32::MIN / 2; 4]).iter().all(|&s| (s + 0.5).abs() < 1e-4));
+
+        // Right on a mono device falls back to the only channel.
+        assert_eq!(InputChannel::Right.select(&[0.3]), 0.3);
+        assert_eq!("L".parse::<InputChannel>().unwrap(), InputChannel::Left);
+    }
+
+    #[test]
+    fn test_pipeline_resamples() {
+        let mut pipeline = InputPipeline::new(1, InputChannel::Mix, 48000, 16000);
+        let out: usize = (0..10).map(|_| pipeline.process(&[0.0f32; 480]).len()).sum();
+        assert!((out as i64 - 1600).abs() <= 1, "got {}", out);
+    }
+}
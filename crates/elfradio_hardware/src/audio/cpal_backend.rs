@@ -0,0 +1,318 @@
+// cpal (system sound card) audio backend.
+
+use super::convert::{choose_input_config, InputOptions, InputPipeline};
+use super::output::{AudioOutputSender, OutputFormat, OutputItem};
+use super::{calculate_rms, AudioBackend, StreamControl, StreamFault};
+use crate::HardwareError;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, InputCallbackInfo, SampleFormat, SizedSample, StreamError, SupportedStreamConfig};
+use elfradio_types::AudioMessage;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, trace, warn};
+
+/// Audio backend for real sound card devices through `cpal::default_host()`.
+pub struct CpalBackend {
+    input_device: Option<String>,
+    output_device: Option<String>,
+    input_options: InputOptions,
+}
+
+impl CpalBackend {
+    /// Uses the named devices, or the system defaults when `None`.
+    pub fn new(input_device: Option<String>, output_device: Option<String>) -> Self {
+        Self { input_device, output_device, input_options: InputOptions::default() }
+    }
+
+    /// Sets the channel selection and target rate for input audio.
+    pub fn with_input_options(mut self, input_options: InputOptions) -> Self {
+        self.input_options = input_options;
+        self
+    }
+}
+
+/// Negotiates an input configuration for `target_rate`, falling back to the device default.
+pub fn negotiate_input_config(device: &cpal::Device, target_rate: Option<u32>) -> Result<SupportedStreamConfig, HardwareError> {
+    let Some(target_rate) = target_rate else {
+        return Ok(device.default_input_config()?);
+    };
+    match choose_input_config(device.supported_input_configs()?, target_rate) {
+        Some(config) => Ok(config),
+        None => {
+            warn!("No convertible input configuration found; using the device default.");
+            Ok(device.default_input_config()?)
+        }
+    }
+}
+
+fn find_input_device(host: &cpal::Host, device_name: Option<&str>) -> Result<cpal::Device, HardwareError> {
+    match device_name {
+        Some(name) => {
+            trace!("Searching for specific input device: {}", name);
+            host.input_devices()?
+                .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+                .ok_or_else(|| HardwareError::DeviceNotFound(name.to_string()))
+        }
+        None => {
+            trace!("Using default input device.");
+            host.default_input_device()
+                .ok_or_else(|| HardwareError::DeviceNotFound("Default input device".to_string()))
+        }
+    }
+}
+
+fn find_output_device(host: &cpal::Host, device_name: Option<&str>) -> Result<cpal::Device, HardwareError> {
+    match device_name {
+        Some(name) => host
+            .output_devices()?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| HardwareError::DeviceNotFound(name.to_string())),
+        None => host
+            .default_output_device()
+            .ok_or(HardwareError::DefaultDeviceError("No default output device available".to_string())),
+    }
+}
+
+impl AudioBackend for CpalBackend {
+    fn name(&self) -> &str {
+        "cpal"
+    }
+
+    fn start_input(&self, data_tx: mpsc::UnboundedSender<AudioMessage>) -> Result<StreamControl, HardwareError> {
+        let host = cpal::default_host();
+        let device = find_input_device(&host, self.input_device.as_deref())?;
+        let config = negotiate_input_config(&device, self.input_options.target_sample_rate)?;
+        start_audio_input_stream(self.input_device.as_deref(), &config, &self.input_options, data_tx)
+    }
+
+    fn start_output(&self) -> Result<(StreamControl, AudioOutputSender), HardwareError> {
+        let host = cpal::default_host();
+        let config = find_output_device(&host, self.output_device.as_deref())?.default_output_config()?;
+        start_audio_output_stream(self.output_device.as_deref(), &config)
+    }
+
+    fn input_present(&self) -> bool {
+        find_input_device(&cpal::default_host(), self.input_device.as_deref()).is_ok()
+    }
+
+    fn output_present(&self) -> bool {
+        find_output_device(&cpal::default_host(), self.output_device.as_deref()).is_ok()
+    }
+}
+
+/// Starts capturing audio from the specified input device.
+///
+/// Sends audio data chunks and RMS values over the provided MPSC channel. F32,
+/// I16, U16 and I32 devices are accepted; every buffer is reduced to mono with
+/// `options.channel` and resampled to `options.target_sample_rate` if the
+/// device runs at a different rate.
+///
+/// # Arguments
+/// * `device_name` - Optional name of the specific input device to use. If `None`, the default input device is used.
+/// * `config` - The desired stream configuration (sample rate, channels, format), e.g. from `negotiate_input_config`.
+/// * `options` - Channel selection and target sample rate.
+/// * `data_tx` - An unbounded MPSC sender to send `AudioMessage`s (Data chunks, RMS) to.
+///
+/// # Returns
+/// A `StreamControl` struct which, when dropped, will stop the audio stream.
+pub fn start_audio_input_stream(
+    device_name: Option<&str>,
+    config: &SupportedStreamConfig,
+    options: &InputOptions,
+    data_tx: mpsc::UnboundedSender<AudioMessage>,
+) -> Result<StreamControl, HardwareError> {
+    debug!(?device_name, ?config, ?options, "Starting audio input stream...");
+
+    let host = cpal::default_host();
+
+    // Find the device
+    let device = find_input_device(&host, device_name)?;
+    info!("Using audio input device: {}", device.name()?);
+
+    let device_rate = config.sample_rate().0;
+    let target_rate = options.target_sample_rate.unwrap_or(device_rate);
+    if device_rate != target_rate {
+        info!(device_rate, target_rate, "Input device can't run at the target rate natively; resampling.");
+    }
+    let pipeline = InputPipeline::new(config.channels(), options.channel, device_rate, target_rate);
+
+    // --- Build Input Stream ---
+    // Define the error callback
+    let fault = StreamFault::default();
+    let err_fn = {
+        let data_tx = data_tx.clone(); // Clone sender for error callback
+        let fault = fault.clone();
+        move |err: StreamError| {
+            error!("An error occurred on audio input stream: {}", err);
+            fault.report(err.to_string());
+            // Send error message over the channel
+            let _ = data_tx.send(AudioMessage::Error(err.to_string()));
+        }
+    };
+
+    // Build the stream
+    debug!("Building input stream with config: {:?}", config);
+    let stream_config = config.config();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_converting_input_stream::<f32>(&device, &stream_config, pipeline, data_tx, err_fn)?,
+        SampleFormat::I16 => build_converting_input_stream::<i16>(&device, &stream_config, pipeline, data_tx, err_fn)?,
+        SampleFormat::U16 => build_converting_input_stream::<u16>(&device, &stream_config, pipeline, data_tx, err_fn)?,
+        SampleFormat::I32 => build_converting_input_stream::<i32>(&device, &stream_config, pipeline, data_tx, err_fn)?,
+        other => {
+            error!("Unsupported input sample format: {:?}", other);
+            return Err(HardwareError::UnsupportedSampleFormat);
+        }
+    };
+    debug!("Audio input stream built successfully.");
+
+    // --- Play Stream ---
+    stream.play()?;
+    info!("Audio input stream started successfully.");
+
+    // --- Return Control ---
+    Ok(StreamControl::from_cpal(stream, fault))
+}
+
+/// Builds an input stream for sample type `T`, converting each buffer through `pipeline`.
+fn build_converting_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut pipeline: InputPipeline,
+    data_tx: mpsc::UnboundedSender<AudioMessage>,
+    err_fn: impl FnMut(StreamError) + Send + 'static,
+) -> Result<cpal::Stream, HardwareError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let data_callback = move |data: &[T], _: &InputCallbackInfo| {
+        trace!("Received audio data chunk, len: {}", data.len());
+        let samples = pipeline.process(data);
+        if samples.is_empty() {
+            return;
+        }
+        // Send RMS then the converted chunk (ignore errors if the receiver dropped)
+        let _ = data_tx.send(AudioMessage::Rms(calculate_rms(&samples)));
+        let _ = data_tx.send(AudioMessage::Data(samples));
+    };
+    Ok(device.build_input_stream(config, data_callback, err_fn, None)?)
+}
+
+// --- 新增: 启动音频输出流 ---
+/// Starts the audio output stream using the specified device and configuration.
+///
+/// Returns a `StreamControl` handle and an `AudioOutputSender` that carries the
+/// device's real rate and channel count. Items sent through it are converted to
+/// that format, and each send's `Playback` resolves once its last sample has
+/// left the output callback. F32, I16, U16 and I32 devices are accepted.
+pub fn start_audio_output_stream(
+    device_name: Option<&str>,
+    config: &cpal::SupportedStreamConfig,
+) -> Result<(StreamControl, AudioOutputSender), HardwareError> {
+    info!(
+        "Attempting to start audio output stream with device: {:?}, config: {:?}",
+        device_name,
+        config.config() // Log the actual StreamConfig part
+    );
+
+    let host = cpal::default_host();
+
+    // Find the output device
+    let device = find_output_device(&host, device_name)?;
+
+    info!("Using output device: {}", device.name()?);
+
+    // --- Create channel for sending audio data to the callback ---
+    let format = OutputFormat { sample_rate: config.sample_rate().0, channels: config.channels() };
+    let (data_tx, data_rx) = AudioOutputSender::channel(format);
+
+    // --- Define the error callback ---
+    // Reported through the StreamControl so a supervisor can reopen the device.
+    let fault = StreamFault::default();
+    let err_fn = {
+        let fault = fault.clone();
+        move |err: StreamError| {
+            error!("An error occurred on audio output stream: {}", err);
+            fault.report(err.to_string());
+        }
+    };
+
+    // --- Build the output stream in the device's native sample format ---
+    let stream_config = config.config();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_queued_output_stream::<f32>(&device, &stream_config, data_rx, err_fn)?,
+        SampleFormat::I16 => build_queued_output_stream::<i16>(&device, &stream_config, data_rx, err_fn)?,
+        SampleFormat::U16 => build_queued_output_stream::<u16>(&device, &stream_config, data_rx, err_fn)?,
+        SampleFormat::I32 => build_queued_output_stream::<i32>(&device, &stream_config, data_rx, err_fn)?,
+        other => {
+            error!("Unsupported output sample format: {:?}", other);
+            return Err(HardwareError::UnsupportedSampleFormat);
+        }
+    };
+
+    // Play the stream
+    stream.play()?; // Uses PlayStreamError via ? and From trait
+
+    info!(sample_rate = format.sample_rate, channels = format.channels, "Audio output stream started successfully.");
+
+    Ok((StreamControl::from_cpal(stream, fault), data_tx))
+}
+
+/// Builds an output stream for sample type `T` that plays queued items back to back.
+fn build_queued_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut data_rx: mpsc::UnboundedReceiver<OutputItem>,
+    err_fn: impl FnMut(StreamError) + Send + 'static,
+) -> Result<cpal::Stream, HardwareError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut current: Option<OutputItem> = None; // Item being played
+    let mut item_pos: usize = 0; // Current read position within the item
+    let mut disconnected = false;
+
+    let output_callback = move |output: &mut [T], _: &cpal::OutputCallbackInfo| {
+        let mut output_pos = 0; // Position within the cpal `output` buffer
+        while output_pos < output.len() {
+            // Fetch the next item *non-blockingly* once the current one is done
+            if current.is_none() {
+                match data_rx.try_recv() {
+                    Ok(item) => {
+                        current = Some(item);
+                        item_pos = 0;
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => {
+                        if !disconnected {
+                            error!("Audio output channel disconnected!");
+                            disconnected = true;
+                        }
+                        break;
+                    }
+                }
+            }
+            let Some(item) = current.as_ref() else { break };
+
+            let samples_to_write = (item.samples.len() - item_pos).min(output.len() - output_pos);
+            let source = &item.samples[item_pos..item_pos + samples_to_write];
+            for (out, &sample) in output[output_pos..output_pos + samples_to_write].iter_mut().zip(source) {
+                *out = T::from_sample(sample);
+            }
+            item_pos += samples_to_write;
+            output_pos += samples_to_write;
+
+            if item_pos >= item.samples.len()
+                && let Some(finished) = current.take()
+            {
+                finished.complete();
+            }
+        }
+        // Nothing (more) queued: fill the rest of the buffer with silence
+        if output_pos < output.len() {
+            trace!("Audio output idle - writing silence for {} samples.", output.len() - output_pos);
+            output[output_pos..].fill(T::EQUILIBRIUM);
+        }
+    };
+
+    Ok(device.build_output_stream(config, output_callback, err_fn, None)?)
+}
@@ -0,0 +1,267 @@
+// WAV file audio backend: replay a recording as RX audio, capture TX audio to a file.
+
+use super::convert::InputChannel;
+use super::{drain_output, send_input_chunk, AudioBackend, FaultInjector, StreamControl, CHUNK_DURATION};
+use super::output::{AudioOutputSender, OutputFormat};
+use crate::HardwareError;
+use elfradio_dsp::resample;
+use elfradio_types::AudioMessage;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// Backend that reads input from and writes output to WAV files.
+///
+/// Input is reduced to mono (mix by default), resampled to `sample_rate` and
+/// delivered in 20 ms chunks, paced in real time by default so downstream
+/// timing behaves like a live receiver. Output is written as mono 32-bit float
+/// WAV at `sample_rate`.
+pub struct WavFileBackend {
+    input_path: Option<PathBuf>,
+    output_path: Option<PathBuf>,
+    sample_rate: u32,
+    input_channel: InputChannel,
+    realtime: bool,
+    faults: Option<FaultInjector>,
+}
+
+impl WavFileBackend {
+    /// Either path may be `None`, in which case that direction is unavailable.
+    pub fn new(input_path: Option<PathBuf>, output_path: Option<PathBuf>, sample_rate: u32) -> Self {
+        Self { input_path, output_path, sample_rate, input_channel: InputChannel::default(), realtime: true, faults: None }
+    }
+
+    /// Selects which channel of a multi-channel input file is replayed.
+    pub fn with_input_channel(mut self, input_channel: InputChannel) -> Self {
+        self.input_channel = input_channel;
+        self
+    }
+
+    /// Lets `injector` simulate the device being unplugged (for testing recovery).
+    pub fn with_fault_injector(mut self, injector: FaultInjector) -> Self {
+        self.faults = Some(injector);
+        self
+    }
+
+    fn present(&self) -> bool {
+        !self.faults.as_ref().is_some_and(FaultInjector::is_unplugged)
+    }
+
+    /// Disables real-time pacing of input chunks (replay as fast as the consumer reads).
+    pub fn with_realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+}
+
+/// Reads a WAV file as mono f32 samples, reducing frames with `channel`.
+fn read_wav_mono(path: &Path, channel: InputChannel) -> Result<(Vec<f32>, u32), HardwareError> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| wav_error(path, e))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>().map(|s| s.map(|v| v as f32 * scale)).collect::<Result<_, _>>()
+        }
+    }
+    .map_err(|e| wav_error(path, e))?;
+
+    let channels = spec.channels.max(1) as usize;
+    let mono = samples.chunks(channels).map(|frame| channel.select(frame)).collect();
+    Ok((mono, spec.sample_rate))
+}
+
+fn wav_error(path: &Path, e: hound::Error) -> HardwareError {
+    HardwareError::GenericError(format!("WAV file error ({}): {}", path.display(), e))
+}
+
+impl AudioBackend for WavFileBackend {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn start_input(&self, data_tx: mpsc::UnboundedSender<AudioMessage>) -> Result<StreamControl, HardwareError> {
+        let path = self
+            .input_path
+            .clone()
+            .ok_or_else(|| HardwareError::DeviceNotFound("No audio input file configured".to_string()))?;
+        FaultInjector::check(self.faults.as_ref(), "WAV input")?;
+        let (mut samples, file_rate) = read_wav_mono(&path, self.input_channel)?;
+        if file_rate != self.sample_rate {
+            warn!(file_rate, expected = self.sample_rate, "Input WAV sample rate differs from the configured rate; resampling");
+            samples = resample(&samples, file_rate, self.sample_rate);
+        }
+        info!("Replaying {} ({} samples at {} Hz)", path.display(), samples.len(), self.sample_rate);
+
+        let chunk_len = ((self.sample_rate as f32 * CHUNK_DURATION.as_secs_f32()).round() as usize).max(1);
+        let realtime = self.realtime;
+        StreamControl::spawn_worker("wav-audio-in", self.faults.clone(), move |flags| {
+            let mut chunks = samples.chunks(chunk_len);
+            let mut next_chunk_at = Instant::now();
+            while !flags.should_stop() {
+                if !flags.is_paused() {
+                    let Some(chunk) = chunks.next() else {
+                        info!("Finished replaying {}", path.display());
+                        break;
+                    };
+                    if !send_input_chunk(&data_tx, chunk.to_vec()) {
+                        break;
+                    }
+                }
+                if realtime || flags.is_paused() {
+                    next_chunk_at += CHUNK_DURATION;
+                    std::thread::sleep(next_chunk_at.saturating_duration_since(Instant::now()));
+                }
+            }
+        })
+    }
+
+    fn start_output(&self) -> Result<(StreamControl, AudioOutputSender), HardwareError> {
+        let path = self
+            .output_path
+            .clone()
+            .ok_or_else(|| HardwareError::DeviceNotFound("No audio output file configured".to_string()))?;
+        FaultInjector::check(self.faults.as_ref(), "WAV output")?;
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).map_err(|e| wav_error(&path, e))?;
+        info!("Capturing TX audio to {}", path.display());
+
+        let (data_tx, mut data_rx) = AudioOutputSender::channel(OutputFormat { sample_rate: self.sample_rate, channels: 1 });
+        let control = StreamControl::spawn_worker("wav-audio-out", self.faults.clone(), move |flags| {
+            let mut write_failed = false;
+            drain_output(&flags, &mut data_rx, |chunk| {
+                if write_failed {
+                    return;
+                }
+                if let Err(e) = chunk.iter().try_for_each(|&s| writer.write_sample(s)) {
+                    error!("Failed to write TX audio to {}: {}", path.display(), e);
+                    write_failed = true;
+                }
+            });
+            match writer.finalize() {
+                Ok(()) => debug!("Finalized {}", path.display()),
+                Err(e) => error!("Failed to finalize {}: {}", path.display(), e),
+            }
+        })?;
+        Ok((control, data_tx))
+    }
+
+    fn has_input(&self) -> bool {
+        self.input_path.is_some()
+    }
+
+    fn has_output(&self) -> bool {
+        self.output_path.is_some()
+    }
+
+    fn input_present(&self) -> bool {
+        self.present()
+    }
+
+    fn output_present(&self) -> bool {
+        self.present()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_test_wav(path: &PathBuf, samples: &[i16], channels: u16, sample_rate: u32) {
+        let spec = hound::WavSpec { channels, sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_replay_delivers_all_samples_as_mono() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("qso.wav");
+        // Stereo: left = 0.5, right = 0.0 → mono 0.25.
+        let frames = 8000 / 10;
+        let stereo: Vec<i16> = (0..frames).flat_map(|_| [16384i16, 0]).collect();
+        write_test_wav(&input, &stereo, 2, 8000);
+
+        let backend = WavFileBackend::new(Some(input), None, 8000).with_realtime(false);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let control = backend.start_input(tx).unwrap();
+
+        let mut received = Vec::new();
+        let mut rms_count = 0;
+        while let Some(message) = rx.blocking_recv() {
+            match message {
+                AudioMessage::Data(chunk) => received.extend(chunk),
+                AudioMessage::Rms(_) => rms_count += 1,
+                AudioMessage::Error(e) => panic!("Unexpected error: {}", e),
+            }
+        }
+        drop(control);
+
+        assert_eq!(received.len(), frames);
+        assert_eq!(rms_count, frames / 160, "One RMS message per 20 ms chunk");
+        assert!(received.iter().all(|&s| (s - 0.25).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_replay_selects_channel_and_resamples() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("stereo48k.wav");
+        let stereo: Vec<i16> = (0..4800).flat_map(|_| [16384i16, -8192]).collect();
+        write_test_wav(&input, &stereo, 2, 48000);
+
+        let backend = WavFileBackend::new(Some(input), None, 16000)
+            .with_input_channel(InputChannel::Right)
+            .with_realtime(false);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let control = backend.start_input(tx).unwrap();
+        let mut received = Vec::new();
+        while let Some(message) = rx.blocking_recv() {
+            if let AudioMessage::Data(chunk) = message {
+                received.extend(chunk);
+            }
+        }
+        drop(control);
+
+        assert!((received.len() as i64 - 1600).abs() <= 1, "got {}", received.len());
+        // Past the anti-alias filter's settling time the right channel comes through unchanged.
+        assert!(received[400..].iter().all(|&s| (s + 0.25).abs() < 1e-2));
+    }
+
+    #[test]
+    fn test_capture_writes_everything_sent() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("tx.wav");
+        let backend = WavFileBackend::new(None, Some(output.clone()), 16000);
+
+        let (control, sender) = backend.start_output().unwrap();
+        let _ = sender.send(&[0.1; 500], 16000).unwrap();
+        // 8 kHz input is resampled to the file rate; completion means it has been written.
+        sender.send(&[-0.2; 150], 8000).unwrap().blocking_finished().unwrap();
+        drop(control); // Finalizes the file.
+
+        let mut reader = hound::WavReader::open(&output).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        assert!((samples.len() as i64 - 800).abs() <= 2, "got {}", samples.len());
+        assert_eq!(samples[0], 0.1);
+        assert!((samples[samples.len() - 10] + 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_missing_paths_are_errors() {
+        let backend = WavFileBackend::new(None, None, 16000);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        assert!(backend.start_input(tx).is_err());
+        assert!(backend.start_output().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+// Audio I/O backends.
+//
+// Every backend produces the same `AudioMessage` stream on input and accepts
+// audio through the same `AudioOutputSender` channel on output, so the RX/TX
+// pipeline doesn't care whether it is talking to a sound card (cpal), WAV
+// files (replay/capture) or nothing at all (null, for headless operation).
+
+mod convert;
+mod cpal_backend;
+mod file;
+mod null;
+mod output;
+mod supervisor;
+
+pub use convert::{choose_input_config, InputChannel, InputOptions, InputPipeline};
+pub use cpal_backend::{negotiate_input_config, start_audio_input_stream, start_audio_output_stream, CpalBackend};
+pub use file::WavFileBackend;
+pub use null::NullBackend;
+pub use output::{upmix, AudioOutputSender, OutputFormat, Playback};
+pub use elfradio_dsp::{resample, LinearResampler};
+pub use supervisor::{AudioSupervisor, SupervisorTiming};
+
+use crate::HardwareError;
+use cpal::traits::StreamTrait;
+use elfradio_types::{AudioMessage, ConnectionStatus, HardwareConfig, WebSocketMessage};
+use output::OutputItem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// Length of the chunks emitted by the non-cpal input backends.
+pub(crate) const CHUNK_DURATION: Duration = Duration::from_millis(20);
+
+/// A source of RX audio and a sink for TX audio.
+pub trait AudioBackend: Send + Sync {
+    /// Short backend name for logs ("cpal", "file", "null", "rtl_tcp").
+    fn name(&self) -> &str;
+
+    /// Starts delivering input audio to `data_tx` as `AudioMessage::Rms` + `AudioMessage::Data` pairs.
+    fn start_input(&self, data_tx: mpsc::UnboundedSender<AudioMessage>) -> Result<StreamControl, HardwareError>;
+
+    /// Starts the output sink and returns the sender that feeds it.
+    ///
+    /// The sender reports the sink's real rate and channel count.
+    fn start_output(&self) -> Result<(StreamControl, AudioOutputSender), HardwareError>;
+
+    /// Whether this backend is configured to capture at all.
+    fn has_input(&self) -> bool {
+        true
+    }
+
+    /// Whether this backend is configured to play at all.
+    fn has_output(&self) -> bool {
+        true
+    }
+
+    /// Whether the input device is currently present (e.g. still plugged in).
+    fn input_present(&self) -> bool {
+        true
+    }
+
+    /// Whether the output device is currently present.
+    fn output_present(&self) -> bool {
+        true
+    }
+
+    /// The status message the supervisor publishes for this backend.
+    fn status_message(&self, status: ConnectionStatus) -> WebSocketMessage {
+        WebSocketMessage::AudioStatusUpdate(status)
+    }
+}
+
+/// Creates the backend selected by `hardware.audio_backend` ("cpal" when unset).
+pub fn open_audio_backend(config: &HardwareConfig) -> Result<Box<dyn AudioBackend>, HardwareError> {
+    let kind = config.audio_backend.as_deref().unwrap_or("cpal").trim().to_lowercase();
+    let input_options = InputOptions {
+        channel: config.input_channel.as_deref().map(str::parse).transpose()?.unwrap_or_default(),
+        target_sample_rate: Some(config.input_sample_rate),
+    };
+    let backend: Box<dyn AudioBackend> = match kind.as_str() {
+        "cpal" => Box::new(
+            CpalBackend::new(config.audio_input_device.clone(), config.audio_output_device.clone())
+                .with_input_options(input_options),
+        ),
+        "file" | "wav" => Box::new(
+            WavFileBackend::new(
+                config.audio_input_file.clone().map(Into::into),
+                config.audio_output_file.clone().map(Into::into),
+                config.input_sample_rate,
+            )
+            .with_input_channel(input_options.channel),
+        ),
+        "null" | "none" => Box::new(NullBackend::new(config.input_sample_rate)),
+        "rtl_tcp" | "sdr" => {
+            let args = config.sdr_device_args.as_deref().ok_or_else(|| {
+                HardwareError::SdrError("audio_backend = \"rtl_tcp\" needs hardware.sdr_device_args".to_string())
+            })?;
+            Box::new(crate::sdr::RtlTcpBackend::new(args.parse()?, config.input_sample_rate))
+        }
+        other => return Err(HardwareError::GenericError(format!("Unknown audio backend: {}", other))),
+    };
+    info!("Using '{}' audio backend", backend.name());
+    Ok(backend)
+}
+
+/// Simulates a device dropping out on the file and null backends.
+///
+/// While unplugged, running streams fail with a fault and new streams can't be
+/// started, just like a USB sound card that has been pulled.
+#[derive(Clone, Default)]
+pub struct FaultInjector {
+    unplugged: Arc<AtomicBool>,
+}
+
+impl FaultInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the device disappear.
+    pub fn unplug(&self) {
+        self.unplugged.store(true, Ordering::Release);
+    }
+
+    /// Brings the device back.
+    pub fn replug(&self) {
+        self.unplugged.store(false, Ordering::Release);
+    }
+
+    pub fn is_unplugged(&self) -> bool {
+        self.unplugged.load(Ordering::Acquire)
+    }
+
+    /// Fails a stream start while unplugged.
+    pub(crate) fn check(injector: Option<&FaultInjector>, what: &str) -> Result<(), HardwareError> {
+        match injector {
+            Some(injector) if injector.is_unplugged() => Err(HardwareError::DeviceNotFound(format!("{} (unplugged)", what))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// First error reported by a running stream.
+#[derive(Clone, Default)]
+pub(crate) struct StreamFault(Arc<Mutex<Option<String>>>);
+
+impl StreamFault {
+    /// Records `message` unless an earlier fault is already recorded.
+    pub(crate) fn report(&self, message: impl Into<String>) {
+        let mut fault = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if fault.is_none() {
+            *fault = Some(message.into());
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Flags shared between a `StreamControl` and its worker thread.
+#[derive(Clone, Default)]
+pub(crate) struct WorkerFlags {
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    fault: StreamFault,
+    injector: Option<FaultInjector>,
+}
+
+impl WorkerFlags {
+    /// `true` once the stream is dropped, or the injected device is unplugged (which records a fault).
+    pub(crate) fn should_stop(&self) -> bool {
+        if let Some(injector) = &self.injector
+            && injector.is_unplugged()
+        {
+            self.fault.report("Audio device unplugged");
+            return true;
+        }
+        self.stop.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Records a fault for the supervisor, e.g. when the worker's source goes away.
+    pub(crate) fn fail(&self, message: impl Into<String>) {
+        self.fault.report(message);
+    }
+}
+
+enum StreamInner {
+    Cpal(cpal::Stream),
+    Worker { flags: WorkerFlags, thread: Option<JoinHandle<()>> },
+}
+
+/// Provides control over a running audio stream.
+/// Dropping this struct stops the stream (and, for worker-backed streams, joins the worker).
+pub struct StreamControl {
+    inner: StreamInner,
+    fault: StreamFault,
+}
+
+impl StreamControl {
+    /// Wraps a cpal stream whose error callback reports into `fault`.
+    pub(crate) fn from_cpal(stream: cpal::Stream, fault: StreamFault) -> Self {
+        Self { inner: StreamInner::Cpal(stream), fault }
+    }
+
+    /// Runs `body` on a named worker thread that should return once `should_stop()` is set.
+    ///
+    /// With an `injector`, `should_stop()` also fires (and records a fault) when it unplugs.
+    pub(crate) fn spawn_worker(
+        name: &str,
+        injector: Option<FaultInjector>,
+        body: impl FnOnce(WorkerFlags) + Send + 'static,
+    ) -> Result<Self, HardwareError> {
+        let flags = WorkerFlags { injector, ..WorkerFlags::default() };
+        let worker_flags = flags.clone();
+        let fault = flags.fault.clone();
+        let thread = std::thread::Builder::new().name(name.to_string()).spawn(move || body(worker_flags))?;
+        Ok(Self { inner: StreamInner::Worker { flags, thread: Some(thread) }, fault })
+    }
+
+    /// The first error the stream reported, if any. A faulted stream should be dropped and reopened.
+    pub fn fault(&self) -> Option<String> {
+        self.fault.get()
+    }
+
+    /// Explicitly pause the audio stream.
+    pub fn pause(&self) -> Result<(), HardwareError> {
+        match &self.inner {
+            StreamInner::Cpal(stream) => stream.pause().map_err(HardwareError::PauseStreamError),
+            StreamInner::Worker { flags, .. } => {
+                flags.paused.store(true, Ordering::Release);
+                Ok(())
+            }
+        }
+    }
+
+    /// Explicitly resume the audio stream.
+    pub fn play(&self) -> Result<(), HardwareError> {
+        match &self.inner {
+            StreamInner::Cpal(stream) => stream.play().map_err(HardwareError::PlayStreamError),
+            StreamInner::Worker { flags, .. } => {
+                flags.paused.store(false, Ordering::Release);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for StreamControl {
+    fn drop(&mut self) {
+        // cpal streams stop on drop by themselves.
+        if let StreamInner::Worker { flags, thread } = &mut self.inner {
+            flags.stop.store(true, Ordering::Release);
+            if let Some(handle) = thread.take()
+                && handle.join().is_err()
+            {
+                error!("Audio worker thread panicked");
+            }
+        }
+    }
+}
+
+/// Calculates the Root Mean Square (RMS) of a slice of f32 audio samples.
+pub(crate) fn calculate_rms(data: &[f32]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = data.iter().map(|&sample| sample * sample).sum();
+    let mean_sq = sum_sq / (data.len() as f32);
+    mean_sq.sqrt()
+}
+
+/// Sends one chunk the same way the cpal input callback does. Returns `false` once the receiver is gone.
+pub(crate) fn send_input_chunk(data_tx: &mpsc::UnboundedSender<AudioMessage>, chunk: Vec<f32>) -> bool {
+    data_tx.send(AudioMessage::Rms(calculate_rms(&chunk))).is_ok() && data_tx.send(AudioMessage::Data(chunk)).is_ok()
+}
+
+/// Drains an output channel on a worker thread, handing each item to `sink`
+/// and completing it afterwards.
+///
+/// Returns when the stream is stopped (after draining what is queued) or all senders are gone.
+pub(crate) fn drain_output(
+    flags: &WorkerFlags,
+    data_rx: &mut mpsc::UnboundedReceiver<OutputItem>,
+    mut sink: impl FnMut(&[f32]),
+) {
+    loop {
+        if flags.is_paused() && !flags.should_stop() {
+            std::thread::sleep(Duration::from_millis(5));
+            continue;
+        }
+        match data_rx.try_recv() {
+            Ok(item) => {
+                sink(&item.samples);
+                item.complete();
+            }
+            Err(mpsc::error::TryRecvError::Empty) => {
+                if flags.should_stop() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Err(mpsc::error::TryRecvError::Disconnected) => break,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Null audio backend: silent input, discarded output.
+
+use super::{drain_output, send_input_chunk, AudioBackend, FaultInjector, StreamControl, CHUNK_DURATION};
+use super::output::{AudioOutputSender, OutputFormat};
+use crate::HardwareError;
+use elfradio_types::AudioMessage;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tracing::{debug, trace};
+
+/// Backend for machines without audio hardware.
+///
+/// Input delivers real-time paced silence so downstream consumers see a live
+/// stream; output accepts and discards everything.
+pub struct NullBackend {
+    sample_rate: u32,
+    faults: Option<FaultInjector>,
+}
+
+impl NullBackend {
+    /// `sample_rate` sets the size of the silent input chunks.
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate: sample_rate.max(1), faults: None }
+    }
+
+    /// Lets `injector` simulate the device being unplugged (for testing recovery).
+    pub fn with_fault_injector(mut self, injector: FaultInjector) -> Self {
+        self.faults = Some(injector);
+        self
+    }
+
+    fn present(&self) -> bool {
+        !self.faults.as_ref().is_some_and(FaultInjector::is_unplugged)
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn name(&self) -> &str {
+        "null"
+    }
+
+    fn start_input(&self, data_tx: mpsc::UnboundedSender<AudioMessage>) -> Result<StreamControl, HardwareError> {
+        FaultInjector::check(self.faults.as_ref(), "null input")?;
+        let chunk_len = (self.sample_rate as f32 * CHUNK_DURATION.as_secs_f32()).round() as usize;
+        debug!(chunk_len, "Starting null audio input");
+        StreamControl::spawn_worker("null-audio-in", self.faults.clone(), move |flags| {
+            let mut next_chunk_at = Instant::now();
+            while !flags.should_stop() {
+                if !flags.is_paused() && !send_input_chunk(&data_tx, vec![0.0; chunk_len]) {
+                    break;
+                }
+                next_chunk_at += CHUNK_DURATION;
+                std::thread::sleep(next_chunk_at.saturating_duration_since(Instant::now()));
+            }
+        })
+    }
+
+    fn start_output(&self) -> Result<(StreamControl, AudioOutputSender), HardwareError> {
+        FaultInjector::check(self.faults.as_ref(), "null output")?;
+        let (data_tx, mut data_rx) = AudioOutputSender::channel(OutputFormat { sample_rate: self.sample_rate, channels: 1 });
+        let control = StreamControl::spawn_worker("null-audio-out", self.faults.clone(), move |flags| {
+            drain_output(&flags, &mut data_rx, |chunk| trace!("Discarding {} output samples", chunk.len()));
+        })?;
+        Ok((control, data_tx))
+    }
+
+    fn input_present(&self) -> bool {
+        self.present()
+    }
+
+    fn output_present(&self) -> bool {
+        self.present()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_null_input_is_silent_and_output_accepts() {
+        let backend = NullBackend::new(8000);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let input = backend.start_input(tx).unwrap();
+        std::thread::sleep(Duration::from_millis(70));
+        drop(input);
+
+        let mut chunks = 0;
+        while let Ok(message) = rx.try_recv() {
+            match message {
+                AudioMessage::Data(data) => {
+                    assert_eq!(data.len(), 160);
+                    assert!(data.iter().all(|&s| s == 0.0));
+                    chunks += 1;
+                }
+                AudioMessage::Rms(rms) => assert_eq!(rms, 0.0),
+                AudioMessage::Error(e) => panic!("Unexpected error: {}", e),
+            }
+        }
+        assert!(chunks >= 2, "Expected a few paced chunks, got {}", chunks);
+
+        let (output, sender) = backend.start_output().unwrap();
+        assert_eq!(sender.format(), OutputFormat { sample_rate: 8000, channels: 1 });
+        let playback = sender.send(&[0.5; 1000], 8000).expect("Null output keeps its receiver alive");
+        playback.blocking_finished().unwrap();
+        drop(output);
+    }
+}
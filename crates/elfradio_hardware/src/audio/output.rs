@@ -0,0 +1,145 @@
+// TX audio handle: converts items to the device format and reports when they
+// have actually been played.
+
+use crate::HardwareError;
+use elfradio_dsp::resample;
+use tokio::sync::{mpsc, oneshot};
+
+/// Sample rate and channel count the output device really runs at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// One queued TX item, already interleaved in the device format.
+pub(crate) struct OutputItem {
+    pub(crate) samples: Vec<f32>,
+    done: oneshot::Sender<()>,
+}
+
+impl OutputItem {
+    /// Signals that the last sample has left the output callback.
+    pub(crate) fn complete(self) {
+        let _ = self.done.send(());
+    }
+}
+
+/// Sends TX audio to a running output stream.
+///
+/// Items are mono at any rate; they are resampled to the device rate and
+/// duplicated across the device channels before being queued. Cloning is cheap
+/// and all clones feed the same stream.
+#[derive(Clone)]
+pub struct AudioOutputSender {
+    tx: mpsc::UnboundedSender<OutputItem>,
+    format: OutputFormat,
+}
+
+impl AudioOutputSender {
+    pub(crate) fn channel(format: OutputFormat) -> (Self, mpsc::UnboundedReceiver<OutputItem>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx, format }, rx)
+    }
+
+    /// Format of the underlying device.
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Device sample rate in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.format.sample_rate
+    }
+
+    /// Device channel count.
+    pub fn channels(&self) -> u16 {
+        self.format.channels
+    }
+
+    /// Returns `true` once the output stream has stopped.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Queues mono `samples` recorded at `sample_rate` and returns a handle
+    /// that resolves once they have been played.
+    pub fn send(&self, samples: &[f32], sample_rate: u32) -> Result<Playback, HardwareError> {
+        let mono = if sample_rate == self.format.sample_rate {
+            samples.to_vec()
+        } else {
+            resample(samples, sample_rate, self.format.sample_rate)
+        };
+        let (done, rx) = oneshot::channel();
+        let item = OutputItem { samples: upmix(&mono, self.format.channels), done };
+        self.tx.send(item).map_err(|_| HardwareError::OutputStopped)?;
+        Ok(Playback { rx })
+    }
+
+    /// Queues `samples` and waits until they have been played.
+    pub async fn play(&self, samples: &[f32], sample_rate: u32) -> Result<(), HardwareError> {
+        self.send(samples, sample_rate)?.finished().await
+    }
+}
+
+/// Completion handle for one queued item.
+#[must_use = "dropping a Playback doesn't cancel it, but the completion is lost"]
+pub struct Playback {
+    rx: oneshot::Receiver<()>,
+}
+
+impl Playback {
+    /// Resolves when the last sample has left the output callback.
+    ///
+    /// Fails with `OutputStopped` if the stream went away first.
+    pub async fn finished(self) -> Result<(), HardwareError> {
+        self.rx.await.map_err(|_| HardwareError::OutputStopped)
+    }
+
+    /// Blocking variant of [`Playback::finished`] for non-async callers.
+    pub fn blocking_finished(self) -> Result<(), HardwareError> {
+        self.rx.blocking_recv().map_err(|_| HardwareError::OutputStopped)
+    }
+}
+
+/// Duplicates a mono signal across `channels` interleaved channels.
+pub fn upmix(mono: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    if channels == 1 {
+        return mono.to_vec();
+    }
+    mono.iter().flat_map(|&s| std::iter::repeat_n(s, channels)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_converts_to_device_format() {
+        let (sender, mut rx) = AudioOutputSender::channel(OutputFormat { sample_rate: 48000, channels: 2 });
+        let playback = sender.send(&[0.25; 1600], 16000).unwrap();
+
+        let item = rx.try_recv().unwrap();
+        assert!((item.samples.len() as i64 - 2 * 4800).abs() <= 6, "got {}", item.samples.len());
+        assert_eq!(item.samples[200], item.samples[201], "Both channels carry the same signal");
+        item.complete();
+        playback.blocking_finished().unwrap();
+    }
+
+    #[test]
+    fn test_playback_fails_when_stream_stops() {
+        let (sender, rx) = AudioOutputSender::channel(OutputFormat { sample_rate: 16000, channels: 1 });
+        let queued = sender.send(&[0.1; 10], 16000).unwrap();
+        drop(rx);
+        assert!(matches!(queued.blocking_finished(), Err(HardwareError::OutputStopped)));
+        assert!(sender.is_closed());
+        assert!(matches!(sender.send(&[0.1; 10], 16000), Err(HardwareError::OutputStopped)));
+    }
+
+    #[test]
+    fn test_upmix() {
+        assert_eq!(upmix(&[0.1, 0.2], 1), vec![0.1, 0.2]);
+        assert_eq!(upmix(&[0.1, 0.2], 3), vec![0.1, 0.1, 0.1, 0.2, 0.2, 0.2]);
+    }
+}
@@ -0,0 +1,282 @@
+// Audio device supervisor: notices when a sound card drops out and keeps
+// reopening it until it comes back.
+
+use super::{AudioBackend, AudioOutputSender, StreamControl};
+use crate::HardwareError;
+use elfradio_types::{AudioMessage, ConnectionStatus, WebSocketMessage};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// How often the supervisor checks streams and retries failed devices.
+#[derive(Debug, Clone, Copy)]
+pub struct SupervisorTiming {
+    /// Interval between checks of the running streams for errors.
+    pub poll_interval: Duration,
+    /// Interval between reopen attempts and device presence checks.
+    pub retry_interval: Duration,
+}
+
+impl Default for SupervisorTiming {
+    fn default() -> Self {
+        Self { poll_interval: Duration::from_millis(250), retry_interval: Duration::from_secs(2) }
+    }
+}
+
+/// One supervised direction (input or output).
+struct Slot {
+    label: &'static str,
+    stream: Option<StreamControl>,
+    next_check: Instant,
+    /// Set after the first failed open of an outage, to keep retries quiet.
+    failing: bool,
+}
+
+impl Slot {
+    fn new(label: &'static str) -> Self {
+        Self { label, stream: None, next_check: Instant::now(), failing: false }
+    }
+
+    /// Drops the stream if it reported an error or its device vanished.
+    /// Returns `true` if the stream was dropped.
+    fn check(&mut self, now: Instant, timing: &SupervisorTiming, present: impl FnOnce() -> bool) -> bool {
+        let Some(stream) = &self.stream else { return false };
+        let reason = match stream.fault() {
+            Some(fault) => fault,
+            None if now >= self.next_check => {
+                self.next_check = now + timing.retry_interval;
+                if present() {
+                    return false;
+                }
+                "device disappeared".to_string()
+            }
+            None => return false,
+        };
+        warn!("Audio {} lost: {}. Will keep trying to reopen it.", self.label, reason);
+        self.stream = None;
+        self.next_check = now;
+        true
+    }
+
+    /// Tries to (re)open the stream if it is down and a retry is due.
+    fn reopen<T>(
+        &mut self,
+        now: Instant,
+        timing: &SupervisorTiming,
+        present: impl FnOnce() -> bool,
+        open: impl FnOnce() -> Result<(StreamControl, T), HardwareError>,
+    ) -> Option<T> {
+        if self.stream.is_some() || now < self.next_check {
+            return None;
+        }
+        self.next_check = now + timing.retry_interval;
+        let result = if present() {
+            open()
+        } else {
+            Err(HardwareError::DeviceNotFound(format!("Audio {} device", self.label)))
+        };
+        match result {
+            Ok((stream, extra)) => {
+                info!("Audio {} {}.", self.label, if self.failing { "recovered" } else { "started" });
+                self.stream = Some(stream);
+                self.failing = false;
+                Some(extra)
+            }
+            Err(e) => {
+                if self.failing {
+                    debug!("Audio {} still unavailable: {}", self.label, e);
+                } else {
+                    warn!("Audio {} unavailable: {}. Retrying every {:?}.", self.label, e, timing.retry_interval);
+                    self.failing = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Owns the audio streams of a backend and reopens them when they fail.
+///
+/// Runs on its own thread (cpal streams can't move between threads on every
+/// platform, so they are opened and dropped there). Input audio keeps flowing
+/// into the same `AudioMessage` channel across reconnects; every new output
+/// sender, or `None` while the output is down, is handed to `on_output`.
+/// Status updates (`AudioStatusUpdate`, or the backend's own kind) go out on `status_tx` whenever the overall state
+/// changes. Dropping the supervisor stops the streams.
+pub struct AudioSupervisor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AudioSupervisor {
+    pub fn spawn(
+        backend: Box<dyn AudioBackend>,
+        input_tx: mpsc::UnboundedSender<AudioMessage>,
+        mut on_output: impl FnMut(Option<AudioOutputSender>) + Send + 'static,
+        status_tx: mpsc::UnboundedSender<WebSocketMessage>,
+        timing: SupervisorTiming,
+    ) -> Result<Self, HardwareError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new().name("audio-supervisor".to_string()).spawn(move || {
+            let mut input = Slot::new("input");
+            let mut output = Slot::new("output");
+            let mut last_status = None;
+            let mut publish = |status: ConnectionStatus| {
+                if last_status.as_ref() != Some(&status) {
+                    debug!(?status, "Audio supervisor status changed");
+                    if status_tx.send(backend.status_message(status.clone())).is_err() {
+                        error!("Failed to send status update ({:?}) to MPSC channel.", status);
+                    }
+                    last_status = Some(status);
+                }
+            };
+            publish(ConnectionStatus::Checking);
+
+            while !thread_stop.load(Ordering::Acquire) {
+                let now = Instant::now();
+                if backend.has_input() {
+                    input.check(now, &timing, || backend.input_present());
+                    input.reopen(now, &timing, || backend.input_present(), || {
+                        backend.start_input(input_tx.clone()).map(|stream| (stream, ()))
+                    });
+                }
+                if backend.has_output() {
+                    if output.check(now, &timing, || backend.output_present()) {
+                        on_output(None);
+                    }
+                    if let Some(sender) = output.reopen(now, &timing, || backend.output_present(), || backend.start_output()) {
+                        on_output(Some(sender));
+                    }
+                }
+
+                let input_ok = !backend.has_input() || input.stream.is_some();
+                let output_ok = !backend.has_output() || output.stream.is_some();
+                publish(if input_ok && output_ok { ConnectionStatus::Connected } else { ConnectionStatus::Disconnected });
+
+                std::thread::sleep(timing.poll_interval);
+            }
+
+            if output.stream.take().is_some() {
+                on_output(None);
+            }
+            debug!("Audio supervisor stopped");
+        })?;
+        Ok(Self { stop, thread: Some(thread) })
+    }
+}
+
+impl Drop for AudioSupervisor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.thread.take()
+            && handle.join().is_err()
+        {
+            error!("Audio supervisor thread panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{FaultInjector, NullBackend};
+    use std::sync::Mutex;
+
+    fn fast() -> SupervisorTiming {
+        SupervisorTiming { poll_interval: Duration::from_millis(5), retry_interval: Duration::from_millis(20) }
+    }
+
+    /// Polls `condition` for up to two seconds.
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    fn drain_statuses(rx: &mut mpsc::UnboundedReceiver<WebSocketMessage>, seen: &mut Vec<ConnectionStatus>) {
+        while let Ok(message) = rx.try_recv() {
+            if let WebSocketMessage::AudioStatusUpdate(status) = message {
+                seen.push(status);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recovers_after_unplug() {
+        let injector = FaultInjector::new();
+        let backend = NullBackend::new(8000).with_fault_injector(injector.clone());
+        let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+        let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+        let output_slot: Arc<Mutex<Option<AudioOutputSender>>> = Arc::default();
+        let slot = output_slot.clone();
+        let supervisor = AudioSupervisor::spawn(
+            Box::new(backend),
+            input_tx,
+            move |sender| *slot.lock().unwrap() = sender,
+            status_tx,
+            fast(),
+        )
+        .unwrap();
+
+        let mut statuses = Vec::new();
+        assert!(wait_for(|| {
+            drain_statuses(&mut status_rx, &mut statuses);
+            statuses.last() == Some(&ConnectionStatus::Connected)
+        }));
+        assert_eq!(statuses[0], ConnectionStatus::Checking);
+        assert!(output_slot.lock().unwrap().is_some());
+        assert!(wait_for(|| matches!(input_rx.try_recv(), Ok(AudioMessage::Data(_)))));
+
+        injector.unplug();
+        assert!(wait_for(|| {
+            drain_statuses(&mut status_rx, &mut statuses);
+            statuses.last() == Some(&ConnectionStatus::Disconnected)
+        }));
+        assert!(wait_for(|| output_slot.lock().unwrap().is_none()));
+
+        // Let a few failed retries go by, then bring the device back.
+        std::thread::sleep(Duration::from_millis(60));
+        while input_rx.try_recv().is_ok() {}
+        injector.replug();
+        assert!(wait_for(|| {
+            drain_statuses(&mut status_rx, &mut statuses);
+            statuses.last() == Some(&ConnectionStatus::Connected)
+        }));
+        assert!(wait_for(|| matches!(input_rx.try_recv(), Ok(AudioMessage::Data(_)))));
+        let sender = output_slot.lock().unwrap().clone().expect("Output reopened");
+        sender.send(&[0.1; 100], 8000).unwrap().blocking_finished().unwrap();
+
+        drop(supervisor);
+        assert!(output_slot.lock().unwrap().is_none());
+        assert_eq!(
+            statuses.iter().filter(|s| **s == ConnectionStatus::Disconnected).count(),
+            1,
+            "Retries don't repeat the status: {:?}",
+            statuses
+        );
+    }
+
+    #[test]
+    fn test_unconfigured_directions_are_not_supervised() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = crate::audio::WavFileBackend::new(None, Some(dir.path().join("tx.wav")), 8000);
+        let (input_tx, _input_rx) = mpsc::unbounded_channel();
+        let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+        let _supervisor = AudioSupervisor::spawn(Box::new(backend), input_tx, |_| {}, status_tx, fast()).unwrap();
+
+        let mut statuses = Vec::new();
+        assert!(wait_for(|| {
+            drain_statuses(&mut status_rx, &mut statuses);
+            statuses.last() == Some(&ConnectionStatus::Connected)
+        }));
+    }
+}
@@ -0,0 +1,160 @@
+// Shared transport for the semicolon-terminated ASCII CAT dialects
+// (Kenwood, Elecraft and newer Yaesu rigs).
+
+use crate::HardwareError;
+use std::io::{Read, Write};
+use tracing::trace;
+
+/// Longest reply we accept before giving up on finding the `;` terminator.
+const MAX_REPLY_LEN: usize = 128;
+
+/// Sends `XX...;` commands and reads `XX...;` replies over a byte transport.
+pub(crate) struct AsciiCatPort<T> {
+    transport: T,
+}
+
+impl<T: Read + Write> AsciiCatPort<T> {
+    pub(crate) fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// Sends a command that produces no reply (e.g. `FA00014074000`).
+    pub(crate) fn send(&mut self, command: &str) -> Result<(), HardwareError> {
+        trace!(command, "ASCII CAT send");
+        self.transport.write_all(format!("{};", command).as_bytes())?;
+        self.transport.flush()?;
+        Ok(())
+    }
+
+    /// Sends a query (e.g. `FA`) and returns the reply without its two-letter
+    /// command prefix and `;` terminator.
+    pub(crate) fn query(&mut self, command: &str) -> Result<String, HardwareError> {
+        self.send(command)?;
+        let reply = self.read_reply()?;
+        trace!(command, reply = %reply, "ASCII CAT reply");
+
+        if reply == "?" || reply == "E" || reply == "O" {
+            // "?;" = syntax/state error, "E;"/"O;" = Elecraft communication/overflow errors.
+            return Err(HardwareError::CatCommandRejected { command: command.to_string(), code: -1 });
+        }
+
+        let prefix = &command[..command.len().min(2)];
+        reply.strip_prefix(prefix).map(str::to_string).ok_or_else(|| {
+            HardwareError::CatError(format!("Unexpected reply to '{};': '{};'", command, reply))
+        })
+    }
+
+    fn read_reply(&mut self) -> Result<String, HardwareError> {
+        let mut reply = Vec::new();
+        let mut byte = [0u8; 1];
+        loop {
+            self.transport.read_exact(&mut byte)?;
+            match byte[0] {
+                b';' => break,
+                // Some rigs emit stray CR/LF between replies.
+                b'\r' | b'\n' => continue,
+                b => reply.push(b),
+            }
+            if reply.len() > MAX_REPLY_LEN {
+                return Err(HardwareError::CatError("CAT reply too long or missing ';' terminator".to_string()));
+            }
+        }
+        String::from_utf8(reply).map_err(|_| HardwareError::CatError("CAT reply is not valid ASCII".to_string()))
+    }
+}
+
+/// Parses a fixed-width decimal field, e.g. the frequency digits of an `FA` reply.
+pub(crate) fn parse_decimal<N: std::str::FromStr>(field: &str, what: &str) -> Result<N, HardwareError> {
+    field
+        .trim()
+        .parse::<N>()
+        .map_err(|_| HardwareError::CatError(format!("Could not parse {} from CAT reply field '{}'", what, field)))
+}
+
+/// Piecewise-linear interpolation through `(raw, dB relative to S9)` calibration points.
+pub(crate) fn interpolate_meter(raw: f32, table: &[(f32, f32)]) -> i32 {
+    let (first, last) = (table[0], table[table.len() - 1]);
+    if raw <= first.0 {
+        return first.1.round() as i32;
+    }
+    if raw >= last.0 {
+        return last.1.round() as i32;
+    }
+    for pair in table.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if raw <= x1 {
+            return (y0 + (raw - x0) / (x1 - x0) * (y1 - y0)).round() as i32;
+        }
+    }
+    last.1.round() as i32
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::collections::VecDeque;
+    use std::io::{self, Read, Write};
+
+    type Responder = Box<dyn FnMut(&[u8]) -> Vec<u8> + Send>;
+
+    /// In-memory serial port: every write is handed to `responder`, whose
+    /// output becomes readable, much like a rig answering on the wire.
+    pub(crate) struct MockSerial {
+        responder: Responder,
+        pending: VecDeque<u8>,
+    }
+
+    impl MockSerial {
+        pub(crate) fn new(responder: impl FnMut(&[u8]) -> Vec<u8> + Send + 'static) -> Self {
+            Self { responder: Box::new(responder), pending: VecDeque::new() }
+        }
+    }
+
+    impl Read for MockSerial {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "mock serial: no data"));
+            }
+            let n = buf.len().min(self.pending.len());
+            for slot in buf.iter_mut().take(n) {
+                *slot = self.pending.pop_front().unwrap();
+            }
+            Ok(n)
+        }
+    }
+
+    impl Write for MockSerial {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let reply = (self.responder)(buf);
+            self.pending.extend(reply);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_meter() {
+        let table = [(0.0, -54.0), (15.0, 0.0), (30.0, 60.0)];
+        assert_eq!(interpolate_meter(-1.0, &table), -54);
+        assert_eq!(interpolate_meter(15.0, &table), 0);
+        assert_eq!(interpolate_meter(22.5, &table), 30);
+        assert_eq!(interpolate_meter(99.0, &table), 60);
+    }
+
+    #[test]
+    fn test_query_rejected_and_unexpected() {
+        let mut port = AsciiCatPort::new(mock::MockSerial::new(|cmd: &[u8]| match cmd {
+            b"FA;" => b"?;".to_vec(),
+            _ => b"XX1;".to_vec(),
+        }));
+        assert!(matches!(port.query("FA"), Err(HardwareError::CatCommandRejected { .. })));
+        assert!(matches!(port.query("MD"), Err(HardwareError::CatError(_))));
+    }
+}
@@ -0,0 +1,388 @@
+// Icom CI-V binary CAT driver (IC-705, IC-7100, IC-7300, IC-7610, IC-9700, ...).
+//
+// Frame layout: FE FE <to> <from> <cmd> [sub] [data...] FD
+// The rig answers set commands with FB (OK) or FA (NG). Frequencies and meter
+// values are packed BCD, least significant byte first for frequencies.
+// On a shared single-wire CI-V bus every frame we send is echoed back, so
+// frames originating from our own address are skipped while reading.
+
+use super::{RigControl, RigMode};
+use crate::HardwareError;
+use std::io::{Read, Write};
+use tracing::{debug, trace};
+
+/// Conventional CI-V address of the controlling PC.
+pub const CIV_CONTROLLER_ADDRESS: u8 = 0xE0;
+
+const PREAMBLE: u8 = 0xFE;
+const END_OF_MESSAGE: u8 = 0xFD;
+const REPLY_OK: u8 = 0xFB;
+const REPLY_NG: u8 = 0xFA;
+
+/// Longest frame we accept before deciding the stream is garbage.
+const MAX_FRAME_LEN: usize = 64;
+/// Frames (echoes, transceive broadcasts) we are willing to skip while waiting for a reply.
+const MAX_SKIPPED_FRAMES: usize = 8;
+
+/// Returns the factory-default CI-V address for a known Icom model name.
+pub fn default_civ_address(model: &str) -> Option<u8> {
+    let normalized: String = model.to_uppercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let address = match normalized.as_str() {
+        "IC705" => 0xA4,
+        "IC706MKIIG" => 0x58,
+        "IC718" => 0x5E,
+        "IC7000" => 0x70,
+        "IC7100" => 0x88,
+        "IC7300" => 0x94,
+        "IC7410" => 0x80,
+        "IC7600" => 0x7A,
+        "IC7610" => 0x98,
+        "IC7851" => 0x8E,
+        "IC905" => 0xAC,
+        "IC9100" => 0x7C,
+        "IC9700" => 0xA2,
+        _ => return None,
+    };
+    Some(address)
+}
+
+/// CAT driver for Icom rigs speaking CI-V.
+pub struct IcomCiv<T> {
+    transport: T,
+    rig_address: u8,
+    controller_address: u8,
+}
+
+impl<T: Read + Write + Send> IcomCiv<T> {
+    /// Wraps an open transport (normally a serial port) talking to the rig at `rig_address`.
+    pub fn new(transport: T, rig_address: u8) -> Self {
+        Self {
+            transport,
+            rig_address,
+            controller_address: CIV_CONTROLLER_ADDRESS,
+        }
+    }
+
+    /// Sends `command` + `payload` and returns the payload of the rig's reply
+    /// (everything after the to/from addresses, command byte included).
+    fn transact(&mut self, command: &[u8], payload: &[u8]) -> Result<Vec<u8>, HardwareError> {
+        let mut frame = vec![PREAMBLE, PREAMBLE, self.rig_address, self.controller_address];
+        frame.extend_from_slice(command);
+        frame.extend_from_slice(payload);
+        frame.push(END_OF_MESSAGE);
+        trace!(frame = ?frame, "CI-V send");
+        self.transport.write_all(&frame)?;
+        self.transport.flush()?;
+
+        for _ in 0..MAX_SKIPPED_FRAMES {
+            let reply = self.read_frame()?;
+            let (to, from, body) = (reply[0], reply[1], &reply[2..]);
+            if from == self.controller_address || to != self.controller_address || from != self.rig_address {
+                trace!(frame = ?reply, "CI-V skipping frame not addressed to us");
+                continue;
+            }
+            return match body {
+                [REPLY_NG] => Err(HardwareError::CatCommandRejected {
+                    command: format!("CI-V {:02X?}", command),
+                    code: REPLY_NG as i32,
+                }),
+                _ => Ok(body.to_vec()),
+            };
+        }
+        Err(HardwareError::CatError(format!("No CI-V reply from rig 0x{:02X}", self.rig_address)))
+    }
+
+    /// Sends a set command and expects a bare FB acknowledgement.
+    fn command(&mut self, command: &[u8], payload: &[u8]) -> Result<(), HardwareError> {
+        match self.transact(command, payload)?.as_slice() {
+            [REPLY_OK] => Ok(()),
+            other => Err(HardwareError::CatError(format!("Unexpected CI-V acknowledgement {:02X?}", other))),
+        }
+    }
+
+    /// Sends a read command and returns the data following the echoed command bytes.
+    fn query(&mut self, command: &[u8]) -> Result<Vec<u8>, HardwareError> {
+        let body = self.transact(command, &[])?;
+        body.strip_prefix(command).map(<[u8]>::to_vec).ok_or_else(|| {
+            HardwareError::CatError(format!("CI-V reply {:02X?} does not match command {:02X?}", body, command))
+        })
+    }
+
+    /// Reads one frame and returns the bytes between the preamble and FD.
+    fn read_frame(&mut self) -> Result<Vec<u8>, HardwareError> {
+        let mut byte = [0u8; 1];
+        // Hunt for the FE FE preamble, discarding line noise.
+        let mut preamble_bytes = 0;
+        while preamble_bytes < 2 {
+            self.transport.read_exact(&mut byte)?;
+            preamble_bytes = if byte[0] == PREAMBLE { preamble_bytes + 1 } else { 0 };
+        }
+
+        let mut frame = Vec::new();
+        loop {
+            self.transport.read_exact(&mut byte)?;
+            match byte[0] {
+                END_OF_MESSAGE => break,
+                PREAMBLE if frame.is_empty() => continue, // Extra preamble bytes are allowed.
+                b => frame.push(b),
+            }
+            if frame.len() > MAX_FRAME_LEN {
+                return Err(HardwareError::CatError("CI-V frame too long or missing FD".to_string()));
+            }
+        }
+        if frame.len() < 3 {
+            return Err(HardwareError::CatError(format!("Truncated CI-V frame {:02X?}", frame)));
+        }
+        Ok(frame)
+    }
+}
+
+/// Decodes little-endian packed BCD (two digits per byte, low byte first).
+fn bcd_le_to_u64(bytes: &[u8]) -> Result<u64, HardwareError> {
+    bytes.iter().rev().try_fold(0u64, |acc, &b| {
+        let (hi, lo) = (b >> 4, b & 0x0F);
+        if hi > 9 || lo > 9 {
+            return Err(HardwareError::CatError(format!("Invalid BCD byte 0x{:02X} in CI-V reply", b)));
+        }
+        Ok(acc * 100 + (hi * 10 + lo) as u64)
+    })
+}
+
+/// Encodes `value` as `len` bytes of little-endian packed BCD.
+fn u64_to_bcd_le(mut value: u64, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            let pair = (value % 100) as u8;
+            value /= 100;
+            ((pair / 10) << 4) | (pair % 10)
+        })
+        .collect()
+}
+
+/// Meter and level values are 0..=255 as big-endian BCD ("0128" → 01 28).
+fn level_from_bcd(bytes: &[u8]) -> Result<u32, HardwareError> {
+    let reversed: Vec<u8> = bytes.iter().rev().copied().collect();
+    Ok(bcd_le_to_u64(&reversed)? as u32)
+}
+
+fn level_to_bcd(value: u32) -> Vec<u8> {
+    let mut bytes = u64_to_bcd_le(value as u64, 2);
+    bytes.reverse();
+    bytes
+}
+
+fn mode_from_code(code: u8, data: bool) -> Result<RigMode, HardwareError> {
+    let mode = match (code, data) {
+        (0x00, false) => RigMode::Lsb,
+        (0x00, true) => RigMode::PktLsb,
+        (0x01, false) => RigMode::Usb,
+        (0x01, true) => RigMode::PktUsb,
+        (0x02, _) => RigMode::Am,
+        (0x03, _) => RigMode::Cw,
+        (0x04, _) => RigMode::Rtty,
+        (0x05, false) => RigMode::Fm,
+        (0x05, true) => RigMode::PktFm,
+        (0x06, _) => RigMode::WideFm,
+        (0x07, _) => RigMode::CwR,
+        (0x08, _) => RigMode::RttyR,
+        (0x17, _) => RigMode::Fm, // DV; closest analogue.
+        (other, _) => return Err(HardwareError::CatError(format!("Unknown CI-V mode code 0x{:02X}", other))),
+    };
+    Ok(mode)
+}
+
+fn mode_to_code(mode: RigMode) -> (u8, bool) {
+    match mode {
+        RigMode::Lsb => (0x00, false),
+        RigMode::Usb => (0x01, false),
+        RigMode::Am => (0x02, false),
+        RigMode::Cw => (0x03, false),
+        RigMode::Rtty => (0x04, false),
+        RigMode::Fm => (0x05, false),
+        RigMode::WideFm => (0x06, false),
+        RigMode::CwR => (0x07, false),
+        RigMode::RttyR => (0x08, false),
+        RigMode::PktLsb => (0x00, true),
+        RigMode::PktUsb => (0x01, true),
+        RigMode::PktFm => (0x05, true),
+    }
+}
+
+impl<T: Read + Write + Send> RigControl for IcomCiv<T> {
+    fn get_frequency(&mut self) -> Result<u64, HardwareError> {
+        bcd_le_to_u64(&self.query(&[0x03])?)
+    }
+
+    fn set_frequency(&mut self, freq_hz: u64) -> Result<(), HardwareError> {
+        self.command(&[0x05], &u64_to_bcd_le(freq_hz, 5))
+    }
+
+    fn get_mode(&mut self) -> Result<(RigMode, Option<u32>), HardwareError> {
+        let data = self.query(&[0x04])?;
+        let code = *data
+            .first()
+            .ok_or_else(|| HardwareError::CatError("Empty CI-V mode reply".to_string()))?;
+        // Data mode (1A 06) is not supported by older rigs; treat NG as "off".
+        let data_mode = match self.query(&[0x1A, 0x06]) {
+            Ok(d) => d.first().copied().unwrap_or(0) != 0,
+            Err(e) => {
+                debug!("CI-V data mode query failed, assuming off: {}", e);
+                false
+            }
+        };
+        // The second byte is a filter slot (FIL1..3), not a width in Hz.
+        Ok((mode_from_code(code, data_mode)?, None))
+    }
+
+    fn set_mode(&mut self, mode: RigMode, passband_hz: Option<u32>) -> Result<(), HardwareError> {
+        if passband_hz.is_some() {
+            debug!("CI-V driver ignores passband width; keeping the rig's filter selection");
+        }
+        let (code, data) = mode_to_code(mode);
+        self.command(&[0x06], &[code])?;
+        if matches!(code, 0x00 | 0x01 | 0x05) {
+            // 1A 06 <data on/off> <filter>; filter 00 means "off" when data is off, else FIL1.
+            let payload = if data { [0x01, 0x01] } else { [0x00, 0x00] };
+            if let Err(e) = self.command(&[0x1A, 0x06], &payload) {
+                if data {
+                    return Err(e);
+                }
+                debug!("CI-V data mode reset not supported by rig: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_ptt(&mut self) -> Result<bool, HardwareError> {
+        Ok(self.query(&[0x1C, 0x00])?.first().copied().unwrap_or(0) != 0)
+    }
+
+    fn set_ptt(&mut self, on: bool) -> Result<(), HardwareError> {
+        self.command(&[0x1C, 0x00], &[on as u8])
+    }
+
+    fn get_signal_strength(&mut self) -> Result<i32, HardwareError> {
+        // 0 = S0, 120 = S9, 241 = S9+60 dB (IC-7300 calibration).
+        let raw = level_from_bcd(&self.query(&[0x15, 0x02])?)? as f32;
+        let db = if raw <= 120.0 { raw / 120.0 * 54.0 - 54.0 } else { (raw - 120.0) / 121.0 * 60.0 };
+        Ok(db.round().min(60.0) as i32)
+    }
+
+    fn get_power(&mut self) -> Result<f32, HardwareError> {
+        let raw = level_from_bcd(&self.query(&[0x14, 0x0A])?)?;
+        Ok((raw as f32 / 255.0).clamp(0.0, 1.0))
+    }
+
+    fn set_power(&mut self, level: f32) -> Result<(), HardwareError> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(HardwareError::CatError(format!("RF power level {} is outside 0.0..=1.0", level)));
+        }
+        self.command(&[0x14, 0x0A], &level_to_bcd((level * 255.0).round() as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cat::ascii::mock::MockSerial;
+    use std::sync::{Arc, Mutex};
+
+    const RIG: u8 = 0x94;
+
+    struct FakeIc7300 {
+        freq: u64,
+        mode: u8,
+        data: bool,
+        tx: bool,
+        power: u32,
+    }
+
+    /// Emulates an IC-7300 on a bus with echo enabled (like a CT-17 interface).
+    fn fake_rig() -> (IcomCiv<MockSerial>, Arc<Mutex<FakeIc7300>>) {
+        let state = Arc::new(Mutex::new(FakeIc7300 { freq: 14_074_000, mode: 0x01, data: false, tx: false, power: 128 }));
+        let rig_state = state.clone();
+        let serial = MockSerial::new(move |frame: &[u8]| {
+            assert_eq!(&frame[..4], &[PREAMBLE, PREAMBLE, RIG, CIV_CONTROLLER_ADDRESS]);
+            let body = &frame[4..frame.len() - 1];
+            let mut rig = rig_state.lock().unwrap();
+            let reply: Vec<u8> = match body {
+                [0x03] => [&[0x03][..], &u64_to_bcd_le(rig.freq, 5)].concat(),
+                [0x05, f @ ..] => { rig.freq = bcd_le_to_u64(f).unwrap(); vec![REPLY_OK] }
+                [0x04] => vec![0x04, rig.mode, 0x01],
+                [0x06, m] => { rig.mode = *m; vec![REPLY_OK] }
+                [0x1A, 0x06] => vec![0x1A, 0x06, rig.data as u8, rig.data as u8],
+                [0x1A, 0x06, d, _] => { rig.data = *d != 0; vec![REPLY_OK] }
+                [0x1C, 0x00] => vec![0x1C, 0x00, rig.tx as u8],
+                [0x1C, 0x00, t] => { rig.tx = *t != 0; vec![REPLY_OK] }
+                [0x15, 0x02] => [&[0x15, 0x02][..], &level_to_bcd(120)].concat(),
+                [0x14, 0x0A] => [&[0x14, 0x0A][..], &level_to_bcd(rig.power)].concat(),
+                [0x14, 0x0A, p @ ..] => { rig.power = level_from_bcd(p).unwrap(); vec![REPLY_OK] }
+                _ => vec![REPLY_NG],
+            };
+            let mut wire = frame.to_vec(); // Bus echo of our own frame.
+            wire.extend_from_slice(&[PREAMBLE, PREAMBLE, CIV_CONTROLLER_ADDRESS, RIG]);
+            wire.extend_from_slice(&reply);
+            wire.push(END_OF_MESSAGE);
+            wire
+        });
+        (IcomCiv::new(serial, RIG), state)
+    }
+
+    #[test]
+    fn test_bcd_helpers() {
+        assert_eq!(u64_to_bcd_le(14_074_000, 5), vec![0x00, 0x40, 0x07, 0x14, 0x00]);
+        assert_eq!(bcd_le_to_u64(&[0x00, 0x40, 0x07, 0x14, 0x00]).unwrap(), 14_074_000);
+        assert_eq!(level_to_bcd(255), vec![0x02, 0x55]);
+        assert_eq!(level_from_bcd(&[0x01, 0x28]).unwrap(), 128);
+        assert!(bcd_le_to_u64(&[0x1A]).is_err());
+        assert_eq!(default_civ_address("IC-7300"), Some(0x94));
+        assert_eq!(default_civ_address("ic-9700"), Some(0xA2));
+        assert_eq!(default_civ_address("FT-991"), None);
+    }
+
+    #[test]
+    fn test_frequency_roundtrip_with_echo() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_frequency().unwrap(), 14_074_000);
+        rig.set_frequency(1_296_200_000).unwrap();
+        assert_eq!(state.lock().unwrap().freq, 1_296_200_000);
+        assert_eq!(rig.get_frequency().unwrap(), 1_296_200_000);
+    }
+
+    #[test]
+    fn test_mode_and_data_mode() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_mode().unwrap(), (RigMode::Usb, None));
+        rig.set_mode(RigMode::PktUsb, None).unwrap();
+        assert!(state.lock().unwrap().data);
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::PktUsb);
+        rig.set_mode(RigMode::Fm, None).unwrap();
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::Fm);
+        assert!(!state.lock().unwrap().data);
+    }
+
+    #[test]
+    fn test_ptt_meter_and_power() {
+        let (mut rig, _state) = fake_rig();
+        assert!(!rig.get_ptt().unwrap());
+        rig.set_ptt(true).unwrap();
+        assert!(rig.get_ptt().unwrap());
+        rig.set_ptt(false).unwrap();
+        assert!(!rig.get_ptt().unwrap());
+
+        assert_eq!(rig.get_signal_strength().unwrap(), 0, "Raw 120 is S9");
+
+        assert!((rig.get_power().unwrap() - 128.0 / 255.0).abs() < 1e-3);
+        rig.set_power(1.0).unwrap();
+        assert!((rig.get_power().unwrap() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_ng_reply_is_rejection() {
+        let (mut rig, _state) = fake_rig();
+        assert!(matches!(
+            rig.command(&[0x1B, 0x00], &[0x08]),
+            Err(HardwareError::CatCommandRejected { code, .. }) if code == REPLY_NG as i32
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+// Kenwood / Elecraft ASCII CAT driver (TS-480/TS-590/TS-2000, K3/KX3 in K2/K3 mode).
+//
+// Commands are two letters plus parameters, terminated by `;`. Set commands
+// produce no reply; queries echo the command letters followed by the value.
+
+use super::ascii::{interpolate_meter, parse_decimal, AsciiCatPort};
+use super::{RigControl, RigMode};
+use crate::HardwareError;
+use std::io::{Read, Write};
+use tracing::debug;
+
+/// S-meter calibration (`SM0` raw 0..=30 → dB relative to S9), TS-590 scale.
+const SMETER_TABLE: [(f32, f32); 3] = [(0.0, -54.0), (15.0, 0.0), (30.0, 60.0)];
+
+/// CAT driver for Kenwood and Elecraft rigs.
+pub struct KenwoodRig<T> {
+    port: AsciiCatPort<T>,
+    max_power_w: f32,
+}
+
+impl<T: Read + Write + Send> KenwoodRig<T> {
+    /// Wraps an open transport (normally a serial port).
+    ///
+    /// `max_power_w` is the rig's full output power; `PC` works in watts and
+    /// [`RigControl`] works in fractions of full power.
+    pub fn new(transport: T, max_power_w: u32) -> Self {
+        Self {
+            port: AsciiCatPort::new(transport),
+            max_power_w: max_power_w.max(1) as f32,
+        }
+    }
+
+    /// Data sub-mode (`DA`); rigs without it are treated as "data off".
+    fn data_mode(&mut self) -> bool {
+        match self.port.query("DA") {
+            Ok(value) => value == "1",
+            Err(e) => {
+                debug!("Kenwood DA query failed, assuming data mode off: {}", e);
+                false
+            }
+        }
+    }
+}
+
+fn mode_from_code(code: &str, data: bool) -> Result<RigMode, HardwareError> {
+    let mode = match (code, data) {
+        ("1", false) => RigMode::Lsb,
+        ("1", true) => RigMode::PktLsb,
+        ("2", false) => RigMode::Usb,
+        ("2", true) => RigMode::PktUsb,
+        ("3", _) => RigMode::Cw,
+        ("4", false) => RigMode::Fm,
+        ("4", true) => RigMode::PktFm,
+        ("5", _) => RigMode::Am,
+        ("6", _) => RigMode::Rtty,
+        ("7", _) => RigMode::CwR,
+        ("9", _) => RigMode::RttyR,
+        (other, _) => return Err(HardwareError::CatError(format!("Unknown Kenwood mode code '{}'", other))),
+    };
+    Ok(mode)
+}
+
+fn mode_to_code(mode: RigMode) -> Result<(&'static str, bool), HardwareError> {
+    let code = match mode {
+        RigMode::Lsb => ("1", false),
+        RigMode::Usb => ("2", false),
+        RigMode::Cw => ("3", false),
+        RigMode::Fm => ("4", false),
+        RigMode::Am => ("5", false),
+        RigMode::Rtty => ("6", false),
+        RigMode::CwR => ("7", false),
+        RigMode::RttyR => ("9", false),
+        RigMode::PktLsb => ("1", true),
+        RigMode::PktUsb => ("2", true),
+        RigMode::PktFm => ("4", true),
+        RigMode::WideFm => {
+            return Err(HardwareError::CatError("Kenwood CAT has no wide FM mode".to_string()))
+        }
+    };
+    Ok(code)
+}
+
+impl<T: Read + Write + Send> RigControl for KenwoodRig<T> {
+    fn get_frequency(&mut self) -> Result<u64, HardwareError> {
+        parse_decimal(&self.port.query("FA")?, "frequency")
+    }
+
+    fn set_frequency(&mut self, freq_hz: u64) -> Result<(), HardwareError> {
+        self.port.send(&format!("FA{:011}", freq_hz))
+    }
+
+    fn get_mode(&mut self) -> Result<(RigMode, Option<u32>), HardwareError> {
+        let code = self.port.query("MD")?;
+        let data = matches!(code.as_str(), "1" | "2" | "4") && self.data_mode();
+        Ok((mode_from_code(&code, data)?, None))
+    }
+
+    fn set_mode(&mut self, mode: RigMode, passband_hz: Option<u32>) -> Result<(), HardwareError> {
+        if passband_hz.is_some() {
+            debug!("Kenwood driver ignores passband width; keeping the rig's filter setting");
+        }
+        let (code, data) = mode_to_code(mode)?;
+        self.port.send(&format!("MD{}", code))?;
+        if data || matches!(code, "1" | "2" | "4") {
+            self.port.send(if data { "DA1" } else { "DA0" })?;
+        }
+        Ok(())
+    }
+
+    fn get_ptt(&mut self) -> Result<bool, HardwareError> {
+        Ok(self.port.query("TQ")? == "1")
+    }
+
+    fn set_ptt(&mut self, on: bool) -> Result<(), HardwareError> {
+        self.port.send(if on { "TX" } else { "RX" })
+    }
+
+    fn get_signal_strength(&mut self) -> Result<i32, HardwareError> {
+        // "SM0" → "SM0nnnn": main receiver, four digit reading.
+        let value = self.port.query("SM0")?;
+        let raw: u32 = parse_decimal(value.get(1..).unwrap_or(""), "S-meter")?;
+        Ok(interpolate_meter(raw as f32, &SMETER_TABLE))
+    }
+
+    fn get_power(&mut self) -> Result<f32, HardwareError> {
+        let watts: u32 = parse_decimal(&self.port.query("PC")?, "RF power")?;
+        Ok((watts as f32 / self.max_power_w).clamp(0.0, 1.0))
+    }
+
+    fn set_power(&mut self, level: f32) -> Result<(), HardwareError> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(HardwareError::CatError(format!("RF power level {} is outside 0.0..=1.0", level)));
+        }
+        self.port.send(&format!("PC{:03}", (level * self.max_power_w).round() as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cat::ascii::mock::MockSerial;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct FakeTs590 {
+        freq: u64,
+        mode: char,
+        data: bool,
+        tx: bool,
+        power_w: u32,
+    }
+
+    /// Emulates the subset of the TS-590 command set the driver uses.
+    fn fake_rig() -> (KenwoodRig<MockSerial>, Arc<Mutex<FakeTs590>>) {
+        let state = Arc::new(Mutex::new(FakeTs590 { freq: 7_074_000, mode: '2', power_w: 100, ..Default::default() }));
+        let rig_state = state.clone();
+        let serial = MockSerial::new(move |bytes: &[u8]| {
+            let cmd = std::str::from_utf8(bytes).unwrap().trim_end_matches(';');
+            let mut rig = rig_state.lock().unwrap();
+            let reply = match (cmd.get(..2).unwrap_or(""), cmd.get(2..).unwrap_or("")) {
+                ("FA", "") => format!("FA{:011};", rig.freq),
+                ("FA", f) => { rig.freq = f.parse().unwrap(); String::new() }
+                ("MD", "") => format!("MD{};", rig.mode),
+                ("MD", m) => { rig.mode = m.chars().next().unwrap(); String::new() }
+                ("DA", "") => format!("DA{};", rig.data as u8),
+                ("DA", d) => { rig.data = d == "1"; String::new() }
+                ("TX", "") => { rig.tx = true; String::new() }
+                ("RX", "") => { rig.tx = false; String::new() }
+                ("TQ", "") => format!("TQ{};", rig.tx as u8),
+                ("SM", "0") => "SM00015;".to_string(),
+                ("PC", "") => format!("PC{:03};", rig.power_w),
+                ("PC", p) => { rig.power_w = p.parse().unwrap(); String::new() }
+                _ => "?;".to_string(),
+            };
+            reply.into_bytes()
+        });
+        (KenwoodRig::new(serial, 100), state)
+    }
+
+    #[test]
+    fn test_frequency_roundtrip() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_frequency().unwrap(), 7_074_000);
+        rig.set_frequency(144_390_000).unwrap();
+        assert_eq!(state.lock().unwrap().freq, 144_390_000);
+        assert_eq!(rig.get_frequency().unwrap(), 144_390_000);
+    }
+
+    #[test]
+    fn test_mode_and_data_submode() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_mode().unwrap(), (RigMode::Usb, None));
+        rig.set_mode(RigMode::PktUsb, Some(3000)).unwrap();
+        assert!(state.lock().unwrap().data);
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::PktUsb);
+        rig.set_mode(RigMode::CwR, None).unwrap();
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::CwR);
+        assert!(rig.set_mode(RigMode::WideFm, None).is_err());
+    }
+
+    #[test]
+    fn test_ptt_meter_and_power() {
+        let (mut rig, _state) = fake_rig();
+        rig.set_ptt(true).unwrap();
+        assert!(rig.get_ptt().unwrap());
+        rig.set_ptt(false).unwrap();
+        assert!(!rig.get_ptt().unwrap());
+
+        assert_eq!(rig.get_signal_strength().unwrap(), 0, "SM 15 is S9");
+
+        rig.set_power(0.5).unwrap();
+        assert!((rig.get_power().unwrap() - 0.5).abs() < 1e-3);
+        assert!(rig.set_power(-0.1).is_err());
+    }
+}
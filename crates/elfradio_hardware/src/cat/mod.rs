@@ -0,0 +1,236 @@
+// CAT (Computer Aided Transceiver) rig control.
+//
+// `RigControl` is the common interface every CAT driver implements. Drivers
+// perform blocking I/O (TCP or serial), so async callers should go through
+// `tokio::task::spawn_blocking` when talking to a shared rig.
+//
+// Native serial drivers (Icom CI-V, Kenwood/Elecraft, Yaesu) are generic over
+// any `Read + Write` transport so they can be exercised against in-memory mocks.
+
+mod ascii;
+pub mod icom;
+pub mod kenwood;
+pub mod rigctld;
+pub mod yaesu;
+
+pub use icom::IcomCiv;
+pub use kenwood::KenwoodRig;
+pub use rigctld::RigctldClient;
+pub use yaesu::YaesuRig;
+
+use crate::HardwareError;
+use elfradio_types::HardwareConfig;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::info;
+
+/// Baud rate used for native CAT drivers when none is configured.
+pub const DEFAULT_CAT_BAUD_RATE: u32 = 9600;
+/// Full power assumed for watt-based protocols when none is configured.
+pub const DEFAULT_RIG_MAX_POWER_W: u32 = 100;
+/// Per-command I/O timeout for CAT connections.
+const CAT_IO_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// A rig control handle that can be shared between tasks.
+pub type SharedRigControl = Arc<Mutex<dyn RigControl>>;
+
+/// Supported CAT protocols, selected with `hardware.cat_protocol`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatProtocol {
+    /// Hamlib `rigctld` over TCP.
+    Rigctld,
+    /// Icom CI-V over serial.
+    IcomCiv,
+    /// Kenwood / Elecraft ASCII over serial.
+    Kenwood,
+    /// Yaesu "new CAT" ASCII over serial.
+    Yaesu,
+}
+
+impl FromStr for CatProtocol {
+    type Err = HardwareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "rigctld" | "hamlib" => Ok(CatProtocol::Rigctld),
+            "icom" | "civ" | "ci-v" | "icom_civ" => Ok(CatProtocol::IcomCiv),
+            "kenwood" | "elecraft" => Ok(CatProtocol::Kenwood),
+            "yaesu" => Ok(CatProtocol::Yaesu),
+            other => Err(HardwareError::CatError(format!("Unknown CAT protocol: {}", other))),
+        }
+    }
+}
+
+/// Opens the CAT connection described by `config`.
+///
+/// Returns `Ok(None)` when no CAT protocol is configured. Blocks while the
+/// port or socket is opened, so call it from `spawn_blocking` in async code.
+pub fn open_rig_control(config: &HardwareConfig) -> Result<Option<SharedRigControl>, HardwareError> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+    let rigctld_address = non_empty(&config.rigctld_address);
+
+    let protocol = match (non_empty(&config.cat_protocol), &rigctld_address) {
+        (Some(name), _) => name.parse::<CatProtocol>()?,
+        (None, Some(_)) => CatProtocol::Rigctld,
+        (None, None) => return Ok(None),
+    };
+
+    if protocol == CatProtocol::Rigctld {
+        let address = rigctld_address
+            .ok_or_else(|| HardwareError::CatError("cat_protocol is rigctld but rigctld_address is not set".to_string()))?;
+        return Ok(Some(Arc::new(Mutex::new(RigctldClient::connect(&address, CAT_IO_TIMEOUT * 3)?))));
+    }
+
+    let port_name = non_empty(&config.cat_serial_port)
+        .or_else(|| non_empty(&config.serial_port))
+        .ok_or_else(|| HardwareError::SerialPortNotFound("No serial port configured for CAT".to_string()))?;
+    let baud_rate = config.cat_baud_rate.unwrap_or(DEFAULT_CAT_BAUD_RATE);
+    let max_power_w = config.rig_max_power_w.unwrap_or(DEFAULT_RIG_MAX_POWER_W);
+
+    info!("Opening {:?} CAT on {} at {} baud", protocol, port_name, baud_rate);
+    let port = serialport::new(&port_name, baud_rate).timeout(CAT_IO_TIMEOUT).open()?;
+
+    let rig: SharedRigControl = match protocol {
+        CatProtocol::IcomCiv => {
+            let address = config
+                .civ_address
+                .or_else(|| config.rig_model.as_deref().and_then(icom::default_civ_address))
+                .ok_or_else(|| {
+                    HardwareError::CatError("Icom CAT needs civ_address or a known rig_model".to_string())
+                })?;
+            Arc::new(Mutex::new(IcomCiv::new(port, address)))
+        }
+        CatProtocol::Kenwood => Arc::new(Mutex::new(KenwoodRig::new(port, max_power_w))),
+        CatProtocol::Yaesu => Arc::new(Mutex::new(YaesuRig::new(port, max_power_w))),
+        CatProtocol::Rigctld => unreachable!("rigctld handled above"),
+    };
+    Ok(Some(rig))
+}
+
+/// Operating modes understood by the CAT layer.
+///
+/// The string form matches the Hamlib/rigctld mode names (`USB`, `PKTUSB`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RigMode {
+    Usb,
+    Lsb,
+    Cw,
+    CwR,
+    Am,
+    Fm,
+    WideFm,
+    Rtty,
+    RttyR,
+    PktUsb,
+    PktLsb,
+    PktFm,
+}
+
+impl RigMode {
+    /// Returns the Hamlib name of this mode.
+    pub fn as_hamlib_str(&self) -> &'static str {
+        match self {
+            RigMode::Usb => "USB",
+            RigMode::Lsb => "LSB",
+            RigMode::Cw => "CW",
+            RigMode::CwR => "CWR",
+            RigMode::Am => "AM",
+            RigMode::Fm => "FM",
+            RigMode::WideFm => "WFM",
+            RigMode::Rtty => "RTTY",
+            RigMode::RttyR => "RTTYR",
+            RigMode::PktUsb => "PKTUSB",
+            RigMode::PktLsb => "PKTLSB",
+            RigMode::PktFm => "PKTFM",
+        }
+    }
+}
+
+impl fmt::Display for RigMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_hamlib_str())
+    }
+}
+
+impl FromStr for RigMode {
+    type Err = HardwareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "USB" => Ok(RigMode::Usb),
+            "LSB" => Ok(RigMode::Lsb),
+            "CW" => Ok(RigMode::Cw),
+            "CWR" => Ok(RigMode::CwR),
+            "AM" => Ok(RigMode::Am),
+            "FM" => Ok(RigMode::Fm),
+            "WFM" => Ok(RigMode::WideFm),
+            "RTTY" => Ok(RigMode::Rtty),
+            "RTTYR" => Ok(RigMode::RttyR),
+            "PKTUSB" => Ok(RigMode::PktUsb),
+            "PKTLSB" => Ok(RigMode::PktLsb),
+            "PKTFM" => Ok(RigMode::PktFm),
+            other => Err(HardwareError::CatError(format!("Unknown rig mode: {}", other))),
+        }
+    }
+}
+
+/// Common interface for CAT rig control drivers.
+///
+/// All methods block until the rig has answered (or the driver's I/O timeout expires).
+pub trait RigControl: Send {
+    /// Returns the current VFO frequency in Hz.
+    fn get_frequency(&mut self) -> Result<u64, HardwareError>;
+
+    /// Tunes the current VFO to `freq_hz`.
+    fn set_frequency(&mut self, freq_hz: u64) -> Result<(), HardwareError>;
+
+    /// Returns the current mode and passband width in Hz (`None` if the rig doesn't report it).
+    fn get_mode(&mut self) -> Result<(RigMode, Option<u32>), HardwareError>;
+
+    /// Sets the mode. `passband_hz` of `None` keeps the rig's default passband for that mode.
+    fn set_mode(&mut self, mode: RigMode, passband_hz: Option<u32>) -> Result<(), HardwareError>;
+
+    /// Returns `true` if the rig is currently transmitting.
+    fn get_ptt(&mut self) -> Result<bool, HardwareError>;
+
+    /// Keys (`true`) or unkeys (`false`) the transmitter.
+    fn set_ptt(&mut self, on: bool) -> Result<(), HardwareError>;
+
+    /// Returns the S-meter reading in dB relative to S9 (S9 = 0, S0 = -54).
+    fn get_signal_strength(&mut self) -> Result<i32, HardwareError>;
+
+    /// Returns the RF power setting as a fraction of full power (0.0 to 1.0).
+    fn get_power(&mut self) -> Result<f32, HardwareError>;
+
+    /// Sets the RF power as a fraction of full power (0.0 to 1.0).
+    fn set_power(&mut self, level: f32) -> Result<(), HardwareError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elfradio_types::Config;
+
+    #[test]
+    fn test_cat_protocol_parsing() {
+        assert_eq!("ICOM".parse::<CatProtocol>().unwrap(), CatProtocol::IcomCiv);
+        assert_eq!("elecraft".parse::<CatProtocol>().unwrap(), CatProtocol::Kenwood);
+        assert_eq!("yaesu".parse::<CatProtocol>().unwrap(), CatProtocol::Yaesu);
+        assert!("flex".parse::<CatProtocol>().is_err());
+    }
+
+    #[test]
+    fn test_open_rig_control_configuration_errors() {
+        let mut hardware = Config::default().hardware;
+        assert!(open_rig_control(&hardware).unwrap().is_none(), "No CAT configured means no rig");
+
+        hardware.cat_protocol = Some("kenwood".to_string());
+        hardware.serial_port = None;
+        assert!(matches!(open_rig_control(&hardware), Err(HardwareError::SerialPortNotFound(_))));
+
+        hardware.cat_protocol = Some("rigctld".to_string());
+        assert!(matches!(open_rig_control(&hardware), Err(HardwareError::CatError(_))));
+    }
+}
@@ -0,0 +1,315 @@
+// Hamlib `rigctld` network protocol client.
+//
+// rigctld speaks a line-oriented text protocol over TCP (default port 4532).
+// "Get" commands answer with one value per line, "set" commands answer with
+// `RPRT <code>` where 0 means success and negative values are Hamlib errors.
+
+use super::{RigControl, RigMode};
+use crate::HardwareError;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tracing::{debug, info, trace};
+
+/// Default TCP port rigctld listens on.
+pub const RIGCTLD_DEFAULT_PORT: u16 = 4532;
+
+/// Appends the rigctld port to `address` unless it already names one. A bare IPv6
+/// address has colons of its own, so addresses are parsed rather than searched for ':'.
+fn with_default_port(address: &str) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        return address.to_string();
+    }
+    let bare_ip = address.strip_prefix('[').and_then(|a| a.strip_suffix(']')).unwrap_or(address);
+    if let Ok(ip) = bare_ip.parse::<IpAddr>() {
+        return SocketAddr::new(ip, RIGCTLD_DEFAULT_PORT).to_string();
+    }
+    if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, RIGCTLD_DEFAULT_PORT)
+    }
+}
+
+/// A CAT client talking to a running `rigctld` daemon.
+pub struct RigctldClient {
+    address: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl RigctldClient {
+    /// Connects to rigctld at `address` ("host:port", "[v6]:port", or a bare host or IP
+    /// address for the default port).
+    ///
+    /// `timeout` bounds both the connection attempt and every subsequent read/write.
+    pub fn connect(address: &str, timeout: Duration) -> Result<Self, HardwareError> {
+        let address = with_default_port(address);
+        debug!("Connecting to rigctld at {}", address);
+
+        let socket_addr = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| HardwareError::CatError(format!("Could not resolve rigctld address '{}'", address)))?;
+
+        let stream = TcpStream::connect_timeout(&socket_addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+
+        let writer = stream.try_clone()?;
+        info!("Connected to rigctld at {}", address);
+        Ok(Self {
+            address,
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+
+    /// Returns the address this client is connected to.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn read_reply_line(&mut self) -> Result<String, HardwareError> {
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)?;
+        if read == 0 {
+            return Err(HardwareError::CatError(format!("rigctld at {} closed the connection", self.address)));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    /// Sends a "get" command and reads `value_lines` lines of reply.
+    fn query(&mut self, command: &str, value_lines: usize) -> Result<Vec<String>, HardwareError> {
+        trace!(command, "rigctld query");
+        writeln!(self.writer, "{}", command)?;
+
+        let mut values = Vec::with_capacity(value_lines);
+        for _ in 0..value_lines {
+            let line = self.read_reply_line()?;
+            // rigctld answers a failed "get" with a single RPRT line instead of the values.
+            if let Some(code) = parse_rprt(&line) {
+                return Err(rejection(command, code));
+            }
+            values.push(line);
+        }
+        Ok(values)
+    }
+
+    /// Sends a "set" command and checks the `RPRT` status line.
+    fn command(&mut self, command: &str) -> Result<(), HardwareError> {
+        trace!(command, "rigctld command");
+        writeln!(self.writer, "{}", command)?;
+
+        let line = self.read_reply_line()?;
+        match parse_rprt(&line) {
+            Some(0) => Ok(()),
+            Some(code) => Err(rejection(command, code)),
+            None => Err(HardwareError::CatError(format!(
+                "Unexpected rigctld reply to '{}': '{}'",
+                command, line
+            ))),
+        }
+    }
+
+    fn query_value<T: std::str::FromStr>(&mut self, command: &str) -> Result<T, HardwareError> {
+        let value = self.query(command, 1)?.remove(0);
+        value.trim().parse::<T>().map_err(|_| {
+            HardwareError::CatError(format!("Could not parse rigctld reply to '{}': '{}'", command, value))
+        })
+    }
+}
+
+fn parse_rprt(line: &str) -> Option<i32> {
+    line.strip_prefix("RPRT ").and_then(|code| code.trim().parse().ok())
+}
+
+fn rejection(command: &str, code: i32) -> HardwareError {
+    HardwareError::CatCommandRejected {
+        command: command.to_string(),
+        code,
+    }
+}
+
+impl RigControl for RigctldClient {
+    fn get_frequency(&mut self) -> Result<u64, HardwareError> {
+        // rigctld may print the frequency as "14074000" or "14074000.000000".
+        let freq: f64 = self.query_value("f")?;
+        Ok(freq.round() as u64)
+    }
+
+    fn set_frequency(&mut self, freq_hz: u64) -> Result<(), HardwareError> {
+        self.command(&format!("F {}", freq_hz))
+    }
+
+    fn get_mode(&mut self) -> Result<(RigMode, Option<u32>), HardwareError> {
+        let lines = self.query("m", 2)?;
+        let mode: RigMode = lines[0].parse()?;
+        let passband = lines[1].trim().parse::<u32>().ok().filter(|&hz| hz > 0);
+        Ok((mode, passband))
+    }
+
+    fn set_mode(&mut self, mode: RigMode, passband_hz: Option<u32>) -> Result<(), HardwareError> {
+        // A passband of 0 asks Hamlib for the rig's default width for that mode.
+        self.command(&format!("M {} {}", mode.as_hamlib_str(), passband_hz.unwrap_or(0)))
+    }
+
+    fn get_ptt(&mut self) -> Result<bool, HardwareError> {
+        let ptt: u8 = self.query_value("t")?;
+        Ok(ptt != 0)
+    }
+
+    fn set_ptt(&mut self, on: bool) -> Result<(), HardwareError> {
+        self.command(if on { "T 1" } else { "T 0" })
+    }
+
+    fn get_signal_strength(&mut self) -> Result<i32, HardwareError> {
+        self.query_value("l STRENGTH")
+    }
+
+    fn get_power(&mut self) -> Result<f32, HardwareError> {
+        self.query_value("l RFPOWER")
+    }
+
+    fn set_power(&mut self, level: f32) -> Result<(), HardwareError> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(HardwareError::CatError(format!("RF power level {} is outside 0.0..=1.0", level)));
+        }
+        self.command(&format!("L RFPOWER {:.3}", level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// State of the fake rig behind the fake rigctld server.
+    struct FakeRig {
+        freq: u64,
+        mode: String,
+        passband: u32,
+        ptt: bool,
+        power: f32,
+    }
+
+    /// Spawns a minimal rigctld look-alike on an ephemeral port and returns its address.
+    fn spawn_fake_rigctld() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind fake rigctld");
+        let address = listener.local_addr().unwrap().to_string();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut writer = stream.try_clone().unwrap();
+            let reader = BufReader::new(stream);
+            let mut rig = FakeRig { freq: 7_074_000, mode: "USB".into(), passband: 2400, ptt: false, power: 0.5 };
+
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                let reply = match parts.as_slice() {
+                    ["f"] => format!("{}\n", rig.freq),
+                    ["F", freq] => match freq.parse() {
+                        Ok(f) => { rig.freq = f; "RPRT 0\n".to_string() }
+                        Err(_) => "RPRT -1\n".to_string(),
+                    },
+                    ["m"] => format!("{}\n{}\n", rig.mode, rig.passband),
+                    ["M", mode, passband] => {
+                        rig.mode = mode.to_string();
+                        rig.passband = match passband.parse().unwrap_or(0) { 0 => 2400, p => p };
+                        "RPRT 0\n".to_string()
+                    }
+                    ["t"] => format!("{}\n", rig.ptt as u8),
+                    ["T", state] => { rig.ptt = *state == "1"; "RPRT 0\n".to_string() }
+                    ["l", "STRENGTH"] => "-12\n".to_string(),
+                    ["l", "RFPOWER"] => format!("{:.6}\n", rig.power),
+                    ["L", "RFPOWER", level] => { rig.power = level.parse().unwrap(); "RPRT 0\n".to_string() }
+                    // Any other command is "not implemented" in Hamlib terms.
+                    _ => "RPRT -4\n".to_string(),
+                };
+                if writer.write_all(reply.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        address
+    }
+
+    #[test]
+    fn test_default_port() {
+        assert_eq!(with_default_port("localhost"), "localhost:4532");
+        assert_eq!(with_default_port("rig.lan:4533"), "rig.lan:4533");
+        assert_eq!(with_default_port("192.168.1.20"), "192.168.1.20:4532");
+        assert_eq!(with_default_port("::1"), "[::1]:4532");
+        assert_eq!(with_default_port("[fe80::1]"), "[fe80::1]:4532");
+        assert_eq!(with_default_port("[::1]:4533"), "[::1]:4533");
+    }
+
+    fn connect_fake() -> RigctldClient {
+        RigctldClient::connect(&spawn_fake_rigctld(), Duration::from_secs(2)).expect("connect to fake rigctld")
+    }
+
+    #[test]
+    fn test_frequency_roundtrip() {
+        let mut rig = connect_fake();
+        assert_eq!(rig.get_frequency().unwrap(), 7_074_000);
+        rig.set_frequency(14_074_000).unwrap();
+        assert_eq!(rig.get_frequency().unwrap(), 14_074_000);
+    }
+
+    #[test]
+    fn test_mode_roundtrip() {
+        let mut rig = connect_fake();
+        assert_eq!(rig.get_mode().unwrap(), (RigMode::Usb, Some(2400)));
+        rig.set_mode(RigMode::PktUsb, Some(3000)).unwrap();
+        assert_eq!(rig.get_mode().unwrap(), (RigMode::PktUsb, Some(3000)));
+        rig.set_mode(RigMode::Fm, None).unwrap();
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::Fm);
+    }
+
+    #[test]
+    fn test_ptt_meter_and_power() {
+        let mut rig = connect_fake();
+        assert!(!rig.get_ptt().unwrap());
+        rig.set_ptt(true).unwrap();
+        assert!(rig.get_ptt().unwrap());
+        rig.set_ptt(false).unwrap();
+        assert!(!rig.get_ptt().unwrap());
+
+        assert_eq!(rig.get_signal_strength().unwrap(), -12);
+
+        rig.set_power(0.25).unwrap();
+        assert!((rig.get_power().unwrap() - 0.25).abs() < 1e-3);
+        assert!(rig.set_power(1.5).is_err(), "Out-of-range power must be rejected locally");
+    }
+
+    #[test]
+    fn test_rejected_command_reports_code() {
+        let mut rig = connect_fake();
+        match rig.command("U TUNER 1") {
+            Err(HardwareError::CatCommandRejected { code, .. }) => assert_eq!(code, -4),
+            other => panic!("Expected CatCommandRejected, got {:?}", other),
+        }
+        // The connection stays usable after a rejected command.
+        assert_eq!(rig.get_frequency().unwrap(), 7_074_000);
+    }
+
+    #[test]
+    fn test_connect_refused() {
+        // Bind and immediately drop a listener to get a port nobody listens on.
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let result = RigctldClient::connect(&format!("127.0.0.1:{}", port), Duration::from_millis(500));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rig_mode_parsing() {
+        assert_eq!("pktusb".parse::<RigMode>().unwrap(), RigMode::PktUsb);
+        assert_eq!(RigMode::CwR.to_string(), "CWR");
+        assert!("FOO".parse::<RigMode>().is_err());
+    }
+}
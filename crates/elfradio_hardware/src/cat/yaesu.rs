@@ -0,0 +1,227 @@
+// Yaesu "new CAT" ASCII driver (FT-991/FT-991A, FT-891, FT-710, FTDX10, FTDX101).
+//
+// Same `XX...;` framing as Kenwood, but with Yaesu's own parameter layout:
+// 9-digit `FA`, `MD0x` with hex mode codes, `TX0/TX1` and 0..=255 meters.
+
+use super::ascii::{interpolate_meter, parse_decimal, AsciiCatPort};
+use super::{RigControl, RigMode};
+use crate::HardwareError;
+use std::io::{Read, Write};
+use tracing::debug;
+
+/// S-meter calibration (`SM0` raw 0..=255 → dB relative to S9), FT-991 scale.
+const SMETER_TABLE: [(f32, f32); 16] = [
+    (0.0, -54.0),
+    (12.0, -48.0),
+    (27.0, -42.0),
+    (40.0, -36.0),
+    (55.0, -30.0),
+    (65.0, -24.0),
+    (80.0, -18.0),
+    (95.0, -12.0),
+    (112.0, -6.0),
+    (130.0, 0.0),
+    (150.0, 10.0),
+    (172.0, 20.0),
+    (190.0, 30.0),
+    (220.0, 40.0),
+    (240.0, 50.0),
+    (255.0, 60.0),
+];
+
+/// CAT driver for current Yaesu HF/VHF rigs.
+pub struct YaesuRig<T> {
+    port: AsciiCatPort<T>,
+    max_power_w: f32,
+}
+
+impl<T: Read + Write + Send> YaesuRig<T> {
+    /// Wraps an open transport (normally a serial port).
+    ///
+    /// `max_power_w` is the rig's full output power (100 W for the FT-991, 200 W for the FTDX101MP).
+    pub fn new(transport: T, max_power_w: u32) -> Self {
+        Self {
+            port: AsciiCatPort::new(transport),
+            max_power_w: max_power_w.max(1) as f32,
+        }
+    }
+}
+
+fn mode_from_code(code: char) -> Result<RigMode, HardwareError> {
+    let mode = match code {
+        '1' => RigMode::Lsb,
+        '2' => RigMode::Usb,
+        '3' => RigMode::Cw,
+        '4' | 'B' | 'E' => RigMode::Fm, // FM, FM-N, C4FM
+        '5' | 'D' => RigMode::Am,       // AM, AM-N
+        '6' => RigMode::Rtty,
+        '7' => RigMode::CwR,
+        '8' => RigMode::PktLsb,
+        '9' => RigMode::RttyR,
+        'A' => RigMode::PktFm,
+        'C' => RigMode::PktUsb,
+        other => return Err(HardwareError::CatError(format!("Unknown Yaesu mode code '{}'", other))),
+    };
+    Ok(mode)
+}
+
+fn mode_to_code(mode: RigMode) -> Result<char, HardwareError> {
+    let code = match mode {
+        RigMode::Lsb => '1',
+        RigMode::Usb => '2',
+        RigMode::Cw => '3',
+        RigMode::Fm => '4',
+        RigMode::Am => '5',
+        RigMode::Rtty => '6',
+        RigMode::CwR => '7',
+        RigMode::PktLsb => '8',
+        RigMode::RttyR => '9',
+        RigMode::PktFm => 'A',
+        RigMode::PktUsb => 'C',
+        RigMode::WideFm => {
+            return Err(HardwareError::CatError("Yaesu CAT has no wide FM mode".to_string()))
+        }
+    };
+    Ok(code)
+}
+
+impl<T: Read + Write + Send> RigControl for YaesuRig<T> {
+    fn get_frequency(&mut self) -> Result<u64, HardwareError> {
+        parse_decimal(&self.port.query("FA")?, "frequency")
+    }
+
+    fn set_frequency(&mut self, freq_hz: u64) -> Result<(), HardwareError> {
+        self.port.send(&format!("FA{:09}", freq_hz))
+    }
+
+    fn get_mode(&mut self) -> Result<(RigMode, Option<u32>), HardwareError> {
+        // "MD0" → "MD0x": main VFO, single mode character.
+        let value = self.port.query("MD0")?;
+        let code = value
+            .chars()
+            .nth(1)
+            .ok_or_else(|| HardwareError::CatError(format!("Short Yaesu mode reply 'MD{}'", value)))?;
+        Ok((mode_from_code(code.to_ascii_uppercase())?, None))
+    }
+
+    fn set_mode(&mut self, mode: RigMode, passband_hz: Option<u32>) -> Result<(), HardwareError> {
+        if passband_hz.is_some() {
+            debug!("Yaesu driver ignores passband width; keeping the rig's filter setting");
+        }
+        self.port.send(&format!("MD0{}", mode_to_code(mode)?))
+    }
+
+    fn get_ptt(&mut self) -> Result<bool, HardwareError> {
+        // TX0 = receive, TX1 = CAT transmit, TX2 = transmit from MIC/DATA PTT.
+        Ok(self.port.query("TX")? != "0")
+    }
+
+    fn set_ptt(&mut self, on: bool) -> Result<(), HardwareError> {
+        self.port.send(if on { "TX1" } else { "TX0" })
+    }
+
+    fn get_signal_strength(&mut self) -> Result<i32, HardwareError> {
+        let value = self.port.query("SM0")?;
+        let raw: u32 = parse_decimal(value.get(1..).unwrap_or(""), "S-meter")?;
+        Ok(interpolate_meter(raw as f32, &SMETER_TABLE))
+    }
+
+    fn get_power(&mut self) -> Result<f32, HardwareError> {
+        let watts: u32 = parse_decimal(&self.port.query("PC")?, "RF power")?;
+        Ok((watts as f32 / self.max_power_w).clamp(0.0, 1.0))
+    }
+
+    fn set_power(&mut self, level: f32) -> Result<(), HardwareError> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(HardwareError::CatError(format!("RF power level {} is outside 0.0..=1.0", level)));
+        }
+        // Yaesu rejects 0 W; 5 W is the lowest setting on HF rigs.
+        let watts = ((level * self.max_power_w).round() as u32).max(5);
+        self.port.send(&format!("PC{:03}", watts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cat::ascii::mock::MockSerial;
+    use std::sync::{Arc, Mutex};
+
+    struct FakeFt991 {
+        freq: u64,
+        mode: char,
+        tx: u8,
+        power_w: u32,
+    }
+
+    /// Emulates the subset of the FT-991 command set the driver uses.
+    fn fake_rig() -> (YaesuRig<MockSerial>, Arc<Mutex<FakeFt991>>) {
+        let state = Arc::new(Mutex::new(FakeFt991 { freq: 14_074_000, mode: '2', tx: 0, power_w: 50 }));
+        let rig_state = state.clone();
+        let serial = MockSerial::new(move |bytes: &[u8]| {
+            let cmd = std::str::from_utf8(bytes).unwrap().trim_end_matches(';');
+            let mut rig = rig_state.lock().unwrap();
+            let reply = match (cmd.get(..2).unwrap_or(""), cmd.get(2..).unwrap_or("")) {
+                ("FA", "") => format!("FA{:09};", rig.freq),
+                ("FA", f) if f.len() == 9 => { rig.freq = f.parse().unwrap(); String::new() }
+                ("MD", "0") => format!("MD0{};", rig.mode),
+                ("MD", m) => { rig.mode = m.chars().nth(1).unwrap(); String::new() }
+                ("TX", "") => format!("TX{};", rig.tx),
+                ("TX", t) => { rig.tx = t.parse().unwrap(); String::new() }
+                ("SM", "0") => "SM0130;".to_string(),
+                ("PC", "") => format!("PC{:03};", rig.power_w),
+                ("PC", p) => { rig.power_w = p.parse().unwrap(); String::new() }
+                _ => "?;".to_string(),
+            };
+            reply.into_bytes()
+        });
+        (YaesuRig::new(serial, 100), state)
+    }
+
+    #[test]
+    fn test_frequency_roundtrip() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_frequency().unwrap(), 14_074_000);
+        rig.set_frequency(433_500_000).unwrap();
+        assert_eq!(state.lock().unwrap().freq, 433_500_000);
+        assert_eq!(rig.get_frequency().unwrap(), 433_500_000);
+    }
+
+    #[test]
+    fn test_mode_roundtrip() {
+        let (mut rig, state) = fake_rig();
+        assert_eq!(rig.get_mode().unwrap(), (RigMode::Usb, None));
+        rig.set_mode(RigMode::PktUsb, None).unwrap();
+        assert_eq!(state.lock().unwrap().mode, 'C');
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::PktUsb);
+
+        // FM-N reads back as plain FM.
+        state.lock().unwrap().mode = 'B';
+        assert_eq!(rig.get_mode().unwrap().0, RigMode::Fm);
+    }
+
+    #[test]
+    fn test_ptt_meter_and_power() {
+        let (mut rig, _state) = fake_rig();
+        assert!(!rig.get_ptt().unwrap());
+        rig.set_ptt(true).unwrap();
+        assert!(rig.get_ptt().unwrap());
+        rig.set_ptt(false).unwrap();
+        assert!(!rig.get_ptt().unwrap());
+
+        assert_eq!(rig.get_signal_strength().unwrap(), 0, "SM 130 is S9");
+
+        assert!((rig.get_power().unwrap() - 0.5).abs() < 1e-3);
+        rig.set_power(0.0).unwrap();
+        assert!((rig.get_power().unwrap() - 0.05).abs() < 1e-3, "Power is clamped to the 5 W minimum");
+    }
+
+    #[test]
+    fn test_rejected_command() {
+        let (mut rig, _state) = fake_rig();
+        assert!(matches!(
+            rig.port.query("XX"),
+            Err(HardwareError::CatCommandRejected { .. })
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+// Device discovery for the settings page: audio devices with their
+// capabilities and serial ports with their USB identification.
+
+use crate::HardwareError;
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::SupportedStreamConfigRange;
+use elfradio_types::{AudioDeviceInfo, AudioDirection, SerialPortInfo};
+use serialport::SerialPortType;
+use tracing::{debug, trace, warn};
+
+/// Sample rates offered to the user when a device supports a continuous range.
+const COMMON_SAMPLE_RATES: [u32; 11] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Lists every audio input and output device with its capabilities.
+///
+/// A device that can both capture and play appears twice, once per direction.
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, HardwareError> {
+    debug!("Listing audio devices...");
+    let host = cpal::default_host();
+    let default_input = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output = host.default_output_device().and_then(|d| d.name().ok());
+    let mut devices = Vec::new();
+
+    for device in host.input_devices()? {
+        let name = device.name()?;
+        trace!("Found input device: {}", name);
+        let configs = match device.supported_input_configs() {
+            Ok(configs) => configs.collect(),
+            Err(e) => {
+                warn!("Can't query input configurations of {}: {}", name, e);
+                Vec::new()
+            }
+        };
+        let is_default = default_input.as_deref() == Some(name.as_str());
+        devices.push(describe_device(name, AudioDirection::Input, is_default, &configs));
+    }
+
+    for device in host.output_devices()? {
+        let name = device.name()?;
+        trace!("Found output device: {}", name);
+        let configs = match device.supported_output_configs() {
+            Ok(configs) => configs.collect(),
+            Err(e) => {
+                warn!("Can't query output configurations of {}: {}", name, e);
+                Vec::new()
+            }
+        };
+        let is_default = default_output.as_deref() == Some(name.as_str());
+        devices.push(describe_device(name, AudioDirection::Output, is_default, &configs));
+    }
+
+    debug!("Found {} audio device entries.", devices.len());
+    Ok(devices)
+}
+
+/// Summarizes a device's supported configuration ranges.
+fn describe_device(
+    name: String,
+    direction: AudioDirection,
+    is_default: bool,
+    configs: &[SupportedStreamConfigRange],
+) -> AudioDeviceInfo {
+    let mut sample_rates: Vec<u32> = configs
+        .iter()
+        .flat_map(|range| {
+            let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+            // Keep odd range limits (e.g. a fixed 24 kHz device) alongside the common rates inside the range.
+            COMMON_SAMPLE_RATES.into_iter().filter(move |rate| (min..=max).contains(rate)).chain([min, max])
+        })
+        .collect();
+    sample_rates.sort_unstable();
+    sample_rates.dedup();
+
+    let mut sample_formats: Vec<String> = Vec::new();
+    for range in configs {
+        let format = range.sample_format().to_string();
+        if !sample_formats.contains(&format) {
+            sample_formats.push(format);
+        }
+    }
+
+    let mut channels: Vec<u16> = configs.iter().map(|range| range.channels()).collect();
+    channels.sort_unstable();
+    channels.dedup();
+
+    AudioDeviceInfo { name, direction, is_default, sample_rates, sample_formats, channels }
+}
+
+/// Lists available serial ports suitable for PTT/CAT, with USB VID/PID and serial number where known.
+pub fn list_serial_ports() -> Result<Vec<SerialPortInfo>, HardwareError> {
+    debug!("Listing available serial ports...");
+    let ports: Vec<SerialPortInfo> = serialport::available_ports()?.into_iter().map(describe_port).collect();
+    debug!("Found {} serial ports.", ports.len());
+    Ok(ports)
+}
+
+fn describe_port(port: serialport::SerialPortInfo) -> SerialPortInfo {
+    trace!("Found port: {}, type: {:?}", port.port_name, port.port_type);
+    let mut info = SerialPortInfo {
+        name: port.port_name,
+        port_type: "unknown".to_string(),
+        vid: None,
+        pid: None,
+        serial_number: None,
+        manufacturer: None,
+        product: None,
+    };
+    match port.port_type {
+        SerialPortType::UsbPort(usb) => {
+            info.port_type = "usb".to_string();
+            info.vid = Some(usb.vid);
+            info.pid = Some(usb.pid);
+            info.serial_number = usb.serial_number;
+            info.manufacturer = usb.manufacturer;
+            info.product = usb.product;
+        }
+        SerialPortType::PciPort => info.port_type = "pci".to_string(),
+        SerialPortType::BluetoothPort => info.port_type = "bluetooth".to_string(),
+        SerialPortType::Unknown => {}
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::{SampleFormat, SampleRate, SupportedBufferSize};
+
+    fn range(channels: u16, min: u32, max: u32, format: SampleFormat) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(channels, SampleRate(min), SampleRate(max), SupportedBufferSize::Unknown, format)
+    }
+
+    #[test]
+    fn test_describe_device_summarizes_ranges() {
+        let configs = [
+            range(2, 8000, 48000, SampleFormat::I16),
+            range(1, 8000, 48000, SampleFormat::I16),
+            range(2, 24000, 24000, SampleFormat::F32),
+        ];
+        let info = describe_device("USB Audio CODEC".to_string(), AudioDirection::Input, true, &configs);
+        assert_eq!(info.sample_rates, vec![8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000]);
+        assert_eq!(info.sample_formats, vec!["i16".to_string(), "f32".to_string()]);
+        assert_eq!(info.channels, vec![1, 2]);
+        assert!(info.is_default);
+
+        let empty = describe_device("Broken".to_string(), AudioDirection::Output, false, &[]);
+        assert!(empty.sample_rates.is_empty() && empty.channels.is_empty());
+    }
+
+    #[test]
+    fn test_describe_port_types() {
+        let pci = describe_port(serialport::SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: SerialPortType::PciPort,
+        });
+        assert_eq!(pci.port_type, "pci");
+        assert_eq!(pci.vid, None);
+
+        let unknown = describe_port(serialport::SerialPortInfo {
+            port_name: "/dev/ttyACM9".to_string(),
+            port_type: SerialPortType::Unknown,
+        });
+        assert_eq!(unknown.port_type, "unknown");
+
+        let usb = describe_port(serialport::SerialPortInfo {
+            port_name: "/dev/ttyUSB0".to_string(),
+            port_type: SerialPortType::UsbPort(serialport::UsbPortInfo {
+                vid: 0x10c4,
+                pid: 0xea60,
+                serial_number: Some("IC7300-03001234".to_string()),
+                manufacturer: Some("Silicon Labs".to_string()),
+                product: Some("CP2102 USB to UART Bridge Controller".to_string()),
+            }),
+        });
+        assert_eq!(usb.port_type, "usb");
+        assert_eq!((usb.vid, usb.pid), (Some(0x10c4), Some(0xea60)));
+        assert_eq!(usb.serial_number.as_deref(), Some("IC7300-03001234"));
+    }
+}
@@ -0,0 +1,104 @@
+// CM108/CM119 (and compatible: CM108AH, CM109, SSS1623) USB audio GPIO PTT.
+//
+// These chips expose their GPIO pins through a HID output report. Writing the
+// 5-byte report `00 00 <mask> <data> 00` to the hidraw device sets the pins in
+// `mask` to the bits in `data`. Most interfaces wire PTT to GPIO3.
+
+use super::PttBackend;
+use crate::HardwareError;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error};
+
+/// GPIO pin most CM108 interfaces use for PTT.
+pub const CM108_DEFAULT_GPIO: u8 = 3;
+
+/// Keys the transmitter through a GPIO pin of a CM108-family USB audio chip.
+pub struct Cm108Ptt {
+    path: PathBuf,
+    device: File,
+    gpio: u8,
+}
+
+impl Cm108Ptt {
+    /// Opens the hidraw device (e.g. `/dev/hidraw0`) and drives `gpio` (1-8) low.
+    pub fn open(path: impl AsRef<Path>, gpio: u8) -> Result<Self, HardwareError> {
+        let path = path.as_ref().to_path_buf();
+        if !(1..=8).contains(&gpio) {
+            return Err(HardwareError::PttError(format!("CM108 GPIO must be 1-8, got {}", gpio)));
+        }
+
+        let device = OpenOptions::new().write(true).open(&path).map_err(|e| {
+            error!("Failed to open CM108 HID device {}: {}", path.display(), e);
+            HardwareError::PttError(format!("Cannot open CM108 HID device {}: {}", path.display(), e))
+        })?;
+
+        let mut ptt = Self { path, device, gpio };
+        ptt.set_keyed(false)?;
+        debug!(device = %ptt.path.display(), gpio, "Opened CM108 PTT");
+        Ok(ptt)
+    }
+}
+
+/// Builds the HID output report that sets `gpio` (1-based) high or low.
+fn gpio_report(gpio: u8, high: bool) -> [u8; 5] {
+    let mask = 1u8 << (gpio - 1);
+    [0x00, 0x00, mask, if high { mask } else { 0x00 }, 0x00]
+}
+
+impl PttBackend for Cm108Ptt {
+    fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError> {
+        let report = gpio_report(self.gpio, keyed);
+        let written = self.device.write(&report)?;
+        if written != report.len() {
+            return Err(HardwareError::PttError(format!(
+                "Short write to CM108 HID device {} ({} of {} bytes)",
+                self.path.display(),
+                written,
+                report.len()
+            )));
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("CM108 GPIO{} on {}", self.gpio, self.path.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_written_to_fake_hidraw() {
+        let dir = tempfile::tempdir().unwrap();
+        let fake_hidraw = dir.path().join("hidraw0");
+        std::fs::write(&fake_hidraw, b"").unwrap();
+
+        let mut ptt = Cm108Ptt::open(&fake_hidraw, CM108_DEFAULT_GPIO).unwrap();
+        ptt.set_keyed(true).unwrap();
+        ptt.set_keyed(false).unwrap();
+
+        let written = std::fs::read(&fake_hidraw).unwrap();
+        assert_eq!(
+            written,
+            [
+                [0, 0, 0x04, 0x00, 0], // Unkey on open
+                [0, 0, 0x04, 0x04, 0], // Key
+                [0, 0, 0x04, 0x00, 0], // Unkey
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn test_invalid_gpio_and_missing_device() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Cm108Ptt::open(dir.path().join("hidraw0"), 0).is_err());
+        assert!(Cm108Ptt::open(dir.path().join("missing"), 3).is_err());
+        assert_eq!(gpio_report(1, true), [0, 0, 0x01, 0x01, 0]);
+        assert_eq!(gpio_report(8, false), [0, 0, 0x80, 0x00, 0]);
+    }
+}
@@ -0,0 +1,153 @@
+// Linux GPIO character device (`/dev/gpiochipN`) PTT.
+//
+// Uses the v1 line-handle uAPI: GPIO_GET_LINEHANDLE_IOCTL requests a line as
+// an output and returns a handle fd, and GPIOHANDLE_SET_LINE_VALUES_IOCTL on
+// that fd drives it. The kernel releases the line (and our claim on it) when
+// the handle is closed.
+
+use super::PttBackend;
+use crate::HardwareError;
+use std::fs::{File, OpenOptions};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+use tracing::{debug, error};
+
+const GPIOHANDLES_MAX: usize = 64;
+const GPIOHANDLE_REQUEST_OUTPUT: u32 = 1 << 1;
+const GPIOHANDLE_REQUEST_ACTIVE_LOW: u32 = 1 << 2;
+
+/// `_IOWR(0xB4, 0x03, struct gpiohandle_request)`
+const GPIO_GET_LINEHANDLE_IOCTL: u32 = ioctl_iowr(0x03, std::mem::size_of::<GpioHandleRequest>());
+/// `_IOWR(0xB4, 0x09, struct gpiohandle_data)`
+const GPIOHANDLE_SET_LINE_VALUES_IOCTL: u32 = ioctl_iowr(0x09, std::mem::size_of::<GpioHandleData>());
+
+const fn ioctl_iowr(nr: u32, size: usize) -> u32 {
+    (3 << 30) | ((size as u32) << 16) | (0xB4 << 8) | nr
+}
+
+/// `struct gpiohandle_request` from `<linux/gpio.h>`.
+#[repr(C)]
+struct GpioHandleRequest {
+    line_offsets: [u32; GPIOHANDLES_MAX],
+    flags: u32,
+    default_values: [u8; GPIOHANDLES_MAX],
+    consumer_label: [u8; 32],
+    lines: u32,
+    fd: i32,
+}
+
+/// `struct gpiohandle_data` from `<linux/gpio.h>`.
+#[repr(C)]
+struct GpioHandleData {
+    values: [u8; GPIOHANDLES_MAX],
+}
+
+fn line_request(offset: u32, active_low: bool) -> GpioHandleRequest {
+    let mut request = GpioHandleRequest {
+        line_offsets: [0; GPIOHANDLES_MAX],
+        flags: GPIOHANDLE_REQUEST_OUTPUT,
+        default_values: [0; GPIOHANDLES_MAX],
+        consumer_label: [0; 32],
+        lines: 1,
+        fd: -1,
+    };
+    request.line_offsets[0] = offset;
+    if active_low {
+        request.flags |= GPIOHANDLE_REQUEST_ACTIVE_LOW;
+    }
+    let label = b"elfradio-ptt";
+    request.consumer_label[..label.len()].copy_from_slice(label);
+    request
+}
+
+/// Keys the transmitter with one line of a Linux GPIO chip.
+pub struct GpioChipPtt {
+    path: PathBuf,
+    offset: u32,
+    /// Line handle returned by the kernel; closing it releases the line.
+    handle: File,
+}
+
+impl GpioChipPtt {
+    /// Requests line `offset` of `path` (e.g. `/dev/gpiochip0`) as an output, initially inactive.
+    ///
+    /// With `active_low` the kernel inverts the line, so "keyed" drives it low.
+    pub fn open(path: impl AsRef<Path>, offset: u32, active_low: bool) -> Result<Self, HardwareError> {
+        let path = path.as_ref().to_path_buf();
+        let chip = OpenOptions::new().read(true).write(true).open(&path).map_err(|e| {
+            error!("Failed to open GPIO chip {}: {}", path.display(), e);
+            HardwareError::PttError(format!("Cannot open GPIO chip {}: {}", path.display(), e))
+        })?;
+
+        let mut request = line_request(offset, active_low);
+        // SAFETY: `request` is a valid, properly sized `gpiohandle_request` that outlives the call.
+        let rc = unsafe { libc::ioctl(chip.as_raw_fd(), GPIO_GET_LINEHANDLE_IOCTL as _, &mut request) };
+        if rc < 0 || request.fd < 0 {
+            let e = std::io::Error::last_os_error();
+            return Err(HardwareError::PttError(format!(
+                "{} is not a usable GPIO chip or line {} is unavailable: {}",
+                path.display(),
+                offset,
+                e
+            )));
+        }
+
+        // SAFETY: the kernel handed us a fresh fd that nothing else owns.
+        let handle = unsafe { File::from_raw_fd(request.fd) };
+        debug!(chip = %path.display(), offset, active_low, "Requested GPIO PTT line");
+        Ok(Self { path, offset, handle })
+    }
+}
+
+impl PttBackend for GpioChipPtt {
+    fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError> {
+        let mut data = GpioHandleData { values: [0; GPIOHANDLES_MAX] };
+        data.values[0] = keyed as u8;
+        // SAFETY: `data` is a valid `gpiohandle_data` and `handle` is an open line handle fd.
+        let rc = unsafe { libc::ioctl(self.handle.as_raw_fd(), GPIOHANDLE_SET_LINE_VALUES_IOCTL as _, &mut data) };
+        if rc < 0 {
+            return Err(HardwareError::IoError(std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("GPIO line {} on {}", self.offset, self.path.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ioctl_numbers_match_linux_headers() {
+        assert_eq!(std::mem::size_of::<GpioHandleRequest>(), 364);
+        assert_eq!(GPIO_GET_LINEHANDLE_IOCTL, 0xC16C_B403);
+        assert_eq!(GPIOHANDLE_SET_LINE_VALUES_IOCTL, 0xC040_B409);
+    }
+
+    #[test]
+    fn test_line_request() {
+        let request = line_request(17, true);
+        assert_eq!(request.line_offsets[0], 17);
+        assert_eq!(request.lines, 1);
+        assert_eq!(request.flags, GPIOHANDLE_REQUEST_OUTPUT | GPIOHANDLE_REQUEST_ACTIVE_LOW);
+        assert!(request.consumer_label.starts_with(b"elfradio-ptt\0"));
+    }
+
+    #[test]
+    fn test_fake_gpio_device_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let fake_chip = dir.path().join("gpiochip0");
+        std::fs::write(&fake_chip, b"").unwrap();
+
+        // A regular file doesn't understand the line-handle ioctl.
+        match GpioChipPtt::open(&fake_chip, 4, false) {
+            Err(HardwareError::PttError(msg)) => assert!(msg.contains("not a usable GPIO chip"), "{}", msg),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("Fake gpiochip must be rejected"),
+        }
+        assert!(GpioChipPtt::open(dir.path().join("missing"), 4, false).is_err());
+    }
+}
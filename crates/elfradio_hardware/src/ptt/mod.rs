@@ -0,0 +1,387 @@
+// Persistent PTT control.
+//
+// `PttController` keeps the keying line open for the lifetime of the
+// application instead of reopening it for every key and unkey. The line itself
+// is a pluggable `PttBackend`: serial RTS/DTR, CAT, CM108 HID GPIO or a Linux
+// gpiochip line. A watchdog thread unkeys the transmitter if it stays keyed longer than
+// the configured limit, and `PttGuard` unkeys on drop so an early return or a
+// panic in the transmit path can never leave the rig transmitting.
+
+mod cm108;
+#[cfg(target_os = "linux")]
+mod gpio;
+mod serial;
+
+pub use cm108::Cm108Ptt;
+#[cfg(target_os = "linux")]
+pub use gpio::GpioChipPtt;
+pub use serial::SerialPtt;
+
+use crate::cat::SharedRigControl;
+use crate::HardwareError;
+use elfradio_types::{HardwareConfig, PttSignal};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// A physical or logical line that keys the transmitter.
+///
+/// Implementations should hold their device open; `set_keyed` is called on every key and unkey.
+pub trait PttBackend: Send {
+    /// Keys (`true`) or unkeys (`false`) the transmitter.
+    fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError>;
+
+    /// Human readable description for logs (e.g. "RTS on /dev/ttyUSB0").
+    fn describe(&self) -> String;
+}
+
+/// Keys the transmitter through a CAT rig control connection.
+pub struct CatPtt {
+    rig: SharedRigControl,
+}
+
+impl CatPtt {
+    /// Wraps a shared rig control handle.
+    pub fn new(rig: SharedRigControl) -> Self {
+        Self { rig }
+    }
+}
+
+impl PttBackend for CatPtt {
+    fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError> {
+        let mut rig = self.rig.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        rig.set_ptt(keyed)
+    }
+
+    fn describe(&self) -> String {
+        "CAT".to_string()
+    }
+}
+
+struct PttState {
+    line: Box<dyn PttBackend>,
+    /// When the current transmission started; `None` while unkeyed.
+    keyed_since: Option<Instant>,
+    /// Set when the watchdog forced an unkey during the current keying.
+    watchdog_tripped: bool,
+    shutdown: bool,
+}
+
+struct Shared {
+    state: Mutex<PttState>,
+    wakeup: Condvar,
+    watchdog: Option<Duration>,
+}
+
+impl Shared {
+    /// Locks the state even if a previous holder panicked; releasing PTT matters more than the poison.
+    fn lock(&self) -> MutexGuard<'_, PttState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Long-lived owner of the PTT line with a transmit-time watchdog.
+pub struct PttController {
+    shared: Arc<Shared>,
+    description: String,
+    watchdog_thread: Option<JoinHandle<()>>,
+}
+
+impl PttController {
+    /// Builds a controller from the hardware configuration.
+    ///
+    /// `rig` is required when `ptt_signal` is "cat". `watchdog` of `None` (or zero)
+    /// disables the transmit-time limit.
+    pub fn from_config(
+        config: &HardwareConfig,
+        rig: Option<SharedRigControl>,
+        watchdog: Option<Duration>,
+    ) -> Result<Self, HardwareError> {
+        let signal: PttSignal = config
+            .ptt_signal
+            .parse()
+            .map_err(|e| HardwareError::PttError(format!("{:?}", e)))?;
+
+        let backend: Box<dyn PttBackend> = match signal {
+            PttSignal::Cat => {
+                let rig = rig.ok_or_else(|| HardwareError::PttError("CAT PTT requires a connected rig".to_string()))?;
+                Box::new(CatPtt::new(rig))
+            }
+            PttSignal::Rts | PttSignal::Dtr => {
+                let port_name = config
+                    .serial_port
+                    .as_deref()
+                    .filter(|p| !p.trim().is_empty())
+                    .ok_or_else(|| HardwareError::SerialPortNotFound("No PTT serial port configured".to_string()))?;
+                Box::new(SerialPtt::open(port_name, signal)?)
+            }
+            PttSignal::Cm108 => {
+                let gpio = config.ptt_gpio_line.unwrap_or(cm108::CM108_DEFAULT_GPIO as u32);
+                let gpio = u8::try_from(gpio)
+                    .map_err(|_| HardwareError::PttError(format!("CM108 GPIO {} is out of range", gpio)))?;
+                Box::new(Cm108Ptt::open(ptt_device(config)?, gpio)?)
+            }
+            #[cfg(target_os = "linux")]
+            PttSignal::Gpio => {
+                let line = config
+                    .ptt_gpio_line
+                    .ok_or_else(|| HardwareError::PttError("gpio PTT requires ptt_gpio_line".to_string()))?;
+                Box::new(GpioChipPtt::open(ptt_device(config)?, line, config.ptt_active_low)?)
+            }
+            #[cfg(not(target_os = "linux"))]
+            PttSignal::Gpio => {
+                return Err(HardwareError::PttError("gpiochip PTT is only available on Linux".to_string()))
+            }
+        };
+        Ok(Self::new(backend, watchdog))
+    }
+
+    /// Creates a controller that keys through CAT.
+    pub fn with_rig_control(rig: SharedRigControl, watchdog: Option<Duration>) -> Self {
+        Self::new(Box::new(CatPtt::new(rig)), watchdog)
+    }
+
+    /// Creates a controller around any PTT backend.
+    pub fn new(line: Box<dyn PttBackend>, watchdog: Option<Duration>) -> Self {
+        let watchdog = watchdog.filter(|d| !d.is_zero());
+        let description = line.describe();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(PttState { line, keyed_since: None, watchdog_tripped: false, shutdown: false }),
+            wakeup: Condvar::new(),
+            watchdog,
+        });
+
+        let watchdog_thread = watchdog.map(|limit| {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("ptt-watchdog".to_string())
+                .spawn(move || run_watchdog(shared, limit))
+                .expect("failed to spawn PTT watchdog thread")
+        });
+
+        info!(line = %description, ?watchdog, "PTT controller ready");
+        Self { shared, description, watchdog_thread }
+    }
+
+    /// Keys the transmitter and returns a guard that unkeys when released or dropped.
+    pub fn key(self: &Arc<Self>) -> Result<PttGuard, HardwareError> {
+        let mut state = self.shared.lock();
+        if state.keyed_since.is_some() {
+            return Err(HardwareError::PttError(format!("PTT ({}) is already keyed", self.description)));
+        }
+        debug!(line = %self.description, "Keying PTT");
+        state.line.set_keyed(true)?;
+        state.keyed_since = Some(Instant::now());
+        state.watchdog_tripped = false;
+        self.shared.wakeup.notify_all();
+        Ok(PttGuard { controller: self.clone(), released: false })
+    }
+
+    /// Unkeys the transmitter. Safe to call when already unkeyed.
+    pub fn unkey(&self) -> Result<(), HardwareError> {
+        let mut state = self.shared.lock();
+        if state.keyed_since.take().is_some() {
+            debug!(line = %self.description, "Unkeying PTT");
+        }
+        self.shared.wakeup.notify_all();
+        // Always drive the line low, even if we think it already is.
+        state.line.set_keyed(false)
+    }
+
+    /// Returns `true` while the transmitter is keyed.
+    pub fn is_keyed(&self) -> bool {
+        self.shared.lock().keyed_since.is_some()
+    }
+
+    /// Returns `true` if the watchdog forced an unkey since the last `key()`.
+    pub fn watchdog_tripped(&self) -> bool {
+        self.shared.lock().watchdog_tripped
+    }
+
+    /// Returns a description of the PTT line (e.g. "RTS on /dev/ttyUSB0").
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Drop for PttController {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.lock();
+            state.shutdown = true;
+            if state.keyed_since.take().is_some() {
+                warn!(line = %self.description, "PTT controller dropped while keyed; unkeying");
+            }
+            if let Err(e) = state.line.set_keyed(false) {
+                error!(line = %self.description, "Failed to unkey PTT on shutdown: {}", e);
+            }
+            self.shared.wakeup.notify_all();
+        }
+        if let Some(handle) = self.watchdog_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn ptt_device(config: &HardwareConfig) -> Result<&str, HardwareError> {
+    config
+        .ptt_device
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .ok_or_else(|| HardwareError::PttError(format!("ptt_device must be set for {} PTT", config.ptt_signal)))
+}
+
+/// Waits for transmissions and unkeys any that exceed `limit`.
+fn run_watchdog(shared: Arc<Shared>, limit: Duration) {
+    let mut state = shared.lock();
+    while !state.shutdown {
+        match state.keyed_since {
+            Some(since) => {
+                let elapsed = since.elapsed();
+                if elapsed >= limit {
+                    warn!(limit_s = limit.as_secs(), "PTT watchdog expired; forcing transmitter off");
+                    if let Err(e) = state.line.set_keyed(false) {
+                        error!("PTT watchdog failed to unkey: {}", e);
+                    }
+                    state.keyed_since = None;
+                    state.watchdog_tripped = true;
+                } else {
+                    state = shared
+                        .wakeup
+                        .wait_timeout(state, limit - elapsed)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0;
+                }
+            }
+            None => {
+                state = shared.wakeup.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+    }
+}
+
+/// Keeps the transmitter keyed until released or dropped.
+#[must_use = "dropping the guard unkeys the transmitter immediately"]
+pub struct PttGuard {
+    controller: Arc<PttController>,
+    released: bool,
+}
+
+impl PttGuard {
+    /// Unkeys the transmitter.
+    ///
+    /// Fails with `PttError` if the watchdog had already cut this transmission short.
+    pub fn release(mut self) -> Result<(), HardwareError> {
+        self.released = true;
+        let tripped = self.controller.watchdog_tripped();
+        self.controller.unkey()?;
+        if tripped {
+            let limit = self.controller.shared.watchdog.unwrap_or_default();
+            return Err(HardwareError::PttError(format!(
+                "Transmission exceeded the {} s limit and was cut off by the PTT watchdog",
+                limit.as_secs()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PttGuard {
+    fn drop(&mut self) {
+        if !self.released {
+            warn!(line = %self.controller.description, "PTT guard dropped without release; unkeying");
+            if let Err(e) = self.controller.unkey() {
+                error!("Failed to unkey PTT from guard drop: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Records the line state so tests can observe keying.
+    #[derive(Clone, Default)]
+    struct FakeLine {
+        keyed: Arc<AtomicBool>,
+        writes: Arc<AtomicUsize>,
+    }
+
+    impl PttBackend for FakeLine {
+        fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError> {
+            self.keyed.store(keyed, Ordering::SeqCst);
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn describe(&self) -> String {
+            "fake".to_string()
+        }
+    }
+
+    fn controller(watchdog: Option<Duration>) -> (Arc<PttController>, FakeLine) {
+        let line = FakeLine::default();
+        (Arc::new(PttController::new(Box::new(line.clone()), watchdog)), line)
+    }
+
+    #[test]
+    fn test_key_and_release() {
+        let (ptt, line) = controller(None);
+        let guard = ptt.key().unwrap();
+        assert!(line.keyed.load(Ordering::SeqCst) && ptt.is_keyed());
+        assert!(ptt.key().is_err(), "Double keying must be rejected");
+        guard.release().unwrap();
+        assert!(!line.keyed.load(Ordering::SeqCst) && !ptt.is_keyed());
+    }
+
+    #[test]
+    fn test_guard_drop_and_panic_unkey() {
+        let (ptt, line) = controller(None);
+        {
+            let _guard = ptt.key().unwrap();
+        }
+        assert!(!line.keyed.load(Ordering::SeqCst));
+
+        let ptt_clone = ptt.clone();
+        let result = std::thread::spawn(move || {
+            let _guard = ptt_clone.key().unwrap();
+            panic!("transmit path blew up");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(!line.keyed.load(Ordering::SeqCst), "PTT must be released during unwinding");
+        assert!(ptt.key().is_ok(), "Controller stays usable after a panic");
+    }
+
+    #[test]
+    fn test_watchdog_unkeys_long_transmission() {
+        let (ptt, line) = controller(Some(Duration::from_millis(50)));
+        let guard = ptt.key().unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!line.keyed.load(Ordering::SeqCst), "Watchdog should have unkeyed");
+        assert!(ptt.watchdog_tripped());
+        assert!(guard.release().is_err(), "Release reports the watchdog cut-off");
+
+        // A short transmission afterwards is unaffected.
+        let guard = ptt.key().unwrap();
+        assert!(!ptt.watchdog_tripped());
+        guard.release().unwrap();
+    }
+
+    #[test]
+    fn test_controller_drop_unkeys() {
+        let line = FakeLine::default();
+        let ptt = PttController::new(Box::new(line.clone()), Some(Duration::from_secs(60)));
+        {
+            // Key without a guard, as if the guard had been leaked.
+            let mut state = ptt.shared.lock();
+            state.line.set_keyed(true).unwrap();
+            state.keyed_since = Some(Instant::now());
+        }
+        drop(ptt);
+        assert!(!line.keyed.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,55 @@
+// Serial RTS/DTR PTT line.
+
+use super::PttBackend;
+use crate::HardwareError;
+use elfradio_types::PttSignal;
+use serialport::SerialPort;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// Keys the transmitter with the RTS or DTR line of a serial port held open.
+pub struct SerialPtt {
+    port_name: String,
+    signal: PttSignal,
+    port: Box<dyn SerialPort>,
+}
+
+impl SerialPtt {
+    /// Opens `port_name` and immediately drives the PTT line low.
+    ///
+    /// Most OS drivers assert RTS and DTR on open, which would key the rig.
+    pub fn open(port_name: &str, signal: PttSignal) -> Result<Self, HardwareError> {
+        if !matches!(signal, PttSignal::Rts | PttSignal::Dtr) {
+            return Err(HardwareError::PttError(format!("{:?} is not a serial signal line", signal)));
+        }
+
+        // Baud rate is irrelevant for signal control; a short timeout keeps calls snappy.
+        let port = serialport::new(port_name, 9600)
+            .timeout(Duration::from_millis(100))
+            .open()
+            .map_err(|e| {
+                error!("Failed to open PTT serial port '{}': {}", port_name, e);
+                HardwareError::SerialPortError(e)
+            })?;
+
+        let mut line = Self { port_name: port_name.to_string(), signal, port };
+        line.set_keyed(false)?;
+        debug!(port = port_name, ?signal, "Opened serial PTT line");
+        Ok(line)
+    }
+}
+
+impl PttBackend for SerialPtt {
+    fn set_keyed(&mut self, keyed: bool) -> Result<(), HardwareError> {
+        match self.signal {
+            PttSignal::Rts => self.port.write_request_to_send(keyed)?,
+            PttSignal::Dtr => self.port.write_data_terminal_ready(keyed)?,
+            other => unreachable!("{:?} rejected in SerialPtt::open", other),
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?} on {}", self.signal, self.port_name)
+    }
+}
@@ -0,0 +1,334 @@
+// SDR receive path: IQ from an rtl_tcp server, demodulated into the same
+// `AudioMessage` stream the sound card delivers.
+
+pub mod rtl_tcp;
+
+pub use rtl_tcp::{iq_to_u8, RtlTcpClient, RTL_TCP_DEFAULT_ADDRESS};
+
+use crate::audio::{send_input_chunk, AudioBackend, AudioOutputSender, StreamControl, CHUNK_DURATION};
+use crate::HardwareError;
+use elfradio_dsp::{DemodMode, Demodulator};
+use elfradio_types::{AudioMessage, ConnectionStatus, WebSocketMessage};
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+/// Default IQ rate: comfortably above the NBFM channel, cheap to decimate.
+pub const DEFAULT_SDR_SAMPLE_RATE: u32 = 1_024_000;
+/// Default distance between the dongle's centre and the wanted signal, keeping it off the DC spike.
+pub const DEFAULT_SDR_OFFSET_HZ: i32 = 100_000;
+
+/// Receiver settings parsed from `hardware.sdr_device_args`.
+///
+/// The format is comma-separated `key=value` pairs, e.g.
+/// `driver=rtl_tcp,host=192.168.1.20:1234,freq=145.5M,mode=nbfm,gain=auto,ppm=1`.
+/// Only `freq` is required.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdrSettings {
+    /// rtl_tcp server address.
+    pub host: String,
+    /// Frequency of the wanted signal (carrier / suppressed carrier) in Hz.
+    pub frequency_hz: u32,
+    pub mode: DemodMode,
+    pub sample_rate: u32,
+    /// The dongle is tuned this far below `frequency_hz`.
+    pub offset_hz: i32,
+    /// Manual tuner gain in dB; `None` uses the tuner AGC.
+    pub gain_db: Option<f32>,
+    pub ppm: i32,
+}
+
+/// Parses "145500000", "145.5M" or "7074k" as Hz.
+fn parse_hz(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, scale) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1e3),
+        (i, 'm' | 'M') => (&value[..i], 1e6),
+        (i, 'g' | 'G') => (&value[..i], 1e9),
+        _ => (value, 1.0),
+    };
+    number.trim().parse::<f64>().ok().map(|n| (n * scale).round())
+}
+
+impl FromStr for SdrSettings {
+    type Err = HardwareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |key: &str, value: &str| HardwareError::SdrError(format!("Invalid SDR argument {}={}", key, value));
+        let mut settings = SdrSettings {
+            host: RTL_TCP_DEFAULT_ADDRESS.to_string(),
+            frequency_hz: 0,
+            mode: DemodMode::Nbfm,
+            sample_rate: DEFAULT_SDR_SAMPLE_RATE,
+            offset_hz: DEFAULT_SDR_OFFSET_HZ,
+            gain_db: None,
+            ppm: 0,
+        };
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| invalid(pair, ""))?;
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+            match key.as_str() {
+                "driver" => {
+                    if !matches!(value.to_lowercase().as_str(), "rtl_tcp" | "rtltcp" | "rtlsdr") {
+                        return Err(HardwareError::SdrError(format!("Unsupported SDR driver: {}", value)));
+                    }
+                }
+                "host" | "address" => settings.host = value.to_string(),
+                "freq" | "frequency" => {
+                    settings.frequency_hz = parse_hz(value).filter(|f| *f > 0.0 && *f <= u32::MAX as f64).ok_or_else(|| invalid(&key, value))? as u32
+                }
+                "mode" => settings.mode = value.parse().map_err(|_| invalid(&key, value))?,
+                "rate" | "sample_rate" => {
+                    settings.sample_rate = parse_hz(value).filter(|r| *r > 0.0).ok_or_else(|| invalid(&key, value))? as u32
+                }
+                "offset" => settings.offset_hz = parse_hz(value).ok_or_else(|| invalid(&key, value))? as i32,
+                "gain" => {
+                    settings.gain_db = if value.eq_ignore_ascii_case("auto") {
+                        None
+                    } else {
+                        Some(value.parse().map_err(|_| invalid(&key, value))?)
+                    }
+                }
+                "ppm" => settings.ppm = value.parse().map_err(|_| invalid(&key, value))?,
+                _ => return Err(HardwareError::SdrError(format!("Unknown SDR argument: {}", key))),
+            }
+        }
+        if settings.frequency_hz == 0 {
+            return Err(HardwareError::SdrError("SDR arguments need a frequency (freq=...)".to_string()));
+        }
+        Ok(settings)
+    }
+}
+
+impl SdrSettings {
+    /// Frequency the dongle itself is tuned to.
+    pub fn center_frequency_hz(&self) -> u32 {
+        (self.frequency_hz as i64 - self.offset_hz as i64).max(0) as u32
+    }
+}
+
+/// Receive-only audio backend that demodulates an rtl_tcp IQ stream.
+pub struct RtlTcpBackend {
+    settings: SdrSettings,
+    audio_rate: u32,
+}
+
+impl RtlTcpBackend {
+    /// Delivers demodulated audio at `audio_rate`.
+    pub fn new(settings: SdrSettings, audio_rate: u32) -> Self {
+        Self { settings, audio_rate: audio_rate.max(1) }
+    }
+}
+
+impl AudioBackend for RtlTcpBackend {
+    fn name(&self) -> &str {
+        "rtl_tcp"
+    }
+
+    fn start_input(&self, data_tx: mpsc::UnboundedSender<AudioMessage>) -> Result<StreamControl, HardwareError> {
+        let settings = &self.settings;
+        let mut demodulator = Demodulator::new(settings.mode, settings.sample_rate, settings.offset_hz as f64, self.audio_rate)
+            .map_err(|e| HardwareError::SdrError(e.to_string()))?;
+
+        let mut client = RtlTcpClient::connect(&settings.host)?;
+        client.set_sample_rate(settings.sample_rate)?;
+        client.set_center_frequency(settings.center_frequency_hz())?;
+        client.set_freq_correction(settings.ppm)?;
+        client.set_gain(settings.gain_db)?;
+        info!(
+            frequency_hz = settings.frequency_hz,
+            mode = ?settings.mode,
+            sample_rate = settings.sample_rate,
+            "Receiving from rtl_tcp at {}",
+            settings.host
+        );
+
+        let chunk_len = ((self.audio_rate as f32 * CHUNK_DURATION.as_secs_f32()).round() as usize).max(1);
+        StreamControl::spawn_worker("rtl-tcp-in", None, move |flags| {
+            let mut buffer = vec![0u8; 32 * 1024];
+            let mut pending: Vec<f32> = Vec::with_capacity(chunk_len * 2);
+            while !flags.should_stop() {
+                let iq = match client.read_iq(&mut buffer) {
+                    Ok(Some(iq)) => iq,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        error!("rtl_tcp stream failed: {}", e);
+                        flags.fail(e.to_string());
+                        break;
+                    }
+                };
+                // Keep draining the socket while paused so the server doesn't stall.
+                let audio = demodulator.process(&iq);
+                if flags.is_paused() {
+                    continue;
+                }
+                pending.extend(audio);
+                while pending.len() >= chunk_len {
+                    let chunk: Vec<f32> = pending.drain(..chunk_len).collect();
+                    if !send_input_chunk(&data_tx, chunk) {
+                        return;
+                    }
+                }
+            }
+        })
+    }
+
+    fn start_output(&self) -> Result<(StreamControl, AudioOutputSender), HardwareError> {
+        Err(HardwareError::SdrError("rtl_tcp is receive-only".to_string()))
+    }
+
+    fn has_output(&self) -> bool {
+        false
+    }
+
+    fn status_message(&self, status: ConnectionStatus) -> WebSocketMessage {
+        WebSocketMessage::SdrStatusUpdate(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elfradio_dsp::Complex32;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    const IQ_RATE: u32 = 240_000;
+
+    /// rtl_tcp commands (opcode, argument) the fake server received.
+    type CommandLog = Arc<Mutex<Vec<(u8, u32)>>>;
+
+    /// Writes a synthetic NBFM recording: 1 kHz tone at 3 kHz deviation, `offset_hz` from centre.
+    fn write_iq_file(path: &std::path::Path, offset_hz: f32, seconds: f32) {
+        let n = (seconds * IQ_RATE as f32) as usize;
+        let iq: Vec<Complex32> = (0..n)
+            .map(|i| {
+                let t = i as f32 / IQ_RATE as f32;
+                let phase = 2.0 * std::f32::consts::PI * offset_hz * t + 3.0 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin();
+                Complex32::from_polar(0.7, phase)
+            })
+            .collect();
+        std::fs::write(path, iq_to_u8(&iq)).unwrap();
+    }
+
+    /// Fake rtl_tcp server: sends the header, records commands and streams `iq_file` once.
+    fn fake_server(iq_file: std::path::PathBuf) -> (String, CommandLog) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let recorded = commands.clone();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut header = b"RTL0".to_vec();
+            header.extend(5u32.to_be_bytes()); // R820T
+            header.extend(29u32.to_be_bytes());
+            socket.write_all(&header).unwrap();
+
+            let mut command_reader = socket.try_clone().unwrap();
+            std::thread::spawn(move || {
+                let mut frame = [0u8; 5];
+                while command_reader.read_exact(&mut frame).is_ok() {
+                    let param = u32::from_be_bytes([frame[1], frame[2], frame[3], frame[4]]);
+                    recorded.lock().unwrap().push((frame[0], param));
+                }
+            });
+
+            let data = std::fs::read(iq_file).unwrap();
+            // Odd-sized writes split I/Q pairs across reads.
+            for block in data.chunks(7777) {
+                if socket.write_all(block).is_err() {
+                    return;
+                }
+            }
+            // The command reader still holds a clone, so hang up explicitly.
+            let _ = socket.shutdown(std::net::Shutdown::Write);
+        });
+        (address, commands)
+    }
+
+    fn tone_level(samples: &[f32], freq: f32, rate: u32) -> f32 {
+        let (mut re, mut im) = (0.0f32, 0.0f32);
+        for (i, &s) in samples.iter().enumerate() {
+            let phase = 2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32;
+            re += s * phase.cos();
+            im += s * phase.sin();
+        }
+        (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let settings: SdrSettings = "driver=rtl_tcp, host=10.0.0.5:1234, freq=145.5M, mode=nbfm, gain=28.5, ppm=-2".parse().unwrap();
+        assert_eq!(settings.host, "10.0.0.5:1234");
+        assert_eq!(settings.frequency_hz, 145_500_000);
+        assert_eq!(settings.gain_db, Some(28.5));
+        assert_eq!(settings.ppm, -2);
+        assert_eq!(settings.center_frequency_hz(), 145_400_000);
+
+        let ssb: SdrSettings = "freq=7074k,mode=usb,rate=240000,offset=-20k,gain=auto".parse().unwrap();
+        assert_eq!((ssb.frequency_hz, ssb.mode, ssb.sample_rate, ssb.offset_hz), (7_074_000, DemodMode::Usb, 240_000, -20_000));
+        assert_eq!(ssb.gain_db, None);
+
+        assert!("mode=am".parse::<SdrSettings>().is_err(), "freq is required");
+        assert!("freq=1M,driver=hackrf".parse::<SdrSettings>().is_err());
+        assert!("freq=1M,bogus=1".parse::<SdrSettings>().is_err());
+    }
+
+    #[test]
+    fn test_streams_demodulated_audio_from_fake_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let iq_file = dir.path().join("nbfm_1khz.iq");
+        write_iq_file(&iq_file, 40_000.0, 1.0);
+        let (address, commands) = fake_server(iq_file);
+
+        let settings: SdrSettings = format!("host={},freq=145500000,rate={},offset=40000,gain=20", address, IQ_RATE).parse().unwrap();
+        let backend = RtlTcpBackend::new(settings, 16000);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let control = backend.start_input(tx).unwrap();
+
+        let mut audio = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while audio.len() < 14_000 && Instant::now() < deadline {
+            match rx.try_recv() {
+                Ok(AudioMessage::Data(chunk)) => {
+                    assert_eq!(chunk.len(), 320, "20 ms chunks at 16 kHz");
+                    audio.extend(chunk);
+                }
+                Ok(_) => {}
+                Err(_) => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        assert!(audio.len() >= 14_000, "only got {} samples", audio.len());
+        let settled = &audio[4000..];
+        assert!(tone_level(settled, 1000.0, 16000) > 0.25);
+        assert!(tone_level(settled, 2000.0, 16000) < 0.03);
+
+        // The whole file has been sent, so the server hangs up and the stream reports a fault.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while control.fault().is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(control.fault().is_some());
+        drop(control);
+
+        let commands = commands.lock().unwrap().clone();
+        assert!(commands.contains(&(0x02, IQ_RATE)));
+        assert!(commands.contains(&(0x01, 145_460_000)));
+        assert!(commands.contains(&(0x03, 1)));
+        assert!(commands.contains(&(0x04, 200)));
+    }
+
+    #[test]
+    fn test_rejects_non_rtl_tcp_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let _ = socket.write_all(b"HTTP/1.1 400");
+        });
+        assert!(matches!(RtlTcpClient::connect(&address), Err(HardwareError::SdrError(_))));
+        assert!(!RtlTcpBackend::new("freq=1M".parse().unwrap(), 16000).has_output());
+    }
+}
@@ -0,0 +1,141 @@
+// rtl_tcp network protocol client.
+//
+// On connect the server sends a 12-byte header ("RTL0", tuner type, gain
+// count, both big-endian u32), then streams interleaved 8-bit unsigned I/Q
+// bytes for as long as the connection stays open. Commands are 5 bytes: a
+// command byte followed by a big-endian u32 parameter.
+
+use crate::HardwareError;
+use elfradio_dsp::Complex32;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tracing::{debug, info, trace};
+
+pub const RTL_TCP_DEFAULT_ADDRESS: &str = "127.0.0.1:1234";
+const RTL_TCP_MAGIC: &[u8; 4] = b"RTL0";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Bounded so a reader can notice it has been asked to stop.
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+// Command codes (see rtl_tcp.c).
+const CMD_SET_FREQUENCY: u8 = 0x01;
+const CMD_SET_SAMPLE_RATE: u8 = 0x02;
+const CMD_SET_GAIN_MODE: u8 = 0x03;
+const CMD_SET_GAIN: u8 = 0x04;
+const CMD_SET_FREQ_CORRECTION: u8 = 0x05;
+const CMD_SET_AGC_MODE: u8 = 0x08;
+
+/// Connection to an rtl_tcp server.
+pub struct RtlTcpClient {
+    stream: TcpStream,
+    tuner_type: u32,
+    gain_count: u32,
+    /// Odd trailing byte of the last read (I without its Q).
+    leftover: Option<u8>,
+}
+
+impl RtlTcpClient {
+    /// Connects to `address` (e.g. "127.0.0.1:1234") and reads the dongle header.
+    pub fn connect(address: &str) -> Result<Self, HardwareError> {
+        let socket_address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| HardwareError::SdrError(format!("Can't resolve rtl_tcp address {}", address)))?;
+        let mut stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+
+        let mut header = [0u8; 12];
+        stream.read_exact(&mut header).map_err(|e| HardwareError::SdrError(format!("No rtl_tcp header from {}: {}", address, e)))?;
+        if &header[..4] != RTL_TCP_MAGIC {
+            return Err(HardwareError::SdrError(format!("{} is not an rtl_tcp server (bad magic {:02X?})", address, &header[..4])));
+        }
+        let tuner_type = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let gain_count = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        info!(address, tuner_type, gain_count, "Connected to rtl_tcp server");
+        Ok(Self { stream, tuner_type, gain_count, leftover: None })
+    }
+
+    /// Tuner type reported by the server (e.g. 5 = R820T).
+    pub fn tuner_type(&self) -> u32 {
+        self.tuner_type
+    }
+
+    /// Number of discrete gain steps the tuner supports.
+    pub fn gain_count(&self) -> u32 {
+        self.gain_count
+    }
+
+    fn command(&mut self, command: u8, param: u32) -> Result<(), HardwareError> {
+        let mut frame = [0u8; 5];
+        frame[0] = command;
+        frame[1..].copy_from_slice(&param.to_be_bytes());
+        trace!(command, param, "Sending rtl_tcp command");
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
+
+    pub fn set_center_frequency(&mut self, hz: u32) -> Result<(), HardwareError> {
+        debug!(hz, "Setting rtl_tcp centre frequency");
+        self.command(CMD_SET_FREQUENCY, hz)
+    }
+
+    pub fn set_sample_rate(&mut self, rate: u32) -> Result<(), HardwareError> {
+        debug!(rate, "Setting rtl_tcp sample rate");
+        self.command(CMD_SET_SAMPLE_RATE, rate)
+    }
+
+    /// Sets a manual tuner gain in dB, or tuner AGC with `None`.
+    pub fn set_gain(&mut self, gain_db: Option<f32>) -> Result<(), HardwareError> {
+        match gain_db {
+            Some(db) => {
+                self.command(CMD_SET_GAIN_MODE, 1)?;
+                self.command(CMD_SET_GAIN, (db * 10.0).round().max(0.0) as u32) // Tenths of a dB
+            }
+            None => self.command(CMD_SET_GAIN_MODE, 0),
+        }
+    }
+
+    pub fn set_freq_correction(&mut self, ppm: i32) -> Result<(), HardwareError> {
+        self.command(CMD_SET_FREQ_CORRECTION, ppm as u32) // Two's complement on the wire
+    }
+
+    /// Enables or disables the RTL2832's digital AGC.
+    pub fn set_agc(&mut self, enabled: bool) -> Result<(), HardwareError> {
+        self.command(CMD_SET_AGC_MODE, enabled as u32)
+    }
+
+    /// Reads the next block of IQ samples, using `buffer` as scratch space.
+    ///
+    /// Returns `Ok(None)` if nothing arrived within the read timeout, and an
+    /// error once the server closes the connection.
+    pub fn read_iq(&mut self, buffer: &mut [u8]) -> Result<Option<Vec<Complex32>>, HardwareError> {
+        let start = usize::from(self.leftover.is_some());
+        let read = match self.stream.read(&mut buffer[start..]) {
+            Ok(0) => return Err(HardwareError::SdrError("rtl_tcp server closed the connection".to_string())),
+            Ok(n) => n,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(byte) = self.leftover.take() {
+            buffer[0] = byte;
+        }
+        let total = start + read;
+        if total % 2 == 1 {
+            self.leftover = Some(buffer[total - 1]);
+        }
+        Ok(Some(buffer[..total - total % 2].chunks_exact(2).map(|iq| Complex32::new(u8_to_f32(iq[0]), u8_to_f32(iq[1]))).collect()))
+    }
+}
+
+/// Converts an offset-binary rtl-sdr sample to ±1.0.
+fn u8_to_f32(sample: u8) -> f32 {
+    (sample as f32 - 127.5) / 127.5
+}
+
+/// Encodes IQ samples the way an rtl-sdr delivers them (for fake servers and recordings).
+pub fn iq_to_u8(iq: &[Complex32]) -> Vec<u8> {
+    let quantize = |v: f32| (v.clamp(-1.0, 1.0) * 127.5 + 127.5).round().clamp(0.0, 255.0) as u8;
+    iq.iter().flat_map(|s| [quantize(s.re), quantize(s.im)]).collect()
+}
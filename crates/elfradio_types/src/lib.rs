@@ -43,14 +43,41 @@ pub struct TaskInfo {
     pub is_simulation: bool,
 }
 
+/// How `TxItem::GeneratedVoice` audio is framed on the air.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxFraming {
+    /// Speech: runs through the `tx_audio` chain, gets the configured signal tones
+    /// around it and the TX subtone under it.
+    Voice,
+    /// Tones a repeater acts on, such as DTMF: not conditioned, but opened with the
+    /// 1750 Hz burst and carrying the TX subtone so the repeater hears them.
+    AnalogData,
+    /// Digital-mode baseband (SSTV, PSK, POCSAG), sent exactly as its modulator made it.
+    /// A tone next to an SSTV header or a DCS code under POCSAG would corrupt it.
+    Raw,
+}
+
 // 4. TxItem 枚举
 #[derive(Debug, Clone)]
 pub enum TxItem {
     ManualText { id: Uuid, text: String, priority: u8 },
     ManualVoice { id: Uuid, path: PathBuf, priority: u8 },
     AiReply { id: Uuid, text: String, priority: u8 },
-    GeneratedVoice { id: Uuid, audio_data: Vec<f32>, priority: u8 },
-    // 后续阶段添加其他变体，如 SSTV, CW 等
+    /// Audio ready to transmit at `TX_AUDIO_SAMPLE_RATE`; `framing` says what is added
+    /// to it on the way out.
+    GeneratedVoice { id: Uuid, audio_data: Vec<f32>, priority: u8, framing: TxFraming },
+    /// Picture to send as SSTV in the configured `sstv_settings.mode`.
+    /// `their_call` and `rst` fill the `{theircall}` and `{rst}` template fields.
+    SstvImage { id: Uuid, path: PathBuf, their_call: Option<String>, rst: Option<String>, priority: u8 },
+    /// Text to send as PSK31; `mode` is "BPSK31" or "QPSK31".
+    PskText { id: Uuid, text: String, mode: String, priority: u8 },
+    /// POCSAG page for `capcode` at `baud` (512, 1200 or 2400), sent as baseband for the
+    /// transmitter's data input. `numeric` selects the numeric character set; an empty
+    /// `message` sends a tone-only page.
+    PocsagPage { id: Uuid, capcode: u32, message: String, numeric: bool, baud: u32, priority: u8 },
+    /// DTMF keys (0-9, A-D, * and #) sent with the `dtmf` mark/space timing.
+    DtmfDigits { id: Uuid, digits: String, priority: u8 },
+    // 后续阶段添加其他变体，如 CW 等
 }
 
 impl TxItem {
@@ -60,6 +87,10 @@ impl TxItem {
             TxItem::ManualVoice { priority, .. } => *priority,
             TxItem::AiReply { priority, .. } => *priority,
             TxItem::GeneratedVoice { priority, .. } => *priority,
+            TxItem::SstvImage { priority, .. } => *priority,
+            TxItem::PskText { priority, .. } => *priority,
+            TxItem::PocsagPage { priority, .. } => *priority,
+            TxItem::DtmfDigits { priority, .. } => *priority,
         }
     }
 
@@ -69,6 +100,10 @@ impl TxItem {
             TxItem::ManualVoice { id, .. } => *id,
             TxItem::AiReply { id, .. } => *id,
             TxItem::GeneratedVoice { id, .. } => *id,
+            TxItem::SstvImage { id, .. } => *id,
+            TxItem::PskText { id, .. } => *id,
+            TxItem::PocsagPage { id, .. } => *id,
+            TxItem::DtmfDigits { id, .. } => *id,
         }
     }
 }
@@ -104,11 +139,55 @@ pub struct HardwareConfig {
     pub audio_output_device: Option<String>,
     /// Input audio sample rate in Hz (e.g., 16000, 48000).
     pub input_sample_rate: u32,
+    /// Input channel fed to the RX pipeline: "left", "right" or "mix" (default).
+    #[serde(default)]
+    pub input_channel: Option<String>,
+    /// Audio backend: "cpal" (sound card, default), "file" (WAV replay/capture) or "null".
+    #[serde(default)]
+    pub audio_backend: Option<String>,
+    /// WAV file replayed as RX audio by the "file" backend.
+    #[serde(default)]
+    pub audio_input_file: Option<String>,
+    /// WAV file that captures TX audio with the "file" backend.
+    #[serde(default)]
+    pub audio_output_file: Option<String>,
     /// Serial port for PTT/CAT control (e.g., "COM3" or "/dev/ttyUSB0").
     pub serial_port: Option<String>,
-    /// PTT signal line ("rts" or "dtr").
+    /// PTT method: "rts", "dtr", "cat" (key through rig control), "cm108" or "gpio".
     pub ptt_signal: String,
-    /// SDR device arguments (e.g., "driver=rtlsdr"). (Phase 2+)
+    /// Device for "cm108" (e.g., "/dev/hidraw0") or "gpio" (e.g., "/dev/gpiochip0") PTT.
+    #[serde(default)]
+    pub ptt_device: Option<String>,
+    /// GPIO number for "cm108" PTT (1-8, None uses 3) or line offset for "gpio" PTT.
+    #[serde(default)]
+    pub ptt_gpio_line: Option<u32>,
+    /// Drive the "gpio" PTT line low to transmit.
+    #[serde(default)]
+    pub ptt_active_low: bool,
+    /// Address of a Hamlib `rigctld` daemon for CAT control (e.g., "127.0.0.1:4532").
+    #[serde(default)]
+    pub rigctld_address: Option<String>,
+    /// CAT protocol: "rigctld", "icom" (CI-V), "kenwood" (also Elecraft) or "yaesu".
+    /// None falls back to "rigctld" when `rigctld_address` is set, otherwise CAT is disabled.
+    #[serde(default)]
+    pub cat_protocol: Option<String>,
+    /// Rig model name (e.g., "IC-7300"), used to pick protocol defaults such as the CI-V address.
+    #[serde(default)]
+    pub rig_model: Option<String>,
+    /// Serial port for native CAT drivers. None reuses `serial_port`.
+    #[serde(default)]
+    pub cat_serial_port: Option<String>,
+    /// CAT serial baud rate. None uses 9600.
+    #[serde(default)]
+    pub cat_baud_rate: Option<u32>,
+    /// Icom CI-V address of the rig (e.g., 0x94 = 148 for an IC-7300). Overrides `rig_model`.
+    #[serde(default)]
+    pub civ_address: Option<u8>,
+    /// Full output power of the rig in watts, for protocols that set power in watts. None uses 100.
+    #[serde(default)]
+    pub rig_max_power_w: Option<u32>,
+    /// SDR receiver arguments for the "rtl_tcp" audio backend, as comma-separated
+    /// key=value pairs (e.g., "driver=rtl_tcp,host=127.0.0.1:1234,freq=145.5M,mode=nbfm,gain=auto").
     pub sdr_device_args: Option<String>,
     /// Enable separate RX/TX hardware paths. (Phase 3+)
     pub enable_rx_tx_separation: bool,
@@ -291,6 +370,145 @@ pub struct RadioEtiquetteConfig {
 pub struct SecurityConfig {
     /// Voice command phrase to immediately stop the current task.
     pub end_task_phrase: String,
+    /// PIN that must start every DTMF remote command. Remote commands are disabled without one.
+    #[serde(default)]
+    pub dtmf_pin: Option<String>,
+}
+
+/// What a DTMF remote command does.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DtmfAction {
+    /// Stop the running task.
+    StopTask,
+    /// Queue `text` for transmission, spoken with TTS.
+    SendText { text: String },
+}
+
+/// A DTMF remote command: the PIN, then `code`, then '#'.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DtmfCommand {
+    /// Keys (0-9, A-D, *) that select the command.
+    pub code: String,
+    pub action: DtmfAction,
+}
+
+/// DTMF signalling and remote control settings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DtmfConfig {
+    /// Tone length (milliseconds) of each transmitted key.
+    pub mark_ms: u64,
+    /// Silence (milliseconds) between transmitted keys.
+    pub space_ms: u64,
+    /// Listen for DTMF keys on received audio.
+    pub detect_enabled: bool,
+    /// Quiet time (milliseconds) that ends a received key sequence; '#' also ends one.
+    pub sequence_timeout_ms: u64,
+    /// Remote commands, checked against each received sequence.
+    pub commands: Vec<DtmfCommand>,
+}
+
+impl Default for DtmfConfig {
+    fn default() -> Self {
+        DtmfConfig {
+            mark_ms: 100,
+            space_ms: 100,
+            detect_enabled: true,
+            sequence_timeout_ms: 3000,
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl DtmfConfig {
+    /// The command selected by a received sequence ('#' already removed): the PIN
+    /// followed by exactly one command's code. Nothing matches without a PIN.
+    pub fn command_for(&self, sequence: &str, pin: Option<&str>) -> Option<&DtmfCommand> {
+        let pin = pin.filter(|pin| !pin.is_empty())?;
+        let code = sequence.strip_prefix(pin)?;
+        self.commands.iter().find(|command| command.code.eq_ignore_ascii_case(code))
+    }
+}
+
+/// Transmit audio conditioning, applied to voice before it goes to the radio.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TxAudioConfig {
+    pub enabled: bool,
+    /// Band-pass voice to 300–2700 Hz.
+    pub bandpass: bool,
+    /// 6 dB/octave pre-emphasis, for radios fed flat audio that skip their own.
+    pub pre_emphasis: bool,
+    /// Compressor threshold (dBFS RMS).
+    pub compressor_threshold_dbfs: f32,
+    /// Compression ratio above the threshold; 1.0 turns the compressor off.
+    pub compressor_ratio: f32,
+    /// Loudness (dBFS RMS, pauses excluded) voice is brought to.
+    pub target_dbfs: f32,
+    /// Peak ceiling (dBFS) of the limiter.
+    pub limit_dbfs: f32,
+}
+
+impl Default for TxAudioConfig {
+    fn default() -> Self {
+        TxAudioConfig {
+            enabled: true,
+            bandpass: true,
+            pre_emphasis: false,
+            compressor_threshold_dbfs: -24.0,
+            compressor_ratio: 3.0,
+            target_dbfs: -18.0,
+            limit_dbfs: -1.0,
+        }
+    }
+}
+
+/// Receive audio conditioning, applied to received audio before it is transcribed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RxAudioConfig {
+    pub enabled: bool,
+    /// Noise reduction strength, from 0.0 (off) to 1.0 (noise pulled down by 24 dB).
+    pub noise_reduction: f32,
+    /// Find heterodynes and notch them out.
+    pub notch: bool,
+    pub agc: bool,
+    /// Level (dBFS RMS) the AGC holds received audio at.
+    pub agc_target_dbfs: f32,
+}
+
+impl Default for RxAudioConfig {
+    fn default() -> Self {
+        RxAudioConfig {
+            enabled: false,
+            noise_reduction: 0.5,
+            notch: true,
+            agc: true,
+            agc_target_dbfs: -20.0,
+        }
+    }
+}
+
+/// CTCSS/DCS squelch tone settings. Tones are written like "88.5" or "CTCSS 88.5" and
+/// codes like "D023N" or "DCS 023I".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SubtoneConfig {
+    /// Tone or code mixed under all transmitted audio, for radios fed flat audio.
+    pub tx: Option<String>,
+    /// Tone or code received audio must carry before it is decoded or transcribed.
+    pub rx: Option<String>,
+    /// Subtone share of full scale (0.0 to 0.5).
+    pub level: f32,
+    /// Log the tone or code each received station is using.
+    pub scan: bool,
+}
+
+impl Default for SubtoneConfig {
+    fn default() -> Self {
+        SubtoneConfig { tx: None, rx: None, level: 0.1, scan: false }
+    }
 }
 
 /// Configuration for start/end signal tones.
@@ -304,13 +522,100 @@ pub struct SignalToneConfig {
     pub end_freqs_hz: Vec<f32>,
     /// Duration (milliseconds) for each tone segment.
     pub duration_ms: u64,
+    /// End transmissions with a Roger beep (courtesy tone) instead of `end_freqs_hz`.
+    #[serde(default)]
+    pub roger_beep: bool,
+    /// Length (milliseconds) of a 1750 Hz tone burst sent first, to open European
+    /// repeaters. 0 sends none.
+    #[serde(default)]
+    pub tone_burst_ms: u64,
 }
 
 /// SSTV (Slow-Scan Television) specific settings.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SstvConfig {
-    /// Default SSTV mode (e.g., "Martin M1", "Scottie S1").
+    /// Default SSTV mode: "Martin M1", "Martin M2", "Scottie S1", "Scottie S2",
+    /// "Scottie DX", "Robot 36", "Robot 72", "PD90" or "PD120".
     pub mode: String,
+    /// Overlay template name, loaded from `sstv_templates/<name>.toml` next to the
+    /// config file (falling back to `config/sstv_templates/`). None still crops pictures
+    /// to the mode's resolution, but draws no overlays.
+    #[serde(default = "default_sstv_template")]
+    pub template: Option<String>,
+}
+
+fn default_sstv_template() -> Option<String> {
+    Some("default".to_string())
+}
+
+/// How a picture is fitted to the SSTV mode's native resolution.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SstvImageFit {
+    /// Scale to cover the frame and crop the overflow, keeping the centre.
+    #[default]
+    Crop,
+    /// Scale to fit inside the frame and fill the borders with `background`.
+    Letterbox,
+}
+
+/// Corner or edge that a text overlay is positioned from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SstvTextAnchor {
+    #[default]
+    TopLeft,
+    TopCenter,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+/// One line of text drawn on an SSTV picture.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SstvTextOverlay {
+    /// Text with `{field}` placeholders: `{mycall}`, `{theircall}`, `{rst}`, `{date}`,
+    /// `{time}` (UTC, "HH:MMZ"), `{mode}` and `{task}`. Lines with a field that has no value are skipped.
+    pub text: String,
+    #[serde(default)]
+    pub anchor: SstvTextAnchor,
+    /// Offset (pixels) from the anchor, towards the centre of the picture.
+    #[serde(default)]
+    pub x: u32,
+    #[serde(default)]
+    pub y: u32,
+    /// Integer magnification of the 10x20 built-in font.
+    #[serde(default = "default_overlay_scale")]
+    pub scale: u32,
+    #[serde(default = "default_overlay_color")]
+    pub color: [u8; 3],
+    /// Outline colour that keeps the text readable over the picture. None draws no outline.
+    #[serde(default)]
+    pub outline: Option<[u8; 3]>,
+}
+
+fn default_overlay_scale() -> u32 {
+    1
+}
+
+fn default_overlay_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+/// Image preparation applied before SSTV encoding.
+///
+/// Positions and sizes are in pixels of a 320-pixel-wide picture and scale up
+/// for wider modes (PD120).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SstvTemplate {
+    #[serde(default)]
+    pub fit: SstvImageFit,
+    /// Letterbox border colour.
+    #[serde(default)]
+    pub background: [u8; 3],
+    #[serde(default)]
+    pub text: Vec<SstvTextOverlay>,
 }
 
 /// Network configuration settings.
@@ -350,6 +655,18 @@ pub struct Config {
     pub signal_tone: SignalToneConfig,
     /// SSTV settings.
     pub sstv_settings: SstvConfig,
+    /// DTMF settings.
+    #[serde(default)]
+    pub dtmf: DtmfConfig,
+    /// CTCSS/DCS settings.
+    #[serde(default)]
+    pub subtone: SubtoneConfig,
+    /// Transmit audio chain settings.
+    #[serde(default)]
+    pub tx_audio: TxAudioConfig,
+    /// Receive audio chain settings.
+    #[serde(default)]
+    pub rx_audio: RxAudioConfig,
     /// Network configuration
     pub network: Option<NetworkConfig>,
     /// User UUID for this ElfRadio installation
@@ -367,8 +684,22 @@ impl Default for Config {
                 audio_input_device: None,
                 audio_output_device: None,
                 input_sample_rate: 16000,
+                input_channel: None,
                 serial_port: None,
                 ptt_signal: "rts".to_string(),
+                audio_backend: None,
+                audio_input_file: None,
+                audio_output_file: None,
+                ptt_device: None,
+                ptt_gpio_line: None,
+                ptt_active_low: false,
+                rigctld_address: None,
+                cat_protocol: None,
+                rig_model: None,
+                cat_serial_port: None,
+                cat_baud_rate: None,
+                civ_address: None,
+                rig_max_power_w: None,
                 sdr_device_args: None,
                 enable_rx_tx_separation: false,
                 rx_audio_input_device: None,
@@ -401,16 +732,24 @@ impl Default for Config {
             },
             security: SecurityConfig {
                 end_task_phrase: "STOP TASK NOW".to_string(), // Example phrase
+                dtmf_pin: None,
             },
             signal_tone: SignalToneConfig {
                 enabled: false, // Disabled by default
                 start_freqs_hz: vec![1000.0, 1500.0], // Example tones
                 end_freqs_hz: vec![1500.0, 1000.0], // Example tones
                 duration_ms: 100, // 100ms per tone
+                roger_beep: false,
+                tone_burst_ms: 0, // No 1750 Hz burst
             },
             sstv_settings: SstvConfig {
                 mode: "Martin M1".to_string(), // Default SSTV mode
+                template: default_sstv_template(),
             },
+            dtmf: DtmfConfig::default(),
+            subtone: SubtoneConfig::default(),
+            tx_audio: TxAudioConfig::default(),
+            rx_audio: RxAudioConfig::default(),
             network: Some(NetworkConfig {
                 listen_address: Some("0.0.0.0".to_string()),
                 listen_port: Some(5900),
@@ -425,11 +764,17 @@ impl Default for Config {
 /// Value: Sender channel to forward messages to the client's WebSocket task.
 pub type ClientMap = Arc<Mutex<HashMap<Uuid, mpsc::UnboundedSender<Result<Message, axum::Error>>>>>;
 
-/// Defines which serial port signal line to use for PTT control.
+/// Defines how the transmitter is keyed: a serial port signal line, a CAT command or a GPIO.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PttSignal {
     Rts,
     Dtr,
+    /// Key the rig with a CAT command through the configured rig control.
+    Cat,
+    /// GPIO pin of a CM108/CM119 USB audio chip, driven through its hidraw device.
+    Cm108,
+    /// Line of a Linux GPIO character device (`/dev/gpiochipN`).
+    Gpio,
 }
 
 /// Represents messages sent from the audio input stream handler.
@@ -443,8 +788,9 @@ pub enum AudioMessage {
     Error(String), // Optional: For reporting errors from the callback
 }
 
-// Define the sender type alias (assuming f32 samples for output)
-pub type AudioOutputSender = mpsc::UnboundedSender<Vec<f32>>;
+/// Sample rate of `TxItem::GeneratedVoice` audio in Hz. The output backend
+/// converts it to whatever the device runs at.
+pub const TX_AUDIO_SAMPLE_RATE: u32 = 16000;
 
 // Add FromStr impl for PttSignal if not already present
 #[derive(Debug, Error)]
@@ -458,6 +804,9 @@ impl FromStr for PttSignal {
         match s.to_lowercase().as_str() {
             "rts" => Ok(PttSignal::Rts),
             "dtr" => Ok(PttSignal::Dtr),
+            "cat" => Ok(PttSignal::Cat),
+            "cm108" | "cm119" => Ok(PttSignal::Cm108),
+            "gpio" | "gpiochip" => Ok(PttSignal::Gpio),
             _ => Err(PttSignalParseError(format!("Unknown PTT signal type: {}", s))),
         }
     }
@@ -477,7 +826,8 @@ pub enum LogContentType {
     Text,
     Audio, // Represents a path to an audio file
     Status, // For logging start/end events, status changes etc.
-    // Add other types later like Image(SSTV), Error etc.
+    Image, // Represents a path to an image file (e.g. a received SSTV picture)
+    // Add other types later like Error etc.
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -515,6 +865,7 @@ pub enum WebSocketMessage {
 
     // New System Status Update Variants to be added:
     RadioStatusUpdate(ConnectionStatus),
+    AudioStatusUpdate(ConnectionStatus),
     SdrStatusUpdate(ConnectionStatus),
     LlmStatusUpdate(SystemServiceStatus),
     SttStatusUpdate(SystemServiceStatus),
@@ -579,6 +930,10 @@ mod tests {
             TxItem::ManualVoice { id, .. } => *id,
             TxItem::AiReply { id, .. } => *id,
             TxItem::GeneratedVoice { id, .. } => *id, // 确保包含所有变体
+            TxItem::SstvImage { id, .. } => *id,
+            TxItem::PskText { id, .. } => *id,
+            TxItem::PocsagPage { id, .. } => *id,
+            TxItem::DtmfDigits { id, .. } => *id,
         }
     }
 
@@ -640,6 +995,36 @@ mod tests {
 
         println!("Sorted items by priority: {:?}", items.iter().map(|i| i.priority()).collect::<Vec<_>>());
     }
+
+    #[test]
+    fn test_dtmf_command_matching() {
+        use super::{DtmfAction, DtmfCommand, DtmfConfig};
+
+        let config = DtmfConfig {
+            commands: vec![
+                DtmfCommand { code: "0".into(), action: DtmfAction::StopTask },
+                DtmfCommand { code: "1A".into(), action: DtmfAction::SendText { text: "QRT".into() } },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(config.command_for("47110", Some("4711")).map(|c| &c.action), Some(&DtmfAction::StopTask));
+        assert_eq!(config.command_for("47111a", Some("4711")).map(|c| c.code.as_str()), Some("1A"));
+        // Wrong PIN, unknown code, trailing keys, and no PIN configured.
+        assert!(config.command_for("47120", Some("4711")).is_none());
+        assert!(config.command_for("47119", Some("4711")).is_none());
+        assert!(config.command_for("471100", Some("4711")).is_none());
+        assert!(config.command_for("0", None).is_none());
+        assert!(config.command_for("0", Some("")).is_none());
+    }
+
+    #[test]
+    fn test_sstv_template_default_matches_config_default() {
+        use super::{Config, SstvConfig};
+
+        let parsed: SstvConfig = serde_json::from_str(r#"{"mode": "Martin M1"}"#).unwrap();
+        assert_eq!(parsed.template, Config::default().sstv_settings.template);
+        assert_eq!(parsed.template.as_deref(), Some("default"));
+    }
 }
 
 // --- Frontend-Safe AI Configuration ---
@@ -702,6 +1087,18 @@ pub struct FrontendConfig {
     // Example: SSTV mode is likely safe
     pub sstv_settings: SstvConfig,
 
+    // DTMF timing and commands; the PIN lives in `security`
+    pub dtmf: DtmfConfig,
+
+    // CTCSS/DCS tones and scan mode
+    pub subtone: SubtoneConfig,
+
+    // Transmit audio chain
+    pub tx_audio: TxAudioConfig,
+
+    // Receive audio chain
+    pub rx_audio: RxAudioConfig,
+
     // Example: Network settings (Port/Address) might be useful for frontend
     pub network: Option<NetworkConfig>,
     
@@ -761,6 +1158,10 @@ impl From<&Config> for FrontendConfig {
             radio_etiquette: config.radio_etiquette.clone(),
             signal_tone: config.signal_tone.clone(),
             sstv_settings: config.sstv_settings.clone(),
+            dtmf: config.dtmf.clone(),
+            subtone: config.subtone.clone(),
+            tx_audio: config.tx_audio.clone(),
+            rx_audio: config.rx_audio.clone(),
             network: config.network.clone(),
             user_uuid: config.user_uuid.clone(), // 添加 user_uuid 映射
             // Omit sensitive structs like `security` unless specific fields are mapped
@@ -768,6 +1169,46 @@ impl From<&Config> for FrontendConfig {
     }
 }
 
+// --- Hardware Device Listing ---
+
+/// Whether an audio device captures or plays.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioDirection {
+    Input,
+    Output,
+}
+
+/// One side (input or output) of an audio device and what it supports.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AudioDeviceInfo {
+    /// Name as accepted by `audio_input_device` / `audio_output_device`.
+    pub name: String,
+    pub direction: AudioDirection,
+    /// Whether this is the system default for its direction.
+    pub is_default: bool,
+    /// Common sample rates (Hz) inside the supported ranges, ascending.
+    pub sample_rates: Vec<u32>,
+    /// Supported sample formats, e.g. "f32", "i16".
+    pub sample_formats: Vec<String>,
+    /// Supported channel counts, ascending.
+    pub channels: Vec<u16>,
+}
+
+/// A serial port, with USB identification where available.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SerialPortInfo {
+    /// Port name as accepted by `serial_port` / `cat_serial_port`.
+    pub name: String,
+    /// "usb", "pci", "bluetooth" or "unknown".
+    pub port_type: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
 /// Defines the interface for auxiliary services like translation, TTS, and STT.
 #[async_trait]
 pub trait AuxServiceClient: Send + Sync {
@@ -118,6 +118,19 @@ const StatusBar: React.FC = () => {
       radioDotForIndicator = 'neutral';
   }
 
+  // Audio Item Logic
+  const audioStatusFromStore = useSystemStatusStore((state) => state.audioStatus); // audioStatus is ConnectionStatus
+  let audioDotForIndicator: 'ok' | 'warning' | 'error' | 'neutral' = 'neutral';
+  if (audioStatusFromStore === 'Connected') {
+      audioDotForIndicator = 'ok';
+  } else if (audioStatusFromStore === 'Checking') {
+      audioDotForIndicator = 'warning';
+  } else if (audioStatusFromStore === 'Disconnected' || audioStatusFromStore === 'Error') {
+      audioDotForIndicator = 'error';
+  } else if (audioStatusFromStore === 'Unknown') {
+      audioDotForIndicator = 'neutral';
+  }
+
   // SDR Item Logic
   const sdrStatusFromStore = useSystemStatusStore((state) => state.sdrStatus); // sdrStatus is ConnectionStatus
   let sdrDotForIndicator: 'ok' | 'warning' | 'error' | 'neutral' = 'neutral';
@@ -330,6 +343,15 @@ const StatusBar: React.FC = () => {
           </Box>
           <Divider orientation="vertical" flexItem sx={dividerSx} />
 
+          {/* Audio Item Box */}
+          <Box sx={statusItemSx}>
+            <Typography variant="caption" sx={labelSx}>
+              Audio:
+            </Typography>
+            <StatusIndicatorDot status={audioDotForIndicator} />
+          </Box>
+          <Divider orientation="vertical" flexItem sx={dividerSx} />
+
           {/* SDR Item Box */}
           <Box sx={statusItemSx}>
             <Typography variant="caption" sx={labelSx}>
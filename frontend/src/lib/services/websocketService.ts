@@ -198,6 +198,10 @@ export function initializeWebSocket() {
                             systemStatusStore.setRadioStatus(messageData.payload as ConnectionStatus);
                             console.log('SystemStatusStore: RadioStatus updated via WebSocket:', messageData.payload);
                             break;
+                        case 'AudioStatusUpdate':
+                            systemStatusStore.setAudioStatus(messageData.payload as ConnectionStatus);
+                            console.log('SystemStatusStore: AudioStatus updated via WebSocket:', messageData.payload);
+                            break;
                         case 'SdrStatusUpdate':
                             systemStatusStore.setSdrStatus(messageData.payload as ConnectionStatus);
                             console.log('SystemStatusStore: SdrStatus updated via WebSocket:', messageData.payload);
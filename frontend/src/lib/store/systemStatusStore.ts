@@ -9,6 +9,7 @@ export type SystemServiceStatus = 'Ok' | 'Warning' | 'Error' | 'Unknown';
 export interface SystemStatusState {
   userUuid: string | null;
   radioStatus: ConnectionStatus;
+  audioStatus: ConnectionStatus;
   sdrStatus: ConnectionStatus;
   llmStatus: SystemServiceStatus;
   sttStatus: SystemServiceStatus;
@@ -18,6 +19,7 @@ export interface SystemStatusState {
 
   setUserUuid: (uuid: string | null) => void;
   setRadioStatus: (status: ConnectionStatus) => void;
+  setAudioStatus: (status: ConnectionStatus) => void;
   setSdrStatus: (status: ConnectionStatus) => void;
   setLlmStatus: (status: SystemServiceStatus) => void;
   setSttStatus: (status: SystemServiceStatus) => void;
@@ -29,6 +31,7 @@ export interface SystemStatusState {
 export const useSystemStatusStore = create<SystemStatusState>((set) => ({
   userUuid: null,
   radioStatus: 'Unknown',
+  audioStatus: 'Unknown',
   sdrStatus: 'Disconnected', // 根据占位符逻辑，SDR 默认为 Disconnected
   llmStatus: 'Unknown',
   sttStatus: 'Unknown',
@@ -38,6 +41,7 @@ export const useSystemStatusStore = create<SystemStatusState>((set) => ({
 
   setUserUuid: (uuid) => set({ userUuid: uuid }),
   setRadioStatus: (status) => set({ radioStatus: status }),
+  setAudioStatus: (status) => set({ audioStatus: status }),
   setSdrStatus: (status) => set({ sdrStatus: status }),
   setLlmStatus: (status) => set({ llmStatus: status }),
   setSttStatus: (status) => set({ sttStatus: status }),